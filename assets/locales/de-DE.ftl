@@ -0,0 +1,137 @@
+loading = LADEN
+loading-progress = LADEN { $percent }%
+
+lobby = LOBBY
+lobby-you = DU
+lobby-ready = BEREIT
+lobby-waiting = ...
+lobby-hint = [R] BEREIT
+match-over = SPIEL VORBEI
+round = RUNDE { $round }/{ $total }
+winner-red = ROT GEWINNT
+winner-blue = BLAU GEWINNT
+winner-players = GEWONNEN
+winner-nobody = UNENTSCHIEDEN
+knocked-out = K.O.
+respawn-countdown = RESPAWN IN { $seconds }
+spectating = ZUSCHAUEN: { $name }
+spectate-hint = [LMT] NÄCHSTER [RMT] VORHERIGER
+feed-you-hit = DU WURDEST GETROFFEN
+feed-you-fell = DU BIST GEFALLEN
+feed-hit = EIN SPIELER WURDE GETROFFEN
+feed-fell = EIN SPIELER IST GEFALLEN
+team-score = ROT { $red } - { $blue } BLAU
+
+levels = LEVEL
+levels-hint =
+    [HOCH/RUNTER] WÄHLEN [F2] NEUER SEED
+    [L] LADEN [ESC] ZURÜCK
+
+wave-started = WELLE { $wave }/{ $total }
+wave-cleared = WELLE { $wave } GESCHAFFT
+waves-cleared = ALLE WELLEN GESCHAFFT
+
+time-attack = [F7] ZEITRENNEN
+time-attack-running = ZEIT { $time }  BESTZEIT { $best }
+time-attack-best = BESTZEIT { $best }  [F7] ZEITRENNEN
+
+popup-goal = TOR!
+popup-upgrade = SCHWERHEBER!
+
+skills = FÄHIGKEITEN
+skills-coins = MÜNZEN { $coins }
+skills-unlock = FREISCHALTEN ({ $cost })
+skills-unlocked = FREIGESCHALTET
+skill-double-jump = Doppelsprung
+skill-long-reach = Lange Reichweite
+skill-multi-catch = Mehrfachfang
+skill-fast-throw = Schneller Wurf
+
+stats = STATISTIK
+stats-thrown = Würfel geworfen
+stats-distance = Zurückgelegte Strecke
+stats-goals = Tore erzielt
+stats-best-times = Bestzeiten
+
+display = ANZEIGE
+display-frame-limit = Bildratenbegrenzung
+display-vsync = VSync
+display-on = An
+display-off = Aus
+display-custom = Eigene
+display-graphics = Grafik
+display-graphics-auto = Automatisch ({ $preset })
+display-motion-stretch = Bewegungsschlieren
+graphics-low = Niedrig
+graphics-medium = Mittel
+graphics-high = Hoch
+display-gpu = GPU
+display-backend = Backend
+display-max-texture = Max. Texturgröße
+display-max-buffer = Max. Speicherpuffer
+
+crash-title = Pumpkin Jam ist abgestürzt
+crash-restart = Ein Absturzprotokoll wurde gespeichert. Ohne Path Tracing und in einem kleineren Fenster neu starten?
+
+log = PROTOKOLL
+log-filter = Filter
+log-apply = Anwenden
+
+achievement-unlocked = ERFOLG FREIGESCHALTET
+achievement-long-throw = Weitwurf
+achievement-tower = Turmbauer
+achievement-first-goal = Erstes Tor
+achievement-marathon = Marathon
+
+leaderboard-local = DEINE BESTZEITEN
+leaderboard-global = WELTRANGLISTE
+
+presence-free-play = Freies Spiel
+presence-match = Match
+presence-waves = Welle { $wave }
+presence-time-attack = Zeitrennen
+presence-score = { $goals ->
+    [one] 1 Tor
+   *[other] { $goals } Tore
+}
+
+## Level content
+
+pillars-title = SÄULEN
+pillars-intro-hint = Erreiche das Tor
+pillars-goal = TOR!
+pillars-gate-open = Das Tor ist offen.
+pillars-gate-hint = Wirf einen Ball durch den Ring da oben!
+
+tutorial-move = [WASD] BEWEGEN
+tutorial-look = MAUS BEWEGEN ZUM UMSEHEN
+tutorial-jump = [LEER] SPRINGEN
+tutorial-sprint = [SHIFT] BEIM LAUFEN HALTEN ZUM SPRINTEN
+tutorial-catch = [RMT] HALTEN, UM DEN WÜRFEL ZU ZIEHEN
+tutorial-throw = [RMT] LOSLASSEN ZUM WERFEN
+tutorial-repel = [LMT] ABSTOSSEN
+tutorial-skip = [F1] TUTORIAL ÜBERSPRINGEN
+
+practice-throw = { $distance }M WEIT  { $height }M HOCH
+practice-accuracy = { $percent }%
+practice-best = NEUER REKORD!
+emblem-star = STERN
+emblem-cross = KREUZ
+emblem-grid = GITTER
+emblem-ring = RING
+emblem-arrow = PFEIL
+emblem-bolt = BLITZ
+ability-repel = ABSTOSSEN
+ability-attract = ANZIEHEN
+ability-lift = ANHEBEN
+goal-replay = WIEDERHOLUNG
+interact-prompt = [{ $key }] { $action }
+interact-press = DRÜCKEN
+interact-pickup = AUFHEBEN
+interact-insert = EINSETZEN
+rings-hit = +{ $points } X{ $combo }
+rings-combo = KOMBO X{ $combo }  { $score }  { $seconds }S
+style-air-catch = LUFTFANG
+style-bank-shot = BANDE
+style-juggle = JONGLIEREN
+style-points = { $move } +{ $points }
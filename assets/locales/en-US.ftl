@@ -0,0 +1,137 @@
+loading = LOADING
+loading-progress = LOADING { $percent }%
+
+lobby = LOBBY
+lobby-you = YOU
+lobby-ready = READY
+lobby-waiting = ...
+lobby-hint = [R] READY
+match-over = MATCH OVER
+round = ROUND { $round }/{ $total }
+winner-red = RED WINS
+winner-blue = BLUE WINS
+winner-players = YOU WIN
+winner-nobody = DRAW
+knocked-out = KNOCKED OUT
+respawn-countdown = RESPAWN IN { $seconds }
+spectating = SPECTATING { $name }
+spectate-hint = [LMB] NEXT [RMB] PREVIOUS
+feed-you-hit = YOU WERE HIT
+feed-you-fell = YOU FELL
+feed-hit = A PLAYER WAS HIT
+feed-fell = A PLAYER FELL
+team-score = RED { $red } - { $blue } BLUE
+
+levels = LEVELS
+levels-hint =
+    [UP/DOWN] SELECT [F2] NEW SEED
+    [L] LOAD [ESC] BACK
+
+wave-started = WAVE { $wave }/{ $total }
+wave-cleared = WAVE { $wave } CLEARED
+waves-cleared = ALL WAVES CLEARED
+
+time-attack = [F7] TIME ATTACK
+time-attack-running = TIME { $time }  BEST { $best }
+time-attack-best = BEST { $best }  [F7] TIME ATTACK
+
+popup-goal = GOAL!
+popup-upgrade = HEAVY LIFTER!
+
+skills = SKILLS
+skills-coins = COINS { $coins }
+skills-unlock = UNLOCK ({ $cost })
+skills-unlocked = UNLOCKED
+skill-double-jump = Double jump
+skill-long-reach = Long reach
+skill-multi-catch = Multi-catch
+skill-fast-throw = Fast throw
+
+stats = STATS
+stats-thrown = Cubes thrown
+stats-distance = Distance traveled
+stats-goals = Goals scored
+stats-best-times = Best times
+
+display = DISPLAY
+display-frame-limit = Frame rate limit
+display-vsync = Vsync
+display-on = On
+display-off = Off
+display-custom = Custom
+display-graphics = Graphics
+display-graphics-auto = Auto ({ $preset })
+display-motion-stretch = Motion streaks
+graphics-low = Low
+graphics-medium = Medium
+graphics-high = High
+display-gpu = GPU
+display-backend = Backend
+display-max-texture = Max texture size
+display-max-buffer = Max storage buffer
+
+crash-title = Pumpkin Jam crashed
+crash-restart = A crash log was saved. Restart with path tracing off and a smaller window?
+
+log = LOG
+log-filter = Filter
+log-apply = Apply
+
+achievement-unlocked = ACHIEVEMENT UNLOCKED
+achievement-long-throw = Long Shot
+achievement-tower = Tower Builder
+achievement-first-goal = First Goal
+achievement-marathon = Marathon
+
+leaderboard-local = YOUR BEST TIMES
+leaderboard-global = WORLD RANKING
+
+presence-free-play = Free play
+presence-match = Match
+presence-waves = Wave { $wave }
+presence-time-attack = Time attack
+presence-score = { $goals ->
+    [one] 1 goal
+   *[other] { $goals } goals
+}
+
+## Level content
+
+pillars-title = PILLARS
+pillars-intro-hint = Reach the gate
+pillars-goal = GOAL!
+pillars-gate-open = The gate is open.
+pillars-gate-hint = Get a ball through the ring up top!
+
+tutorial-move = [WASD] MOVE
+tutorial-look = MOVE THE MOUSE TO LOOK AROUND
+tutorial-jump = [SPACE] JUMP
+tutorial-sprint = HOLD [SHIFT] WHILE MOVING TO SPRINT
+tutorial-catch = HOLD [RMB] TO PULL THE CUBE
+tutorial-throw = RELEASE [RMB] TO THROW IT
+tutorial-repel = [LMB] REPEL
+tutorial-skip = [F1] SKIP TUTORIAL
+
+practice-throw = { $distance }M FAR  { $height }M HIGH
+practice-accuracy = { $percent }%
+practice-best = NEW BEST!
+emblem-star = STAR
+emblem-cross = CROSS
+emblem-grid = GRID
+emblem-ring = RING
+emblem-arrow = ARROW
+emblem-bolt = BOLT
+ability-repel = REPEL
+ability-attract = ATTRACT
+ability-lift = LIFT
+goal-replay = REPLAY
+interact-prompt = [{ $key }] { $action }
+interact-press = PRESS
+interact-pickup = PICK UP
+interact-insert = INSERT
+rings-hit = +{ $points } X{ $combo }
+rings-combo = COMBO X{ $combo }  { $score }  { $seconds }S
+style-air-catch = AIR CATCH
+style-bank-shot = BANK SHOT
+style-juggle = JUGGLE
+style-points = { $move } +{ $points }
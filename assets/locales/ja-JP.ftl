@@ -0,0 +1,134 @@
+loading = ロード中
+loading-progress = ロード中 { $percent }%
+
+lobby = ロビー
+lobby-you = あなた
+lobby-ready = 準備完了
+lobby-waiting = ...
+lobby-hint = [R] 準備完了
+match-over = 試合終了
+round = ラウンド { $round }/{ $total }
+winner-red = 赤の勝ち
+winner-blue = 青の勝ち
+winner-players = 勝利
+winner-nobody = 引き分け
+knocked-out = ノックアウト
+respawn-countdown = 復活まで { $seconds }
+spectating = 観戦中: { $name }
+spectate-hint = [左クリック] 次 [右クリック] 前
+feed-you-hit = 被弾した
+feed-you-fell = 落下した
+feed-hit = プレイヤーが被弾した
+feed-fell = プレイヤーが落下した
+team-score = 赤 { $red } - { $blue } 青
+
+levels = ステージ
+levels-hint =
+    [↑/↓] 選択 [F2] 新しいシード
+    [L] ロード [ESC] 戻る
+
+wave-started = ウェーブ { $wave }/{ $total }
+wave-cleared = ウェーブ { $wave } クリア
+waves-cleared = 全ウェーブ クリア
+
+time-attack = [F7] タイムアタック
+time-attack-running = タイム { $time }  ベスト { $best }
+time-attack-best = ベスト { $best }  [F7] タイムアタック
+
+popup-goal = ゴール!
+popup-upgrade = 重量級!
+
+skills = スキル
+skills-coins = コイン { $coins }
+skills-unlock = 解放 ({ $cost })
+skills-unlocked = 解放済み
+skill-double-jump = 二段ジャンプ
+skill-long-reach = ロングリーチ
+skill-multi-catch = マルチキャッチ
+skill-fast-throw = 速投げ
+
+stats = 記録
+stats-thrown = 投げたキューブ
+stats-distance = 移動距離
+stats-goals = ゴール数
+stats-best-times = ベストタイム
+
+display = 画面
+display-frame-limit = フレームレート上限
+display-vsync = 垂直同期
+display-on = オン
+display-off = オフ
+display-custom = カスタム
+display-graphics = グラフィック
+display-graphics-auto = 自動 ({ $preset })
+display-motion-stretch = モーションストリーク
+graphics-low = 低
+graphics-medium = 中
+graphics-high = 高
+display-gpu = GPU
+display-backend = バックエンド
+display-max-texture = 最大テクスチャサイズ
+display-max-buffer = 最大ストレージバッファ
+
+crash-title = Pumpkin Jam がクラッシュしました
+crash-restart = クラッシュログを保存しました。パストレーシングを切り、小さいウィンドウで再起動しますか？
+
+log = ログ
+log-filter = フィルター
+log-apply = 適用
+
+achievement-unlocked = 実績解除
+achievement-long-throw = 遠投
+achievement-tower = タワービルダー
+achievement-first-goal = 初ゴール
+achievement-marathon = マラソン
+
+leaderboard-local = 自己ベスト
+leaderboard-global = 世界ランキング
+
+presence-free-play = フリープレイ
+presence-match = 対戦
+presence-waves = ウェーブ { $wave }
+presence-time-attack = タイムアタック
+presence-score = ゴール { $goals }
+
+## Level content
+
+pillars-title = 柱の間
+pillars-intro-hint = 門までたどり着け
+pillars-goal = ゴール!
+pillars-gate-open = 門が開いた。
+pillars-gate-hint = 上のリングにボールを通せ!
+
+tutorial-move = [WASD] 移動
+tutorial-look = マウスで視点を動かす
+tutorial-jump = [SPACE] ジャンプ
+tutorial-sprint = 移動中に [SHIFT] 長押しでダッシュ
+tutorial-catch = [右クリック] 長押しでキューブを引き寄せる
+tutorial-throw = [右クリック] を離して投げる
+tutorial-repel = [左クリック] はじき飛ばす
+tutorial-skip = [F1] チュートリアルをスキップ
+
+practice-throw = 距離 { $distance }M  高さ { $height }M
+practice-accuracy = { $percent }%
+practice-best = 自己ベスト更新!
+emblem-star = 星
+emblem-cross = 十字
+emblem-grid = 格子
+emblem-ring = 輪
+emblem-arrow = 矢印
+emblem-bolt = 稲妻
+ability-repel = 反発
+ability-attract = 引力
+ability-lift = 浮上
+goal-replay = リプレイ
+interact-prompt = [{ $key }] { $action }
+interact-press = 押す
+interact-pickup = 拾う
+interact-insert = 差し込む
+rings-hit = +{ $points } X{ $combo }
+rings-combo = コンボ X{ $combo }  { $score }  { $seconds }秒
+style-air-catch = 空中キャッチ
+style-bank-shot = バンクショット
+style-juggle = ジャグリング
+style-points = { $move } +{ $points }
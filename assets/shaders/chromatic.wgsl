@@ -0,0 +1,15 @@
+#define_import_path pumpkin_jam::chromatic
+
+// Pulls red and blue apart toward the edges, up to a hundredth of the screen at full `strength`.
+fn apply_chromatic(
+    source: texture_2d<f32>,
+    source_sampler: sampler,
+    uv: vec2<f32>,
+    strength: f32,
+) -> vec3<f32> {
+    let offset = (uv - vec2<f32>(0.5)) * 0.02 * strength;
+    let red = textureSample(source, source_sampler, uv + offset).r;
+    let green = textureSample(source, source_sampler, uv).g;
+    let blue = textureSample(source, source_sampler, uv - offset).b;
+    return vec3<f32>(red, green, blue);
+}
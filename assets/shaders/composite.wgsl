@@ -0,0 +1,38 @@
+#import bevy_sprite::mesh2d_types
+#import bevy_sprite::mesh2d_view_bindings
+#import pumpkin_jam::fog
+
+struct CompositeParams {
+    ui_offset: vec2<f32>,
+    ui_alpha: f32,
+};
+
+@group(1) @binding(0)
+var<uniform> params: CompositeParams;
+@group(1) @binding(1)
+var scene_texture: texture_2d<f32>;
+@group(1) @binding(2)
+var scene_sampler: sampler;
+@group(1) @binding(3)
+var ui_texture: texture_2d<f32>;
+@group(1) @binding(4)
+var ui_sampler: sampler;
+@group(1) @binding(5)
+var depth_texture: texture_2d<f32>;
+@group(1) @binding(6)
+var<uniform> fog: FogParams;
+
+@fragment
+fn fragment(
+    #import bevy_sprite::mesh2d_vertex_output
+) -> @location(0) vec4<f32> {
+    let size = vec2<f32>(textureDimensions(depth_texture));
+    let depth = textureLoad(depth_texture, vec2<i32>(uv * size), 0).r;
+    let scene = apply_fog(textureSample(scene_texture, scene_sampler, uv).rgb, uv, depth, fog);
+    let ui = textureSample(ui_texture, ui_sampler, uv - params.ui_offset);
+
+    // Sprites blend onto the cleared HUD image, so its color is already multiplied by alpha.
+    let alpha = ui.a * params.ui_alpha;
+    let color = scene * (1.0 - alpha) + ui.rgb * params.ui_alpha;
+    return vec4<f32>(color, 1.0);
+}
@@ -0,0 +1,8 @@
+#define_import_path pumpkin_jam::crt
+
+// Dark gaps between the `lines` pixel rows, and a little glow to make up for them.
+fn apply_crt(color: vec3<f32>, uv: vec2<f32>, strength: f32, lines: f32) -> vec3<f32> {
+    let row = fract(uv.y * lines);
+    let scanline = 1.0 - strength * 0.6 * pow(abs(row * 2.0 - 1.0), 4.0);
+    return color * scanline * (1.0 + 0.2 * strength);
+}
@@ -0,0 +1,31 @@
+#define_import_path pumpkin_jam::fog
+
+struct FogParams {
+    color: vec4<f32>,
+    view: mat4x4<f32>,
+    inverse_projection: mat4x4<f32>,
+    near: f32,
+    start: f32,
+    end: f32,
+    height: f32,
+    height_falloff: f32,
+};
+
+// Lays fog over `color`, seen at `uv` where the depth image reads `depth`.
+fn apply_fog(color: vec3<f32>, uv: vec2<f32>, depth: f32, fog_params: FogParams) -> vec3<f32> {
+    // Depth is reversed and the far plane is at infinity, so nothing drawn reads as zero.
+    if (fog_params.color.a <= 0.0 || depth <= 0.0) {
+        return color;
+    }
+
+    // Walk along the ray through this pixel until it is as deep as the depth says.
+    let ndc = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 1.0, 1.0);
+    let near_point = fog_params.inverse_projection * ndc;
+    let ray = near_point.xyz / near_point.w;
+    let view_position = ray / -ray.z * (fog_params.near / depth);
+    let world_position = (fog_params.view * vec4<f32>(view_position, 1.0)).xyz;
+
+    let distance = smoothstep(fog_params.start, fog_params.end, length(view_position));
+    let height = exp(-max(world_position.y - fog_params.height, 0.0) * fog_params.height_falloff);
+    return mix(color, fog_params.color.rgb, distance * height * fog_params.color.a);
+}
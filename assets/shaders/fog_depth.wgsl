@@ -0,0 +1,28 @@
+// Copies the depth buffer of the fog camera into a color image the composite pass can read.
+
+#ifdef MULTISAMPLED
+@group(0) @binding(0)
+var depth_texture: texture_depth_multisampled_2d;
+#else
+@group(0) @binding(0)
+var depth_texture: texture_depth_2d;
+#endif
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+};
+
+// One triangle covering the whole target.
+@vertex
+fn vertex(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    var out: VertexOutput;
+    out.position = vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+    return out;
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    let depth = textureLoad(depth_texture, vec2<i32>(in.position.xy), 0);
+    return vec4<f32>(depth, 0.0, 0.0, 1.0);
+}
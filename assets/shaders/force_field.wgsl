@@ -0,0 +1,43 @@
+#import bevy_pbr::mesh_view_bindings
+#import bevy_pbr::mesh_bindings
+
+struct ForceFieldParams {
+    color: vec4<f32>,
+    // Where the last hit landed, in world space, and when, in seconds.
+    hit: vec4<f32>,
+    time: f32,
+};
+
+@group(1) @binding(0)
+var<uniform> params: ForceFieldParams;
+// Reversed depth of the scene, from the fog camera.
+@group(1) @binding(1)
+var depth_texture: texture_2d<f32>;
+
+@fragment
+fn fragment(
+    @builtin(position) frag_coord: vec4<f32>,
+    #import bevy_pbr::mesh_vertex_output
+) -> @location(0) vec4<f32> {
+    // The path tracer doesn't share its depth, so hide behind the scene by hand.
+    let scene_depth = textureLoad(depth_texture, vec2<i32>(frag_coord.xy), 0).r;
+    if (scene_depth > frag_coord.z) {
+        discard;
+    }
+
+    // Slow bands drifting over a hex-ish grid.
+    let p = world_position.xyz;
+    let grid = abs(fract(p.x * 0.5 + sin(p.z * 0.5)) - 0.5) + abs(fract(p.z * 0.5 + p.y * 0.25) - 0.5);
+    let lines = smoothstep(0.42, 0.5, grid);
+    let bands = 0.5 + 0.5 * sin(p.y * 0.8 + p.x * 0.3 + params.time * 1.5);
+    var alpha = params.color.a * (0.25 * bands + 0.75 * lines);
+
+    // A ring running out from the last hit, fading as it goes.
+    let since = params.time - params.hit.w;
+    let ring = 1.0 - smoothstep(0.0, 0.6, abs(distance(p, params.hit.xyz) - since * 8.0));
+    let pulse = ring * (1.0 - smoothstep(0.0, 1.2, since));
+    alpha = clamp(alpha + pulse, 0.0, 1.0);
+
+    let color = mix(params.color.rgb, vec3<f32>(1.0), pulse);
+    return vec4<f32>(color, alpha);
+}
@@ -0,0 +1,28 @@
+#import bevy_sprite::mesh2d_types
+#import bevy_sprite::mesh2d_view_bindings
+#import pumpkin_jam::vignette
+
+struct OverlayParams {
+    // Red creeping in from the corners as health runs low.
+    hurt: f32,
+    // White over everything, fading out after a pickup.
+    flash: f32,
+};
+
+@group(1) @binding(0)
+var<uniform> params: OverlayParams;
+
+@fragment
+fn fragment(
+    #import bevy_sprite::mesh2d_vertex_output
+) -> @location(0) vec4<f32> {
+    let hurt = params.hurt * vignette_mask(uv);
+    let red = vec4<f32>(0.6, 0.0, 0.0, hurt);
+    // The flash goes over the vignette.
+    let alpha = params.flash + hurt * (1.0 - params.flash);
+    if (alpha <= 0.0) {
+        return vec4<f32>(0.0);
+    }
+    let color = (vec3<f32>(1.0) * params.flash + red.rgb * hurt * (1.0 - params.flash)) / alpha;
+    return vec4<f32>(color, alpha);
+}
@@ -0,0 +1,6 @@
+#define_import_path pumpkin_jam::palette
+
+// Color correction for colorblind palettes, kept in range.
+fn correct_palette(color: vec3<f32>, palette: mat3x3<f32>) -> vec3<f32> {
+    return clamp(palette * color, vec3<f32>(0.0), vec3<f32>(1.0));
+}
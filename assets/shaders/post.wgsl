@@ -0,0 +1,39 @@
+#import bevy_sprite::mesh2d_types
+#import bevy_sprite::mesh2d_view_bindings
+#import pumpkin_jam::palette
+#import pumpkin_jam::vignette
+#import pumpkin_jam::chromatic
+#import pumpkin_jam::crt
+
+// One pass of the post-process chain; `effect` picks which, in the order of `PostEffect`.
+struct PostParams {
+    palette: mat3x3<f32>,
+    effect: u32,
+    strength: f32,
+    // Pixel rows of the scene, for the CRT to put its scanlines between.
+    lines: f32,
+};
+
+@group(1) @binding(0)
+var<uniform> params: PostParams;
+@group(1) @binding(1)
+var source_texture: texture_2d<f32>;
+@group(1) @binding(2)
+var source_sampler: sampler;
+
+@fragment
+fn fragment(
+    #import bevy_sprite::mesh2d_vertex_output
+) -> @location(0) vec4<f32> {
+    var color = textureSample(source_texture, source_sampler, uv).rgb;
+    if (params.effect == 0u) {
+        color = mix(color, correct_palette(color, params.palette), params.strength);
+    } else if (params.effect == 1u) {
+        color = apply_vignette(color, uv, params.strength);
+    } else if (params.effect == 2u) {
+        color = apply_chromatic(source_texture, source_sampler, uv, params.strength);
+    } else if (params.effect == 3u) {
+        color = apply_crt(color, uv, params.strength, params.lines);
+    }
+    return vec4<f32>(color, 1.0);
+}
@@ -0,0 +1,12 @@
+#define_import_path pumpkin_jam::vignette
+
+// How far into the corners `uv` is, from 0 in the middle to 1 at the edge.
+fn vignette_mask(uv: vec2<f32>) -> f32 {
+    let distance = length((uv - vec2<f32>(0.5)) * vec2<f32>(1.0, 0.75)) * 1.6;
+    return smoothstep(0.3, 1.0, distance);
+}
+
+// Darkens the corners, more with `strength`.
+fn apply_vignette(color: vec3<f32>, uv: vec2<f32>, strength: f32) -> vec3<f32> {
+    return color * (1.0 - strength * vignette_mask(uv));
+}
@@ -0,0 +1,91 @@
+//! Choosing what the alt-fire of the catcher does.
+//!
+//! Holding Abilities opens a [`RadialMenu`] of every [`Ability`]; letting go makes the picked one
+//! the [`ActiveAbility`]. Playing offline, time slows down while the menu is open, so there is a
+//! moment to choose in the middle of a fight.
+
+use crate::{
+    feedback::Feedback,
+    hud::radial::{RadialKind, RadialMenu, RadialOption},
+    interact::InteractTarget,
+    locale::Locale,
+    net::Network,
+    Action, Player,
+};
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::*;
+
+pub struct AbilityPlugin;
+
+impl Plugin for AbilityPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ActiveAbility>()
+            .add_system(ability_menu)
+            .add_system(slow_time.after(ability_menu));
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Ability {
+    /// Shoves everything in front of the catcher away.
+    #[default]
+    Repel,
+    /// Draws everything in front of the catcher closer.
+    Attract,
+    /// Tosses everything in front of the catcher up into the air.
+    Lift,
+}
+
+impl Ability {
+    pub const ALL: [Ability; 3] = [Ability::Repel, Ability::Attract, Ability::Lift];
+
+    fn option(self, locale: &Locale) -> RadialOption {
+        let (icon, color, name) = match self {
+            Ability::Repel => ('>', Color::rgb(1.0, 0.5, 0.3), "ability-repel"),
+            Ability::Attract => ('<', Color::rgb(0.4, 0.7, 1.0), "ability-attract"),
+            Ability::Lift => ('+', Color::rgb(0.5, 1.0, 0.5), "ability-lift"),
+        };
+        RadialOption {
+            icon,
+            color,
+            label: locale.get(name),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ActiveAbility(pub Ability);
+
+fn ability_menu(
+    locale: Res<Locale>,
+    mut menu: ResMut<RadialMenu>,
+    mut active: ResMut<ActiveAbility>,
+    target: Res<InteractTarget>,
+    players: Query<&ActionState<Action>, With<Player>>,
+) {
+    let action_state = match players.get_single() {
+        Ok(action_state) => action_state,
+        Err(_) => return,
+    };
+    // Abilities share a key with Interact, which wins when there is something to use.
+    if action_state.just_pressed(Action::Abilities) && !menu.is_open() && target.0.is_none() {
+        let options = Ability::ALL
+            .iter()
+            .map(|ability| ability.option(&locale))
+            .collect();
+        menu.open(RadialKind::Ability, options);
+    }
+    if menu.is_open_for(RadialKind::Ability) && action_state.just_released(Action::Abilities) {
+        if let Some(ability) = menu.close().and_then(|index| Ability::ALL.get(index)) {
+            active.0 = *ability;
+        }
+    }
+}
+
+/// Other peers keep playing at full speed, so time only slows down offline.
+fn slow_time(network: Res<Network>, menu: Res<RadialMenu>, mut feedback: ResMut<Feedback>) {
+    let slow_motion = matches!(*network, Network::Offline) && menu.is_open_for(RadialKind::Ability);
+    if feedback.slow_motion != slow_motion {
+        feedback.slow_motion = slow_motion;
+    }
+}
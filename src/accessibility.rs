@@ -0,0 +1,243 @@
+//! Options for players who find the default controls or effects hard going.
+//!
+//! The settings are read from `assets/accessibility.ron` at startup; anything left out keeps its
+//! default, which is the game as designed. A colorblind [`PaletteMode`] both corrects the final
+//! image in the composite pass and picks a [`Palette`] for the colors that carry meaning in play,
+//! like goals and markers. A [`ControlPreset`] lays the controls out for the other hand.
+
+use crate::{level::Goal, Action, Player};
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::*;
+use serde::Deserialize;
+use std::fs;
+
+pub const ACCESSIBILITY_PATH: &str = "assets/accessibility.ron";
+
+pub struct AccessibilityPlugin;
+
+impl Plugin for AccessibilityPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(AccessibilitySettings::load())
+            .add_system(recolor_goals)
+            .add_system(apply_control_preset);
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum CrosshairStyle {
+    #[default]
+    Dot,
+    /// A large cross with a dark outline, readable on any background.
+    HighContrast,
+    Hidden,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum ControlPreset {
+    #[default]
+    Default,
+    /// Arrow keys and the keys around them, with the mouse buttons swapped.
+    LeftHanded,
+    /// Moving with the right stick and looking with the left one.
+    Southpaw,
+}
+
+impl ControlPreset {
+    pub fn input_map(self) -> InputMap<Action> {
+        let mut map = InputMap::default();
+        if self == ControlPreset::LeftHanded {
+            map.insert(VirtualDPad::arrow_keys(), Action::Move)
+                .insert(KeyCode::RControl, Action::Jump)
+                .insert(KeyCode::RShift, Action::Sprint)
+                .insert(MouseButton::Left, Action::Catch)
+                .insert(MouseButton::Right, Action::Repel)
+                .insert(KeyCode::Delete, Action::Inspect)
+                .insert(KeyCode::End, Action::Stamp)
+                .insert(KeyCode::PageDown, Action::Abilities)
+                .insert(KeyCode::PageDown, Action::Interact)
+                .insert(KeyCode::Home, Action::Drop);
+        } else {
+            map.insert(VirtualDPad::wasd(), Action::Move)
+                .insert(KeyCode::Space, Action::Jump)
+                .insert(KeyCode::LShift, Action::Sprint)
+                .insert(MouseButton::Right, Action::Catch)
+                .insert(MouseButton::Left, Action::Repel)
+                .insert(KeyCode::F, Action::Inspect)
+                .insert(KeyCode::Q, Action::Stamp)
+                .insert(KeyCode::E, Action::Abilities)
+                .insert(KeyCode::E, Action::Interact)
+                .insert(KeyCode::G, Action::Drop);
+        }
+        map.insert(DualAxis::mouse_motion(), Action::Look)
+            .insert(MouseButton::Middle, Action::Zoom);
+
+        if self == ControlPreset::Southpaw {
+            map.insert(DualAxis::right_stick(), Action::Move)
+                .insert(DualAxis::left_stick(), Action::Look)
+                .insert(GamepadButtonType::RightThumb, Action::Sprint);
+        } else {
+            map.insert(DualAxis::left_stick(), Action::Move)
+                .insert(DualAxis::right_stick(), Action::Look)
+                .insert(GamepadButtonType::LeftThumb, Action::Sprint);
+        }
+        map.insert(GamepadButtonType::RightTrigger2, Action::Repel)
+            .insert(GamepadButtonType::North, Action::Inspect)
+            .insert(GamepadButtonType::West, Action::Stamp)
+            .insert(GamepadButtonType::East, Action::Interact)
+            .insert(GamepadButtonType::DPadDown, Action::Drop)
+            .insert(GamepadButtonType::LeftTrigger, Action::Abilities)
+            .insert(GamepadButtonType::LeftTrigger2, Action::Zoom);
+        map
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum PaletteMode {
+    #[default]
+    Normal,
+    Deuteranopia,
+    Protanopia,
+    Tritanopia,
+}
+
+/// Colors that tell things apart in play.
+#[derive(Debug, Clone, Copy)]
+pub struct Palette {
+    pub goal: Color,
+    pub marker: Color,
+    pub player: Color,
+    /// Trims of the two teams, see [`crate::teams::Team`].
+    pub teams: [Color; 2],
+}
+
+impl PaletteMode {
+    pub fn palette(self) -> Palette {
+        match self {
+            PaletteMode::Normal => Palette {
+                goal: Color::rgb(0.9, 0.6, 0.2),
+                marker: Color::rgb(0.2, 0.9, 0.4),
+                player: Color::rgb(1.0, 0.9, 0.2),
+                teams: [Color::rgb(0.95, 0.25, 0.2), Color::rgb(0.2, 0.45, 0.95)],
+            },
+            // Red and green look alike, so lean on orange against blue.
+            PaletteMode::Deuteranopia | PaletteMode::Protanopia => Palette {
+                goal: Color::rgb(1.0, 0.6, 0.0),
+                marker: Color::rgb(0.0, 0.45, 0.9),
+                player: Color::rgb(0.95, 0.95, 0.95),
+                teams: [Color::rgb(1.0, 0.6, 0.0), Color::rgb(0.0, 0.45, 0.9)],
+            },
+            // Blue and yellow look alike, so lean on red against teal.
+            PaletteMode::Tritanopia => Palette {
+                goal: Color::rgb(0.9, 0.2, 0.3),
+                marker: Color::rgb(0.0, 0.7, 0.7),
+                player: Color::rgb(0.95, 0.95, 0.95),
+                teams: [Color::rgb(0.9, 0.2, 0.3), Color::rgb(0.0, 0.7, 0.7)],
+            },
+        }
+    }
+
+    /// Linear color matrix for the final image, which moves the contrast a viewer misses into
+    /// channels they can see (daltonization, after Machado et al. and Fidaner et al.).
+    pub fn correction(self) -> Mat3 {
+        match self {
+            PaletteMode::Normal => Mat3::IDENTITY,
+            PaletteMode::Deuteranopia => Mat3::from_cols(
+                Vec3::new(1.0, 0.163, 0.455),
+                Vec3::new(0.0, 0.725, -0.645),
+                Vec3::new(0.0, 0.112, 1.191),
+            ),
+            PaletteMode::Protanopia => Mat3::from_cols(
+                Vec3::new(1.0, 0.479, 0.597),
+                Vec3::new(0.0, 0.477, -0.689),
+                Vec3::new(0.0, 0.044, 1.091),
+            ),
+            PaletteMode::Tritanopia => Mat3::from_cols(
+                Vec3::new(0.997, -0.003, 0.0),
+                Vec3::new(-0.484, 0.516, 0.0),
+                Vec3::new(0.487, 0.487, 1.0),
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct AccessibilitySettings {
+    /// Press catch once to start pulling and again to let go, instead of holding it.
+    pub catch_toggle: bool,
+    /// Half angle in degrees of a cone in front of the catcher whose objects are caught before
+    /// closer ones, or `None` for no assist.
+    pub aim_assist: Option<f32>,
+    /// Turns off camera and HUD shake.
+    pub reduce_motion: bool,
+    pub crosshair: CrosshairStyle,
+    pub palette: PaletteMode,
+    /// Shows where important sounds come from at the screen edges.
+    pub sound_indicators: bool,
+    pub controls: ControlPreset,
+    /// Looks down when pushing the mouse or stick up.
+    pub invert_y: bool,
+}
+
+impl AccessibilitySettings {
+    pub fn load() -> Self {
+        let settings = fs::read_to_string(ACCESSIBILITY_PATH)
+            .map_err(|err| err.to_string())
+            .and_then(|text| ron::from_str(&text).map_err(|err| err.to_string()));
+        match settings {
+            Ok(settings) => settings,
+            Err(err) => {
+                warn!(
+                    "Failed to load {}, using the default settings: {}",
+                    ACCESSIBILITY_PATH, err
+                );
+                Self::default()
+            }
+        }
+    }
+
+    pub fn palette(&self) -> Palette {
+        self.palette.palette()
+    }
+
+    /// The aim assist cone in radians.
+    pub fn aim_assist_angle(&self) -> Option<f32> {
+        self.aim_assist.map(f32::to_radians)
+    }
+}
+
+/// Paints goals, and the meshes under them, in the goal color of the palette.
+fn recolor_goals(
+    settings: Res<AccessibilitySettings>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    goals: Query<(Entity, Option<&Children>, ChangeTrackers<Goal>)>,
+    handles: Query<&Handle<StandardMaterial>>,
+) {
+    let color = settings.palette().goal;
+    let mut emissive = color;
+    emissive.set_a(0.5);
+    for (entity, children, tracker) in &goals {
+        if !settings.is_changed() && !tracker.is_added() {
+            continue;
+        }
+        let entities = std::iter::once(entity).chain(children.into_iter().flatten().copied());
+        for handle in entities.filter_map(|entity| handles.get(entity).ok()) {
+            if let Some(material) = materials.get_mut(handle) {
+                material.base_color = color;
+                material.emissive = emissive;
+            }
+        }
+    }
+}
+
+/// Lays out the controls of the player by the preset, whenever either changes.
+fn apply_control_preset(
+    settings: Res<AccessibilitySettings>,
+    mut players: Query<(&mut InputMap<Action>, ChangeTrackers<Player>)>,
+) {
+    for (mut map, player) in &mut players {
+        if settings.is_changed() || player.is_added() {
+            *map = settings.controls.input_map();
+        }
+    }
+}
@@ -0,0 +1,243 @@
+//! Achievements, with a toast sliding in when one is earned.
+//!
+//! Every [`Achievement`] has a detector system that sends [`AchievementEarned`] once its condition
+//! holds. The first time an achievement is earned it is stored in the [`Profile`] and announced by
+//! a toast in the corner of the HUD; toasts queue up if several come at once.
+
+use crate::{
+    hud::bitmap::{BitmapAlign, BitmapText, BitmapTextBundle, GLYPH_ADVANCE},
+    layers::SpawnOnLayerExt,
+    locale::Locale,
+    merge::Mergeable,
+    profile::Profile,
+    stamina::Stamina,
+    Player, PlayerCatch, CUBE_SIZE, RENDER_SIZE,
+};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+const LONG_THROW_DISTANCE: f32 = 50.0;
+/// How long a throw is followed for [`Achievement::LongThrow`].
+const THROW_WATCH_TIME: f32 = 5.0;
+const TOWER_HEIGHT: usize = 5;
+/// Seconds between looking for towers, since it checks every pair of cubes.
+const TOWER_CHECK_INTERVAL: f32 = 0.5;
+/// Slower than this, in meters per second, a cube counts as resting.
+const REST_SPEED: f32 = 0.2;
+const MARATHON_DISTANCE: f32 = 10_000.0;
+
+const TOAST_Z: f32 = 7.0;
+/// Size of a toast, in HUD pixels.
+const TOAST_SIZE: Vec2 = Vec2::new(100.0, 18.0);
+const TOAST_MARGIN: f32 = 4.0;
+const TOAST_SLIDE: f32 = 0.3;
+const TOAST_TIME: f32 = 3.0;
+
+pub struct AchievementPlugin;
+
+impl Plugin for AchievementPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Toasts>()
+            .add_event::<AchievementEarned>()
+            .add_system(detect_long_throw)
+            .add_system(detect_tower)
+            .add_system(detect_profile_achievements)
+            .add_system(record_achievements)
+            .add_system(show_toasts.after(record_achievements));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Achievement {
+    /// A thrown cube lands 50 m away.
+    LongThrow,
+    /// Five cubes stacked on each other.
+    Tower,
+    FirstGoal,
+    /// Travel 10 km in total.
+    Marathon,
+}
+
+impl Achievement {
+    /// Locale key of the name.
+    pub fn key(&self) -> &'static str {
+        match self {
+            Achievement::LongThrow => "achievement-long-throw",
+            Achievement::Tower => "achievement-tower",
+            Achievement::FirstGoal => "achievement-first-goal",
+            Achievement::Marathon => "achievement-marathon",
+        }
+    }
+}
+
+/// Sent by detectors every time the condition holds, earned before or not.
+#[derive(Debug, Clone, Copy)]
+pub struct AchievementEarned(pub Achievement);
+
+/// Follows what the player throws, to see how far it gets.
+fn detect_long_throw(
+    time: Res<Time>,
+    mut holding: Local<Option<Entity>>,
+    mut thrown: Local<Vec<(Entity, Vec3, f32)>>,
+    players: Query<(&PlayerCatch, Option<&Stamina>), With<Player>>,
+    transforms: Query<&GlobalTransform>,
+    mut earned: EventWriter<AchievementEarned>,
+) {
+    if let Ok((catch, stamina)) = players.get_single() {
+        let dropped = stamina.map_or(false, Stamina::is_exhausted);
+        if let (Some(entity), None, false) = (*holding, catch.target, dropped) {
+            if let Ok(transform) = transforms.get(entity) {
+                thrown.push((entity, transform.translation(), 0.0));
+            }
+        }
+        *holding = catch.target;
+    }
+
+    let delta = time.delta_seconds();
+    thrown.retain_mut(|(entity, start, elapsed)| {
+        *elapsed += delta;
+        match transforms.get(*entity) {
+            Ok(transform) if transform.translation().distance(*start) >= LONG_THROW_DISTANCE => {
+                earned.send(AchievementEarned(Achievement::LongThrow));
+                false
+            }
+            Ok(_) => *elapsed < THROW_WATCH_TIME,
+            Err(_) => false,
+        }
+    });
+}
+
+/// Looks for a column of resting cubes, each right on top of the one below.
+fn detect_tower(
+    time: Res<Time>,
+    mut since_check: Local<f32>,
+    cubes: Query<(&GlobalTransform, &Velocity), With<Mergeable>>,
+    mut earned: EventWriter<AchievementEarned>,
+) {
+    *since_check += time.delta_seconds();
+    if *since_check < TOWER_CHECK_INTERVAL {
+        return;
+    }
+    *since_check = 0.0;
+
+    let resting: Vec<Vec3> = cubes
+        .iter()
+        .filter(|(_, velocity)| velocity.linvel.length() < REST_SPEED)
+        .map(|(transform, _)| transform.translation())
+        .collect();
+    let on_top = |below: Vec3, above: Vec3| {
+        let offset = above - below;
+        offset.x.abs() < 0.3 * CUBE_SIZE
+            && offset.z.abs() < 0.3 * CUBE_SIZE
+            && (offset.y - CUBE_SIZE).abs() < 0.2 * CUBE_SIZE
+    };
+
+    for &base in &resting {
+        let mut top = base;
+        let mut height = 1;
+        while let Some(&next) = resting.iter().find(|&&cube| on_top(top, cube)) {
+            top = next;
+            height += 1;
+        }
+        if height >= TOWER_HEIGHT {
+            earned.send(AchievementEarned(Achievement::Tower));
+            return;
+        }
+    }
+}
+
+/// Achievements that follow from the stats in the profile.
+fn detect_profile_achievements(profile: Res<Profile>, mut earned: EventWriter<AchievementEarned>) {
+    if !profile.is_changed() {
+        return;
+    }
+    let stats = &profile.stats;
+    if stats.goals > 0 {
+        earned.send(AchievementEarned(Achievement::FirstGoal));
+    }
+    if stats.distance >= MARATHON_DISTANCE {
+        earned.send(AchievementEarned(Achievement::Marathon));
+    }
+}
+
+#[derive(Default)]
+struct Toasts {
+    pending: VecDeque<Achievement>,
+}
+
+fn record_achievements(
+    mut profile: ResMut<Profile>,
+    mut toasts: ResMut<Toasts>,
+    mut earned: EventReader<AchievementEarned>,
+) {
+    for AchievementEarned(achievement) in earned.iter() {
+        if !profile.achievements.contains(achievement) {
+            info!("Achievement earned: {:?}", achievement);
+            profile.achievements.push(*achievement);
+            toasts.pending.push_back(*achievement);
+        }
+    }
+}
+
+#[derive(Component)]
+struct Toast {
+    elapsed: f32,
+}
+
+fn show_toasts(
+    mut commands: Commands,
+    time: Res<Time>,
+    locale: Res<Locale>,
+    mut pending: ResMut<Toasts>,
+    mut toasts: Query<(Entity, &mut Toast, &mut Transform)>,
+) {
+    let size = Vec2::new(RENDER_SIZE[0] as f32, RENDER_SIZE[1] as f32);
+    let shown = 0.5 * (size - TOAST_SIZE) - TOAST_MARGIN;
+    let hidden = shown.x + TOAST_SIZE.x + 2.0 * TOAST_MARGIN;
+
+    if toasts.is_empty() {
+        if let Some(achievement) = pending.pending.pop_front() {
+            let line = |value: String, color: Color, y: f32| {
+                let mut text = BitmapTextBundle::new(
+                    BitmapText::new(value, color, BitmapAlign::Center),
+                    Vec2::new(0.0, y),
+                );
+                text.spatial.transform.translation.z = 0.1;
+                text
+            };
+            commands
+                .spawn_on_hud_layer(SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::rgba(0.0, 0.0, 0.0, 0.8),
+                        custom_size: Some(TOAST_SIZE),
+                        ..default()
+                    },
+                    transform: Transform::from_xyz(hidden, shown.y, TOAST_Z),
+                    ..default()
+                })
+                .insert(Toast { elapsed: 0.0 })
+                .with_children(|parent| {
+                    let title = locale.get("achievement-unlocked");
+                    let name = locale.get(achievement.key());
+                    let half = 0.5 * GLYPH_ADVANCE.y;
+                    parent.spawn_on_hud_layer(line(title, Color::rgb(0.9, 0.6, 0.2), half));
+                    parent.spawn_on_hud_layer(line(name, Color::WHITE, -half));
+                });
+        }
+    }
+
+    for (entity, mut toast, mut transform) in &mut toasts {
+        toast.elapsed += time.delta_seconds();
+        let remaining = TOAST_TIME - toast.elapsed;
+        if remaining <= 0.0 {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        // Ease in, hold, ease out.
+        let t = (toast.elapsed.min(remaining) / TOAST_SLIDE).clamp(0.0, 1.0);
+        let eased = t * t * (3.0 - 2.0 * t);
+        transform.translation.x = hidden + (shown.x - hidden) * eased;
+    }
+}
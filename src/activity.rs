@@ -0,0 +1,160 @@
+//! Keeps idle physics cheap by putting far cubes to sleep.
+//!
+//! Rapier only lets bodies sleep after they have been still for a while, and a pile of cubes that
+//! keeps nudging itself never does. Catchable bodies get the sleep thresholds of
+//! [`ActivitySettings`], and those far from the player that have slowed down are put to sleep
+//! outright. They wake again when the [`SpatialIndex`] finds the player near them or aiming the
+//! catcher at them; anything hitting them wakes them as usual. [`ActivityStats`] counts bodies for
+//! the perf overlay.
+
+use crate::{
+    catch_cone_candidates, hold::Held, spatial::SpatialIndex, CatchObject, Player, PlayerCatcher,
+};
+use bevy::{prelude::*, utils::HashSet};
+use bevy_inspector_egui::Inspectable;
+use bevy_rapier3d::prelude::*;
+
+pub struct PhysicsActivityPlugin;
+
+impl Plugin for PhysicsActivityPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ActivitySettings>()
+            .init_resource::<ActivityStats>()
+            .add_system(apply_sleep_thresholds)
+            .add_system(manage_activity.after(apply_sleep_thresholds))
+            .add_system(count_activity);
+    }
+}
+
+#[derive(Debug, Clone, Inspectable)]
+pub struct ActivitySettings {
+    /// Slower than this, in meters per second, counts as still.
+    #[inspectable(min = 0.0, max = 2.0)]
+    pub linear_threshold: f32,
+    /// Slower than this, in radians per second, counts as still.
+    #[inspectable(min = 0.0, max = 2.0)]
+    pub angular_threshold: f32,
+    /// Bodies farther than this from the player are put to sleep once still, in meters.
+    #[inspectable(min = 5.0, max = 200.0)]
+    pub sleep_distance: f32,
+    /// Bodies closer than this to the player are woken, in meters.
+    #[inspectable(min = 1.0, max = 50.0)]
+    pub wake_distance: f32,
+}
+
+impl Default for ActivitySettings {
+    fn default() -> Self {
+        Self {
+            linear_threshold: 0.4,
+            angular_threshold: 0.5,
+            sleep_distance: 30.0,
+            wake_distance: 8.0,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ActivityStats {
+    pub awake: usize,
+    pub asleep: usize,
+    /// Bodies put to sleep early this frame.
+    pub forced: usize,
+    /// Bodies woken by the player this frame.
+    pub woken: usize,
+}
+
+fn apply_sleep_thresholds(
+    mut commands: Commands,
+    settings: Res<ActivitySettings>,
+    added: Query<Entity, Added<CatchObject>>,
+    mut bodies: Query<&mut Sleeping, With<CatchObject>>,
+) {
+    let sleeping = Sleeping {
+        linear_threshold: settings.linear_threshold,
+        angular_threshold: settings.angular_threshold,
+        sleeping: false,
+    };
+    for entity in &added {
+        commands.entity(entity).insert(sleeping);
+    }
+    if settings.is_changed() {
+        for mut body in &mut bodies {
+            body.linear_threshold = settings.linear_threshold;
+            body.angular_threshold = settings.angular_threshold;
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn manage_activity(
+    settings: Res<ActivitySettings>,
+    rapier_context: Res<RapierContext>,
+    index: Res<SpatialIndex<CatchObject>>,
+    mut stats: ResMut<ActivityStats>,
+    players: Query<(&GlobalTransform, &Player)>,
+    catchers: Query<&GlobalTransform, With<PlayerCatcher>>,
+    mut bodies: Query<
+        (Entity, &GlobalTransform, &Velocity, &mut Sleeping),
+        (With<CatchObject>, Without<Held>),
+    >,
+) {
+    stats.forced = 0;
+    stats.woken = 0;
+    let (player, reach) = match players.get_single() {
+        Ok((transform, player)) => (transform.translation(), player),
+        Err(_) => return,
+    };
+    // Close by or in the catch cone.
+    let mut wanted: HashSet<Entity> = index
+        .within(player, settings.wake_distance)
+        .into_iter()
+        .map(|entry| entry.entity)
+        .collect();
+    if let Ok(catcher) = catchers.get_single() {
+        wanted.extend(catch_cone_candidates(
+            &index,
+            catcher,
+            reach.catch_range,
+            reach.catch_angle,
+        ));
+    }
+
+    for (entity, transform, velocity, mut sleeping) in &mut bodies {
+        let asleep = rapier_context
+            .entity2body()
+            .get(&entity)
+            .and_then(|handle| rapier_context.bodies.get(*handle))
+            .map_or(false, |body| body.is_sleeping());
+        let distance = transform.translation().distance(player);
+        let wanted = wanted.contains(&entity);
+
+        if asleep {
+            if wanted {
+                sleeping.sleeping = false;
+                stats.woken += 1;
+            }
+        } else if !wanted
+            && distance > settings.sleep_distance
+            && velocity.linvel.length() < settings.linear_threshold
+            && velocity.angvel.length() < settings.angular_threshold
+        {
+            sleeping.sleeping = true;
+            stats.forced += 1;
+        }
+    }
+}
+
+fn count_activity(rapier_context: Res<RapierContext>, mut stats: ResMut<ActivityStats>) {
+    stats.asleep = 0;
+    stats.awake = 0;
+    for (_, body) in rapier_context.bodies.iter() {
+        if !body.is_dynamic() {
+            continue;
+        }
+        if body.is_sleeping() {
+            stats.asleep += 1;
+        } else {
+            stats.awake += 1;
+        }
+    }
+}
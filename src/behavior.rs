@@ -0,0 +1,114 @@
+//! Behavior trees for AI agents.
+//!
+//! A tree is built from composable [`Behavior`] nodes and ticked from the root every frame against
+//! a context `C`, which the agent system fills with what the agent knows and reads the decisions
+//! back from. Nodes are plain functions, so trees hold no state of their own; anything that must
+//! last across frames lives in the context's source components.
+
+use bevy::prelude::*;
+
+pub struct BehaviorPlugin;
+
+impl Plugin for BehaviorPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<ActiveBehavior>();
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Success,
+    Failure,
+    Running,
+}
+
+pub enum Behavior<C> {
+    /// Runs children in order until one doesn't succeed.
+    Sequence(&'static str, Vec<Behavior<C>>),
+    /// Runs children in order until one doesn't fail.
+    Selector(&'static str, Vec<Behavior<C>>),
+    Condition(&'static str, fn(&C) -> bool),
+    Action(&'static str, fn(&mut C) -> Status),
+}
+
+impl<C> Behavior<C> {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Behavior::Sequence(name, _)
+            | Behavior::Selector(name, _)
+            | Behavior::Condition(name, _)
+            | Behavior::Action(name, _) => name,
+        }
+    }
+
+    /// Ticks the node, leaving the names of the nodes that decided the result in `path`.
+    pub fn tick(&self, context: &mut C, path: &mut Vec<&'static str>) -> Status {
+        path.push(self.name());
+        let depth = path.len();
+
+        let status = match self {
+            Behavior::Sequence(_, children) => {
+                let mut status = Status::Success;
+                for child in children {
+                    path.truncate(depth);
+                    status = child.tick(context, path);
+                    if status != Status::Success {
+                        break;
+                    }
+                }
+                status
+            }
+            Behavior::Selector(_, children) => {
+                let mut status = Status::Failure;
+                for child in children {
+                    path.truncate(depth);
+                    status = child.tick(context, path);
+                    if status != Status::Failure {
+                        break;
+                    }
+                }
+                status
+            }
+            Behavior::Condition(_, condition) => match condition(context) {
+                true => Status::Success,
+                false => Status::Failure,
+            },
+            Behavior::Action(_, action) => action(context),
+        };
+
+        if status == Status::Failure {
+            path.truncate(depth - 1);
+        }
+        status
+    }
+}
+
+#[derive(Component)]
+pub struct BehaviorTree<C: 'static> {
+    pub root: Behavior<C>,
+}
+
+impl<C: 'static> BehaviorTree<C> {
+    pub fn new(root: Behavior<C>) -> Self {
+        Self { root }
+    }
+
+    /// Ticks the whole tree and records the active path for debugging.
+    pub fn tick(&self, context: &mut C, active: &mut ActiveBehavior) -> Status {
+        let mut path = vec![];
+        let status = self.root.tick(context, &mut path);
+
+        let path = path.join(" > ");
+        if active.path != path {
+            active.path = path;
+        }
+        status
+    }
+}
+
+/// The nodes that decided the last tick of an agent's [`BehaviorTree`], shown in the inspector.
+#[derive(Debug, Default, Component, Reflect)]
+#[reflect(Component)]
+pub struct ActiveBehavior {
+    pub path: String,
+}
@@ -0,0 +1,606 @@
+use crate::{
+    behavior::{ActiveBehavior, Behavior, BehaviorTree, Status},
+    catch_class::CatchClass,
+    catch_closest,
+    hold::{CatchPoint, Held},
+    input_block::InputBlock,
+    layers::SpawnOnLayerExt,
+    match_flow::{MatchState, SpawnPoint},
+    nav::{nav_agent_follow, NavAgent},
+    net::{Network, PeerId, RemotePlayer, ServerMessage},
+    perception::{perceive, Awareness, Perception},
+    spatial::SpatialIndex,
+    tuning::Tuning,
+    CatchObject, Player, PlayerCatch,
+};
+use bevy::prelude::*;
+use bevy_mod_wanderlust::{CharacterControllerBundle, ControllerInput};
+use bevy_rapier3d::prelude::*;
+use rand::Rng;
+use std::time::Duration;
+
+/// Bots are replicated as players with ids starting here.
+pub const BOT_PEER_BASE: PeerId = 1000;
+
+const ROAM_EXTENT: f32 = 40.0;
+const PILLAR_CLEARANCE: f32 = 14.0;
+const ARRIVE_DISTANCE: f32 = 2.0;
+const CATCH_RANGE: f32 = 6.0;
+/// An object this close to the catcher counts as held.
+const HOLD_RANGE: f32 = 2.0;
+const AIM_TIME: f32 = 0.6;
+const TURN_RATE: f32 = 8.0;
+const THROW_LOB: f32 = 0.08;
+
+const DODGE_SPEED: f32 = 10.0;
+const DODGE_HORIZON: f32 = 0.6;
+const DODGE_RADIUS: f32 = 1.5;
+const DODGE_TIME: f32 = 0.4;
+
+pub struct BotPlugin;
+
+impl Plugin for BotPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BotAssets>()
+            .add_event::<BotCommand>()
+            .add_system_set(SystemSet::on_update(MatchState::Lobby).with_system(bot_lobby_keys))
+            .add_system(bot_commands.after(bot_lobby_keys))
+            .add_system(bot_think.after(perceive).before(nav_agent_follow))
+            .add_system(bot_catch.after(bot_think));
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum BotCommand {
+    Spawn,
+    RemoveAll,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum BotState {
+    Roam { destination: Vec3 },
+    Investigate { position: Vec3 },
+    Fetch,
+    Attack { target: Entity, aim: f32 },
+}
+
+#[derive(Debug, Component)]
+pub struct Bot {
+    pub peer: PeerId,
+    pub state: BotState,
+    pub speed: f32,
+    pub max_catch_speed: f32,
+    pub throw_speed: f32,
+    pub head: Entity,
+    pub catcher: Entity,
+    catching: bool,
+    was_catching: bool,
+    dodge: Option<(Vec3, Timer)>,
+}
+
+impl Bot {
+    fn new(peer: PeerId, head: Entity, catcher: Entity) -> Self {
+        let player = Player::default();
+        Self {
+            peer,
+            state: BotState::Roam {
+                destination: random_destination(),
+            },
+            speed: player.speed,
+            max_catch_speed: player.max_catch_speed,
+            throw_speed: player.throw_speed,
+            head,
+            catcher,
+            catching: false,
+            was_catching: false,
+            dodge: None,
+        }
+    }
+}
+
+/// Pitches the view of a bot, like the camera of a player.
+#[derive(Debug, Default, Component)]
+pub struct BotHead;
+
+#[derive(Debug, Default, Component)]
+pub struct BotCatcher;
+
+pub struct BotAssets {
+    mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+}
+
+impl FromWorld for BotAssets {
+    fn from_world(world: &mut World) -> Self {
+        let mesh = world.resource_mut::<Assets<Mesh>>().add(
+            shape::Capsule {
+                radius: 0.5,
+                depth: 1.0,
+                ..default()
+            }
+            .into(),
+        );
+        let material = world
+            .resource_mut::<Assets<StandardMaterial>>()
+            .add(StandardMaterial {
+                base_color: Color::rgb(0.4, 0.8, 0.4),
+                perceptual_roughness: 0.9,
+                ..default()
+            });
+        Self { mesh, material }
+    }
+}
+
+fn random_destination() -> Vec3 {
+    let mut rng = rand::thread_rng();
+    let mut destination = Vec3::new(
+        rng.gen_range(-ROAM_EXTENT..ROAM_EXTENT),
+        0.0,
+        rng.gen_range(-ROAM_EXTENT..ROAM_EXTENT),
+    );
+    // Keep clear of the center pillar.
+    if destination.x.abs() < PILLAR_CLEARANCE && destination.z.abs() < PILLAR_CLEARANCE {
+        destination.x = PILLAR_CLEARANCE.copysign(destination.x);
+    }
+    destination
+}
+
+/// The lowest bot peer id not in `used`.
+pub fn free_bot_peer(used: &[PeerId]) -> PeerId {
+    (BOT_PEER_BASE..)
+        .find(|peer| !used.contains(peer))
+        .unwrap_or(BOT_PEER_BASE)
+}
+
+pub fn spawn_bot(
+    commands: &mut Commands,
+    assets: &BotAssets,
+    transform: Transform,
+    peer: PeerId,
+) -> Entity {
+    let mut head = None;
+    let mut catcher = None;
+
+    let bot = commands
+        .spawn_bundle(CharacterControllerBundle {
+            transform,
+            ..default()
+        })
+        .insert(PlayerCatch::default())
+        .with_children(|parent| {
+            parent.spawn_on_world_layer(PbrBundle {
+                mesh: assets.mesh.clone(),
+                material: assets.material.clone(),
+                transform: Transform::from_xyz(0.0, 1.0, 0.0),
+                ..default()
+            });
+
+            head = Some(
+                parent
+                    .spawn_bundle(SpatialBundle::default())
+                    .insert(BotHead)
+                    .with_children(|parent| {
+                        catcher = Some(
+                            parent
+                                .spawn_bundle(TransformBundle {
+                                    local: Transform::from_xyz(1.0, 1.0, -2.0),
+                                    ..default()
+                                })
+                                .insert(BotCatcher)
+                                .id(),
+                        );
+                    })
+                    .id(),
+            );
+        })
+        .id();
+
+    if let (Some(head), Some(catcher)) = (head, catcher) {
+        commands.entity(catcher).insert(CatchPoint(bot));
+        let bot_state = Bot::new(peer, head, catcher);
+        let agent = NavAgent::new(bot_state.speed);
+        commands.entity(bot).insert_bundle((
+            bot_state,
+            agent,
+            Perception::default(),
+            Awareness::default(),
+            BehaviorTree::new(bot_behavior()),
+            ActiveBehavior::default(),
+        ));
+    }
+    bot
+}
+
+fn bot_lobby_keys(
+    keys: Res<Input<KeyCode>>,
+    block: Res<InputBlock>,
+    network: Res<Network>,
+    mut commands: EventWriter<BotCommand>,
+) {
+    if !network.is_authoritative() || block.typing() {
+        return;
+    }
+    if keys.just_pressed(KeyCode::B) {
+        commands.send(BotCommand::Spawn);
+    }
+    if keys.just_pressed(KeyCode::N) {
+        commands.send(BotCommand::RemoveAll);
+    }
+}
+
+fn bot_commands(
+    mut commands: Commands,
+    assets: Res<BotAssets>,
+    network: Res<Network>,
+    mut events: EventReader<BotCommand>,
+    bots: Query<(Entity, &Bot)>,
+    spawn_points: Query<&Transform, With<SpawnPoint>>,
+) {
+    for event in events.iter() {
+        match event {
+            BotCommand::Spawn => {
+                let used: Vec<_> = bots.iter().map(|(_, bot)| bot.peer).collect();
+                let peer = free_bot_peer(&used);
+
+                let spawn_points: Vec<_> = spawn_points.iter().collect();
+                let transform = match spawn_points.len() {
+                    0 => Transform::from_translation(random_destination() + Vec3::Y * 2.0),
+                    len => *spawn_points[rand::thread_rng().gen_range(0..len)],
+                };
+                let (yaw, _, _) = transform.rotation.to_euler(EulerRot::YXZ);
+                let transform = Transform::from_translation(transform.translation)
+                    .with_rotation(Quat::from_rotation_y(yaw));
+
+                spawn_bot(&mut commands, &assets, transform, peer);
+                info!("Spawned bot {}", peer);
+            }
+            BotCommand::RemoveAll => {
+                for (entity, bot) in &bots {
+                    commands.entity(entity).despawn_recursive();
+                    network.broadcast(&ServerMessage::Leave { peer: bot.peer }, None);
+                }
+            }
+        }
+    }
+}
+
+/// Finds a fast object about to hit `position`, and returns which way to sidestep it.
+fn incoming_threat<'a>(
+    position: Vec3,
+    objects: impl Iterator<Item = (&'a GlobalTransform, &'a Velocity)>,
+) -> Option<Vec3> {
+    objects
+        .filter(|(_, velocity)| velocity.linvel.length() > DODGE_SPEED)
+        .find_map(|(transform, velocity)| {
+            let offset = position - transform.translation();
+            let speed_squared = velocity.linvel.length_squared();
+            let time = offset.dot(velocity.linvel) / speed_squared;
+            if !(0.0..DODGE_HORIZON).contains(&time) {
+                return None;
+            }
+
+            let miss = offset - velocity.linvel * time;
+            if miss.length() > DODGE_RADIUS {
+                return None;
+            }
+
+            // Step sideways, away from the projected line.
+            let side = velocity.linvel.cross(Vec3::Y).normalize_or_zero();
+            Some(if miss.dot(side) >= 0.0 { side } else { -side })
+        })
+}
+
+fn face(transform: &mut Transform, from: Vec3, to: Vec3, delta: f32) {
+    let direction = Vec3::new(to.x - from.x, 0.0, to.z - from.z);
+    if direction.length_squared() < 1e-4 {
+        return;
+    }
+    let yaw = (-direction.x).atan2(-direction.z);
+    let rotation = Quat::from_rotation_y(yaw);
+    transform.rotation = transform
+        .rotation
+        .slerp(rotation, (TURN_RATE * delta).min(1.0));
+}
+
+/// What a bot knows this frame, and what its behavior tree decided to do about it.
+pub struct BotContext {
+    pub state: BotState,
+    pub delta: Duration,
+    pub position: Vec3,
+    pub catcher: Vec3,
+    pub threat: Option<Vec3>,
+    dodge: Option<(Vec3, Timer)>,
+    pub alerted_target: Option<Entity>,
+    /// Where the target of an attack is, if it is still around.
+    pub target_position: Option<Vec3>,
+    pub suspicious_spot: Option<Vec3>,
+    pub nearest_object: Option<Vec3>,
+
+    /// Steers directly instead of walking to `destination`.
+    pub movement: Option<Vec3>,
+    pub jumping: bool,
+    pub destination: Option<Vec3>,
+    pub look_at: Option<Vec3>,
+    pub catching: bool,
+}
+
+fn bot_behavior() -> Behavior<BotContext> {
+    use Behavior::*;
+
+    Selector(
+        "bot",
+        vec![
+            // Dodging overrides whatever the bot was doing.
+            Sequence(
+                "dodge",
+                vec![
+                    Condition("threatened", |context| {
+                        context.threat.is_some() || context.dodge.is_some()
+                    }),
+                    Action("sidestep", sidestep),
+                ],
+            ),
+            Sequence(
+                "attack",
+                vec![
+                    Condition("holding", |context| {
+                        matches!(context.state, BotState::Attack { .. })
+                            && context.target_position.is_some()
+                    }),
+                    Action("aim and throw", aim_and_throw),
+                ],
+            ),
+            Sequence(
+                "fetch",
+                vec![
+                    Condition("alerted", |context| {
+                        context.alerted_target.is_some() && context.nearest_object.is_some()
+                    }),
+                    Action("grab nearest object", fetch_object),
+                ],
+            ),
+            Sequence(
+                "investigate",
+                vec![
+                    Condition("suspicious", |context| context.suspicious_spot.is_some()),
+                    Action("walk to last known", investigate),
+                ],
+            ),
+            Action("roam", roam),
+        ],
+    )
+}
+
+fn sidestep(context: &mut BotContext) -> Status {
+    if context.dodge.is_none() {
+        if let Some(side) = context.threat {
+            context.dodge = Some((side, Timer::from_seconds(DODGE_TIME, false)));
+        }
+    }
+    let (side, mut timer) = match context.dodge.take() {
+        Some(dodge) => dodge,
+        None => return Status::Failure,
+    };
+
+    context.movement = Some(side);
+    context.jumping = true;
+    if !timer.tick(context.delta).finished() {
+        context.dodge = Some((side, timer));
+    }
+    Status::Running
+}
+
+fn aim_and_throw(context: &mut BotContext) -> Status {
+    let (target, aim) = match context.state {
+        BotState::Attack { target, aim } => (target, aim),
+        _ => return Status::Failure,
+    };
+    context.look_at = context.target_position;
+
+    // Keep holding until aimed, then let go to throw.
+    let aim = aim + context.delta.as_secs_f32();
+    context.catching = aim < AIM_TIME;
+    if context.catching {
+        context.state = BotState::Attack { target, aim };
+        Status::Running
+    } else {
+        context.state = BotState::Fetch;
+        Status::Success
+    }
+}
+
+fn fetch_object(context: &mut BotContext) -> Status {
+    let (target, object) = match (context.alerted_target, context.nearest_object) {
+        (Some(target), Some(object)) => (target, object),
+        _ => return Status::Failure,
+    };
+
+    context.look_at = Some(object);
+    context.catching = object.distance(context.position) <= CATCH_RANGE;
+    if !context.catching {
+        context.destination = Some(object);
+    }
+    context.state = if object.distance(context.catcher) < HOLD_RANGE {
+        BotState::Attack { target, aim: 0.0 }
+    } else {
+        BotState::Fetch
+    };
+    Status::Running
+}
+
+fn investigate(context: &mut BotContext) -> Status {
+    let spot = match context.suspicious_spot {
+        Some(spot) => spot,
+        None => return Status::Failure,
+    };
+
+    context.state = BotState::Investigate { position: spot };
+    context.destination = Some(spot);
+    context.look_at = Some(spot);
+    context.catching = false;
+    Status::Running
+}
+
+fn roam(context: &mut BotContext) -> Status {
+    let destination = match context.state {
+        BotState::Roam { destination } => {
+            let offset = destination - context.position;
+            if Vec3::new(offset.x, 0.0, offset.z).length() < ARRIVE_DISTANCE {
+                random_destination()
+            } else {
+                destination
+            }
+        }
+        _ => random_destination(),
+    };
+
+    context.state = BotState::Roam { destination };
+    context.destination = Some(destination);
+    context.look_at = Some(destination);
+    context.catching = false;
+    Status::Running
+}
+
+#[allow(clippy::type_complexity)]
+fn bot_think(
+    time: Res<Time>,
+    network: Res<Network>,
+    index: Res<SpatialIndex<CatchObject>>,
+    mut bots: Query<(
+        &mut Bot,
+        &BehaviorTree<BotContext>,
+        &mut ActiveBehavior,
+        &mut NavAgent,
+        &Awareness,
+        &mut ControllerInput,
+        &mut Transform,
+        &GlobalTransform,
+    )>,
+    mut heads: Query<&mut Transform, (With<BotHead>, Without<Bot>)>,
+    catchers: Query<&GlobalTransform, With<BotCatcher>>,
+    targets: Query<&GlobalTransform, Or<(With<Player>, With<RemotePlayer>)>>,
+    objects: Query<(&GlobalTransform, &Velocity), With<CatchObject>>,
+) {
+    if !network.is_authoritative() {
+        return;
+    }
+    let delta = time.delta_seconds();
+
+    for (mut bot, tree, mut active, mut agent, awareness, mut controller, mut transform, global) in
+        &mut bots
+    {
+        let position = global.translation();
+        let catcher = match catchers.get(bot.catcher) {
+            Ok(catcher) => catcher.translation(),
+            Err(_) => continue,
+        };
+
+        let target_position = match bot.state {
+            BotState::Attack { target, .. } => targets
+                .get(target)
+                .ok()
+                .map(|transform| transform.translation()),
+            _ => None,
+        };
+        let nearest_object = index
+            .nearest(position, f32::INFINITY)
+            .map(|entry| entry.position);
+
+        let mut context = BotContext {
+            state: bot.state,
+            delta: time.delta(),
+            position,
+            catcher,
+            threat: incoming_threat(position, objects.iter()),
+            dodge: bot.dodge.take(),
+            alerted_target: awareness
+                .target
+                .filter(|target| awareness.is_alerted() && targets.get(*target).is_ok()),
+            target_position,
+            suspicious_spot: awareness.last_known.filter(|_| awareness.is_suspicious()),
+            nearest_object,
+            movement: None,
+            jumping: false,
+            destination: None,
+            look_at: None,
+            catching: bot.catching,
+        };
+        tree.tick(&mut context, &mut active);
+
+        bot.state = context.state;
+        bot.dodge = context.dodge;
+        bot.catching = context.catching;
+        controller.jumping = context.jumping;
+
+        if let Some(look_at) = context.look_at {
+            face(&mut transform, position, look_at, delta);
+
+            if let Ok(mut head) = heads.get_mut(bot.head) {
+                let offset = look_at - position;
+                let distance = Vec2::new(offset.x, offset.z).length();
+                let pitch = offset.y.atan2(distance) + THROW_LOB;
+                head.rotation = Quat::from_rotation_x(pitch);
+            }
+        }
+
+        // The navigation agent steers while there is somewhere to go.
+        match context.movement {
+            Some(movement) => {
+                agent.go_to(None);
+                controller.movement = bot.speed * movement;
+            }
+            None => {
+                agent.go_to(context.destination);
+                if context.destination.is_none() {
+                    controller.movement = Vec3::ZERO;
+                }
+            }
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn bot_catch(
+    tuning: Res<Tuning>,
+    mut bots: Query<(Entity, &mut Bot, &mut PlayerCatch)>,
+    catchers: Query<&GlobalTransform, With<BotCatcher>>,
+    mut objects: Query<
+        (
+            Entity,
+            &mut ExternalImpulse,
+            &Velocity,
+            &ReadMassProperties,
+            &GlobalTransform,
+            Option<&CatchClass>,
+            Option<&Held>,
+        ),
+        With<CatchObject>,
+    >,
+) {
+    for (holder, mut bot, mut catch) in &mut bots {
+        let catcher = match catchers.get(bot.catcher) {
+            Ok(catcher) => *catcher,
+            Err(_) => continue,
+        };
+
+        let released = bot.was_catching && !bot.catching;
+        if bot.catching || released {
+            catch.target = catch_closest(
+                bot.catching,
+                released,
+                bot.max_catch_speed,
+                bot.throw_speed,
+                f32::INFINITY,
+                None,
+                CatchClass::Medium,
+                holder,
+                &catcher,
+                objects.iter_mut(),
+                &tuning,
+            );
+        } else {
+            catch.target = None;
+        }
+        bot.was_catching = bot.catching;
+    }
+}
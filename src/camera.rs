@@ -0,0 +1,159 @@
+//! How the player camera and what it carries trail their input.
+//!
+//! Smoothing is exponential in time rather than a fixed blend per frame, so it feels the same at
+//! 30 frames per second as at 240. [`CameraSettings`] holds the time constants: the time it takes to
+//! cover about two thirds of the remaining distance.
+//!
+//! It also holds the field of view of every camera. Holding Zoom narrows the player's view, and
+//! the path tracer's with it, to [`CameraSettings::zoom_fov`], and slows looking around to match.
+
+use crate::{
+    fog::FogCamera, hud::pip::PipCamera, surveillance::FeedCamera, Action, Player, PlayerCamera,
+};
+use bevy::{prelude::*, render::camera::Projection};
+use bevy_inspector_egui::Inspectable;
+use leafwing_input_manager::prelude::*;
+
+/// Frame rate the per-frame blends were first tuned at.
+pub const REFERENCE_RATE: f32 = 60.0;
+
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CameraSettings>()
+            .init_resource::<CameraZoom>()
+            .add_system(update_zoom)
+            .add_system(apply_fov.after(update_zoom));
+    }
+}
+
+#[derive(Debug, Clone, Inspectable)]
+pub struct CameraSettings {
+    /// Time constant of the view following the mouse or stick, in seconds; zero turns it off.
+    #[inspectable(min = 0.0, max = 0.2)]
+    pub look_smoothing: f32,
+    /// Time constant of a held object's anchor following the catch point, in seconds.
+    #[inspectable(min = 0.0, max = 0.5)]
+    pub hold_smoothing: f32,
+    /// Vertical field of view of the player camera, in degrees.
+    #[inspectable(min = 30.0, max = 110.0)]
+    pub fov: f32,
+    /// Field of view of the player camera while zoomed in, in degrees.
+    #[inspectable(min = 10.0, max = 60.0)]
+    pub zoom_fov: f32,
+    /// Time constant of zooming in and out, in seconds.
+    #[inspectable(min = 0.0, max = 0.5)]
+    pub zoom_smoothing: f32,
+    /// Look sensitivity while zoomed in, relative to the usual one.
+    #[inspectable(min = 0.1, max = 1.0)]
+    pub zoom_sensitivity: f32,
+    /// Field of view of security cameras, in degrees.
+    #[inspectable(min = 30.0, max = 110.0)]
+    pub feed_fov: f32,
+    /// Field of view of the goal replay, in degrees.
+    #[inspectable(min = 30.0, max = 110.0)]
+    pub replay_fov: f32,
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        Self {
+            look_smoothing: 0.02,
+            hold_smoothing: 0.05,
+            fov: 45.0,
+            zoom_fov: 20.0,
+            zoom_smoothing: 0.08,
+            zoom_sensitivity: 0.4,
+            feed_fov: 70.0,
+            replay_fov: 45.0,
+        }
+    }
+}
+
+impl CameraSettings {
+    /// Field of view of the player camera `zoom` of the way in, in degrees.
+    pub fn player_fov(&self, zoom: f32) -> f32 {
+        self.fov + (self.zoom_fov - self.fov) * zoom
+    }
+
+    /// Multiplier of look sensitivity `zoom` of the way in.
+    pub fn sensitivity_scale(&self, zoom: f32) -> f32 {
+        1.0 + (self.zoom_sensitivity - 1.0) * zoom
+    }
+}
+
+/// How far the player camera is zoomed in, from 0 to 1.
+#[derive(Debug, Default)]
+pub struct CameraZoom(pub f32);
+
+fn update_zoom(
+    time: Res<Time>,
+    settings: Res<CameraSettings>,
+    mut zoom: ResMut<CameraZoom>,
+    players: Query<&ActionState<Action>, With<Player>>,
+) {
+    let target = match players.get_single() {
+        Ok(action_state) if action_state.pressed(Action::Zoom) => 1.0,
+        _ => 0.0,
+    };
+    let blend = smoothing(settings.zoom_smoothing, time.delta_seconds());
+    let value = zoom.0 + (target - zoom.0) * blend;
+    // Settle exactly, so the projections stop changing.
+    let value = if (target - value).abs() < 0.001 {
+        target
+    } else {
+        value
+    };
+    if zoom.0 != value {
+        zoom.0 = value;
+    }
+}
+
+/// Keeps the projection of every camera at its field of view. The fog camera draws the depth of
+/// the player's view, so it zooms along.
+#[allow(clippy::type_complexity)]
+fn apply_fov(
+    settings: Res<CameraSettings>,
+    zoom: Res<CameraZoom>,
+    mut cameras: Query<(
+        &mut Projection,
+        Option<&PlayerCamera>,
+        Option<&FogCamera>,
+        Option<&FeedCamera>,
+        Option<&PipCamera>,
+    )>,
+) {
+    for (mut projection, player, fog, feed, pip) in &mut cameras {
+        let fov = if player.is_some() || fog.is_some() {
+            settings.player_fov(zoom.0)
+        } else if feed.is_some() {
+            settings.feed_fov
+        } else if pip.is_some() {
+            settings.replay_fov
+        } else {
+            continue;
+        };
+        // Only touched when off, since cameras recompute their matrices on every change.
+        let fov = fov.to_radians();
+        if matches!(&*projection, Projection::Perspective(perspective) if perspective.fov != fov) {
+            if let Projection::Perspective(perspective) = &mut *projection {
+                perspective.fov = fov;
+            }
+        }
+    }
+}
+
+/// Share of the remaining distance to cover in a frame `delta` seconds long.
+pub fn smoothing(time_constant: f32, delta: f32) -> f32 {
+    if time_constant <= 0.0 {
+        1.0
+    } else {
+        1.0 - (-delta / time_constant).exp()
+    }
+}
+
+/// `blend`, tuned as a share per frame at [`REFERENCE_RATE`], for a frame `delta` seconds long.
+pub fn per_frame(blend: f32, delta: f32) -> f32 {
+    1.0 - (1.0 - blend.clamp(0.0, 1.0)).powf(delta * REFERENCE_RATE)
+}
@@ -0,0 +1,209 @@
+//! Items carried in the hand, apart from catching.
+//!
+//! Catching pulls physics objects around; keys and batteries are [`CarryItem`]s instead, which
+//! interacting with puts into the player's [`CarrySlot`]. A carried item has no collider and rides
+//! on the camera on [`layers::VIEW_MODEL`], so it never clips into walls or shows up in mirrors.
+//! Drop puts it back on the floor in front of the player. Interacting with a [`Receptacle`] that
+//! accepts the carried item puts it in there for good and sends an [`ItemInserted`].
+
+use crate::{
+    interact::{InteractEvent, Interactable},
+    layers, Action, Player, PlayerCamera,
+};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use leafwing_input_manager::prelude::*;
+use serde::Deserialize;
+
+/// Edge length of an item, in meters.
+pub const ITEM_SIZE: f32 = 0.3;
+/// Where the carried item sits, relative to the camera.
+const HAND_OFFSET: Vec3 = Vec3::new(0.35, -0.3, -0.6);
+/// How far in front of the player a dropped item lands, in meters.
+const DROP_DISTANCE: f32 = 1.0;
+/// Farthest a dropped item falls to find the floor, in meters.
+const DROP_FALL: f32 = 10.0;
+
+pub struct CarryPlugin;
+
+impl Plugin for CarryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ItemInserted>()
+            .add_system(add_carry_slots)
+            .add_system(use_carry_slots)
+            .add_system(drop_items.after(use_carry_slots));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum ItemKind {
+    Key,
+    Battery,
+}
+
+impl ItemKind {
+    /// Name of its material in the material library.
+    pub fn material(self) -> &'static str {
+        match self {
+            ItemKind::Key => "key",
+            ItemKind::Battery => "battery",
+        }
+    }
+}
+
+/// Something the player can pick up into the hand.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct CarryItem(pub ItemKind);
+
+/// What the player has in the hand.
+#[derive(Debug, Default, Component)]
+pub struct CarrySlot {
+    pub item: Option<Entity>,
+}
+
+/// Takes one item of the kind it `accepts`, which stays in it.
+#[derive(Debug, Component)]
+pub struct Receptacle {
+    pub accepts: ItemKind,
+    pub item: Option<Entity>,
+}
+
+impl Receptacle {
+    pub fn new(accepts: ItemKind) -> Self {
+        Self {
+            accepts,
+            item: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ItemInserted {
+    pub receptacle: Entity,
+    pub item: Entity,
+}
+
+/// The collider of a lying item, which the interact ray finds and the player walks through.
+pub fn item_collider() -> impl Bundle {
+    let half = 0.5 * ITEM_SIZE;
+    (Collider::cuboid(half, half, half), Sensor)
+}
+
+fn add_carry_slots(mut commands: Commands, players: Query<Entity, Added<Player>>) {
+    for entity in &players {
+        commands.entity(entity).insert(CarrySlot::default());
+    }
+}
+
+/// Leaves `item` lying at `transform`, where it can be picked up again.
+fn put_down(commands: &mut Commands, camera: Entity, item: Entity, transform: Transform) {
+    commands.entity(camera).remove_children(&[item]);
+    commands
+        .entity(item)
+        .insert_bundle(item_collider())
+        .insert_bundle((
+            transform,
+            layers::WORLD,
+            Interactable::new("interact-pickup"),
+        ));
+}
+
+/// Picks up items, swapping with the one in hand, and puts items into receptacles.
+fn use_carry_slots(
+    mut commands: Commands,
+    mut events: EventReader<InteractEvent>,
+    mut inserted: EventWriter<ItemInserted>,
+    mut slots: Query<&mut CarrySlot>,
+    cameras: Query<Entity, With<PlayerCamera>>,
+    items: Query<(&CarryItem, &Transform)>,
+    mut receptacles: Query<&mut Receptacle>,
+) {
+    let camera = match cameras.get_single() {
+        Ok(camera) => camera,
+        Err(_) => return,
+    };
+    for event in events.iter() {
+        let mut slot = match slots.get_mut(event.player) {
+            Ok(slot) => slot,
+            Err(_) => continue,
+        };
+
+        if let Ok((_, transform)) = items.get(event.target) {
+            if let Some(held) = slot.item {
+                put_down(&mut commands, camera, held, *transform);
+            }
+            commands
+                .entity(event.target)
+                .remove_bundle::<(Collider, Sensor, Interactable)>()
+                .insert_bundle((Transform::from_translation(HAND_OFFSET), layers::VIEW_MODEL));
+            commands.entity(camera).add_child(event.target);
+            slot.item = Some(event.target);
+            continue;
+        }
+
+        let (mut receptacle, item) = match (receptacles.get_mut(event.target), slot.item) {
+            (Ok(receptacle), Some(item)) => (receptacle, item),
+            _ => continue,
+        };
+        let fits = items
+            .get(item)
+            .map_or(false, |(kind, _)| kind.0 == receptacle.accepts);
+        if receptacle.item.is_some() || !fits {
+            continue;
+        }
+        commands.entity(camera).remove_children(&[item]);
+        commands
+            .entity(item)
+            .insert_bundle((Transform::identity(), layers::WORLD));
+        commands
+            .entity(event.target)
+            .remove::<Interactable>()
+            .add_child(item);
+        receptacle.item = Some(item);
+        slot.item = None;
+        inserted.send(ItemInserted {
+            receptacle: event.target,
+            item,
+        });
+    }
+}
+
+fn drop_items(
+    mut commands: Commands,
+    rapier_context: Res<RapierContext>,
+    mut players: Query<(Entity, &ActionState<Action>, &mut CarrySlot), With<Player>>,
+    cameras: Query<(Entity, &GlobalTransform), With<PlayerCamera>>,
+) {
+    let (player, action_state, mut slot) = match players.get_single_mut() {
+        Ok(player) => player,
+        Err(_) => return,
+    };
+    let (camera, view) = match cameras.get_single() {
+        Ok(camera) => camera,
+        Err(_) => return,
+    };
+    if !action_state.just_pressed(Action::Drop) {
+        return;
+    }
+    let item = match slot.item.take() {
+        Some(item) => item,
+        None => return,
+    };
+
+    let mut forward = view.forward();
+    forward.y = 0.0;
+    let above = view.translation() + DROP_DISTANCE * forward.normalize_or_zero();
+    let filter = QueryFilter::default()
+        .exclude_rigid_body(player)
+        .exclude_sensors();
+    let position = match rapier_context.cast_ray(above, Vec3::NEG_Y, DROP_FALL, true, filter) {
+        Some((_, toi)) => above + (toi - 0.5 * ITEM_SIZE) * Vec3::NEG_Y,
+        None => above,
+    };
+    put_down(
+        &mut commands,
+        camera,
+        item,
+        Transform::from_translation(position),
+    );
+}
@@ -0,0 +1,158 @@
+//! How hard an object is to catch.
+//!
+//! A [`CatchClass`] on a catch object scales how fast it is pulled in, how firmly it is held and
+//! how far it is thrown; objects without one are [`CatchClass::Medium`]. A catcher only lifts up
+//! to its own class, so heavy objects stay put until the player picks up a [`CatcherUpgrade`].
+//!
+//! The class also picks the collision detection, solver iterations and bounciness of the object,
+//! from the [`ClassPhysics`] in the tuning file.
+
+use crate::{
+    hud::{overlay::FlashEvent, popup::PopupEvent},
+    locale::Locale,
+    tuning::{ClassPhysics, Tuning},
+    CatchObject, Player,
+};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use serde::Deserialize;
+
+pub struct CatchClassPlugin;
+
+impl Plugin for CatchClassPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<CatchClass>()
+            .add_system(apply_class_physics)
+            .add_system(collect_upgrades);
+    }
+}
+
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Deserialize,
+    Component,
+    Reflect,
+)]
+#[reflect_value(Component)]
+pub enum CatchClass {
+    Light,
+    #[default]
+    Medium,
+    Heavy,
+}
+
+impl CatchClass {
+    /// Scale of the fastest pull.
+    pub fn pull(&self) -> f32 {
+        match self {
+            CatchClass::Light => 1.5,
+            CatchClass::Medium => 1.0,
+            CatchClass::Heavy => 0.5,
+        }
+    }
+
+    /// How much of the pull correction applies each frame; heavy objects sway behind the catcher.
+    pub fn stability(&self) -> f32 {
+        match self {
+            CatchClass::Light => 1.0,
+            CatchClass::Medium => 0.8,
+            CatchClass::Heavy => 0.35,
+        }
+    }
+
+    /// Scale of the throw speed.
+    pub fn throw(&self) -> f32 {
+        match self {
+            CatchClass::Light => 1.3,
+            CatchClass::Medium => 1.0,
+            CatchClass::Heavy => 0.6,
+        }
+    }
+}
+
+impl ClassPhysics {
+    fn apply(&self, commands: &mut Commands, entity: Entity) {
+        commands.entity(entity).insert_bundle((
+            Ccd { enabled: self.ccd },
+            Restitution::coefficient(self.restitution),
+        ));
+    }
+}
+
+/// Gives new catch objects, and all of them when the tuning changes, the physics of their class.
+#[allow(clippy::type_complexity)]
+fn apply_class_physics(
+    mut commands: Commands,
+    tuning: Res<Tuning>,
+    mut rapier_context: ResMut<RapierContext>,
+    changed: Query<
+        (Entity, Option<&CatchClass>),
+        (
+            With<CatchObject>,
+            Or<(Added<CatchObject>, Changed<CatchClass>)>,
+        ),
+    >,
+    objects: Query<(Entity, Option<&CatchClass>), With<CatchObject>>,
+) {
+    if tuning.is_changed() {
+        let iterations = [CatchClass::Light, CatchClass::Medium, CatchClass::Heavy]
+            .into_iter()
+            .map(|class| tuning.class_physics(class).solver_iterations)
+            .max()
+            .unwrap_or(4);
+        rapier_context
+            .integration_parameters
+            .max_velocity_iterations = iterations;
+        for (entity, class) in &objects {
+            let class = class.copied().unwrap_or_default();
+            tuning.class_physics(class).apply(&mut commands, entity);
+        }
+    } else {
+        for (entity, class) in &changed {
+            let class = class.copied().unwrap_or_default();
+            tuning.class_physics(class).apply(&mut commands, entity);
+        }
+    }
+}
+
+/// A pickup that lets the player catch heavy objects.
+#[derive(Default, Component)]
+pub struct CatcherUpgrade;
+
+fn collect_upgrades(
+    mut commands: Commands,
+    locale: Res<Locale>,
+    mut collisions: EventReader<CollisionEvent>,
+    mut players: Query<&mut Player>,
+    upgrades: Query<&GlobalTransform, With<CatcherUpgrade>>,
+    mut popups: EventWriter<PopupEvent>,
+    mut flashes: EventWriter<FlashEvent>,
+) {
+    for event in collisions.iter() {
+        let (a, b) = match event {
+            CollisionEvent::Started(a, b, _) => (*a, *b),
+            _ => continue,
+        };
+        let (player, upgrade) = if players.contains(a) { (a, b) } else { (b, a) };
+        let (mut player, transform) = match (players.get_mut(player), upgrades.get(upgrade)) {
+            (Ok(player), Ok(transform)) => (player, transform),
+            _ => continue,
+        };
+
+        player.max_class = CatchClass::Heavy;
+        popups.send(PopupEvent::new(
+            locale.get("popup-upgrade"),
+            transform.translation(),
+        ));
+        flashes.send(FlashEvent);
+        commands.entity(upgrade).despawn_recursive();
+    }
+}
@@ -0,0 +1,104 @@
+//! Command-line options, for launching testers and CI straight into a setup.
+//!
+//! [`Args`] is parsed before the app is built, since some options pick the window and render
+//! plugins, and stays around as a resource. The rest are sent as the matching commands on startup,
+//! as if their keys had been pressed.
+
+use crate::{
+    level::{LevelCommand, LevelRegistry},
+    net::{NetCommand, DEFAULT_PORT},
+    replay::{ReplayCommand, WorldSeed},
+};
+use bevy::prelude::*;
+use clap::Parser;
+use std::{net::SocketAddr, path::PathBuf};
+
+pub struct CliPlugin;
+
+impl Plugin for CliPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(apply_args);
+    }
+}
+
+#[derive(Debug, Clone, Parser)]
+#[command(version, about)]
+pub struct Args {
+    /// Level to start in, by its name in the level registry.
+    #[arg(long)]
+    pub level: Option<String>,
+    /// Runs in a window instead of fullscreen.
+    #[arg(long)]
+    pub windowed: bool,
+    /// Window pixels per render target pixel.
+    #[arg(long, default_value_t = 4, value_parser = clap::value_parser!(u32).range(1..))]
+    pub render_scale: u32,
+    /// Renders with the plain forward pipeline instead of path tracing.
+    #[arg(long)]
+    pub no_hikari: bool,
+    /// Runs the simulation without a window or renderer, at a fixed tick.
+    #[arg(long)]
+    pub headless: bool,
+    /// Hosts a session on the default port.
+    #[arg(long, conflicts_with = "connect")]
+    pub server: bool,
+    /// Joins the session at this address.
+    #[arg(long, value_name = "ADDR")]
+    pub connect: Option<SocketAddr>,
+    /// Plays back a replay file.
+    #[arg(long, value_name = "FILE")]
+    pub replay: Option<PathBuf>,
+    /// Splits players into two teams.
+    #[arg(long)]
+    pub teams: bool,
+    /// Reloads assets and shaders when their files change, even in release builds.
+    #[arg(long)]
+    pub hot_reload: bool,
+    /// Log filter, like `info,catch=debug`; overrides `RUST_LOG`.
+    #[arg(long, value_name = "FILTER")]
+    pub log: Option<String>,
+    /// Shows the touch controls without waiting for the first touch.
+    #[arg(long)]
+    pub touch: bool,
+}
+
+impl Args {
+    /// Whether to render with path tracing, which browsers can't run.
+    pub fn hikari(&self) -> bool {
+        !self.no_hikari && !self.headless && cfg!(not(target_arch = "wasm32"))
+    }
+}
+
+fn apply_args(
+    args: Res<Args>,
+    registry: Res<LevelRegistry>,
+    seed: Res<WorldSeed>,
+    mut level_commands: EventWriter<LevelCommand>,
+    mut net_commands: EventWriter<NetCommand>,
+    mut replay_commands: EventWriter<ReplayCommand>,
+) {
+    if let Some(name) = &args.level {
+        match registry
+            .levels
+            .iter()
+            .position(|level| level.name.eq_ignore_ascii_case(name))
+        {
+            Some(index) => level_commands.send(LevelCommand::Load {
+                index,
+                seed: seed.0,
+            }),
+            None => warn!("No level called {} in the registry", name),
+        }
+    }
+
+    if args.server {
+        net_commands.send(NetCommand::Host(DEFAULT_PORT));
+    }
+    if let Some(addr) = args.connect {
+        net_commands.send(NetCommand::Connect(addr));
+    }
+
+    if let Some(path) = &args.replay {
+        replay_commands.send(ReplayCommand::Play(path.clone()));
+    }
+}
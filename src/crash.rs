@@ -0,0 +1,225 @@
+//! What happens when the game panics.
+//!
+//! A panic hook can't reach the world, so [`record_crash_context`] keeps a [`CrashContext`] of
+//! what would help with a bug report: the level, the match state, the GPU and the last few
+//! [`GameEvent`]s. On a panic it goes into a log under [`CRASH_DIR`], and a dialog offers to start
+//! the game again with path tracing off and a smaller window, in case the renderer was to blame.
+
+use crate::{
+    cli::Args,
+    game_event::GameEvent,
+    gpu::GpuInfo,
+    level::{CurrentLevel, LevelRegistry},
+    locale::Locale,
+    match_flow::MatchState,
+    replay::unix_time,
+};
+use bevy::prelude::*;
+use std::{
+    collections::VecDeque,
+    fmt::Write as _,
+    fs,
+    panic::{self, PanicInfo},
+    path::Path,
+    sync::{Mutex, TryLockError},
+};
+
+pub const CRASH_DIR: &str = "crashes";
+/// Game events kept for the log.
+const EVENT_HISTORY: usize = 32;
+/// Seconds between refreshes of the rest of the context.
+const REFRESH_INTERVAL: f32 = 1.0;
+/// Options that restart the game with the safest rendering.
+const SAFE_ARGS: [&str; 4] = ["--no-hikari", "--windowed", "--render-scale", "2"];
+
+static CRASH_CONTEXT: Mutex<CrashContext> = Mutex::new(CrashContext::new());
+
+pub struct CrashPlugin;
+
+impl Plugin for CrashPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(record_crash_context);
+    }
+}
+
+/// Writes crash logs on a panic, and offers the restart unless `args` ask for a headless run.
+///
+/// The hook is global to the process, so this is for `main` to call once, not for every app built.
+pub fn install_panic_hook(args: &Args) {
+    // Nobody is around to answer a dialog on a headless run, or one in the browser.
+    let prompt = cfg!(not(target_arch = "wasm32")) && !args.headless;
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        let path = write_crash_log(info);
+        if prompt {
+            offer_restart(path.as_deref());
+        }
+    }));
+}
+
+/// What the last frames were about, as text ready for the log.
+struct CrashContext {
+    level: String,
+    match_state: String,
+    entities: usize,
+    gpu: String,
+    events: VecDeque<String>,
+    /// Title and question of the restart dialog, in the player's language.
+    prompt: Option<(String, String)>,
+}
+
+impl CrashContext {
+    const fn new() -> Self {
+        Self {
+            level: String::new(),
+            match_state: String::new(),
+            entities: 0,
+            gpu: String::new(),
+            events: VecDeque::new(),
+            prompt: None,
+        }
+    }
+
+    fn report(&self, info: &PanicInfo) -> String {
+        let mut report = String::new();
+        let _ = writeln!(report, "{}", info);
+        let _ = writeln!(report);
+        let _ = writeln!(report, "Version: {}", env!("CARGO_PKG_VERSION"));
+        let _ = writeln!(report, "GPU: {}", self.gpu);
+        let _ = writeln!(report, "Level: {}", self.level);
+        let _ = writeln!(report, "Match: {}", self.match_state);
+        let _ = writeln!(report, "Entities: {}", self.entities);
+        let _ = writeln!(report);
+        let _ = writeln!(report, "Recent events, oldest first:");
+        for event in &self.events {
+            let _ = writeln!(report, "  {}", event);
+        }
+        report
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn record_crash_context(
+    time: Res<Time>,
+    locale: Res<Locale>,
+    gpu: Res<GpuInfo>,
+    registry: Res<LevelRegistry>,
+    current: Res<CurrentLevel>,
+    state: Res<State<MatchState>>,
+    mut since_refresh: Local<f32>,
+    mut events: EventReader<GameEvent>,
+    entities: Query<()>,
+) {
+    let mut context = match CRASH_CONTEXT.lock() {
+        Ok(context) => context,
+        Err(_) => return,
+    };
+    for event in events.iter() {
+        if context.events.len() == EVENT_HISTORY {
+            context.events.pop_front();
+        }
+        let line = format!("{:.2} {:?}", time.seconds_since_startup(), event);
+        context.events.push_back(line);
+    }
+
+    // The first frame fills everything in right away.
+    *since_refresh += time.delta_seconds();
+    if *since_refresh < REFRESH_INTERVAL && context.prompt.is_some() {
+        return;
+    }
+    *since_refresh = 0.0;
+
+    context.level = registry
+        .levels
+        .get(current.index)
+        .map_or_else(|| "none".into(), |level| level.name.clone());
+    if let Some(seed) = current.seed {
+        let _ = write!(context.level, " (seed {})", seed);
+    }
+    context.match_state = format!("{:?}", state.current());
+    context.entities = entities.iter().count();
+    context.gpu = format!("{} ({}, {})", gpu.name, gpu.backend, gpu.device_type);
+    context.prompt = Some((locale.get("crash-title"), locale.get("crash-restart")));
+}
+
+/// Runs `f` on the context as it was when the panic hit. A panic while it was being updated
+/// leaves it poisoned, but still readable, or still locked on this thread, so left out.
+fn with_context<T>(f: impl FnOnce(&CrashContext) -> T) -> T {
+    match CRASH_CONTEXT.try_lock() {
+        Ok(context) => f(&context),
+        Err(TryLockError::Poisoned(poisoned)) => f(&poisoned.into_inner()),
+        Err(TryLockError::WouldBlock) => f(&CrashContext::new()),
+    }
+}
+
+/// Writes the log, returning where it went.
+fn write_crash_log(info: &PanicInfo) -> Option<String> {
+    let report = with_context(|context| context.report(info));
+    let path = Path::new(CRASH_DIR).join(format!("{}.log", unix_time().as_secs()));
+    let written = fs::create_dir_all(CRASH_DIR).and_then(|_| fs::write(&path, report));
+    match written {
+        Ok(()) => Some(path.display().to_string()),
+        Err(err) => {
+            error!("Failed to write crash log {}: {}", path.display(), err);
+            None
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn offer_restart(log: Option<&str>) {
+    let (title, question) = with_context(|context| context.prompt.clone()).unwrap_or_else(|| {
+        (
+            "Pumpkin Jam crashed".into(),
+            "Restart with safe graphics settings?".into(),
+        )
+    });
+    let description = match log {
+        Some(log) => format!("{}\n\n{}", question, log),
+        None => question,
+    };
+    let restart = rfd::MessageDialog::new()
+        .set_level(rfd::MessageLevel::Error)
+        .set_title(&title)
+        .set_description(&description)
+        .set_buttons(rfd::MessageButtons::YesNo)
+        .show();
+    if !restart {
+        return;
+    }
+
+    let exe = match std::env::current_exe() {
+        Ok(exe) => exe,
+        Err(_) => return,
+    };
+    if let Err(err) = std::process::Command::new(exe).args(safe_args()).spawn() {
+        error!("Failed to restart: {}", err);
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn offer_restart(_log: Option<&str>) {}
+
+/// The options the game was started with, with the rendering ones swapped for [`SAFE_ARGS`].
+#[cfg(not(target_arch = "wasm32"))]
+fn safe_args() -> Vec<String> {
+    let mut args = vec![];
+    let mut skip_value = false;
+    for arg in std::env::args().skip(1) {
+        if skip_value {
+            skip_value = false;
+            continue;
+        }
+        if arg == "--render-scale" {
+            skip_value = true;
+            continue;
+        }
+        if arg == "--no-hikari" || arg == "--windowed" || arg.starts_with("--render-scale=") {
+            continue;
+        }
+        args.push(arg);
+    }
+    args.extend(SAFE_ARGS.iter().map(|arg| arg.to_string()));
+    args
+}
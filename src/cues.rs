@@ -0,0 +1,166 @@
+//! Sounds that tell the player something, and where it came from.
+//!
+//! Gameplay sends an [`AudioCue`] for things worth hearing: an object thrown at the player, a bot
+//! coming close, a goal. The cue plays `assets/sounds/<name>.ogg` if there is one, and the HUD
+//! shows the same cues as edge indicators for players who can't hear them.
+
+use crate::{bots::Bot, level::trigger::GoalReached, CatchObject, Player, PlayerCatch};
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+use bevy_rapier3d::prelude::*;
+use std::path::Path;
+
+pub const SOUND_FOLDER: &str = "sounds";
+
+/// An object heading for the player this fast, and this close, is incoming.
+const INCOMING_SPEED: f32 = 8.0;
+const INCOMING_RANGE: f32 = 15.0;
+/// Bots closer than this are nearby.
+const NEARBY_RANGE: f32 = 8.0;
+
+pub struct CuePlugin;
+
+impl Plugin for CuePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CueSounds>()
+            .add_event::<AudioCue>()
+            .add_system(incoming_cues)
+            .add_system(nearby_cues)
+            .add_system(goal_cues)
+            .add_system(
+                play_cue_sounds
+                    .after(incoming_cues)
+                    .after(nearby_cues)
+                    .after(goal_cues),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CueKind {
+    Incoming,
+    EnemyNearby,
+    GoalScored,
+}
+
+impl CueKind {
+    pub const ALL: [CueKind; 3] = [CueKind::Incoming, CueKind::EnemyNearby, CueKind::GoalScored];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CueKind::Incoming => "incoming",
+            CueKind::EnemyNearby => "enemy_nearby",
+            CueKind::GoalScored => "goal_scored",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct AudioCue {
+    pub kind: CueKind,
+    pub position: Vec3,
+}
+
+/// Clips found in the sound folder, by cue.
+pub struct CueSounds(HashMap<CueKind, Handle<AudioSource>>);
+
+impl FromWorld for CueSounds {
+    fn from_world(world: &mut World) -> Self {
+        let asset_server = world.resource::<AssetServer>();
+        let sounds = CueKind::ALL
+            .into_iter()
+            .filter_map(|kind| Some((kind, load_sound(asset_server, kind.name())?)))
+            .collect();
+        Self(sounds)
+    }
+}
+
+/// Loads `assets/sounds/<name>.ogg`, if the game ships one.
+pub fn load_sound(asset_server: &AssetServer, name: &str) -> Option<Handle<AudioSource>> {
+    let path = format!("{}/{}.ogg", SOUND_FOLDER, name);
+    Path::new("assets")
+        .join(&path)
+        .exists()
+        .then(|| asset_server.load(&path))
+}
+
+/// Cues each object once as it flies at the player, and again only after it has slowed down.
+fn incoming_cues(
+    mut cued: Local<HashSet<Entity>>,
+    players: Query<(&GlobalTransform, &PlayerCatch), With<Player>>,
+    objects: Query<(Entity, &GlobalTransform, &Velocity), With<CatchObject>>,
+    mut cues: EventWriter<AudioCue>,
+) {
+    let (player, catch) = match players.get_single() {
+        Ok(player) => player,
+        Err(_) => return,
+    };
+    let player = player.translation();
+
+    for (entity, transform, velocity) in &objects {
+        let position = transform.translation();
+        let offset = player - position;
+        let closing = velocity.linvel.dot(offset.normalize_or_zero());
+        let incoming = closing > INCOMING_SPEED
+            && offset.length() < INCOMING_RANGE
+            && catch.target != Some(entity);
+
+        if !incoming {
+            cued.remove(&entity);
+        } else if cued.insert(entity) {
+            cues.send(AudioCue {
+                kind: CueKind::Incoming,
+                position,
+            });
+        }
+    }
+}
+
+fn nearby_cues(
+    mut nearby: Local<HashSet<Entity>>,
+    players: Query<&GlobalTransform, With<Player>>,
+    bots: Query<(Entity, &GlobalTransform), With<Bot>>,
+    mut cues: EventWriter<AudioCue>,
+) {
+    let player = match players.get_single() {
+        Ok(player) => player.translation(),
+        Err(_) => return,
+    };
+
+    for (entity, transform) in &bots {
+        let position = transform.translation();
+        if position.distance(player) > NEARBY_RANGE {
+            nearby.remove(&entity);
+        } else if nearby.insert(entity) {
+            cues.send(AudioCue {
+                kind: CueKind::EnemyNearby,
+                position,
+            });
+        }
+    }
+}
+
+fn goal_cues(
+    transforms: Query<&GlobalTransform>,
+    mut goals: EventReader<GoalReached>,
+    mut cues: EventWriter<AudioCue>,
+) {
+    for event in goals.iter() {
+        if let Ok(transform) = transforms.get(event.goal) {
+            cues.send(AudioCue {
+                kind: CueKind::GoalScored,
+                position: transform.translation(),
+            });
+        }
+    }
+}
+
+fn play_cue_sounds(audio: Res<Audio>, sounds: Res<CueSounds>, mut cues: EventReader<AudioCue>) {
+    for cue in cues.iter() {
+        if let Some(sound) = sounds.0.get(&cue.kind) {
+            audio.play(sound.clone());
+        }
+    }
+}
@@ -0,0 +1,331 @@
+//! Short scripted sequences for intros and celebrations.
+//!
+//! A [`Cutscene`] belongs to a level file and is started by name with a [`PlayCutscene`] event,
+//! usually from the level script. Camera keys are smoothed with a Catmull-Rom spline; while they
+//! play the camera leaves the player, input is off and the HUD fades out. Text cards show on the
+//! window between letterbox bars, in the HUD's pixel font scaled up to the HUD's pixels.
+
+use crate::{
+    hud::{
+        bitmap::{window_scale, BitmapAlign, BitmapText, BitmapTextBundle},
+        composite::HudEffects,
+    },
+    input_block::{BlockReason, InputBlock},
+    level::{CurrentLevel, LevelDef},
+    loading::AppState,
+    locale::Locale,
+    Player, PlayerCamera,
+};
+use bevy::{prelude::*, render::view::RenderLayers};
+use serde::Deserialize;
+
+const OVERLAY_Z: f32 = 5.0;
+const BAR_HEIGHT: f32 = 80.0;
+const CARD_FADE: f32 = 0.3;
+
+pub struct CutscenePlugin;
+
+impl Plugin for CutscenePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CutscenePlayer>()
+            .add_event::<PlayCutscene>()
+            .add_event::<CutsceneFinished>()
+            .add_system(start_cutscenes)
+            .add_system_set(
+                SystemSet::on_update(AppState::Playing)
+                    .with_system(play_cutscene.after(start_cutscenes))
+                    .with_system(update_cutscene_cards.after(play_cutscene)),
+            );
+    }
+}
+
+/// A camera position at `time` seconds into the cutscene.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct CameraKey {
+    pub time: f32,
+    pub position: [f32; 3],
+    pub look_at: [f32; 3],
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct TextCard {
+    pub start: f32,
+    pub duration: f32,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Cutscene {
+    pub name: String,
+    /// Without keys the camera stays with the player, and so does the input.
+    #[serde(default)]
+    pub keys: Vec<CameraKey>,
+    #[serde(default)]
+    pub cards: Vec<TextCard>,
+}
+
+impl Cutscene {
+    pub fn duration(&self) -> f32 {
+        let keys = self.keys.last().map_or(0.0, |key| key.time);
+        self.cards
+            .iter()
+            .map(|card| card.start + card.duration)
+            .fold(keys, f32::max)
+    }
+
+    /// Camera transform at `time`, if the cutscene moves the camera.
+    pub fn camera(&self, time: f32) -> Option<Transform> {
+        let keys = &self.keys;
+        let last = keys.len().checked_sub(1)?;
+        let index = keys
+            .iter()
+            .rposition(|key| key.time <= time)
+            .unwrap_or(0)
+            .min(last.saturating_sub(1));
+
+        let (p1, p2) = (&keys[index], &keys[(index + 1).min(last)]);
+        let p0 = &keys[index.saturating_sub(1)];
+        let p3 = &keys[(index + 2).min(last)];
+        let span = p2.time - p1.time;
+        let t = match span {
+            span if span > 0.0 => ((time - p1.time) / span).clamp(0.0, 1.0),
+            _ => 1.0,
+        };
+
+        let position = catmull_rom(
+            p0.position.into(),
+            p1.position.into(),
+            p2.position.into(),
+            p3.position.into(),
+            t,
+        );
+        let look_at = catmull_rom(
+            p0.look_at.into(),
+            p1.look_at.into(),
+            p2.look_at.into(),
+            p3.look_at.into(),
+            t,
+        );
+        Some(Transform::from_translation(position).looking_at(look_at, Vec3::Y))
+    }
+}
+
+fn catmull_rom(p0: Vec3, p1: Vec3, p2: Vec3, p3: Vec3, t: f32) -> Vec3 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    0.5 * (2.0 * p1
+        + (p2 - p0) * t
+        + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2
+        + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t3)
+}
+
+/// Starts the cutscene of the current level with this name.
+#[derive(Debug, Clone)]
+pub struct PlayCutscene(pub String);
+
+#[derive(Debug, Clone)]
+pub struct CutsceneFinished(pub String);
+
+struct Playback {
+    cutscene: Cutscene,
+    time: f32,
+    /// Local transform of the camera before the cutscene took it.
+    camera_home: Option<Transform>,
+}
+
+#[derive(Default)]
+pub struct CutscenePlayer {
+    playing: Option<Playback>,
+}
+
+impl CutscenePlayer {
+    pub fn is_playing(&self) -> bool {
+        self.playing.is_some()
+    }
+}
+
+#[derive(Component)]
+struct CutsceneOverlay;
+
+#[derive(Component)]
+struct CutsceneCard;
+
+fn start_cutscenes(
+    mut commands: Commands,
+    windows: Res<Windows>,
+    current: Res<CurrentLevel>,
+    levels: Res<Assets<LevelDef>>,
+    mut player: ResMut<CutscenePlayer>,
+    mut events: EventReader<PlayCutscene>,
+    overlays: Query<Entity, With<CutsceneOverlay>>,
+) {
+    let name = match events.iter().last() {
+        Some(PlayCutscene(name)) => name,
+        None => return,
+    };
+    let cutscene = match levels.get(&current.handle).and_then(|level| {
+        level
+            .cutscenes
+            .iter()
+            .find(|cutscene| cutscene.name == *name)
+    }) {
+        Some(cutscene) => cutscene.clone(),
+        None => {
+            warn!("No cutscene called {}", name);
+            return;
+        }
+    };
+
+    // A new cutscene cuts the running one short, but the camera still goes home.
+    let camera_home = player
+        .playing
+        .take()
+        .and_then(|playback| playback.camera_home);
+    player.playing = Some(Playback {
+        cutscene,
+        time: 0.0,
+        camera_home,
+    });
+
+    for entity in &overlays {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+    let bar = |y: f32| SpriteBundle {
+        sprite: Sprite {
+            color: Color::BLACK,
+            custom_size: Some(Vec2::new(window.width(), BAR_HEIGHT)),
+            ..default()
+        },
+        transform: Transform::from_xyz(0.0, y, 0.0),
+        ..default()
+    };
+    let edge = 0.5 * (window.height() - BAR_HEIGHT);
+    commands
+        .spawn_bundle(SpatialBundle {
+            transform: Transform::from_xyz(0.0, 0.0, OVERLAY_Z),
+            ..default()
+        })
+        .insert(CutsceneOverlay)
+        .with_children(|parent| {
+            parent.spawn_bundle(bar(edge));
+            parent.spawn_bundle(bar(-edge));
+            // Not on the HUD itself, which fades out for the cutscene.
+            let scale = window_scale(window);
+            parent
+                .spawn_bundle(BitmapTextBundle {
+                    text: BitmapText::new("", Color::WHITE, BitmapAlign::Center),
+                    spatial: SpatialBundle {
+                        transform: Transform::from_xyz(0.0, -edge, 0.1)
+                            .with_scale(Vec3::splat(scale)),
+                        ..default()
+                    },
+                })
+                .insert_bundle((RenderLayers::default(), CutsceneCard));
+        });
+}
+
+#[allow(clippy::too_many_arguments)]
+fn play_cutscene(
+    mut commands: Commands,
+    time: Res<Time>,
+    keys: Res<Input<KeyCode>>,
+    mut player: ResMut<CutscenePlayer>,
+    mut block: ResMut<InputBlock>,
+    mut hud: ResMut<HudEffects>,
+    bodies: Query<&GlobalTransform, With<Player>>,
+    mut cameras: Query<&mut Transform, With<PlayerCamera>>,
+    overlays: Query<Entity, With<CutsceneOverlay>>,
+    mut finished: EventWriter<CutsceneFinished>,
+) {
+    let playback = match &mut player.playing {
+        Some(playback) => playback,
+        None => return,
+    };
+    playback.time += time.delta_seconds();
+
+    let skipped = keys.just_pressed(KeyCode::Space) && !block.typing();
+    let done = playback.time >= playback.cutscene.duration() || skipped;
+    let shot = if done {
+        None
+    } else {
+        playback.cutscene.camera(playback.time)
+    };
+    let had_camera = playback.camera_home.is_some();
+
+    if let (Ok(body), Ok(mut camera)) = (bodies.get_single(), cameras.get_single_mut()) {
+        match shot {
+            Some(shot) => {
+                if playback.camera_home.is_none() {
+                    playback.camera_home = Some(*camera);
+                }
+                // The camera hangs off the player, so bring the shot into its space.
+                let (_, rotation, translation) = body.to_scale_rotation_translation();
+                let inverse = rotation.inverse();
+                camera.translation = inverse * (shot.translation - translation);
+                camera.rotation = inverse * shot.rotation;
+            }
+            None => {
+                if let Some(home) = playback.camera_home.take() {
+                    *camera = home;
+                }
+            }
+        }
+    }
+    let has_camera = playback.camera_home.is_some();
+    if has_camera {
+        hud.fade_to(0.0);
+    }
+    // Input and HUD come back once the camera is home, even if a later cutscene has no shots.
+    if has_camera != had_camera {
+        block.set(BlockReason::Cutscene, has_camera);
+        if !has_camera {
+            hud.fade_to(1.0);
+        }
+    }
+
+    if done {
+        finished.send(CutsceneFinished(playback.cutscene.name.clone()));
+        player.playing = None;
+        for entity in &overlays {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn update_cutscene_cards(
+    player: Res<CutscenePlayer>,
+    locale: Res<Locale>,
+    mut cards: Query<&mut BitmapText, With<CutsceneCard>>,
+) {
+    let playback = match &player.playing {
+        Some(playback) => playback,
+        None => return,
+    };
+    let card = playback
+        .cutscene
+        .cards
+        .iter()
+        .find(|card| playback.time >= card.start && playback.time < card.start + card.duration);
+
+    for mut text in &mut cards {
+        match card {
+            Some(card) => {
+                let elapsed = playback.time - card.start;
+                let remaining = card.duration - elapsed;
+                let alpha = (elapsed.min(remaining) / CARD_FADE).clamp(0.0, 1.0);
+                let value = locale.translate(&card.text);
+                if text.value != value {
+                    text.value = value;
+                }
+                text.color.set_a(alpha);
+            }
+            None if !text.value.is_empty() => text.value.clear(),
+            None => {}
+        }
+    }
+}
@@ -0,0 +1,234 @@
+//! The local player getting knocked out, and coming back.
+//!
+//! A catch object hitting the player faster than [`HURT_SPEED`] takes some of their [`Health`],
+//! which comes back after a while without hits. Running out of it, a hit faster than
+//! [`LETHAL_SPEED`] or a fall below [`KILL_HEIGHT`] makes the player [`Dead`]: the body stops
+//! moving and colliding, the catcher lets go and the controls go quiet. Throws by teammates only
+//! count with friendly fire on, see [`TeamSettings`]. After [`RESPAWN_TIME`] the player is back at
+//! their spawn point, healed. [`DeathEvent`] and [`RespawnEvent`] mark both ends, for the ragdoll
+//! and anything else that wants to show them.
+
+use crate::{
+    match_flow::{local_spawn_point, Lobby, SpawnPoint},
+    net::Network,
+    teams::{Team, TeamSettings, ThrownBy},
+    CatchObject, Player, PlayerCatch,
+};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+/// Objects faster than this, in meters per second, knock the player out.
+pub const LETHAL_SPEED: f32 = 30.0;
+/// Objects faster than this hurt the player, more the closer they get to [`LETHAL_SPEED`].
+pub const HURT_SPEED: f32 = 12.0;
+/// Health lost to a hit just under [`LETHAL_SPEED`].
+const MAX_DAMAGE: f32 = 60.0;
+const REGEN_RATE: f32 = 10.0;
+/// Seconds without hits before health comes back.
+const REGEN_DELAY: f32 = 3.0;
+/// Falling below this height knocks the player out.
+pub const KILL_HEIGHT: f32 = -50.0;
+/// Time until the player is back, in seconds.
+pub const RESPAWN_TIME: f32 = 4.0;
+
+pub struct DeathPlugin;
+
+impl Plugin for DeathPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<DeathEvent>()
+            .add_event::<RespawnEvent>()
+            .register_type::<Health>()
+            .add_system(add_health)
+            .add_system(detect_deaths)
+            .add_system(regenerate_health.after(detect_deaths))
+            .add_system(respawn.after(detect_deaths));
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct DeathEvent {
+    pub player: Entity,
+    /// The catch object that hit the player, if it wasn't a fall.
+    pub object: Option<Entity>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RespawnEvent {
+    pub player: Entity,
+}
+
+#[derive(Debug, Clone, Component)]
+pub struct Dead {
+    pub timer: Timer,
+    /// Collision groups to give back on respawn.
+    groups: CollisionGroups,
+}
+
+#[derive(Debug, Component, Reflect)]
+#[reflect(Component)]
+pub struct Health {
+    pub current: f32,
+    pub max: f32,
+    /// Seconds since the last hit.
+    rest: f32,
+}
+
+impl Default for Health {
+    fn default() -> Self {
+        Self {
+            current: 100.0,
+            max: 100.0,
+            rest: 0.0,
+        }
+    }
+}
+
+impl Health {
+    pub fn fraction(&self) -> f32 {
+        (self.current / self.max).clamp(0.0, 1.0)
+    }
+}
+
+fn add_health(mut commands: Commands, players: Query<Entity, Added<Player>>) {
+    for entity in &players {
+        commands.entity(entity).insert(Health::default());
+    }
+}
+
+/// Health a hit at `speed` takes.
+fn impact_damage(speed: f32) -> f32 {
+    if speed > LETHAL_SPEED {
+        f32::INFINITY
+    } else {
+        MAX_DAMAGE * ((speed - HURT_SPEED) / (LETHAL_SPEED - HURT_SPEED)).max(0.0)
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn detect_deaths(
+    mut commands: Commands,
+    mut collisions: EventReader<CollisionEvent>,
+    mut deaths: EventWriter<DeathEvent>,
+    teams: Res<TeamSettings>,
+    mut players: Query<
+        (
+            Entity,
+            &GlobalTransform,
+            &mut PlayerCatch,
+            Option<&mut Health>,
+            Option<&CollisionGroups>,
+            Option<&Team>,
+        ),
+        (With<Player>, Without<Dead>),
+    >,
+    objects: Query<(&Velocity, Option<&ThrownBy>), With<CatchObject>>,
+) {
+    let mut hits: Vec<(Entity, Option<Entity>, f32)> = vec![];
+    for event in collisions.iter() {
+        let (a, b) = match event {
+            CollisionEvent::Started(a, b, _) => (*a, *b),
+            _ => continue,
+        };
+        let (player, object) = if players.contains(a) { (a, b) } else { (b, a) };
+        let (velocity, thrown) = match objects.get(object) {
+            Ok(object) => object,
+            Err(_) => continue,
+        };
+        let team = match players.get(player) {
+            Ok((_, _, _, _, _, team)) => team,
+            Err(_) => continue,
+        };
+        let speed = velocity.linvel.length();
+        if speed > HURT_SPEED && teams.can_hit(thrown, team) {
+            hits.push((player, Some(object), impact_damage(speed)));
+        }
+    }
+    for (player, transform, _, _, _, _) in &players {
+        if transform.translation().y < KILL_HEIGHT {
+            hits.push((player, None, f32::INFINITY));
+        }
+    }
+
+    for (player, object, damage) in hits {
+        let (_, _, mut catch, health, groups, _) = match players.get_mut(player) {
+            Ok(player) => player,
+            Err(_) => continue,
+        };
+        // Something thrown by the player doesn't count once it's back in their hands.
+        if object.is_some() && catch.target == object {
+            continue;
+        }
+        if let Some(mut health) = health {
+            health.current = (health.current - damage).max(0.0);
+            health.rest = 0.0;
+            if health.current > 0.0 {
+                debug!(target: "death", ?player, ?object, damage, "Player hurt");
+                continue;
+            }
+        } else if damage.is_finite() {
+            continue;
+        }
+        catch.target = None;
+        commands
+            .entity(player)
+            .insert(Dead {
+                timer: Timer::from_seconds(RESPAWN_TIME, false),
+                groups: groups.copied().unwrap_or_default(),
+            })
+            .insert(RigidBody::Fixed)
+            .insert(CollisionGroups::new(0, 0));
+        deaths.send(DeathEvent { player, object });
+        info!(target: "death", ?player, ?object, "Player knocked out");
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn respawn(
+    mut commands: Commands,
+    time: Res<Time>,
+    network: Res<Network>,
+    lobby: Res<Lobby>,
+    mut respawns: EventWriter<RespawnEvent>,
+    spawn_points: Query<(&SpawnPoint, &Transform), Without<Player>>,
+    mut players: Query<
+        (
+            Entity,
+            &mut Dead,
+            &mut Transform,
+            Option<&mut Velocity>,
+            Option<&mut Health>,
+        ),
+        With<Player>,
+    >,
+) {
+    for (player, mut dead, mut transform, velocity, health) in &mut players {
+        if !dead.timer.tick(time.delta()).finished() {
+            continue;
+        }
+        if let Some(spawn_point) = local_spawn_point(&network, &lobby, &spawn_points) {
+            *transform = spawn_point;
+        }
+        if let Some(mut velocity) = velocity {
+            *velocity = Velocity::default();
+        }
+        if let Some(mut health) = health {
+            health.current = health.max;
+        }
+        commands
+            .entity(player)
+            .remove::<Dead>()
+            .insert(RigidBody::Dynamic)
+            .insert(dead.groups);
+        respawns.send(RespawnEvent { player });
+    }
+}
+
+fn regenerate_health(time: Res<Time>, mut players: Query<&mut Health, Without<Dead>>) {
+    let delta = time.delta_seconds();
+    for mut health in &mut players {
+        health.rest += delta;
+        if health.rest >= REGEN_DELAY && health.current < health.max {
+            health.current = (health.current + REGEN_RATE * delta).min(health.max);
+        }
+    }
+}
@@ -0,0 +1,325 @@
+//! Immediate-mode debug drawing inside the pixelated view.
+//!
+//! Any system can take `ResMut<DebugDraw>` and add lines, rays, spheres and text tags for the
+//! current frame; they are drawn at the end of it and forgotten. Lines go through a forward camera
+//! on [`layers::DEBUG`] that draws over the scene into the same render target, so the path tracer
+//! never sees them. Tags use the pixel font of the HUD. F3 turns drawing on and off, and
+//! [`DebugDrawSettings`] picks the categories.
+
+use crate::{
+    hud::{
+        bitmap::{BitmapAlign, BitmapText, BitmapTextBundle},
+        world_to_hud,
+    },
+    layers::{self, SpawnOnLayerExt},
+    PlayerCamera, RENDER_IMAGE_HANDLE, RENDER_SIZE,
+};
+use bevy::{
+    core_pipeline::clear_color::ClearColorConfig,
+    prelude::*,
+    render::{
+        camera::RenderTarget,
+        mesh::{Indices, PrimitiveTopology},
+        view::NoFrustumCulling,
+    },
+};
+use bevy_inspector_egui::Inspectable;
+use std::f32::consts::TAU;
+
+const TAG_POOL_SIZE: usize = 32;
+const CIRCLE_SEGMENTS: usize = 16;
+
+pub struct DebugDrawPlugin;
+
+impl Plugin for DebugDrawPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DebugDrawSettings>()
+            .init_resource::<DebugDraw>()
+            .add_startup_system(setup_debug_draw)
+            .add_system(toggle_debug_draw)
+            .add_system(attach_debug_camera)
+            .add_system_to_stage(CoreStage::PostUpdate, flush_debug_draw);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebugCategory {
+    /// Catch targets and throws.
+    Catch,
+    /// Bot paths and perception.
+    Ai,
+    /// Impulses, blasts and force fields.
+    Forces,
+    /// Colliders, contacts and casts.
+    Physics,
+}
+
+#[derive(Debug, Clone, Inspectable)]
+pub struct DebugDrawSettings {
+    pub enabled: bool,
+    pub catch: bool,
+    pub ai: bool,
+    pub forces: bool,
+    pub physics: bool,
+}
+
+impl Default for DebugDrawSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            catch: true,
+            ai: true,
+            forces: true,
+            physics: false,
+        }
+    }
+}
+
+impl DebugDrawSettings {
+    pub fn shows(&self, category: DebugCategory) -> bool {
+        self.enabled
+            && match category {
+                DebugCategory::Catch => self.catch,
+                DebugCategory::Ai => self.ai,
+                DebugCategory::Forces => self.forces,
+                DebugCategory::Physics => self.physics,
+            }
+    }
+}
+
+/// Shapes to draw this frame.
+#[derive(Default)]
+pub struct DebugDraw {
+    shown: Vec<DebugCategory>,
+    lines: Vec<(Vec3, Vec3, Color)>,
+    tags: Vec<(Vec3, String, Color)>,
+}
+
+impl DebugDraw {
+    /// Whether shapes of `category` are drawn; check first if they are costly to work out.
+    pub fn shows(&self, category: DebugCategory) -> bool {
+        self.shown.contains(&category)
+    }
+
+    pub fn line(&mut self, category: DebugCategory, start: Vec3, end: Vec3, color: Color) {
+        if self.shows(category) {
+            self.lines.push((start, end, color));
+        }
+    }
+
+    /// Connects `points` in order.
+    pub fn path(&mut self, category: DebugCategory, points: &[Vec3], color: Color) {
+        for pair in points.windows(2) {
+            self.line(category, pair[0], pair[1], color);
+        }
+    }
+
+    /// A line from `origin` along `direction`, as long as it, with a tick at the end.
+    pub fn ray(&mut self, category: DebugCategory, origin: Vec3, direction: Vec3, color: Color) {
+        let end = origin + direction;
+        self.line(category, origin, end, color);
+        let side = 0.1 * direction.any_orthonormal_vector();
+        self.line(category, end - side, end + side, color);
+    }
+
+    /// Outline of a cone from `apex` along `direction`, `length` long and `angle` radians wide on
+    /// each side.
+    pub fn cone(
+        &mut self,
+        category: DebugCategory,
+        apex: Vec3,
+        direction: Vec3,
+        length: f32,
+        angle: f32,
+        color: Color,
+    ) {
+        if !self.shows(category) {
+            return;
+        }
+        let direction = direction.normalize_or_zero();
+        let center = apex + length * direction;
+        let radius = length * angle.tan();
+        let u = direction.any_orthonormal_vector();
+        let v = direction.cross(u);
+        let point = |index: usize| {
+            let angle = TAU * index as f32 / CIRCLE_SEGMENTS as f32;
+            center + radius * (angle.cos() * u + angle.sin() * v)
+        };
+        for index in 0..CIRCLE_SEGMENTS {
+            self.lines.push((point(index), point(index + 1), color));
+            if index % (CIRCLE_SEGMENTS / 4) == 0 {
+                self.lines.push((apex, point(index), color));
+            }
+        }
+    }
+
+    /// Three circles around `center`, one in each axis plane.
+    pub fn sphere(&mut self, category: DebugCategory, center: Vec3, radius: f32, color: Color) {
+        if !self.shows(category) {
+            return;
+        }
+        for (u, v) in [(Vec3::X, Vec3::Y), (Vec3::Y, Vec3::Z), (Vec3::Z, Vec3::X)] {
+            let point = |index: usize| {
+                let angle = TAU * index as f32 / CIRCLE_SEGMENTS as f32;
+                center + radius * (angle.cos() * u + angle.sin() * v)
+            };
+            for index in 0..CIRCLE_SEGMENTS {
+                self.lines.push((point(index), point(index + 1), color));
+            }
+        }
+    }
+
+    /// Text over `position`, in the pixel font.
+    pub fn text(
+        &mut self,
+        category: DebugCategory,
+        position: Vec3,
+        text: impl Into<String>,
+        color: Color,
+    ) {
+        if self.shows(category) {
+            self.tags.push((position, text.into(), color));
+        }
+    }
+}
+
+#[derive(Component)]
+struct DebugLines;
+
+#[derive(Component)]
+struct DebugTag;
+
+struct DebugTagPool(Vec<Entity>);
+
+fn setup_debug_draw(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands
+        .spawn_bundle(PbrBundle {
+            mesh: meshes.add(Mesh::new(PrimitiveTopology::LineList)),
+            // Vertex colors tint the white base.
+            material: materials.add(StandardMaterial {
+                base_color: Color::WHITE,
+                unlit: true,
+                ..default()
+            }),
+            visibility: Visibility { is_visible: false },
+            ..default()
+        })
+        .insert_bundle((layers::DEBUG, NoFrustumCulling, DebugLines));
+
+    let tags = (0..TAG_POOL_SIZE)
+        .map(|_| {
+            commands
+                .spawn_on_hud_layer(BitmapTextBundle::new(
+                    BitmapText::new("", Color::WHITE, BitmapAlign::Center),
+                    Vec2::ZERO,
+                ))
+                .insert(DebugTag)
+                .id()
+        })
+        .collect();
+    commands.insert_resource(DebugTagPool(tags));
+}
+
+fn toggle_debug_draw(keys: Res<Input<KeyCode>>, mut settings: ResMut<DebugDrawSettings>) {
+    if keys.just_pressed(KeyCode::F3) {
+        settings.enabled = !settings.enabled;
+    }
+}
+
+/// Gives the player camera a twin that draws the debug layer over the scene.
+fn attach_debug_camera(mut commands: Commands, cameras: Query<Entity, Added<PlayerCamera>>) {
+    for entity in &cameras {
+        commands.entity(entity).with_children(|parent| {
+            parent
+                .spawn_bundle(Camera3dBundle {
+                    camera: Camera {
+                        priority: -1,
+                        target: RenderTarget::Image(RENDER_IMAGE_HANDLE.typed()),
+                        ..default()
+                    },
+                    camera_3d: Camera3d {
+                        clear_color: ClearColorConfig::None,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .insert(layers::DEBUG);
+        });
+    }
+}
+
+/// Turns this frame's shapes into the line mesh and tags, and starts the next frame empty.
+fn flush_debug_draw(
+    settings: Res<DebugDrawSettings>,
+    mut draw: ResMut<DebugDraw>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    tag_pool: Res<DebugTagPool>,
+    cameras: Query<(&Camera, &GlobalTransform), With<PlayerCamera>>,
+    mut lines: Query<(&Handle<Mesh>, &mut Visibility), With<DebugLines>>,
+    mut tags: Query<(&mut BitmapText, &mut Transform, &mut Visibility), Without<DebugLines>>,
+) {
+    for (handle, mut visibility) in &mut lines {
+        // An empty mesh has no buffers to draw from.
+        visibility.is_visible = !draw.lines.is_empty();
+        if draw.lines.is_empty() {
+            continue;
+        }
+        if let Some(mesh) = meshes.get_mut(handle) {
+            let positions: Vec<[f32; 3]> = draw
+                .lines
+                .iter()
+                .flat_map(|(start, end, _)| [start.to_array(), end.to_array()])
+                .collect();
+            let colors: Vec<[f32; 4]> = draw
+                .lines
+                .iter()
+                .flat_map(|(_, _, color)| [color.as_linear_rgba_f32(); 2])
+                .collect();
+            let count = positions.len();
+            mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+            mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0.0, 1.0, 0.0]; count]);
+            mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, vec![[0.0, 0.0]; count]);
+            mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+            mesh.set_indices(Some(Indices::U32((0..count as u32).collect())));
+        }
+    }
+
+    let camera = cameras.get_single().ok();
+    let half = 0.5 * Vec2::new(RENDER_SIZE[0] as f32, RENDER_SIZE[1] as f32);
+    let mut shown = draw.tags.iter().filter_map(|(position, value, color)| {
+        let (camera, transform) = camera?;
+        let screen = world_to_hud(camera, transform, *position)
+            .filter(|screen| screen.abs().cmple(half).all())?;
+        Some((screen, value, *color))
+    });
+    for entity in &tag_pool.0 {
+        if let Ok((mut text, mut transform, mut visibility)) = tags.get_mut(*entity) {
+            match shown.next() {
+                Some((screen, value, color)) => {
+                    if text.value != *value || text.color != color {
+                        *text = BitmapText::new(value.clone(), color, BitmapAlign::Center);
+                    }
+                    transform.translation = screen.round().extend(0.0);
+                    visibility.is_visible = true;
+                }
+                None => visibility.is_visible = false,
+            }
+        }
+    }
+
+    draw.lines.clear();
+    draw.tags.clear();
+    draw.shown = [
+        DebugCategory::Catch,
+        DebugCategory::Ai,
+        DebugCategory::Forces,
+        DebugCategory::Physics,
+    ]
+    .into_iter()
+    .filter(|category| settings.shows(*category))
+    .collect();
+}
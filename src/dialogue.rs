@@ -0,0 +1,233 @@
+//! Subtitled conversations.
+//!
+//! A [`Conversation`] is a list of lines, each with a speaker. Conversations belong to a level file
+//! and are queued by name with a [`StartDialogue`] event, usually from the level script; they play
+//! one after another, and a [`DialogueFinished`] event follows each. A line with a voice clip plays
+//! it and stays up as long as the clip, unless it has its own duration. Speakers and lines that
+//! name a message in the [`Locale`] are translated.
+
+use crate::{
+    hud::bitmap::{window_scale, BitmapAlign, BitmapText, BitmapTextBundle, GLYPH_ADVANCE},
+    level::{CurrentLevel, LevelDef},
+    locale::Locale,
+};
+use bevy::{asset::LoadState, audio::Decodable, prelude::*, render::view::RenderLayers};
+use rodio::Source;
+use serde::Deserialize;
+use std::collections::VecDeque;
+
+const SUBTITLE_Z: f32 = 6.0;
+/// Distance of the subtitles from the bottom of the window.
+const SUBTITLE_OFFSET: f32 = 120.0;
+/// Reading time for lines without a duration or voice.
+const SECONDS_PER_CHAR: f32 = 0.06;
+const MIN_LINE_TIME: f32 = 1.5;
+const SPEAKER_COLOR: Color = Color::rgb(0.9, 0.6, 0.2);
+
+pub struct DialoguePlugin;
+
+impl Plugin for DialoguePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DialogueQueue>()
+            .add_event::<StartDialogue>()
+            .add_event::<DialogueFinished>()
+            .add_startup_system(setup_subtitles)
+            .add_system(queue_dialogue)
+            .add_system(play_dialogue.after(queue_dialogue));
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct DialogueLine {
+    pub speaker: String,
+    pub text: String,
+    /// Seconds on screen; defaults to the voice clip length, or a reading time.
+    #[serde(default)]
+    pub duration: Option<f32>,
+    /// Voice clip, relative to the asset folder.
+    #[serde(default)]
+    pub voice: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Conversation {
+    pub name: String,
+    pub lines: Vec<DialogueLine>,
+}
+
+/// Queues the conversation of the current level with this name.
+#[derive(Debug, Clone)]
+pub struct StartDialogue(pub String);
+
+#[derive(Debug, Clone)]
+pub struct DialogueFinished(pub String);
+
+struct ActiveLine {
+    index: usize,
+    voice: Option<Handle<AudioSource>>,
+    /// Starts once the voice clip has loaded and its length is known.
+    timer: Option<Timer>,
+}
+
+impl ActiveLine {
+    fn start(conversation: &Conversation, index: usize, asset_server: &AssetServer) -> Self {
+        let voice = conversation
+            .lines
+            .get(index)
+            .and_then(|line| line.voice.as_ref())
+            .map(|path| asset_server.load(path));
+        Self {
+            index,
+            voice,
+            timer: None,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct DialogueQueue {
+    pending: VecDeque<Conversation>,
+    current: Option<(Conversation, ActiveLine)>,
+}
+
+impl DialogueQueue {
+    pub fn push(&mut self, conversation: Conversation) {
+        self.pending.push_back(conversation);
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.current.is_some()
+    }
+
+    pub fn clear(&mut self) {
+        self.pending.clear();
+        self.current = None;
+    }
+}
+
+/// The speaker's name if `speaker`, otherwise what they say.
+#[derive(Component)]
+struct Subtitle {
+    speaker: bool,
+}
+
+/// Drawn on the window rather than the HUD, so subtitles stay up in cutscenes.
+fn setup_subtitles(mut commands: Commands, windows: Res<Windows>) {
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+    let scale = window_scale(window);
+    let bottom = -0.5 * window.height() + SUBTITLE_OFFSET;
+    for (speaker, color, y) in [
+        (true, SPEAKER_COLOR, bottom + scale * GLYPH_ADVANCE.y),
+        (false, Color::WHITE, bottom),
+    ] {
+        commands
+            .spawn_bundle(BitmapTextBundle {
+                text: BitmapText::new("", color, BitmapAlign::Center),
+                spatial: SpatialBundle {
+                    transform: Transform::from_xyz(0.0, y.round(), SUBTITLE_Z)
+                        .with_scale(Vec3::splat(scale)),
+                    ..default()
+                },
+            })
+            .insert_bundle((RenderLayers::default(), Subtitle { speaker }));
+    }
+}
+
+fn queue_dialogue(
+    current: Res<CurrentLevel>,
+    levels: Res<Assets<LevelDef>>,
+    mut queue: ResMut<DialogueQueue>,
+    mut events: EventReader<StartDialogue>,
+) {
+    for StartDialogue(name) in events.iter() {
+        let conversation = levels.get(&current.handle).and_then(|level| {
+            level
+                .conversations
+                .iter()
+                .find(|conversation| conversation.name == *name)
+        });
+        match conversation {
+            Some(conversation) => queue.push(conversation.clone()),
+            None => warn!("No conversation called {}", name),
+        }
+    }
+}
+
+/// Length of a decoded clip in seconds.
+fn clip_length(source: &AudioSource) -> f32 {
+    let decoder = source.decoder();
+    let rate = decoder.sample_rate() as f32 * decoder.channels() as f32;
+    decoder.count() as f32 / rate.max(1.0)
+}
+
+fn line_time(line: &DialogueLine) -> f32 {
+    (line.text.chars().count() as f32 * SECONDS_PER_CHAR).max(MIN_LINE_TIME)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn play_dialogue(
+    time: Res<Time>,
+    asset_server: Res<AssetServer>,
+    audio: Res<Audio>,
+    sources: Res<Assets<AudioSource>>,
+    locale: Res<Locale>,
+    mut queue: ResMut<DialogueQueue>,
+    mut subtitles: Query<(&mut BitmapText, &Subtitle)>,
+    mut finished: EventWriter<DialogueFinished>,
+) {
+    let queue = &mut *queue;
+    if queue.current.is_none() {
+        queue.current = queue.pending.pop_front().map(|conversation| {
+            let active = ActiveLine::start(&conversation, 0, &asset_server);
+            (conversation, active)
+        });
+    }
+    let (conversation, active) = match &mut queue.current {
+        Some(current) => current,
+        None => return,
+    };
+    let line = match conversation.lines.get(active.index) {
+        Some(line) => line,
+        None => {
+            finished.send(DialogueFinished(conversation.name.clone()));
+            queue.current = None;
+            for (mut text, _) in &mut subtitles {
+                text.value.clear();
+            }
+            return;
+        }
+    };
+
+    if active.timer.is_none() {
+        let seconds = match (&line.duration, &active.voice) {
+            (Some(duration), _) => Some(*duration),
+            (None, Some(voice)) => match asset_server.get_load_state(voice) {
+                LoadState::Failed => Some(line_time(line)),
+                _ => sources.get(voice).map(clip_length),
+            },
+            (None, None) => Some(line_time(line)),
+        };
+        if let Some(seconds) = seconds {
+            if let Some(voice) = &active.voice {
+                audio.play(voice.clone());
+            }
+            active.timer = Some(Timer::from_seconds(seconds, false));
+            for (mut text, subtitle) in &mut subtitles {
+                text.value = if subtitle.speaker {
+                    locale.translate(&line.speaker)
+                } else {
+                    locale.translate(&line.text)
+                };
+            }
+        }
+    }
+
+    if let Some(timer) = &mut active.timer {
+        if timer.tick(time.delta()).finished() {
+            *active = ActiveLine::start(conversation, active.index + 1, &asset_server);
+        }
+    }
+}
@@ -0,0 +1,240 @@
+//! Frame pacing, so path tracing doesn't run the GPU flat out.
+//!
+//! The [`DisplaySettings`] in the [`Profile`] cap the frame rate and pick whether presenting waits
+//! for vsync. O opens a window to change them while playing. The cap sleeps out whatever is left
+//! of each frame; browsers pace frames themselves, so there it does nothing.
+//!
+//! The window also shows the [`GpuInfo`], and the [`GraphicsPreset`], which follows the one
+//! recommended for the GPU until another is picked.
+
+use crate::{
+    cli::Args,
+    gpu::{GpuInfo, GraphicsPreset},
+    input_block::InputBlock,
+    locale::Locale,
+    profile::Profile,
+    surveillance::FeedBudget,
+};
+use bevy::{prelude::*, utils::Instant, window::PresentMode};
+use bevy_egui::{egui, EguiContext};
+#[cfg(not(target_arch = "wasm32"))]
+use bevy_hikari::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+pub struct DisplayPlugin;
+
+impl Plugin for DisplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(display_menu)
+            .add_system(apply_present_mode.after(display_menu))
+            .add_system(apply_graphics_preset.after(display_menu))
+            .add_system_to_stage(CoreStage::Last, limit_frame_rate);
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FrameLimit {
+    Off,
+    Fps30,
+    #[default]
+    Fps60,
+    Fps120,
+    /// Frames per second.
+    Custom(u32),
+}
+
+impl FrameLimit {
+    /// Shortest time a frame may take.
+    pub fn frame_time(self) -> Option<Duration> {
+        let fps = match self {
+            FrameLimit::Off => return None,
+            FrameLimit::Fps30 => 30,
+            FrameLimit::Fps60 => 60,
+            FrameLimit::Fps120 => 120,
+            FrameLimit::Custom(fps) => fps.max(1),
+        };
+        Some(Duration::from_secs_f64(1.0 / fps as f64))
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Vsync {
+    #[default]
+    On,
+    /// Presents as soon as a frame is done, which may tear.
+    Off,
+}
+
+impl Vsync {
+    fn present_mode(self) -> PresentMode {
+        match self {
+            Vsync::On => PresentMode::AutoVsync,
+            Vsync::Off => PresentMode::AutoNoVsync,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DisplaySettings {
+    pub frame_limit: FrameLimit,
+    pub vsync: Vsync,
+    /// `None` for the one recommended for the GPU.
+    pub graphics: Option<GraphicsPreset>,
+    /// Streaks behind fast catch objects, see [`crate::stretch`].
+    pub motion_stretch: bool,
+}
+
+impl DisplaySettings {
+    pub fn graphics_preset(&self, gpu: &GpuInfo) -> GraphicsPreset {
+        self.graphics
+            .unwrap_or_else(|| GraphicsPreset::recommended(gpu))
+    }
+}
+
+fn display_menu(
+    keys: Res<Input<KeyCode>>,
+    block: Res<InputBlock>,
+    locale: Res<Locale>,
+    gpu: Res<GpuInfo>,
+    mut profile: ResMut<Profile>,
+    mut open: Local<bool>,
+    mut egui_context: ResMut<EguiContext>,
+) {
+    if keys.just_pressed(KeyCode::O) && !block.typing() {
+        *open = !*open;
+    }
+    if !*open {
+        return;
+    }
+
+    let mut settings = profile.display.clone();
+    egui::Window::new(locale.get("display"))
+        .collapsible(false)
+        .resizable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.label(locale.get("display-frame-limit"));
+            ui.horizontal(|ui| {
+                let limits = [
+                    (FrameLimit::Off, locale.get("display-off")),
+                    (FrameLimit::Fps30, "30".into()),
+                    (FrameLimit::Fps60, "60".into()),
+                    (FrameLimit::Fps120, "120".into()),
+                ];
+                for (limit, label) in limits {
+                    ui.radio_value(&mut settings.frame_limit, limit, label);
+                }
+                let custom = matches!(settings.frame_limit, FrameLimit::Custom(_));
+                if ui.radio(custom, locale.get("display-custom")).clicked() && !custom {
+                    settings.frame_limit = FrameLimit::Custom(90);
+                }
+                if let FrameLimit::Custom(fps) = &mut settings.frame_limit {
+                    ui.add(egui::DragValue::new(fps).clamp_range(10..=360));
+                }
+            });
+
+            ui.separator();
+            ui.label(locale.get("display-vsync"));
+            ui.horizontal(|ui| {
+                ui.radio_value(&mut settings.vsync, Vsync::On, locale.get("display-on"));
+                ui.radio_value(&mut settings.vsync, Vsync::Off, locale.get("display-off"));
+            });
+
+            ui.separator();
+            ui.label(locale.get("display-graphics"));
+            ui.horizontal(|ui| {
+                let recommended = GraphicsPreset::recommended(&gpu);
+                let auto = locale.get_args(
+                    "display-graphics-auto",
+                    &[("preset", locale.get(recommended.key()).into())],
+                );
+                ui.radio_value(&mut settings.graphics, None, auto);
+                for preset in GraphicsPreset::ALL {
+                    ui.radio_value(
+                        &mut settings.graphics,
+                        Some(preset),
+                        locale.get(preset.key()),
+                    );
+                }
+            });
+
+            ui.checkbox(
+                &mut settings.motion_stretch,
+                locale.get("display-motion-stretch"),
+            );
+
+            ui.separator();
+            egui::Grid::new("gpu").show(ui, |ui| {
+                let rows = [
+                    ("display-gpu", gpu.name.clone()),
+                    (
+                        "display-backend",
+                        format!("{} ({})", gpu.backend, gpu.device_type),
+                    ),
+                    (
+                        "display-max-texture",
+                        format!("{} px", gpu.max_texture_size),
+                    ),
+                    (
+                        "display-max-buffer",
+                        format!("{} MiB", gpu.max_storage_buffer >> 20),
+                    ),
+                ];
+                for (key, value) in rows {
+                    ui.label(locale.get(key));
+                    ui.label(value);
+                    ui.end_row();
+                }
+            });
+        });
+
+    // Only touch the profile on a change, since that saves it.
+    if settings != profile.display {
+        profile.display = settings;
+    }
+}
+
+fn apply_present_mode(profile: Res<Profile>, mut windows: ResMut<Windows>) {
+    let mode = profile.display.vsync.present_mode();
+    if let Some(window) = windows.get_primary_mut() {
+        if window.present_mode() != mode {
+            window.set_present_mode(mode);
+        }
+    }
+}
+
+/// Applies the preset when it changes, leaving later tweaks in the inspector alone.
+fn apply_graphics_preset(
+    profile: Res<Profile>,
+    gpu: Res<GpuInfo>,
+    mut applied: Local<Option<GraphicsPreset>>,
+    mut budget: ResMut<FeedBudget>,
+    #[cfg(not(target_arch = "wasm32"))] mut config: ResMut<HikariConfig>,
+) {
+    let preset = profile.display.graphics_preset(&gpu);
+    if *applied == Some(preset) {
+        return;
+    }
+    *applied = Some(preset);
+    budget.max_active = preset.max_feeds();
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        config.indirect_bounces = preset.indirect_bounces();
+    }
+}
+
+/// Sleeps until the frame has taken as long as the limit asks for.
+fn limit_frame_rate(args: Res<Args>, profile: Res<Profile>, mut last: Local<Option<Instant>>) {
+    // Headless runs keep their own tick.
+    if args.headless {
+        return;
+    }
+    if let (Some(frame_time), Some(last)) = (profile.display.frame_limit.frame_time(), *last) {
+        let elapsed = last.elapsed();
+        if elapsed < frame_time && cfg!(not(target_arch = "wasm32")) {
+            std::thread::sleep(frame_time - elapsed);
+        }
+    }
+    *last = Some(Instant::now());
+}
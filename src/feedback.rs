@@ -0,0 +1,204 @@
+//! Game feel in one place.
+//!
+//! Anything that wants some juice sends a [`FeedbackEvent`] instead of touching time, cameras or
+//! gamepads itself, so a throw feels the same wherever it comes from. Hit-stop slows physics for a
+//! moment, trauma shakes the camera, and rumble is collected in [`Feedback`] for the gamepad.
+
+use crate::{
+    accessibility::AccessibilitySettings, level::trigger::GoalReached, CatchObject, Player,
+    PlayerCamera, PlayerCatch,
+};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use rand::Rng;
+
+/// Physics speed during hit-stop.
+const HIT_STOP_SCALE: f32 = 0.05;
+/// Physics speed during slow motion.
+const SLOW_MOTION_SCALE: f32 = 0.2;
+/// How fast trauma wears off, per second.
+const TRAUMA_DECAY: f32 = 1.5;
+/// Camera offset at full trauma, in meters.
+const MAX_SHAKE: f32 = 0.15;
+const RUMBLE_DECAY: f32 = 4.0;
+/// Falling speed that counts as a landing, and speed of a full-strength one.
+const LANDING_SPEED: [f32; 2] = [4.0, 16.0];
+/// Speed of an object hitting the player that counts as a hit, and of a full-strength one.
+const HIT_SPEED: [f32; 2] = [5.0, 25.0];
+
+pub struct FeedbackPlugin;
+
+impl Plugin for FeedbackPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Feedback>()
+            .add_event::<FeedbackEvent>()
+            .add_system(goal_feedback)
+            .add_system(landing_feedback)
+            .add_system(hit_feedback)
+            .add_system(
+                receive_feedback
+                    .after(goal_feedback)
+                    .after(landing_feedback)
+                    .after(hit_feedback),
+            )
+            .add_system(apply_hit_stop.after(receive_feedback))
+            .add_system(shake_camera.after(receive_feedback));
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FeedbackEvent {
+    /// Seconds of slowed physics.
+    pub hit_stop: f32,
+    /// Added camera shake, from 0 to 1.
+    pub trauma: f32,
+    /// Gamepad vibration, from 0 to 1.
+    pub rumble: f32,
+}
+
+impl FeedbackEvent {
+    /// Feedback for an impact of `strength`, from 0 to 1; only strong ones stop time.
+    pub fn impact(strength: f32) -> Self {
+        let strength = strength.clamp(0.0, 1.0);
+        Self {
+            hit_stop: if strength > 0.5 { 0.1 * strength } else { 0.0 },
+            trauma: 0.5 * strength,
+            rumble: strength,
+        }
+    }
+}
+
+/// Where `speed` falls between `range`, from 0 to 1.
+fn strength(speed: f32, range: [f32; 2]) -> f32 {
+    ((speed - range[0]) / (range[1] - range[0])).clamp(0.0, 1.0)
+}
+
+#[derive(Debug, Default)]
+pub struct Feedback {
+    pub trauma: f32,
+    pub rumble: f32,
+    hit_stop: f32,
+    /// Slows physics down for as long as it is set, e.g. while a menu is open.
+    pub slow_motion: bool,
+    /// Part of the camera translation that is shake.
+    shake_offset: Vec3,
+}
+
+impl Feedback {
+    pub fn is_hit_stopped(&self) -> bool {
+        self.hit_stop > 0.0
+    }
+}
+
+fn goal_feedback(mut goals: EventReader<GoalReached>, mut events: EventWriter<FeedbackEvent>) {
+    for _ in goals.iter() {
+        events.send(FeedbackEvent::impact(1.0));
+    }
+}
+
+/// A landing is a fall that stops; the harder it was, the more it shakes.
+fn landing_feedback(
+    mut falling: Local<f32>,
+    players: Query<&Velocity, With<Player>>,
+    mut events: EventWriter<FeedbackEvent>,
+) {
+    let velocity = match players.get_single() {
+        Ok(velocity) => velocity.linvel.y,
+        Err(_) => return,
+    };
+    if *falling > LANDING_SPEED[0] && velocity > -1.0 {
+        events.send(FeedbackEvent {
+            rumble: strength(*falling, LANDING_SPEED),
+            ..FeedbackEvent::impact(0.5 * strength(*falling, LANDING_SPEED))
+        });
+    }
+    *falling = -velocity;
+}
+
+/// Objects thrown into the player hurt, as far as the player's hands can tell.
+fn hit_feedback(
+    mut collisions: EventReader<CollisionEvent>,
+    players: Query<&PlayerCatch, With<Player>>,
+    objects: Query<&Velocity, With<CatchObject>>,
+    mut events: EventWriter<FeedbackEvent>,
+) {
+    for event in collisions.iter() {
+        let (a, b) = match event {
+            CollisionEvent::Started(a, b, _) => (*a, *b),
+            _ => continue,
+        };
+        let (catch, object) = match (players.get(a), players.get(b)) {
+            (Ok(catch), Err(_)) => (catch, b),
+            (Err(_), Ok(catch)) => (catch, a),
+            _ => continue,
+        };
+        // What the player is pulling in is meant to come close.
+        if catch.target == Some(object) {
+            continue;
+        }
+        if let Ok(velocity) = objects.get(object) {
+            let speed = velocity.linvel.length();
+            if speed > HIT_SPEED[0] {
+                events.send(FeedbackEvent::impact(strength(speed, HIT_SPEED)));
+            }
+        }
+    }
+}
+
+fn receive_feedback(
+    time: Res<Time>,
+    mut feedback: ResMut<Feedback>,
+    mut events: EventReader<FeedbackEvent>,
+) {
+    let delta = time.delta_seconds();
+    feedback.trauma = (feedback.trauma - TRAUMA_DECAY * delta).max(0.0);
+    feedback.rumble = (feedback.rumble - RUMBLE_DECAY * delta).max(0.0);
+    feedback.hit_stop = (feedback.hit_stop - delta).max(0.0);
+
+    for event in events.iter() {
+        feedback.trauma = (feedback.trauma + event.trauma).min(1.0);
+        feedback.rumble = feedback.rumble.max(event.rumble);
+        feedback.hit_stop = feedback.hit_stop.max(event.hit_stop);
+    }
+}
+
+fn apply_hit_stop(feedback: Res<Feedback>, mut rapier_config: ResMut<RapierConfiguration>) {
+    // Replays step with a fixed delta, so they are left alone.
+    if let TimestepMode::Variable { time_scale, .. }
+    | TimestepMode::Interpolated { time_scale, .. } = &mut rapier_config.timestep_mode
+    {
+        let scale = if feedback.is_hit_stopped() {
+            HIT_STOP_SCALE
+        } else if feedback.slow_motion {
+            SLOW_MOTION_SCALE
+        } else {
+            1.0
+        };
+        if *time_scale != scale {
+            *time_scale = scale;
+        }
+    }
+}
+
+/// Shakes by the square of the trauma, so small knocks stay subtle.
+fn shake_camera(
+    settings: Res<AccessibilitySettings>,
+    mut feedback: ResMut<Feedback>,
+    mut cameras: Query<&mut Transform, With<PlayerCamera>>,
+) {
+    let shake = feedback.trauma * feedback.trauma * MAX_SHAKE;
+    let mut rng = rand::thread_rng();
+    let offset = if shake > 0.0 && !settings.reduce_motion {
+        Vec3::new(rng.gen_range(-1.0..=1.0), rng.gen_range(-1.0..=1.0), 0.0) * shake
+    } else {
+        Vec3::ZERO
+    };
+    if offset == feedback.shake_offset {
+        return;
+    }
+
+    for mut transform in &mut cameras {
+        transform.translation += offset - feedback.shake_offset;
+    }
+    feedback.shake_offset = offset;
+}
@@ -0,0 +1,397 @@
+//! Distance and height fog, applied when the display quad composites the scene.
+//!
+//! The path tracer keeps its depth to itself, so a plain forward camera rides along with the
+//! player camera and draws the same layer once more. A node after its main pass copies the depth
+//! buffer into [`FOG_DEPTH_HANDLE`], which [`CompositeMaterial`] reads back to find how far away
+//! each pixel is. Fog thickens with distance and thins with height above [`FogSettings::height`].
+//! Its color follows the horizon of the sky, so it darkens as the sun goes down.
+
+use crate::{hud::composite::CompositeMaterial, layers, sky::SkyColors, PlayerCamera, RENDER_SIZE};
+use bevy::{
+    core_pipeline::{clear_color::ClearColorConfig, core_3d},
+    ecs::query::QueryItem,
+    prelude::*,
+    reflect::TypeUuid,
+    render::{
+        camera::{Projection, RenderTarget},
+        extract_component::{ExtractComponent, ExtractComponentPlugin},
+        render_asset::RenderAssets,
+        render_graph::{Node, NodeRunError, RenderGraph, RenderGraphContext, SlotInfo, SlotType},
+        render_resource::*,
+        renderer::{RenderContext, RenderDevice},
+        texture::{BevyDefault, ImageSampler},
+        view::ViewDepthTexture,
+        RenderApp,
+    },
+};
+use bevy_inspector_egui::Inspectable;
+
+/// Distance to each pixel of the scene, as the reversed depth of the fog camera.
+pub const FOG_DEPTH_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Image::TYPE_UUID, 2718281828459);
+/// The fog camera has to draw color somewhere, even though only its depth is used.
+const FOG_COLOR_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Image::TYPE_UUID, 2718281828460);
+
+const FOG_DEPTH_NODE: &str = "fog_depth";
+
+pub struct FogPlugin;
+
+impl Plugin for FogPlugin {
+    fn build(&self, app: &mut App) {
+        let mut images = app.world.resource_mut::<Assets<Image>>();
+        images.set_untracked(FOG_DEPTH_HANDLE, target_image(TextureFormat::R32Float));
+        images.set_untracked(
+            FOG_COLOR_HANDLE,
+            target_image(TextureFormat::bevy_default()),
+        );
+        let samples = app
+            .world
+            .get_resource::<Msaa>()
+            .map_or(1, |msaa| msaa.samples);
+
+        app.init_resource::<FogSettings>()
+            .add_plugin(ExtractComponentPlugin::<FogCamera>::default())
+            .add_system(attach_fog_camera)
+            .add_system(apply_fog);
+
+        let render_app = match app.get_sub_app_mut(RenderApp) {
+            Ok(render_app) => render_app,
+            Err(_) => return,
+        };
+        let pipeline = FogDepthPipeline::new(&mut render_app.world, samples);
+        render_app.insert_resource(pipeline);
+
+        let node = FogDepthNode::new(&mut render_app.world);
+        let mut graph = render_app.world.resource_mut::<RenderGraph>();
+        if let Some(graph) = graph.get_sub_graph_mut(core_3d::graph::NAME) {
+            graph.add_node(FOG_DEPTH_NODE, node);
+            graph
+                .add_node_edge(core_3d::graph::node::MAIN_PASS, FOG_DEPTH_NODE)
+                .unwrap();
+            let input = graph.input_node().unwrap().id;
+            graph
+                .add_slot_edge(
+                    input,
+                    core_3d::graph::input::VIEW_ENTITY,
+                    FOG_DEPTH_NODE,
+                    FogDepthNode::IN_VIEW,
+                )
+                .unwrap();
+        }
+    }
+}
+
+#[derive(Debug, Clone, Inspectable)]
+pub struct FogSettings {
+    pub enabled: bool,
+    /// Takes the color from the horizon of the sky instead of [`FogSettings::color`].
+    pub follow_sky: bool,
+    pub color: Color,
+    /// Fog starts here, in meters from the camera.
+    #[inspectable(min = 0.0, max = 200.0)]
+    pub start: f32,
+    /// Fog is thickest from here on.
+    #[inspectable(min = 0.0, max = 400.0)]
+    pub end: f32,
+    /// Strongest fog, where 1 hides everything behind it.
+    #[inspectable(min = 0.0, max = 1.0)]
+    pub density: f32,
+    /// Fog is full below this height, in meters.
+    #[inspectable(min = -20.0, max = 50.0)]
+    pub height: f32,
+    /// How fast fog thins above [`FogSettings::height`], per meter.
+    #[inspectable(min = 0.0, max = 1.0)]
+    pub height_falloff: f32,
+}
+
+impl Default for FogSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            follow_sky: true,
+            color: Color::rgb(0.75, 0.82, 0.9),
+            start: 20.0,
+            end: 150.0,
+            density: 0.8,
+            height: 0.0,
+            height_falloff: 0.05,
+        }
+    }
+}
+
+/// Fog as the composite shader sees it.
+#[derive(Debug, Default, Clone, Copy, PartialEq, ShaderType)]
+pub struct FogParams {
+    /// Alpha is the density, and zero turns fog off.
+    pub color: Vec4,
+    /// Camera to world.
+    pub view: Mat4,
+    pub inverse_projection: Mat4,
+    /// Near plane, to undo the reversed depth.
+    pub near: f32,
+    pub start: f32,
+    pub end: f32,
+    pub height: f32,
+    pub height_falloff: f32,
+}
+
+/// Marks the camera whose depth the fog reads.
+#[derive(Clone, Component)]
+pub struct FogCamera;
+
+impl ExtractComponent for FogCamera {
+    type Query = &'static Self;
+    type Filter = ();
+
+    fn extract_component(_: QueryItem<Self::Query>) -> Self {
+        FogCamera
+    }
+}
+
+fn target_image(format: TextureFormat) -> Image {
+    let size = Extent3d {
+        width: RENDER_SIZE[0],
+        height: RENDER_SIZE[1],
+        ..default()
+    };
+    let mut image = Image {
+        texture_descriptor: TextureDescriptor {
+            label: None,
+            size,
+            dimension: TextureDimension::D2,
+            format,
+            mip_level_count: 1,
+            sample_count: 1,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::RENDER_ATTACHMENT,
+        },
+        sampler_descriptor: ImageSampler::Descriptor(SamplerDescriptor {
+            mag_filter: FilterMode::Nearest,
+            min_filter: FilterMode::Nearest,
+            mipmap_filter: FilterMode::Nearest,
+            ..default()
+        }),
+        ..default()
+    };
+    image.resize(size);
+    image
+}
+
+fn attach_fog_camera(mut commands: Commands, cameras: Query<Entity, Added<PlayerCamera>>) {
+    for entity in &cameras {
+        commands.entity(entity).with_children(|parent| {
+            parent
+                .spawn_bundle(Camera3dBundle {
+                    camera: Camera {
+                        priority: -4,
+                        target: RenderTarget::Image(FOG_COLOR_HANDLE.typed()),
+                        ..default()
+                    },
+                    camera_3d: Camera3d {
+                        clear_color: ClearColorConfig::Custom(Color::BLACK),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .insert(layers::PLAYER_VIEW)
+                .insert(FogCamera);
+        });
+    }
+}
+
+fn apply_fog(
+    settings: Res<FogSettings>,
+    sky: Res<SkyColors>,
+    mut materials: ResMut<Assets<CompositeMaterial>>,
+    cameras: Query<(&Camera, &Projection, &GlobalTransform), With<PlayerCamera>>,
+    quads: Query<&Handle<CompositeMaterial>>,
+) {
+    let (camera, projection, transform) = match cameras.get_single() {
+        Ok(camera) => camera,
+        Err(_) => return,
+    };
+    let color = if settings.follow_sky {
+        sky.horizon
+    } else {
+        settings.color
+    };
+    let density = if settings.enabled {
+        settings.density
+    } else {
+        0.0
+    };
+    let near = match projection {
+        Projection::Perspective(projection) => projection.near,
+        Projection::Orthographic(projection) => projection.near,
+    };
+    let [red, green, blue, _] = color.as_linear_rgba_f32();
+    let params = FogParams {
+        color: Vec4::new(red, green, blue, density),
+        view: transform.compute_matrix(),
+        inverse_projection: camera.projection_matrix.inverse(),
+        near,
+        start: settings.start,
+        end: settings.end.max(settings.start + 0.01),
+        height: settings.height,
+        height_falloff: settings.height_falloff,
+    };
+
+    for handle in &quads {
+        if materials
+            .get(handle)
+            .map_or(false, |material| material.fog != params)
+        {
+            if let Some(material) = materials.get_mut(handle) {
+                material.fog = params;
+            }
+        }
+    }
+}
+
+/// Draws the depth buffer of the fog camera into [`FOG_DEPTH_HANDLE`].
+struct FogDepthPipeline {
+    layout: BindGroupLayout,
+    pipeline: CachedRenderPipelineId,
+}
+
+impl FogDepthPipeline {
+    fn new(world: &mut World, samples: u32) -> Self {
+        let layout =
+            world
+                .resource::<RenderDevice>()
+                .create_bind_group_layout(&BindGroupLayoutDescriptor {
+                    label: Some("fog_depth_layout"),
+                    entries: &[BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Texture {
+                            sample_type: TextureSampleType::Depth,
+                            view_dimension: TextureViewDimension::D2,
+                            multisampled: samples > 1,
+                        },
+                        count: None,
+                    }],
+                });
+
+        let shader = world
+            .resource::<AssetServer>()
+            .load("shaders/fog_depth.wgsl");
+        let shader_defs = if samples > 1 {
+            vec!["MULTISAMPLED".to_string()]
+        } else {
+            vec![]
+        };
+        let pipeline =
+            world
+                .resource_mut::<PipelineCache>()
+                .queue_render_pipeline(RenderPipelineDescriptor {
+                    label: Some("fog_depth_pipeline".into()),
+                    layout: Some(vec![layout.clone()]),
+                    vertex: VertexState {
+                        shader: shader.clone(),
+                        shader_defs: shader_defs.clone(),
+                        entry_point: "vertex".into(),
+                        buffers: vec![],
+                    },
+                    fragment: Some(FragmentState {
+                        shader,
+                        shader_defs,
+                        entry_point: "fragment".into(),
+                        targets: vec![Some(ColorTargetState {
+                            format: TextureFormat::R32Float,
+                            blend: None,
+                            write_mask: ColorWrites::ALL,
+                        })],
+                    }),
+                    primitive: default(),
+                    depth_stencil: None,
+                    multisample: default(),
+                });
+
+        Self { layout, pipeline }
+    }
+}
+
+struct FogDepthNode {
+    query: QueryState<&'static ViewDepthTexture, With<FogCamera>>,
+}
+
+impl FogDepthNode {
+    const IN_VIEW: &'static str = "view";
+
+    fn new(world: &mut World) -> Self {
+        Self {
+            query: world.query_filtered(),
+        }
+    }
+}
+
+impl Node for FogDepthNode {
+    fn input(&self) -> Vec<SlotInfo> {
+        vec![SlotInfo::new(Self::IN_VIEW, SlotType::Entity)]
+    }
+
+    fn update(&mut self, world: &mut World) {
+        self.query.update_archetypes(world);
+    }
+
+    fn run(
+        &self,
+        graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        // Every 3D camera runs this graph, but only the fog camera has depth worth keeping.
+        let view_entity = graph.get_input_entity(Self::IN_VIEW)?;
+        let depth = match self.query.get_manual(world, view_entity) {
+            Ok(depth) => depth,
+            Err(_) => return Ok(()),
+        };
+        let fog_pipeline = world.resource::<FogDepthPipeline>();
+        let pipeline = match world
+            .resource::<PipelineCache>()
+            .get_render_pipeline(fog_pipeline.pipeline)
+        {
+            Some(pipeline) => pipeline,
+            None => return Ok(()),
+        };
+        let target = match world
+            .resource::<RenderAssets<Image>>()
+            .get(&FOG_DEPTH_HANDLE.typed())
+        {
+            Some(target) => target,
+            None => return Ok(()),
+        };
+
+        let bind_group = render_context
+            .render_device
+            .create_bind_group(&BindGroupDescriptor {
+                label: Some("fog_depth_bind_group"),
+                layout: &fog_pipeline.layout,
+                entries: &[BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(&depth.view),
+                }],
+            });
+        let mut pass = render_context
+            .command_encoder
+            .begin_render_pass(&RenderPassDescriptor {
+                label: Some("fog_depth_pass"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: &target.texture_view,
+                    resolve_target: None,
+                    ops: Operations {
+                        load: LoadOp::Clear(Color::BLACK.into()),
+                        store: true,
+                    },
+                })],
+                depth_stencil_attachment: None,
+            });
+        pass.set_pipeline(pipeline);
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.draw(0..3, 0..1);
+        Ok(())
+    }
+}
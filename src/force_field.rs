@@ -0,0 +1,180 @@
+//! Energy fields showing where the invisible boundaries of an arena are.
+//!
+//! Hidden walls get a [`ForceField`], which puts a translucent quad on their face. The path tracer
+//! can't draw a custom material, so a forward camera on [`layers::EFFECTS`] rides along with the
+//! player camera and draws the fields over the scene, reading the depth of [`crate::fog`] to stay
+//! behind anything in front. A cube or player touching a field sends a ring out from where it hit.
+
+use crate::{
+    fog::FOG_DEPTH_HANDLE,
+    hud::composite::WatchedShaders,
+    layers::{self, SpawnOnLayerExt},
+    PlayerCamera, RENDER_IMAGE_HANDLE,
+};
+use bevy::{
+    core_pipeline::clear_color::ClearColorConfig,
+    prelude::*,
+    reflect::TypeUuid,
+    render::{
+        camera::RenderTarget,
+        render_resource::{AsBindGroup, ShaderRef, ShaderType},
+    },
+};
+use bevy_rapier3d::prelude::*;
+
+pub const FORCE_FIELD_SHADER: &str = "shaders/force_field.wgsl";
+const FIELD_COLOR: Color = Color::rgba(0.3, 0.8, 1.0, 0.35);
+
+pub struct ForceFieldPlugin;
+
+impl Plugin for ForceFieldPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(MaterialPlugin::<ForceFieldMaterial>::default())
+            .add_startup_system(watch_force_field_shader)
+            .add_system(attach_force_fields)
+            .add_system(attach_field_camera)
+            .add_system(pulse_force_fields)
+            .add_system(animate_force_fields.after(pulse_force_fields));
+    }
+}
+
+/// A wall to show as a force field, with the size of its face, which lies `offset` along its
+/// local Z axis.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct ForceField {
+    pub size: Vec2,
+    pub offset: f32,
+}
+
+/// Points from a wall to the quad showing its field.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct ForceFieldQuad(pub Entity);
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, ShaderType)]
+pub struct ForceFieldParams {
+    pub color: Vec4,
+    /// Position of the last hit, and the time it landed in `w`.
+    pub hit: Vec4,
+    pub time: f32,
+}
+
+#[derive(Debug, Clone, AsBindGroup, TypeUuid)]
+#[uuid = "5e0c7a92-3f1b-4d86-a4e7-91b2c6d8f035"]
+pub struct ForceFieldMaterial {
+    #[uniform(0)]
+    pub params: ForceFieldParams,
+    #[texture(1, sample_type = "float", filterable = false)]
+    pub depth: Handle<Image>,
+}
+
+impl Material for ForceFieldMaterial {
+    fn fragment_shader() -> ShaderRef {
+        FORCE_FIELD_SHADER.into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        AlphaMode::Blend
+    }
+}
+
+fn watch_force_field_shader(asset_server: Res<AssetServer>, mut shaders: ResMut<WatchedShaders>) {
+    shaders.0.push(asset_server.load(FORCE_FIELD_SHADER));
+}
+
+fn attach_force_fields(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ForceFieldMaterial>>,
+    walls: Query<(Entity, &ForceField), Added<ForceField>>,
+) {
+    for (entity, field) in &walls {
+        let material = materials.add(ForceFieldMaterial {
+            params: ForceFieldParams {
+                color: Vec4::from(FIELD_COLOR.as_rgba_f32()),
+                // Long before anything, so nothing pulses yet.
+                hit: Vec4::new(0.0, 0.0, 0.0, -1000.0),
+                time: 0.0,
+            },
+            depth: FOG_DEPTH_HANDLE.typed(),
+        });
+        let quad = commands
+            .spawn_on_layer(
+                MaterialMeshBundle {
+                    mesh: meshes.add(shape::Quad::new(field.size).into()),
+                    material,
+                    transform: Transform::from_xyz(0.0, 0.0, field.offset),
+                    ..default()
+                },
+                layers::EFFECTS,
+            )
+            .id();
+        commands
+            .entity(entity)
+            .insert(ForceFieldQuad(quad))
+            .insert(ActiveEvents::COLLISION_EVENTS)
+            .add_child(quad);
+    }
+}
+
+/// Gives the player camera a twin that draws the effects layer over the scene.
+fn attach_field_camera(mut commands: Commands, cameras: Query<Entity, Added<PlayerCamera>>) {
+    for entity in &cameras {
+        commands.entity(entity).with_children(|parent| {
+            parent
+                .spawn_bundle(Camera3dBundle {
+                    camera: Camera {
+                        priority: -1,
+                        target: RenderTarget::Image(RENDER_IMAGE_HANDLE.typed()),
+                        ..default()
+                    },
+                    camera_3d: Camera3d {
+                        clear_color: ClearColorConfig::None,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .insert(layers::EFFECTS);
+        });
+    }
+}
+
+fn pulse_force_fields(
+    time: Res<Time>,
+    mut collisions: EventReader<CollisionEvent>,
+    mut materials: ResMut<Assets<ForceFieldMaterial>>,
+    walls: Query<&ForceFieldQuad>,
+    quads: Query<&Handle<ForceFieldMaterial>>,
+    bodies: Query<&GlobalTransform>,
+) {
+    for event in collisions.iter() {
+        let (a, b) = match event {
+            CollisionEvent::Started(a, b, _) => (*a, *b),
+            _ => continue,
+        };
+        let (quad, other) = match (walls.get(a), walls.get(b)) {
+            (Ok(quad), _) => (quad.0, b),
+            (_, Ok(quad)) => (quad.0, a),
+            _ => continue,
+        };
+        let (handle, body) = match (quads.get(quad), bodies.get(other)) {
+            (Ok(handle), Ok(body)) => (handle, body),
+            _ => continue,
+        };
+        if let Some(material) = materials.get_mut(handle) {
+            let position = body.translation();
+            material.params.hit = position.extend(time.seconds_since_startup() as f32);
+        }
+    }
+}
+
+fn animate_force_fields(
+    time: Res<Time>,
+    mut materials: ResMut<Assets<ForceFieldMaterial>>,
+    quads: Query<&Handle<ForceFieldMaterial>>,
+) {
+    for handle in &quads {
+        if let Some(material) = materials.get_mut(handle) {
+            material.params.time = time.seconds_since_startup() as f32;
+        }
+    }
+}
@@ -0,0 +1,74 @@
+//! One channel for the moments worth telling the player about.
+//!
+//! Gameplay modules keep sending their own events; this one gathers the notable ones into
+//! [`GameEvent`]s, so the event feed and anything else that logs or announces them listens in one
+//! place instead of to every module.
+
+use crate::{
+    death::DeathEvent,
+    level::trigger::GoalReached,
+    waves::{WaveCleared, WaveStarted},
+};
+use bevy::prelude::*;
+
+pub struct GameEventPlugin;
+
+impl Plugin for GameEventPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<GameEvent>().add_system(gather_game_events);
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum GameEvent {
+    /// A player was knocked out by `object`, or by a fall if there is none.
+    KnockedOut {
+        player: Entity,
+        object: Option<Entity>,
+    },
+    GoalScored {
+        goal: Entity,
+        object: Entity,
+    },
+    WaveStarted {
+        wave: usize,
+        total: usize,
+    },
+    WaveCleared {
+        wave: usize,
+        total: usize,
+    },
+}
+
+fn gather_game_events(
+    mut deaths: EventReader<DeathEvent>,
+    mut goals: EventReader<GoalReached>,
+    mut started: EventReader<WaveStarted>,
+    mut cleared: EventReader<WaveCleared>,
+    mut events: EventWriter<GameEvent>,
+) {
+    for event in deaths.iter() {
+        events.send(GameEvent::KnockedOut {
+            player: event.player,
+            object: event.object,
+        });
+    }
+    for event in goals.iter() {
+        events.send(GameEvent::GoalScored {
+            goal: event.goal,
+            object: event.object,
+        });
+    }
+    for event in started.iter() {
+        events.send(GameEvent::WaveStarted {
+            wave: event.wave,
+            total: event.total,
+        });
+    }
+    for event in cleared.iter() {
+        events.send(GameEvent::WaveCleared {
+            wave: event.wave,
+            total: event.total,
+        });
+    }
+}
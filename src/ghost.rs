@@ -0,0 +1,293 @@
+use crate::{
+    hud::bitmap::{BitmapAlign, BitmapText, BitmapTextBundle},
+    layers::SpawnOnLayerExt,
+    locale::Locale,
+    replay::{Replay, ReplayCommand, ReplayState},
+    Player,
+};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+pub const BEST_REPLAY_FILE: &str = "replays/best.replay";
+
+const TIME_ATTACK_START: Vec3 = Vec3::new(0.0, 2.0, 20.0);
+const FINISH_POSITION: Vec3 = Vec3::new(0.0, 1.0, -20.0);
+const FINISH_SIZE: f32 = 4.0;
+
+pub struct GhostPlugin;
+
+impl Plugin for GhostPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TimeAttack>()
+            .add_event::<TimeAttackFinished>()
+            .add_startup_system(setup_time_attack)
+            .add_system(time_attack_start)
+            .add_system(time_attack_finish.after(time_attack_start))
+            .add_system(ghost_playback)
+            .add_system(time_attack_hud);
+    }
+}
+
+/// Sent when a time-attack run reaches the finish, with its time in seconds.
+#[derive(Debug, Clone, Copy)]
+pub struct TimeAttackFinished {
+    pub time: f32,
+}
+
+/// A time-attack run goes from the start point to the [`FinishZone`], racing the best run so far.
+pub struct TimeAttack {
+    pub running: bool,
+    pub elapsed: f32,
+    pub best: Option<Replay>,
+}
+
+impl Default for TimeAttack {
+    fn default() -> Self {
+        Self {
+            running: false,
+            elapsed: 0.0,
+            best: Replay::load(BEST_REPLAY_FILE).ok(),
+        }
+    }
+}
+
+impl TimeAttack {
+    pub fn best_time(&self) -> Option<f32> {
+        self.best.as_ref().map(Replay::duration)
+    }
+}
+
+#[derive(Default, Component)]
+pub struct FinishZone;
+
+/// Translucent capsule following the poses of the best replay.
+#[derive(Default, Component)]
+pub struct Ghost {
+    elapsed: f32,
+    cursor: usize,
+    /// Time at which the frame under the cursor starts.
+    frame_start: f32,
+}
+
+#[derive(Component)]
+struct TimeAttackText;
+
+fn setup_time_attack(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands
+        .spawn_bundle(SpatialBundle {
+            transform: Transform::from_translation(FINISH_POSITION),
+            ..default()
+        })
+        .insert_bundle((
+            Collider::cuboid(0.5 * FINISH_SIZE, 1.0, 0.5 * FINISH_SIZE),
+            Sensor,
+            FinishZone,
+        ))
+        .with_children(|parent| {
+            parent.spawn_on_world_layer(PbrBundle {
+                mesh: meshes.add(shape::Plane { size: FINISH_SIZE }.into()),
+                material: materials.add(StandardMaterial {
+                    base_color: Color::rgb(0.2, 0.8, 0.3),
+                    emissive: Color::rgba(0.2, 0.8, 0.3, 0.5),
+                    perceptual_roughness: 0.9,
+                    ..default()
+                }),
+                transform: Transform::from_xyz(0.0, 0.01, 0.0),
+                ..default()
+            });
+        });
+
+    commands
+        .spawn_bundle(BitmapTextBundle::new(
+            BitmapText::new("", Color::WHITE, BitmapAlign::Left),
+            Vec2::new(-156.0, 84.0),
+        ))
+        .insert(TimeAttackText);
+}
+
+#[allow(clippy::too_many_arguments)]
+fn time_attack_start(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    keys: Res<Input<KeyCode>>,
+    replay_state: Res<ReplayState>,
+    mut time_attack: ResMut<TimeAttack>,
+    mut replay_commands: EventWriter<ReplayCommand>,
+    mut player: Query<(&mut Transform, Option<&mut Velocity>), With<Player>>,
+    ghosts: Query<Entity, With<Ghost>>,
+) {
+    if !keys.just_pressed(KeyCode::F7) || replay_state.is_playing() {
+        return;
+    }
+
+    for entity in &ghosts {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    if time_attack.running {
+        time_attack.running = false;
+        replay_commands.send(ReplayCommand::StopRecording);
+        return;
+    }
+
+    let (mut transform, velocity) = player.single_mut();
+    *transform = Transform::from_translation(TIME_ATTACK_START);
+    if let Some(mut velocity) = velocity {
+        *velocity = Velocity::default();
+    }
+
+    time_attack.running = true;
+    time_attack.elapsed = 0.0;
+    if let ReplayState::Recording(_) = *replay_state {
+        replay_commands.send(ReplayCommand::StopRecording);
+    }
+    replay_commands.send(ReplayCommand::StartRecording);
+
+    if time_attack.best.is_some() {
+        commands
+            .spawn_bundle(SpatialBundle::default())
+            .insert(Ghost::default())
+            .with_children(|parent| {
+                parent.spawn_on_world_layer(PbrBundle {
+                    mesh: meshes.add(
+                        shape::Capsule {
+                            radius: 0.5,
+                            depth: 1.0,
+                            ..default()
+                        }
+                        .into(),
+                    ),
+                    material: materials.add(StandardMaterial {
+                        base_color: Color::rgba(0.6, 0.8, 1.0, 0.3),
+                        emissive: Color::rgba(0.6, 0.8, 1.0, 0.2),
+                        alpha_mode: AlphaMode::Blend,
+                        unlit: true,
+                        ..default()
+                    }),
+                    transform: Transform::from_xyz(0.0, 1.0, 0.0),
+                    ..default()
+                });
+            });
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn time_attack_finish(
+    mut commands: Commands,
+    time: Res<Time>,
+    rapier_context: Res<RapierContext>,
+    replay_state: Res<ReplayState>,
+    mut time_attack: ResMut<TimeAttack>,
+    mut replay_commands: EventWriter<ReplayCommand>,
+    mut finished_events: EventWriter<TimeAttackFinished>,
+    player: Query<Entity, With<Player>>,
+    finish_zones: Query<Entity, With<FinishZone>>,
+    ghosts: Query<Entity, With<Ghost>>,
+) {
+    if !time_attack.running {
+        return;
+    }
+    time_attack.elapsed += time.delta_seconds();
+
+    let player = player.single();
+    let finished = finish_zones
+        .iter()
+        .any(|zone| rapier_context.intersection_pair(player, zone) == Some(true));
+    if !finished {
+        return;
+    }
+
+    time_attack.running = false;
+    for entity in &ghosts {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    if let ReplayState::Recording(replay) = &*replay_state {
+        let time = replay.duration();
+        info!("Time attack finished in {:.2} s", time);
+        finished_events.send(TimeAttackFinished { time });
+
+        if time_attack.best_time().map_or(true, |best| time < best) {
+            match replay.save(BEST_REPLAY_FILE) {
+                Ok(()) => info!("New best time saved to {}", BEST_REPLAY_FILE),
+                Err(err) => error!("Failed to save best replay: {}", err),
+            }
+            time_attack.best = Some(replay.clone());
+        }
+        replay_commands.send(ReplayCommand::StopRecording);
+    }
+}
+
+fn ghost_playback(
+    time: Res<Time>,
+    time_attack: Res<TimeAttack>,
+    mut ghosts: Query<(&mut Ghost, &mut Transform)>,
+) {
+    let frames = match &time_attack.best {
+        Some(replay) if !replay.frames.is_empty() => &replay.frames,
+        _ => return,
+    };
+
+    for (mut ghost, mut transform) in &mut ghosts {
+        ghost.elapsed += time.delta_seconds();
+        while ghost.cursor + 1 < frames.len()
+            && ghost.frame_start + frames[ghost.cursor].delta <= ghost.elapsed
+        {
+            ghost.frame_start += frames[ghost.cursor].delta;
+            ghost.cursor += 1;
+        }
+
+        let current = &frames[ghost.cursor];
+        let mut current_transform = Transform::default();
+        current.pose.apply(&mut current_transform);
+
+        // Blend towards the next pose so the ghost stays smooth at any frame rate.
+        *transform = match frames.get(ghost.cursor + 1) {
+            Some(next) if current.delta > 0.0 => {
+                let mut next_transform = Transform::default();
+                next.pose.apply(&mut next_transform);
+
+                let t = ((ghost.elapsed - ghost.frame_start) / current.delta).clamp(0.0, 1.0);
+                Transform {
+                    translation: current_transform
+                        .translation
+                        .lerp(next_transform.translation, t),
+                    rotation: current_transform.rotation.slerp(next_transform.rotation, t),
+                    ..default()
+                }
+            }
+            _ => current_transform,
+        };
+    }
+}
+
+fn time_attack_hud(
+    time_attack: Res<TimeAttack>,
+    locale: Res<Locale>,
+    mut text: Query<&mut BitmapText, With<TimeAttackText>>,
+) {
+    let mut text = text.single_mut();
+    let best = time_attack
+        .best_time()
+        .map_or_else(|| "--".into(), |best| format!("{best:.2}"));
+    let value = if time_attack.running {
+        let time = format!("{:.2}", time_attack.elapsed);
+        locale.get_args(
+            "time-attack-running",
+            &[("time", time.into()), ("best", best.into())],
+        )
+    } else if time_attack.best.is_some() {
+        locale.get_args("time-attack-best", &[("best", best.into())])
+    } else {
+        locale.get("time-attack")
+    };
+    // Laying the glyphs out again every frame is wasted while idle.
+    if text.value != value {
+        text.value = value;
+    }
+}
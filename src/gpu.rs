@@ -0,0 +1,115 @@
+//! What the renderer is running on.
+//!
+//! [`GpuInfo`] is read from the adapter and device once at startup, for the display menu to show
+//! and for [`GraphicsPreset::recommended`] to pick a starting preset from. Headless runs have no
+//! adapter, and keep the default.
+
+use bevy::{
+    prelude::*,
+    render::renderer::{RenderAdapterInfo, RenderDevice},
+};
+use serde::{Deserialize, Serialize};
+use wgpu::DeviceType;
+
+pub struct GpuPlugin;
+
+impl Plugin for GpuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GpuInfo>().add_startup_system(probe_gpu);
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct GpuInfo {
+    pub name: String,
+    pub backend: String,
+    pub device_type: String,
+    /// Whether the GPU has memory of its own, rather than sharing the system's.
+    pub discrete: bool,
+    /// Largest 2D texture side, in pixels.
+    pub max_texture_size: u32,
+    /// Largest storage buffer a shader can bind, in bytes.
+    pub max_storage_buffer: u32,
+}
+
+/// How hard the renderer works, from how many bounces path tracing takes to how many security
+/// camera feeds run at once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GraphicsPreset {
+    Low,
+    Medium,
+    High,
+}
+
+impl GraphicsPreset {
+    pub const ALL: [GraphicsPreset; 3] = [
+        GraphicsPreset::Low,
+        GraphicsPreset::Medium,
+        GraphicsPreset::High,
+    ];
+
+    /// Integrated and software adapters get the low preset, and only discrete GPUs with room for
+    /// big scenes the high one.
+    pub fn recommended(info: &GpuInfo) -> Self {
+        if !info.discrete {
+            GraphicsPreset::Low
+        } else if info.max_storage_buffer >= 1 << 30 {
+            GraphicsPreset::High
+        } else {
+            GraphicsPreset::Medium
+        }
+    }
+
+    pub fn key(self) -> &'static str {
+        match self {
+            GraphicsPreset::Low => "graphics-low",
+            GraphicsPreset::Medium => "graphics-medium",
+            GraphicsPreset::High => "graphics-high",
+        }
+    }
+
+    /// Indirect light bounces of the path tracer.
+    pub fn indirect_bounces(self) -> usize {
+        match self {
+            GraphicsPreset::Low => 0,
+            GraphicsPreset::Medium => 1,
+            GraphicsPreset::High => 2,
+        }
+    }
+
+    /// Security camera feeds rendered in a frame.
+    pub fn max_feeds(self) -> usize {
+        match self {
+            GraphicsPreset::Low => 1,
+            GraphicsPreset::Medium => 2,
+            GraphicsPreset::High => 4,
+        }
+    }
+}
+
+fn probe_gpu(
+    mut gpu: ResMut<GpuInfo>,
+    adapter: Option<Res<RenderAdapterInfo>>,
+    device: Option<Res<RenderDevice>>,
+) {
+    let (adapter, device) = match (adapter, device) {
+        (Some(adapter), Some(device)) => (adapter, device),
+        _ => return,
+    };
+    let limits = device.limits();
+    *gpu = GpuInfo {
+        name: adapter.name.clone(),
+        backend: format!("{:?}", adapter.backend),
+        device_type: format!("{:?}", adapter.device_type),
+        discrete: adapter.device_type == DeviceType::DiscreteGpu,
+        max_texture_size: limits.max_texture_dimension_2d,
+        max_storage_buffer: limits.max_storage_buffer_binding_size,
+    };
+    info!(
+        "Rendering on {} ({}, {}), suggesting the {:?} preset",
+        gpu.name,
+        gpu.backend,
+        gpu.device_type,
+        GraphicsPreset::recommended(&gpu)
+    );
+}
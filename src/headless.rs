@@ -0,0 +1,34 @@
+//! Running without a window or GPU, for dedicated servers and automated gameplay tests.
+//!
+//! With `--headless` the app opens no window and never starts the renderer, so nothing is drawn.
+//! [`HeadlessPlugin`] steps it at [`TICK_RATE`] and gives physics the same fixed step, so a run
+//! plays out the same on any machine. Physics, bots, waves and the match rules all run as usual.
+
+use bevy::{
+    app::{ScheduleRunnerPlugin, ScheduleRunnerSettings},
+    prelude::*,
+};
+use bevy_rapier3d::prelude::*;
+use std::time::Duration;
+
+/// Ticks per second.
+pub const TICK_RATE: f64 = 60.0;
+
+pub struct HeadlessPlugin;
+
+impl Plugin for HeadlessPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ScheduleRunnerSettings::run_loop(Duration::from_secs_f64(
+            1.0 / TICK_RATE,
+        )))
+        .add_plugin(ScheduleRunnerPlugin)
+        .add_startup_system(fixed_physics_step);
+    }
+}
+
+fn fixed_physics_step(mut rapier_config: ResMut<RapierConfiguration>) {
+    rapier_config.timestep_mode = TimestepMode::Fixed {
+        dt: (1.0 / TICK_RATE) as f32,
+        substeps: 1,
+    };
+}
@@ -0,0 +1,133 @@
+//! Objects that reached their catcher and are being carried.
+//!
+//! Once a pulled object comes within [`HOLD_RANGE`] of the catch point it becomes [`Held`]: no other
+//! catcher can take it, it only bumps into static geometry, and it follows a smoothed anchor at the
+//! catch point instead of being pulled with a fresh impulse every frame. It is let go as soon as
+//! its holder targets anything else, which is how a throw ends a hold.
+
+use crate::{
+    camera::{per_frame, smoothing, CameraSettings},
+    catch_class::CatchClass,
+    player_catch, PlayerCatch,
+};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+/// Distance from the catch point at which a pulled object counts as caught.
+pub const HOLD_RANGE: f32 = 1.0;
+/// Collision group of held objects, which dynamic bodies filter out.
+pub const HELD_GROUP: u32 = 1 << 1;
+/// How fast a held object closes in on its anchor, per second.
+const FOLLOW_RATE: f32 = 15.0;
+
+pub struct HoldPlugin;
+
+impl Plugin for HoldPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(filter_held_collisions)
+            .add_system(start_holds.after(player_catch))
+            .add_system(update_holds.after(start_holds));
+    }
+}
+
+#[derive(Debug, Clone, Copy, Component)]
+pub struct Held {
+    /// The entity with the [`PlayerCatch`] holding this.
+    pub holder: Entity,
+    anchor: Vec3,
+}
+
+/// Links a catcher to the entity with the [`PlayerCatch`] it catches for.
+#[derive(Component)]
+pub struct CatchPoint(pub Entity);
+
+/// Collision groups of every dynamic body that isn't held.
+fn dynamic_groups() -> CollisionGroups {
+    CollisionGroups::new(u32::MAX, !HELD_GROUP)
+}
+
+/// Dynamic bodies ignore held objects, so only static geometry is left for those to touch.
+fn filter_held_collisions(
+    mut commands: Commands,
+    bodies: Query<(Entity, &RigidBody), (Added<RigidBody>, Without<CollisionGroups>)>,
+) {
+    for (entity, body) in &bodies {
+        if *body == RigidBody::Dynamic {
+            commands.entity(entity).insert(dynamic_groups());
+        }
+    }
+}
+
+/// Where `holder` catches things.
+fn catch_point(holder: Entity, catchers: &Query<(&GlobalTransform, &CatchPoint)>) -> Option<Vec3> {
+    catchers
+        .iter()
+        .find(|(_, point)| point.0 == holder)
+        .map(|(transform, _)| transform.translation())
+}
+
+fn start_holds(
+    mut commands: Commands,
+    holders: Query<(Entity, &PlayerCatch)>,
+    catchers: Query<(&GlobalTransform, &CatchPoint)>,
+    objects: Query<&GlobalTransform, Without<Held>>,
+) {
+    for (holder, catch) in &holders {
+        let (target, point) = match (catch.target, catch_point(holder, &catchers)) {
+            (Some(target), Some(point)) => (target, point),
+            _ => continue,
+        };
+        let position = match objects.get(target) {
+            Ok(transform) => transform.translation(),
+            Err(_) => continue,
+        };
+        if position.distance(point) < HOLD_RANGE {
+            commands
+                .entity(target)
+                .insert(Held {
+                    holder,
+                    anchor: position,
+                })
+                .insert(CollisionGroups::new(HELD_GROUP, u32::MAX));
+        }
+    }
+}
+
+fn update_holds(
+    mut commands: Commands,
+    time: Res<Time>,
+    settings: Res<CameraSettings>,
+    holders: Query<&PlayerCatch>,
+    catchers: Query<(&GlobalTransform, &CatchPoint)>,
+    mut objects: Query<(
+        Entity,
+        &mut Held,
+        &GlobalTransform,
+        &mut Velocity,
+        Option<&CatchClass>,
+    )>,
+) {
+    let delta = time.delta_seconds();
+    let blend = smoothing(settings.hold_smoothing, delta);
+    for (entity, mut held, transform, mut velocity, class) in &mut objects {
+        let holding = holders
+            .get(held.holder)
+            .map_or(false, |catch| catch.target == Some(entity));
+        let point = match catch_point(held.holder, &catchers) {
+            Some(point) if holding => point,
+            _ => {
+                commands
+                    .entity(entity)
+                    .remove::<Held>()
+                    .insert(dynamic_groups());
+                continue;
+            }
+        };
+
+        held.anchor = held.anchor.lerp(point, blend);
+        let stability = per_frame(class.copied().unwrap_or_default().stability(), delta);
+        let follow = (held.anchor - transform.translation()) * FOLLOW_RATE;
+        velocity.linvel = velocity.linvel.lerp(follow, stability);
+        velocity.angvel *= 1.0 - stability;
+    }
+}
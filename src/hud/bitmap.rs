@@ -0,0 +1,300 @@
+//! A 3×5 pixel font for the low-res HUD.
+//!
+//! [`BitmapText`] lays out one sprite per glyph on [`layers::HUD`], which only the HUD camera
+//! sees, so HUD text gets the same chunky pixels as the scene. Text with other [`RenderLayers`]
+//! puts its glyphs there instead. Positions are in render
+//! target pixels with the origin at the center. Lowercase letters are drawn as capitals. Text with
+//! a character the font lacks, like kana, is set in the [`HudFont`] of the language instead, at
+//! about the same size, so nothing goes missing.
+
+use super::HudFont;
+use crate::{layers, RENDER_SIZE};
+use bevy::{
+    prelude::*,
+    render::{render_resource::*, texture::ImageSampler, view::RenderLayers},
+};
+
+/// Space taken by a glyph, including one pixel of spacing.
+pub const GLYPH_ADVANCE: Vec2 = Vec2::new(4.0, 7.0);
+const CELL: [usize; 2] = [4, 6];
+/// Size of text set in the HUD font, about as tall as a glyph.
+const FALLBACK_FONT_SIZE: f32 = 7.0;
+
+const GLYPHS: &[(char, [&str; 5])] = &[
+    (' ', ["...", "...", "...", "...", "..."]),
+    ('A', [".X.", "X.X", "XXX", "X.X", "X.X"]),
+    ('B', ["XX.", "X.X", "XX.", "X.X", "XX."]),
+    ('C', [".XX", "X..", "X..", "X..", ".XX"]),
+    ('D', ["XX.", "X.X", "X.X", "X.X", "XX."]),
+    ('E', ["XXX", "X..", "XX.", "X..", "XXX"]),
+    ('F', ["XXX", "X..", "XX.", "X..", "X.."]),
+    ('G', [".XX", "X..", "X.X", "X.X", ".XX"]),
+    ('H', ["X.X", "X.X", "XXX", "X.X", "X.X"]),
+    ('I', ["XXX", ".X.", ".X.", ".X.", "XXX"]),
+    ('J', ["..X", "..X", "..X", "X.X", ".X."]),
+    ('K', ["X.X", "X.X", "XX.", "X.X", "X.X"]),
+    ('L', ["X..", "X..", "X..", "X..", "XXX"]),
+    ('M', ["X.X", "XXX", "XXX", "X.X", "X.X"]),
+    ('N', ["XX.", "X.X", "X.X", "X.X", "X.X"]),
+    ('O', [".X.", "X.X", "X.X", "X.X", ".X."]),
+    ('P', ["XX.", "X.X", "XX.", "X..", "X.."]),
+    ('Q', [".X.", "X.X", "X.X", "XX.", ".XX"]),
+    ('R', ["XX.", "X.X", "XX.", "X.X", "X.X"]),
+    ('S', [".XX", "X..", ".X.", "..X", "XX."]),
+    ('T', ["XXX", ".X.", ".X.", ".X.", ".X."]),
+    ('U', ["X.X", "X.X", "X.X", "X.X", "XXX"]),
+    ('V', ["X.X", "X.X", "X.X", "X.X", ".X."]),
+    ('W', ["X.X", "X.X", "XXX", "XXX", "X.X"]),
+    ('X', ["X.X", "X.X", ".X.", "X.X", "X.X"]),
+    ('Y', ["X.X", "X.X", ".X.", ".X.", ".X."]),
+    ('Z', ["XXX", "..X", ".X.", "X..", "XXX"]),
+    ('Ä', ["X.X", ".X.", "X.X", "XXX", "X.X"]),
+    ('Ö', ["X.X", "...", "XXX", "X.X", "XXX"]),
+    ('Ü', ["X.X", "...", "X.X", "X.X", "XXX"]),
+    ('ß', [".X.", "X.X", "XX.", "X.X", "XX."]),
+    ('0', ["XXX", "X.X", "X.X", "X.X", "XXX"]),
+    ('1', [".X.", "XX.", ".X.", ".X.", "XXX"]),
+    ('2', ["XX.", "..X", ".X.", "X..", "XXX"]),
+    ('3', ["XX.", "..X", ".X.", "..X", "XX."]),
+    ('4', ["X.X", "X.X", "XXX", "..X", "..X"]),
+    ('5', ["XXX", "X..", "XX.", "..X", "XX."]),
+    ('6', [".XX", "X..", "XXX", "X.X", "XXX"]),
+    ('7', ["XXX", "..X", ".X.", ".X.", ".X."]),
+    ('8', ["XXX", "X.X", "XXX", "X.X", "XXX"]),
+    ('9', ["XXX", "X.X", "XXX", "..X", "XX."]),
+    ('.', ["...", "...", "...", "...", ".X."]),
+    (',', ["...", "...", "...", ".X.", "X.."]),
+    (':', ["...", ".X.", "...", ".X.", "..."]),
+    ('!', [".X.", ".X.", ".X.", "...", ".X."]),
+    ('?', ["XX.", "..X", ".X.", "...", ".X."]),
+    ('-', ["...", "...", "XXX", "...", "..."]),
+    ('+', ["...", ".X.", "XXX", ".X.", "..."]),
+    ('/', ["..X", "..X", ".X.", "X..", "X.."]),
+    ('%', ["X.X", "..X", ".X.", "X..", "X.X"]),
+    ('[', ["XX.", "X..", "X..", "X..", "XX."]),
+    (']', [".XX", "..X", "..X", "..X", ".XX"]),
+    ('(', [".X.", "X..", "X..", "X..", ".X."]),
+    (')', [".X.", "..X", "..X", "..X", ".X."]),
+    ('<', ["..X", ".X.", "X..", ".X.", "..X"]),
+    ('>', ["X..", ".X.", "..X", ".X.", "X.."]),
+    ('\'', [".X.", ".X.", "...", "...", "..."]),
+    ('=', ["...", "XXX", "...", "XXX", "..."]),
+    ('_', ["...", "...", "...", "...", "XXX"]),
+    ('*', ["...", "X.X", ".X.", "X.X", "..."]),
+    ('#', ["X.X", "XXX", "X.X", "XXX", "X.X"]),
+];
+
+pub fn glyph_index(c: char) -> Option<usize> {
+    let find = |c| GLYPHS.iter().position(|(glyph, _)| *glyph == c);
+    // ß has no single capital, so look it up as it is first.
+    find(c).or_else(|| find(c.to_uppercase().next()?))
+}
+
+/// Whether the pixel font can draw all of `value`.
+pub fn has_glyphs(value: &str) -> bool {
+    value.chars().all(|c| c == '\n' || glyph_index(c).is_some())
+}
+
+/// Glyph sheet of the pixel font, in white so sprites can tint it.
+pub struct BitmapFont {
+    pub atlas: Handle<TextureAtlas>,
+}
+
+impl FromWorld for BitmapFont {
+    fn from_world(world: &mut World) -> Self {
+        let width = CELL[0] * GLYPHS.len();
+        let mut data = vec![0; 4 * width * CELL[1]];
+        for (index, (_, rows)) in GLYPHS.iter().enumerate() {
+            for (y, row) in rows.iter().enumerate() {
+                for (x, pixel) in row.chars().enumerate() {
+                    if pixel == 'X' {
+                        let offset = 4 * (y * width + index * CELL[0] + x);
+                        data[offset..offset + 4].copy_from_slice(&[255; 4]);
+                    }
+                }
+            }
+        }
+
+        let mut image = Image::new(
+            Extent3d {
+                width: width as u32,
+                height: CELL[1] as u32,
+                ..default()
+            },
+            TextureDimension::D2,
+            data,
+            TextureFormat::Rgba8UnormSrgb,
+        );
+        image.sampler_descriptor = ImageSampler::Descriptor(SamplerDescriptor {
+            mag_filter: FilterMode::Nearest,
+            min_filter: FilterMode::Nearest,
+            mipmap_filter: FilterMode::Nearest,
+            ..default()
+        });
+
+        let image = world.resource_mut::<Assets<Image>>().add(image);
+        let atlas = TextureAtlas::from_grid(
+            image,
+            Vec2::new(CELL[0] as f32, CELL[1] as f32),
+            GLYPHS.len(),
+            1,
+        );
+        let atlas = world.resource_mut::<Assets<TextureAtlas>>().add(atlas);
+        Self { atlas }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum BitmapAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Debug, Default, Clone, Component)]
+pub struct BitmapText {
+    pub value: String,
+    pub color: Color,
+    pub align: BitmapAlign,
+}
+
+impl BitmapText {
+    pub fn new(value: impl Into<String>, color: Color, align: BitmapAlign) -> Self {
+        Self {
+            value: value.into(),
+            color,
+            align,
+        }
+    }
+}
+
+#[derive(Bundle, Default)]
+pub struct BitmapTextBundle {
+    pub text: BitmapText,
+    #[bundle]
+    pub spatial: SpatialBundle,
+}
+
+impl BitmapTextBundle {
+    /// Text at a pixel position of the render target, measured from its center.
+    pub fn new(text: BitmapText, position: Vec2) -> Self {
+        Self {
+            text,
+            spatial: SpatialBundle {
+                transform: Transform::from_translation(position.round().extend(0.0)),
+                ..default()
+            },
+        }
+    }
+}
+
+/// Scale that makes glyphs on the window as big as they are on the HUD, for text that stays up
+/// while the HUD fades out.
+pub fn window_scale(window: &Window) -> f32 {
+    window.height() / RENDER_SIZE[1] as f32
+}
+
+/// The text the glyph sprites were laid out for.
+#[derive(Component)]
+pub struct BitmapLayout(String);
+
+pub fn layout_bitmap_text(
+    mut commands: Commands,
+    font: Res<BitmapFont>,
+    hud_font: Res<HudFont>,
+    texts: Query<
+        (
+            Entity,
+            &BitmapText,
+            Option<&BitmapLayout>,
+            Option<&RenderLayers>,
+        ),
+        Changed<BitmapText>,
+    >,
+    children: Query<&Children>,
+    mut sprites: Query<&mut TextureAtlasSprite>,
+    mut fallbacks: Query<&mut Text>,
+) {
+    for (entity, text, layout, layers) in &texts {
+        // Only the color changed, as when fading.
+        if layout.map_or(false, |layout| layout.0 == text.value) {
+            for child in children.get(entity).into_iter().flatten() {
+                if let Ok(mut sprite) = sprites.get_mut(*child) {
+                    sprite.color = text.color;
+                }
+                if let Ok(mut fallback) = fallbacks.get_mut(*child) {
+                    for section in &mut fallback.sections {
+                        section.style.color = text.color;
+                    }
+                }
+            }
+            continue;
+        }
+
+        let layers = layers.copied().unwrap_or(layers::HUD);
+        commands.entity(entity).despawn_descendants();
+        commands
+            .entity(entity)
+            .insert(BitmapLayout(text.value.clone()));
+
+        if !has_glyphs(&text.value) {
+            let horizontal = match text.align {
+                BitmapAlign::Left => HorizontalAlign::Left,
+                BitmapAlign::Center => HorizontalAlign::Center,
+                BitmapAlign::Right => HorizontalAlign::Right,
+            };
+            commands.entity(entity).with_children(|parent| {
+                parent
+                    .spawn_bundle(Text2dBundle {
+                        text: Text::from_section(
+                            text.value.clone(),
+                            hud_font.style(FALLBACK_FONT_SIZE, text.color),
+                        )
+                        .with_alignment(TextAlignment {
+                            vertical: VerticalAlign::Top,
+                            horizontal,
+                        }),
+                        // Line up with the top of the glyphs of the first line.
+                        transform: Transform::from_xyz(0.0, 0.5 * GLYPH_ADVANCE.y, 0.0),
+                        ..default()
+                    })
+                    .insert(layers);
+            });
+            continue;
+        }
+
+        commands.entity(entity).with_children(|parent| {
+            for (line, value) in text.value.lines().enumerate() {
+                let width = GLYPH_ADVANCE.x * value.chars().count() as f32;
+                let start = match text.align {
+                    BitmapAlign::Left => 0.0,
+                    BitmapAlign::Center => (-0.5 * width).round(),
+                    BitmapAlign::Right => -width,
+                };
+                for (column, c) in value.chars().enumerate() {
+                    let index = match glyph_index(c) {
+                        Some(index) if c != ' ' => index,
+                        _ => continue,
+                    };
+                    let position = Vec2::new(
+                        start + GLYPH_ADVANCE.x * column as f32,
+                        -GLYPH_ADVANCE.y * line as f32,
+                    );
+                    parent
+                        .spawn_bundle(SpriteSheetBundle {
+                            sprite: TextureAtlasSprite {
+                                index,
+                                color: text.color,
+                                ..default()
+                            },
+                            texture_atlas: font.atlas.clone(),
+                            transform: Transform::from_translation(position.extend(0.0)),
+                            ..default()
+                        })
+                        .insert(layers);
+                }
+            }
+        });
+    }
+}
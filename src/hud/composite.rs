@@ -0,0 +1,155 @@
+//! Puts the low-res HUD over the low-res scene, as the first pass of the post-process chain.
+//!
+//! The scene and the pixel HUD render into separate images of the same size, and
+//! [`CompositeMaterial`] blends them when the quad is drawn, for [`crate::post`] to carry on
+//! from. That keeps the HUD pixels as chunky as the scene's, while letting it fade and shake on its
+//! own through [`HudEffects`]. Fog is laid over the scene here too, from the depth image of
+//! [`crate::fog`].
+//!
+//! The shader imports its fog part from a file of its own. [`WatchedShaders`] keeps those
+//! loaded, both so the imports resolve and so editing any of them while asset watching is on
+//! rebuilds just this pipeline, without a restart.
+
+use crate::{accessibility::AccessibilitySettings, fog::FogParams, RENDER_SIZE};
+use bevy::{
+    prelude::*,
+    reflect::TypeUuid,
+    render::render_resource::{AsBindGroup, ShaderRef, ShaderType},
+    sprite::Material2d,
+};
+use rand::Rng;
+
+pub const COMPOSITE_SHADER: &str = "shaders/composite.wgsl";
+/// Shaders the composite shader imports.
+const SHADER_IMPORTS: [&str; 1] = ["shaders/fog.wgsl"];
+
+/// How fast the HUD fades, in alpha per second.
+const FADE_SPEED: f32 = 3.0;
+/// How fast shaking settles, in pixels per second.
+const SHAKE_DECAY: f32 = 12.0;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, ShaderType)]
+pub struct CompositeParams {
+    /// Shift of the HUD, in UV units.
+    pub ui_offset: Vec2,
+    pub ui_alpha: f32,
+}
+
+#[derive(Debug, Clone, AsBindGroup, TypeUuid)]
+#[uuid = "3c1a8e54-7b2d-4f06-9e3a-5d8c2b7f1a46"]
+pub struct CompositeMaterial {
+    #[uniform(0)]
+    pub params: CompositeParams,
+    #[texture(1)]
+    #[sampler(2)]
+    pub scene: Handle<Image>,
+    #[texture(3)]
+    #[sampler(4)]
+    pub ui: Handle<Image>,
+    /// Read texel by texel, since float images can't be filtered.
+    #[texture(5, sample_type = "float", filterable = false)]
+    pub depth: Handle<Image>,
+    #[uniform(6)]
+    pub fog: FogParams,
+}
+
+impl Material2d for CompositeMaterial {
+    fn fragment_shader() -> ShaderRef {
+        COMPOSITE_SHADER.into()
+    }
+}
+
+/// Handles to the display shaders and their imports, kept so they stay loaded and watched. Starts
+/// with the composite ones; the post-process chain adds its own.
+pub struct WatchedShaders(pub Vec<Handle<Shader>>);
+
+impl FromWorld for WatchedShaders {
+    fn from_world(world: &mut World) -> Self {
+        let asset_server = world.resource::<AssetServer>();
+        let paths = std::iter::once(COMPOSITE_SHADER).chain(SHADER_IMPORTS);
+        Self(paths.map(|path| asset_server.load(path)).collect())
+    }
+}
+
+/// Says which shader changed, since a broken edit otherwise just leaves the old picture up.
+pub fn log_shader_reloads(
+    asset_server: Res<AssetServer>,
+    shaders: Res<WatchedShaders>,
+    mut events: EventReader<AssetEvent<Shader>>,
+) {
+    for event in events.iter() {
+        if let AssetEvent::Modified { handle } = event {
+            if !shaders.0.contains(handle) {
+                continue;
+            }
+            if let Some(path) = asset_server.get_handle_path(handle) {
+                info!(target: "shaders", path = %path.path().display(), "Reloaded");
+            }
+        }
+    }
+}
+
+pub struct HudEffects {
+    pub alpha: f32,
+    target_alpha: f32,
+    /// Shaking strength, in render target pixels.
+    shake: f32,
+}
+
+impl Default for HudEffects {
+    fn default() -> Self {
+        Self {
+            alpha: 1.0,
+            target_alpha: 1.0,
+            shake: 0.0,
+        }
+    }
+}
+
+impl HudEffects {
+    pub fn fade_to(&mut self, alpha: f32) {
+        self.target_alpha = alpha.clamp(0.0, 1.0);
+    }
+
+    pub fn shake(&mut self, pixels: f32) {
+        self.shake = self.shake.max(pixels);
+    }
+}
+
+pub fn apply_hud_effects(
+    time: Res<Time>,
+    settings: Res<AccessibilitySettings>,
+    mut effects: ResMut<HudEffects>,
+    mut materials: ResMut<Assets<CompositeMaterial>>,
+    quads: Query<&Handle<CompositeMaterial>>,
+) {
+    let delta = time.delta_seconds();
+    let effects = &mut *effects;
+    effects.alpha +=
+        (effects.target_alpha - effects.alpha).clamp(-FADE_SPEED * delta, FADE_SPEED * delta);
+    effects.shake = (effects.shake - SHAKE_DECAY * delta).max(0.0);
+    if settings.reduce_motion {
+        effects.shake = 0.0;
+    }
+
+    // Whole pixels only, or the HUD would blur between them.
+    let mut rng = rand::thread_rng();
+    let offset = Vec2::new(rng.gen_range(-1.0..=1.0), rng.gen_range(-1.0..=1.0)) * effects.shake;
+    let size = Vec2::new(RENDER_SIZE[0] as f32, RENDER_SIZE[1] as f32);
+    let params = CompositeParams {
+        ui_offset: offset.round() / size,
+        ui_alpha: effects.alpha,
+    };
+
+    for handle in &quads {
+        // Touching the material makes it upload again, so leave it alone while the HUD is still.
+        if materials
+            .get(handle)
+            .map_or(false, |material| material.params != params)
+        {
+            if let Some(material) = materials.get_mut(handle) {
+                material.params = params;
+            }
+        }
+    }
+}
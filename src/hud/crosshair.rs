@@ -0,0 +1,55 @@
+//! The crosshair in the middle of the HUD, in the style picked in the accessibility settings.
+
+use crate::{
+    accessibility::{AccessibilitySettings, CrosshairStyle},
+    layers::SpawnOnLayerExt,
+};
+use bevy::prelude::*;
+
+#[derive(Component)]
+pub struct Crosshair;
+
+pub fn setup_crosshair(mut commands: Commands) {
+    commands
+        .spawn_bundle(SpatialBundle::default())
+        .insert(Crosshair);
+}
+
+pub fn update_crosshair(
+    mut commands: Commands,
+    settings: Res<AccessibilitySettings>,
+    crosshairs: Query<Entity, With<Crosshair>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    // Each bar is (size, color), drawn back to front.
+    let bars: &[(Vec2, Color)] = match settings.crosshair {
+        CrosshairStyle::Dot => &[(Vec2::splat(1.0), Color::rgba(1.0, 1.0, 1.0, 0.8))],
+        CrosshairStyle::HighContrast => &[
+            (Vec2::new(9.0, 3.0), Color::BLACK),
+            (Vec2::new(3.0, 9.0), Color::BLACK),
+            (Vec2::new(7.0, 1.0), Color::YELLOW),
+            (Vec2::new(1.0, 7.0), Color::YELLOW),
+        ],
+        CrosshairStyle::Hidden => &[],
+    };
+
+    for entity in &crosshairs {
+        commands.entity(entity).despawn_descendants();
+        commands.entity(entity).with_children(|parent| {
+            for (index, (size, color)) in bars.iter().enumerate() {
+                parent.spawn_on_hud_layer(SpriteBundle {
+                    sprite: Sprite {
+                        color: *color,
+                        custom_size: Some(*size),
+                        ..default()
+                    },
+                    transform: Transform::from_xyz(0.0, 0.0, 0.01 * index as f32),
+                    ..default()
+                });
+            }
+        });
+    }
+}
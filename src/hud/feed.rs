@@ -0,0 +1,189 @@
+//! The event feed, a few lines in the top right corner of the HUD.
+//!
+//! Each [`GameEvent`] becomes a line with a colored icon, newest on top. Older lines slide down to
+//! make room and fade out after [`LIFETIME`]; past [`ROWS`] lines the oldest drops off.
+
+use super::bitmap::{
+    glyph_index, BitmapAlign, BitmapFont, BitmapText, BitmapTextBundle, GLYPH_ADVANCE,
+};
+use crate::{
+    accessibility::AccessibilitySettings, camera::smoothing, game_event::GameEvent,
+    layers::SpawnOnLayerExt, locale::Locale, Player, RENDER_SIZE,
+};
+use bevy::prelude::*;
+use std::collections::VecDeque;
+
+const ROWS: usize = 5;
+/// Seconds a line stays up.
+const LIFETIME: f32 = 6.0;
+const FADE_TIME: f32 = 1.0;
+/// Time constant of lines sliding into place, in seconds.
+const SCROLL_SMOOTHING: f32 = 0.08;
+const FEED_MARGIN: f32 = 4.0;
+/// Space between the text and its icon, in render target pixels.
+const ICON_GAP: f32 = 2.0;
+
+#[derive(Debug, Clone)]
+struct FeedLine {
+    text: String,
+    icon: char,
+    color: Color,
+    age: f32,
+}
+
+/// Lines of the feed, newest first.
+#[derive(Default)]
+pub struct EventFeed {
+    lines: VecDeque<FeedLine>,
+    /// How far the lines still have to slide down, in pixels.
+    scroll: f32,
+}
+
+#[derive(Component)]
+pub struct FeedRow {
+    index: usize,
+    icon: Entity,
+    text: Entity,
+}
+
+pub fn setup_event_feed(mut commands: Commands, font: Res<BitmapFont>) {
+    let half = 0.5 * Vec2::new(RENDER_SIZE[0] as f32, RENDER_SIZE[1] as f32);
+    for index in 0..ROWS {
+        let position = Vec2::new(
+            half.x - FEED_MARGIN,
+            half.y - FEED_MARGIN - GLYPH_ADVANCE.y * index as f32,
+        );
+        let mut icon = None;
+        let mut text = None;
+        commands
+            .spawn_bundle(SpatialBundle {
+                transform: Transform::from_translation(position.extend(0.0)),
+                ..default()
+            })
+            .with_children(|parent| {
+                icon = Some(
+                    parent
+                        .spawn_on_hud_layer(SpriteSheetBundle {
+                            texture_atlas: font.atlas.clone(),
+                            visibility: Visibility { is_visible: false },
+                            // Sprites are centered, glyphs hang from the top left of their cell.
+                            transform: Transform::from_xyz(-0.5 * GLYPH_ADVANCE.x, 0.0, 0.0),
+                            ..default()
+                        })
+                        .id(),
+                );
+                text = Some(
+                    parent
+                        .spawn_bundle(BitmapTextBundle::new(
+                            BitmapText::new("", Color::WHITE, BitmapAlign::Right),
+                            Vec2::new(-GLYPH_ADVANCE.x - ICON_GAP, 0.0),
+                        ))
+                        .id(),
+                );
+            })
+            .insert(FeedRow {
+                index,
+                icon: icon.unwrap(),
+                text: text.unwrap(),
+            });
+    }
+}
+
+/// Icon, color and text of the line for `event`.
+fn describe(
+    event: &GameEvent,
+    locale: &Locale,
+    settings: &AccessibilitySettings,
+    players: &Query<(), With<Player>>,
+) -> (char, Color, String) {
+    let palette = settings.palette();
+    match *event {
+        GameEvent::KnockedOut { player, object } => {
+            let key = match (players.contains(player), object.is_some()) {
+                (true, true) => "feed-you-hit",
+                (true, false) => "feed-you-fell",
+                (false, true) => "feed-hit",
+                (false, false) => "feed-fell",
+            };
+            ('*', Color::rgb(1.0, 0.3, 0.3), locale.get(key))
+        }
+        GameEvent::GoalScored { .. } => ('#', palette.goal, locale.get("popup-goal")),
+        GameEvent::WaveStarted { wave, total } => (
+            '>',
+            palette.marker,
+            locale.get_args(
+                "wave-started",
+                &[("wave", (wave + 1).into()), ("total", total.into())],
+            ),
+        ),
+        GameEvent::WaveCleared { wave, .. } => (
+            '!',
+            palette.marker,
+            locale.get_args("wave-cleared", &[("wave", (wave + 1).into())]),
+        ),
+    }
+}
+
+pub fn update_event_feed(
+    time: Res<Time>,
+    locale: Res<Locale>,
+    settings: Res<AccessibilitySettings>,
+    mut feed: ResMut<EventFeed>,
+    mut events: EventReader<GameEvent>,
+    players: Query<(), With<Player>>,
+) {
+    let delta = time.delta_seconds();
+    for line in &mut feed.lines {
+        line.age += delta;
+    }
+    feed.lines.retain(|line| line.age < LIFETIME);
+
+    for event in events.iter() {
+        let (icon, color, text) = describe(event, &locale, &settings, &players);
+        feed.lines.push_front(FeedLine {
+            text,
+            icon,
+            color,
+            age: 0.0,
+        });
+        feed.lines.truncate(ROWS);
+        feed.scroll += GLYPH_ADVANCE.y;
+    }
+    feed.scroll *= 1.0 - smoothing(SCROLL_SMOOTHING, delta);
+}
+
+pub fn show_event_feed(
+    feed: Res<EventFeed>,
+    mut rows: Query<(&FeedRow, &mut Transform)>,
+    mut icons: Query<(&mut TextureAtlasSprite, &mut Visibility)>,
+    mut texts: Query<&mut BitmapText>,
+) {
+    let top = 0.5 * RENDER_SIZE[1] as f32 - FEED_MARGIN;
+    for (row, mut transform) in &mut rows {
+        transform.translation.y = (top - GLYPH_ADVANCE.y * row.index as f32 + feed.scroll).round();
+
+        let line = feed.lines.get(row.index);
+        if let Ok((mut sprite, mut visibility)) = icons.get_mut(row.icon) {
+            visibility.is_visible = line.is_some();
+            if let Some(line) = line {
+                sprite.index = glyph_index(line.icon).unwrap_or_default();
+                sprite.color = faded(line.color, line.age);
+            }
+        }
+        if let Ok(mut text) = texts.get_mut(row.text) {
+            let (value, color) = line.map_or((String::new(), Color::NONE), |line| {
+                (line.text.clone(), faded(Color::WHITE, line.age))
+            });
+            // Only touch the text when it changed, or the glyphs are laid out every frame.
+            if text.value != value || text.color != color {
+                text.value = value;
+                text.color = color;
+            }
+        }
+    }
+}
+
+fn faded(mut color: Color, age: f32) -> Color {
+    color.set_a(((LIFETIME - age) / FADE_TIME).clamp(0.0, 1.0) * color.a());
+    color
+}
@@ -0,0 +1,120 @@
+//! Edge indicators for [`AudioCue`]s, so the cues can be seen as well as heard.
+//!
+//! Each cue puts an icon at the screen edge in the direction of its source, with an arrow pointing
+//! out, for a moment. Shown when `sound_indicators` is on in the accessibility settings.
+
+use super::bitmap::{glyph_index, BitmapFont};
+use crate::{
+    accessibility::AccessibilitySettings,
+    cues::{AudioCue, CueKind},
+    layers::SpawnOnLayerExt,
+    PlayerCamera, RENDER_SIZE,
+};
+use bevy::prelude::*;
+
+/// Seconds an indicator stays up.
+const INDICATOR_TIME: f32 = 1.5;
+/// Distance kept from the screen edge, in render target pixels.
+const EDGE_MARGIN: f32 = 16.0;
+
+#[derive(Component)]
+pub struct CueIndicator {
+    position: Vec3,
+    color: Color,
+    timer: Timer,
+    arrow: Entity,
+}
+
+fn glyph(kind: CueKind) -> char {
+    match kind {
+        CueKind::Incoming => '!',
+        CueKind::EnemyNearby => '*',
+        CueKind::GoalScored => '+',
+    }
+}
+
+pub fn spawn_cue_indicators(
+    mut commands: Commands,
+    settings: Res<AccessibilitySettings>,
+    font: Res<BitmapFont>,
+    mut cues: EventReader<AudioCue>,
+) {
+    let palette = settings.palette();
+    for cue in cues.iter() {
+        if !settings.sound_indicators {
+            continue;
+        }
+        let color = match cue.kind {
+            CueKind::Incoming => Color::WHITE,
+            CueKind::EnemyNearby => palette.marker,
+            CueKind::GoalScored => palette.goal,
+        };
+        let sprite = |c: char, transform: Transform| SpriteSheetBundle {
+            sprite: TextureAtlasSprite {
+                index: glyph_index(c).unwrap_or_default(),
+                color,
+                ..default()
+            },
+            texture_atlas: font.atlas.clone(),
+            transform,
+            ..default()
+        };
+        let arrow = commands
+            .spawn_on_hud_layer(sprite('>', Transform::default()))
+            .id();
+        commands
+            .spawn_bundle(SpatialBundle::default())
+            .insert(CueIndicator {
+                position: cue.position,
+                color,
+                timer: Timer::from_seconds(INDICATOR_TIME, false),
+                arrow,
+            })
+            .add_child(arrow)
+            .with_children(|parent| {
+                parent.spawn_on_hud_layer(sprite(glyph(cue.kind), Transform::default()));
+            });
+    }
+}
+
+/// Keeps indicators on the edge towards their source as the camera turns, and fades them out.
+pub fn update_cue_indicators(
+    mut commands: Commands,
+    time: Res<Time>,
+    cameras: Query<&GlobalTransform, With<PlayerCamera>>,
+    mut indicators: Query<(Entity, &mut CueIndicator, &mut Transform, &Children)>,
+    mut sprites: Query<(&mut TextureAtlasSprite, &mut Transform), Without<CueIndicator>>,
+) {
+    let view = cameras
+        .get_single()
+        .map_or(Mat4::IDENTITY, |camera| camera.compute_matrix().inverse());
+    let edge = 0.5 * Vec2::new(RENDER_SIZE[0] as f32, RENDER_SIZE[1] as f32) - EDGE_MARGIN;
+
+    for (entity, mut indicator, mut transform, children) in &mut indicators {
+        if indicator.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+
+        // Straight ahead or behind has no side, so it goes to the top or the bottom.
+        let local = view.transform_point3(indicator.position);
+        let direction = local
+            .truncate()
+            .try_normalize()
+            .unwrap_or(if local.z < 0.0 { Vec2::Y } else { Vec2::NEG_Y });
+        let scale = (edge.x / direction.x.abs()).min(edge.y / direction.y.abs());
+        transform.translation = (direction * scale).round().extend(0.0);
+        if let Ok((_, mut arrow)) = sprites.get_mut(indicator.arrow) {
+            arrow.translation = (6.0 * direction).round().extend(0.0);
+            arrow.rotation = Quat::from_rotation_z(direction.y.atan2(direction.x));
+        }
+
+        let mut color = indicator.color;
+        color.set_a(indicator.timer.percent_left().min(0.5) * 2.0);
+        for child in children {
+            if let Ok((mut sprite, _)) = sprites.get_mut(*child) {
+                sprite.color = color;
+            }
+        }
+    }
+}
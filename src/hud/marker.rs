@@ -0,0 +1,170 @@
+//! Screen markers for things worth finding.
+//!
+//! Every entity with a [`Marker`] gets an icon on the HUD at its projected position. When it is
+//! off screen, or behind the camera, the icon moves to the screen edge with an arrow and the
+//! distance. Goals are marked on their own, as is anything tagged `key` or `marker`.
+
+use super::bitmap::{glyph_index, BitmapAlign, BitmapFont, BitmapText, BitmapTextBundle};
+use crate::{
+    accessibility::AccessibilitySettings, layers::SpawnOnLayerExt, level::Goal, tags::Tags,
+    PlayerCamera, RENDER_SIZE,
+};
+use bevy::prelude::*;
+
+/// Tags that earn an entity a marker.
+pub const MARKER_TAGS: &[&str] = &["key", "marker"];
+/// Distance kept from the screen edge, in render target pixels.
+const EDGE_MARGIN: f32 = 10.0;
+const ICON_SIZE: f32 = 4.0;
+
+#[derive(Debug, Clone, Copy, Component)]
+pub struct Marker {
+    pub color: Color,
+}
+
+/// The HUD side of a [`Marker`].
+#[derive(Component)]
+pub struct MarkerIcon {
+    target: Entity,
+    arrow: Entity,
+    label: Entity,
+}
+
+pub fn mark_goals(
+    mut commands: Commands,
+    settings: Res<AccessibilitySettings>,
+    goals: Query<Entity, Added<Goal>>,
+) {
+    let color = settings.palette().goal;
+    for entity in &goals {
+        commands.entity(entity).insert(Marker { color });
+    }
+}
+
+pub fn mark_tagged(
+    mut commands: Commands,
+    settings: Res<AccessibilitySettings>,
+    tagged: Query<(Entity, &Tags), (Changed<Tags>, Without<Marker>)>,
+) {
+    for (entity, tags) in &tagged {
+        if MARKER_TAGS.iter().any(|tag| tags.has(tag)) {
+            commands.entity(entity).insert(Marker {
+                color: settings.palette().marker,
+            });
+        }
+    }
+}
+
+pub fn spawn_marker_icons(
+    mut commands: Commands,
+    font: Res<BitmapFont>,
+    markers: Query<(Entity, &Marker), Added<Marker>>,
+) {
+    for (target, marker) in &markers {
+        let mut arrow = None;
+        let mut label = None;
+        commands
+            .spawn_bundle(SpatialBundle::default())
+            .with_children(|parent| {
+                parent.spawn_on_hud_layer(SpriteBundle {
+                    sprite: Sprite {
+                        color: marker.color,
+                        custom_size: Some(Vec2::splat(ICON_SIZE)),
+                        ..default()
+                    },
+                    transform: Transform::from_rotation(Quat::from_rotation_z(
+                        std::f32::consts::FRAC_PI_4,
+                    )),
+                    ..default()
+                });
+                arrow = Some(
+                    parent
+                        .spawn_on_hud_layer(SpriteSheetBundle {
+                            sprite: TextureAtlasSprite {
+                                index: glyph_index('>').unwrap_or_default(),
+                                color: marker.color,
+                                ..default()
+                            },
+                            texture_atlas: font.atlas.clone(),
+                            ..default()
+                        })
+                        .id(),
+                );
+                label = Some(
+                    parent
+                        .spawn_bundle(BitmapTextBundle::new(
+                            BitmapText::new("", Color::WHITE, BitmapAlign::Center),
+                            Vec2::new(0.0, -5.0),
+                        ))
+                        .id(),
+                );
+            })
+            .insert(MarkerIcon {
+                target,
+                arrow: arrow.unwrap(),
+                label: label.unwrap(),
+            });
+    }
+}
+
+/// Projects every marked entity onto the render target and places its icon.
+pub fn update_marker_icons(
+    mut commands: Commands,
+    cameras: Query<(&Camera, &GlobalTransform), With<PlayerCamera>>,
+    markers: Query<&GlobalTransform, With<Marker>>,
+    mut icons: Query<(Entity, &MarkerIcon, &mut Transform)>,
+    mut parts: Query<(&mut Transform, &mut Visibility), Without<MarkerIcon>>,
+    mut labels: Query<&mut BitmapText>,
+) {
+    let (camera, camera_transform) = match cameras.get_single() {
+        Ok(camera) => camera,
+        Err(_) => return,
+    };
+    let view = camera_transform.compute_matrix().inverse();
+    let half = 0.5 * Vec2::new(RENDER_SIZE[0] as f32, RENDER_SIZE[1] as f32);
+    let edge = half - EDGE_MARGIN;
+
+    for (entity, icon, mut transform) in &mut icons {
+        let target = match markers.get(icon.target) {
+            Ok(target) => target.translation(),
+            Err(_) => {
+                commands.entity(entity).despawn_recursive();
+                continue;
+            }
+        };
+
+        // The camera looks down its -Z.
+        let local = view.transform_point3(target);
+        let ndc = camera.projection_matrix().project_point3(local);
+        let on_screen = local.z < 0.0 && ndc.x.abs() <= 1.0 && ndc.y.abs() <= 1.0;
+
+        let position = if on_screen {
+            ndc.truncate() * half
+        } else {
+            // Behind the camera the projection flips, but the view direction still points the way.
+            let direction = local.truncate().try_normalize().unwrap_or(Vec2::NEG_Y);
+            let scale = (edge.x / direction.x.abs()).min(edge.y / direction.y.abs());
+            direction * scale
+        };
+        transform.translation = position.round().extend(0.0);
+
+        if let Ok((mut arrow, mut visibility)) = parts.get_mut(icon.arrow) {
+            visibility.is_visible = !on_screen;
+            let direction = position.normalize_or_zero();
+            arrow.translation = (4.0 * direction).round().extend(0.0);
+            arrow.rotation = Quat::from_rotation_z(direction.y.atan2(direction.x));
+        }
+        if let Ok((_, mut visibility)) = parts.get_mut(icon.label) {
+            visibility.is_visible = !on_screen;
+        }
+        if let Ok(mut label) = labels.get_mut(icon.label) {
+            let distance = format!(
+                "{}M",
+                (target - camera_transform.translation()).length() as u32
+            );
+            if label.value != distance {
+                label.value = distance;
+            }
+        }
+    }
+}
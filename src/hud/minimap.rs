@@ -0,0 +1,263 @@
+//! A top-down map in the corner of the HUD.
+//!
+//! An orthographic camera above the player draws the level, plus flat icons that only it sees,
+//! into a small image; a sprite on the HUD layer shows that image. [`MinimapSettings`] holds the
+//! zoom and whether the map turns with the player. `-` and `=` zoom, `M` toggles turning.
+
+use crate::{
+    accessibility::AccessibilitySettings,
+    input_block::InputBlock,
+    layers::{self, SpawnOnLayerExt},
+    level::Goal,
+    CatchObject, Player, RENDER_SIZE,
+};
+use bevy::{
+    core_pipeline::clear_color::ClearColorConfig,
+    prelude::*,
+    reflect::TypeUuid,
+    render::{
+        camera::{Projection, RenderTarget, ScalingMode},
+        render_resource::*,
+        texture::{BevyDefault, ImageSampler},
+    },
+};
+
+/// Side of the minimap, in render target pixels.
+const MINIMAP_SIZE: u32 = 48;
+const MINIMAP_MARGIN: f32 = 4.0;
+const MINIMAP_IMAGE_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Image::TYPE_UUID, 1145141919812);
+
+const CAMERA_HEIGHT: f32 = 60.0;
+/// Icons float just under the camera so that roofs don't hide them.
+const ICON_HEIGHT: f32 = CAMERA_HEIGHT - 2.0;
+/// Size of an icon, as a fraction of the visible width.
+const ICON_SIZE: f32 = 0.08;
+
+const MIN_ZOOM: f32 = 5.0;
+const MAX_ZOOM: f32 = 80.0;
+const ZOOM_STEP: f32 = 1.25;
+
+pub struct MinimapSettings {
+    /// Half the width of ground shown, in meters.
+    pub zoom: f32,
+    /// Keeps the player's facing up instead of north.
+    pub follow_rotation: bool,
+}
+
+impl Default for MinimapSettings {
+    fn default() -> Self {
+        Self {
+            zoom: 20.0,
+            follow_rotation: true,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct MinimapCamera;
+
+/// A flat marker drawn over `target` on the minimap.
+#[derive(Component)]
+pub struct MinimapIcon {
+    pub target: Entity,
+}
+
+pub struct MinimapAssets {
+    mesh: Handle<Mesh>,
+    player: Handle<StandardMaterial>,
+    object: Handle<StandardMaterial>,
+    goal: Handle<StandardMaterial>,
+}
+
+impl FromWorld for MinimapAssets {
+    fn from_world(world: &mut World) -> Self {
+        let mesh = world
+            .resource_mut::<Assets<Mesh>>()
+            .add(Mesh::from(shape::Plane { size: 1.0 }));
+        let palette = world.resource::<AccessibilitySettings>().palette();
+        let mut materials = world.resource_mut::<Assets<StandardMaterial>>();
+        let mut icon = |color: Color| {
+            materials.add(StandardMaterial {
+                base_color: color,
+                unlit: true,
+                ..default()
+            })
+        };
+        Self {
+            mesh,
+            player: icon(palette.player),
+            object: icon(Color::rgb(0.6, 0.7, 0.8)),
+            goal: icon(palette.goal),
+        }
+    }
+}
+
+pub fn setup_minimap(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    let size = Extent3d {
+        width: MINIMAP_SIZE,
+        height: MINIMAP_SIZE,
+        ..default()
+    };
+    let mut image = Image {
+        texture_descriptor: TextureDescriptor {
+            label: None,
+            size,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::bevy_default(),
+            mip_level_count: 1,
+            sample_count: 1,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::RENDER_ATTACHMENT,
+        },
+        sampler_descriptor: ImageSampler::Descriptor(SamplerDescriptor {
+            mag_filter: FilterMode::Nearest,
+            min_filter: FilterMode::Nearest,
+            mipmap_filter: FilterMode::Nearest,
+            ..default()
+        }),
+        ..default()
+    };
+    image.resize(size);
+    let image_handle = images.set(MINIMAP_IMAGE_HANDLE, image);
+
+    // Plain forward rendering is plenty for a map, and it has to finish before the HUD draws it.
+    commands
+        .spawn_bundle(Camera3dBundle {
+            camera: Camera {
+                priority: -3,
+                target: RenderTarget::Image(image_handle.clone()),
+                ..default()
+            },
+            camera_3d: Camera3d {
+                clear_color: ClearColorConfig::Custom(Color::rgb(0.05, 0.05, 0.08)),
+                ..default()
+            },
+            projection: Projection::Orthographic(OrthographicProjection {
+                left: -1.0,
+                right: 1.0,
+                bottom: -1.0,
+                top: 1.0,
+                far: 2.0 * CAMERA_HEIGHT,
+                scaling_mode: ScalingMode::None,
+                ..default()
+            }),
+            transform: Transform::from_xyz(0.0, CAMERA_HEIGHT, 0.0)
+                .looking_at(Vec3::ZERO, -Vec3::Z),
+            ..default()
+        })
+        .insert(layers::MINIMAP_VIEW)
+        .insert(MinimapCamera);
+
+    let half = 0.5 * Vec2::new(RENDER_SIZE[0] as f32, RENDER_SIZE[1] as f32);
+    let position = half - Vec2::splat(0.5 * MINIMAP_SIZE as f32 + MINIMAP_MARGIN);
+    commands
+        .spawn_on_hud_layer(SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgba(0.0, 0.0, 0.0, 0.6),
+                custom_size: Some(Vec2::splat(MINIMAP_SIZE as f32 + 2.0)),
+                ..default()
+            },
+            transform: Transform::from_translation(position.extend(0.0)),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn_on_hud_layer(SpriteBundle {
+                texture: image_handle,
+                transform: Transform::from_xyz(0.0, 0.0, 0.1),
+                ..default()
+            });
+        });
+}
+
+pub fn control_minimap(
+    keys: Res<Input<KeyCode>>,
+    block: Res<InputBlock>,
+    mut settings: ResMut<MinimapSettings>,
+) {
+    if block.typing() {
+        return;
+    }
+    if keys.just_pressed(KeyCode::Minus) {
+        settings.zoom = (settings.zoom * ZOOM_STEP).min(MAX_ZOOM);
+    }
+    if keys.just_pressed(KeyCode::Equals) {
+        settings.zoom = (settings.zoom / ZOOM_STEP).max(MIN_ZOOM);
+    }
+    if keys.just_pressed(KeyCode::M) {
+        settings.follow_rotation = !settings.follow_rotation;
+    }
+}
+
+pub fn attach_minimap_icons(
+    mut commands: Commands,
+    assets: Res<MinimapAssets>,
+    players: Query<Entity, Added<Player>>,
+    objects: Query<Entity, Added<CatchObject>>,
+    goals: Query<Entity, Added<Goal>>,
+) {
+    let icons = players
+        .iter()
+        .map(|entity| (entity, &assets.player))
+        .chain(objects.iter().map(|entity| (entity, &assets.object)))
+        .chain(goals.iter().map(|entity| (entity, &assets.goal)));
+    for (target, material) in icons {
+        commands
+            .spawn_bundle(PbrBundle {
+                mesh: assets.mesh.clone(),
+                material: material.clone(),
+                ..default()
+            })
+            .insert(layers::MINIMAP)
+            .insert(MinimapIcon { target });
+    }
+}
+
+/// Moves the camera over the player and keeps the icons on their targets, at a constant size.
+pub fn follow_minimap(
+    mut commands: Commands,
+    settings: Res<MinimapSettings>,
+    players: Query<&GlobalTransform, With<Player>>,
+    targets: Query<&GlobalTransform, Without<MinimapIcon>>,
+    mut cameras: Query<
+        (&mut Transform, &mut Projection),
+        (With<MinimapCamera>, Without<MinimapIcon>),
+    >,
+    mut icons: Query<(Entity, &MinimapIcon, &mut Transform)>,
+) {
+    let player = match players.get_single() {
+        Ok(player) => player,
+        Err(_) => return,
+    };
+    let (_, rotation, center) = player.to_scale_rotation_translation();
+    let up = if settings.follow_rotation {
+        rotation * -Vec3::Z
+    } else {
+        -Vec3::Z
+    };
+
+    for (mut transform, mut projection) in &mut cameras {
+        *transform =
+            Transform::from_translation(center + CAMERA_HEIGHT * Vec3::Y).looking_at(center, up);
+        if let Projection::Orthographic(projection) = &mut *projection {
+            projection.scale = settings.zoom;
+        }
+    }
+
+    let size = 2.0 * ICON_SIZE * settings.zoom;
+    for (entity, icon, mut transform) in &mut icons {
+        match targets.get(icon.target) {
+            Ok(target) => {
+                let (_, rotation, translation) = target.to_scale_rotation_translation();
+                let (yaw, _, _) = rotation.to_euler(EulerRot::YXZ);
+                *transform = Transform {
+                    translation: Vec3::new(translation.x, center.y + ICON_HEIGHT, translation.z),
+                    rotation: Quat::from_rotation_y(yaw),
+                    scale: Vec3::splat(size),
+                };
+            }
+            Err(_) => commands.entity(entity).despawn(),
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use crate::RENDER_SIZE;
+use bevy::{diagnostic::FrameTimeDiagnosticsPlugin, prelude::*, sprite::Material2dPlugin};
+
+pub mod bitmap;
+pub mod composite;
+pub mod crosshair;
+pub mod feed;
+pub mod indicator;
+pub mod marker;
+pub mod minimap;
+pub mod overlay;
+pub mod perf;
+pub mod pip;
+pub mod popup;
+pub mod radial;
+pub mod stamina;
+pub mod style;
+
+pub const HUD_FONT: &str = "fonts/DejaVuSansMono.ttf";
+
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(Material2dPlugin::<composite::CompositeMaterial>::default())
+            .add_plugin(Material2dPlugin::<overlay::OverlayMaterial>::default())
+            .add_plugin(FrameTimeDiagnosticsPlugin)
+            .init_resource::<HudFont>()
+            .init_resource::<bitmap::BitmapFont>()
+            .init_resource::<composite::HudEffects>()
+            .init_resource::<composite::WatchedShaders>()
+            .init_resource::<feed::EventFeed>()
+            .init_resource::<minimap::MinimapSettings>()
+            .init_resource::<minimap::MinimapAssets>()
+            .init_resource::<pip::GoalHistory>()
+            .init_resource::<pip::GoalReplay>()
+            .init_resource::<radial::RadialMenu>()
+            .add_event::<overlay::FlashEvent>()
+            .add_event::<popup::PopupEvent>()
+            .add_startup_system(crosshair::setup_crosshair)
+            .add_startup_system(feed::setup_event_feed)
+            .add_startup_system(minimap::setup_minimap)
+            .add_startup_system(overlay::setup_overlay)
+            .add_startup_system(perf::setup_perf_overlay)
+            .add_startup_system(pip::setup_pip)
+            .add_startup_system(popup::setup_popup_pool)
+            .add_startup_system(radial::setup_radial_menu)
+            .add_startup_system(stamina::setup_stamina_meter)
+            .add_startup_system(style::setup_style_gauge)
+            .add_system(composite::apply_hud_effects)
+            .add_system(composite::log_shader_reloads)
+            .add_system(crosshair::update_crosshair)
+            .add_system(feed::update_event_feed)
+            .add_system(feed::show_event_feed.after(feed::update_event_feed))
+            .add_system(indicator::spawn_cue_indicators)
+            .add_system(indicator::update_cue_indicators.after(indicator::spawn_cue_indicators))
+            .add_system(marker::mark_goals)
+            .add_system(marker::mark_tagged)
+            .add_system(
+                marker::spawn_marker_icons
+                    .after(marker::mark_goals)
+                    .after(marker::mark_tagged),
+            )
+            .add_system(marker::update_marker_icons.after(marker::spawn_marker_icons))
+            .add_system(minimap::control_minimap)
+            .add_system(minimap::attach_minimap_icons)
+            .add_system(minimap::follow_minimap.after(minimap::control_minimap))
+            .add_system(overlay::update_overlay)
+            .add_system(perf::update_perf_overlay)
+            .add_system(popup::goal_popups)
+            .add_system(popup::show_popups.after(popup::goal_popups))
+            .add_system(popup::animate_popups.after(popup::show_popups))
+            .add_system(pip::record_goal_history)
+            .add_system(pip::start_goal_replay.after(pip::record_goal_history))
+            .add_system(pip::play_goal_replay.after(pip::start_goal_replay))
+            .add_system(radial::steer_radial_menu)
+            .add_system(radial::show_radial_menu.after(radial::steer_radial_menu))
+            .add_system(stamina::update_stamina_meter)
+            .add_system(style::update_style_gauge)
+            .add_system_to_stage(CoreStage::PostUpdate, bitmap::layout_bitmap_text);
+    }
+}
+
+/// Font shared by every HUD text.
+pub struct HudFont(pub Handle<Font>);
+
+impl FromWorld for HudFont {
+    fn from_world(world: &mut World) -> Self {
+        let asset_server = world.resource::<AssetServer>();
+        Self(asset_server.load(HUD_FONT))
+    }
+}
+
+impl HudFont {
+    pub fn style(&self, font_size: f32, color: Color) -> TextStyle {
+        TextStyle {
+            font: self.0.clone(),
+            font_size,
+            color,
+        }
+    }
+}
+
+/// Pixel position of `position` on the render target, measured from its center, unless it is
+/// behind the camera.
+pub fn world_to_hud(camera: &Camera, transform: &GlobalTransform, position: Vec3) -> Option<Vec2> {
+    let local = transform
+        .compute_matrix()
+        .inverse()
+        .transform_point3(position);
+    if local.z >= 0.0 {
+        return None;
+    }
+    let ndc = camera.projection_matrix().project_point3(local);
+    let half = 0.5 * Vec2::new(RENDER_SIZE[0] as f32, RENDER_SIZE[1] as f32);
+    Some(ndc.truncate() * half)
+}
@@ -0,0 +1,114 @@
+//! Health feedback over the whole screen: a red vignette that deepens as health runs low, and a
+//! white flash on pickups.
+//!
+//! Both live in [`OverlayMaterial`], on a quad just above the composite one, so they go through
+//! the post-process chain like the rest of the picture. The values ease towards their targets
+//! instead of jumping, and with reduced motion on the flash stays faint.
+
+use super::composite::WatchedShaders;
+use crate::{accessibility::AccessibilitySettings, death::Health, layers, post, Player};
+use bevy::{
+    prelude::*,
+    reflect::TypeUuid,
+    render::render_resource::{AsBindGroup, ShaderRef, ShaderType},
+    sprite::{Material2d, MaterialMesh2dBundle},
+};
+
+pub const OVERLAY_SHADER: &str = "shaders/overlay.wgsl";
+/// Health fraction below which the vignette starts to show.
+const HURT_THRESHOLD: f32 = 0.6;
+/// Vignette strength at no health left.
+const MAX_HURT: f32 = 0.8;
+/// How fast the vignette follows health, in strength per second.
+const HURT_EASE: f32 = 1.5;
+/// How fast the flash fades, in alpha per second.
+const FLASH_DECAY: f32 = 4.0;
+const FLASH_ALPHA: f32 = 0.6;
+/// Flash strength with reduced motion on.
+const REDUCED_FLASH_ALPHA: f32 = 0.15;
+
+/// Flashes the screen white, for pickups.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FlashEvent;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, ShaderType)]
+pub struct OverlayParams {
+    pub hurt: f32,
+    pub flash: f32,
+}
+
+#[derive(Debug, Clone, AsBindGroup, TypeUuid)]
+#[uuid = "b2e6f0a3-5c18-4d7e-9f24-6a1d3e8c7b59"]
+pub struct OverlayMaterial {
+    #[uniform(0)]
+    pub params: OverlayParams,
+}
+
+impl Material2d for OverlayMaterial {
+    fn fragment_shader() -> ShaderRef {
+        OVERLAY_SHADER.into()
+    }
+}
+
+pub fn setup_overlay(
+    mut commands: Commands,
+    windows: Res<Windows>,
+    asset_server: Res<AssetServer>,
+    mut shaders: ResMut<WatchedShaders>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<OverlayMaterial>>,
+) {
+    shaders.0.push(asset_server.load(OVERLAY_SHADER));
+    if windows.get_primary().is_none() {
+        return;
+    }
+    commands
+        .spawn_bundle(MaterialMesh2dBundle {
+            mesh: meshes
+                .add(shape::Quad::new(post::post_size()).into())
+                .into(),
+            material: materials.add(OverlayMaterial { params: default() }),
+            transform: Transform::from_xyz(0.0, 0.0, 2.0),
+            ..default()
+        })
+        .insert(layers::COMPOSITE);
+}
+
+pub fn update_overlay(
+    time: Res<Time>,
+    settings: Res<AccessibilitySettings>,
+    mut flashes: EventReader<FlashEvent>,
+    mut params: Local<OverlayParams>,
+    mut materials: ResMut<Assets<OverlayMaterial>>,
+    players: Query<&Health, With<Player>>,
+    quads: Query<&Handle<OverlayMaterial>>,
+) {
+    let delta = time.delta_seconds();
+    let target_hurt = match players.get_single() {
+        Ok(health) => MAX_HURT * (1.0 - health.fraction() / HURT_THRESHOLD).max(0.0),
+        Err(_) => 0.0,
+    };
+    let flash_alpha = if settings.reduce_motion {
+        REDUCED_FLASH_ALPHA
+    } else {
+        FLASH_ALPHA
+    };
+    params.hurt += (target_hurt - params.hurt).clamp(-HURT_EASE * delta, HURT_EASE * delta);
+    params.flash = if flashes.iter().count() > 0 {
+        flash_alpha
+    } else {
+        (params.flash - FLASH_DECAY * delta).max(0.0)
+    };
+
+    for handle in &quads {
+        // As with the composite, only touch the material when there is something new to upload.
+        if materials
+            .get(handle)
+            .map_or(false, |material| material.params != *params)
+        {
+            if let Some(material) = materials.get_mut(handle) {
+                material.params = *params;
+            }
+        }
+    }
+}
@@ -0,0 +1,75 @@
+//! The perf overlay, a few counters in the corner of the HUD. F4 toggles it.
+
+use crate::{
+    activity::ActivityStats,
+    hud::bitmap::{BitmapAlign, BitmapText, BitmapTextBundle},
+    layers::SpawnOnLayerExt,
+    lifetime::CleanupStats,
+    lod::LodStats,
+    RENDER_SIZE,
+};
+use bevy::{
+    diagnostic::{Diagnostics, FrameTimeDiagnosticsPlugin},
+    prelude::*,
+};
+
+const OVERLAY_MARGIN: f32 = 4.0;
+
+#[derive(Component)]
+pub struct PerfOverlay;
+
+pub fn setup_perf_overlay(mut commands: Commands) {
+    let half = 0.5 * Vec2::new(RENDER_SIZE[0] as f32, RENDER_SIZE[1] as f32);
+    let position = Vec2::new(-half.x + OVERLAY_MARGIN, half.y - OVERLAY_MARGIN);
+    commands
+        .spawn_on_hud_layer(BitmapTextBundle::new(
+            BitmapText::new("", Color::rgb(0.6, 1.0, 0.6), BitmapAlign::Left),
+            position,
+        ))
+        .insert(PerfOverlay);
+}
+
+pub fn update_perf_overlay(
+    keys: Res<Input<KeyCode>>,
+    mut shown: Local<bool>,
+    diagnostics: Res<Diagnostics>,
+    activity: Res<ActivityStats>,
+    lod: Res<LodStats>,
+    cleanup: Res<CleanupStats>,
+    mut overlays: Query<&mut BitmapText, With<PerfOverlay>>,
+) {
+    if keys.just_pressed(KeyCode::F4) {
+        *shown = !*shown;
+    }
+    for mut text in &mut overlays {
+        if !*shown {
+            if !text.value.is_empty() {
+                text.value.clear();
+            }
+            continue;
+        }
+
+        let fps = diagnostics
+            .get(FrameTimeDiagnosticsPlugin::FPS)
+            .and_then(|fps| fps.average())
+            .unwrap_or_default();
+        let value = format!(
+            "FPS {:.0}\nAWAKE {} ASLEEP {}\nSLEPT {} WOKE {}\nLOD SWAPS {}\n\
+             ENTITIES {} TIMED {}\nEXPIRED {} GC {}",
+            fps,
+            activity.awake,
+            activity.asleep,
+            activity.forced,
+            activity.woken,
+            lod.swaps,
+            cleanup.entities,
+            cleanup.timed,
+            cleanup.expired,
+            cleanup.collected
+        );
+        // Only touch the text when it changed, or the glyphs are laid out every frame.
+        if text.value != value {
+            text.value = value;
+        }
+    }
+}
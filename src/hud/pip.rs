@@ -0,0 +1,295 @@
+//! A picture-in-picture replay of the last goal.
+//!
+//! Poses of every catch object are kept for the last [`HISTORY`] seconds. When a goal is scored,
+//! a stand-in for the scoring object flies its last moments again on a layer only the replay
+//! camera sees, filmed from the side of its path, and a small window in the bottom left corner of
+//! the HUD shows the result until the replay is over.
+
+use super::bitmap::{BitmapAlign, BitmapText, BitmapTextBundle};
+use crate::{
+    layers::{self, SpawnOnLayerExt},
+    level::trigger::GoalReached,
+    locale::Locale,
+    replay::BodySnapshot,
+    CatchObject, CUBE_SIZE, RENDER_SIZE,
+};
+use bevy::{
+    prelude::*,
+    reflect::TypeUuid,
+    render::{
+        camera::RenderTarget,
+        render_resource::*,
+        texture::{BevyDefault, ImageSampler},
+    },
+};
+use std::collections::VecDeque;
+
+/// Seconds of history kept, and so the longest replay.
+const HISTORY: f32 = 3.0;
+/// The window stays up this long after the replay ends, in seconds.
+const HOLD_TIME: f32 = 0.5;
+/// Size of the window, in render target pixels.
+const PIP_SIZE: [u32; 2] = [96, 54];
+const PIP_MARGIN: f32 = 4.0;
+const PIP_IMAGE_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Image::TYPE_UUID, 1145141919813);
+/// Closest the camera films from, in meters.
+const MIN_CAMERA_DISTANCE: f32 = 6.0;
+
+/// Poses of the catch objects over the last few seconds, oldest first.
+#[derive(Default)]
+pub struct GoalHistory {
+    frames: VecDeque<(f64, Vec<(Entity, BodySnapshot)>)>,
+}
+
+/// The replay being shown, if any.
+#[derive(Default)]
+pub struct GoalReplay {
+    /// Poses of the scoring object, with their time from the start of the replay.
+    track: Vec<(f32, BodySnapshot)>,
+    elapsed: f32,
+}
+
+impl GoalReplay {
+    fn duration(&self) -> f32 {
+        self.track.last().map_or(0.0, |(time, _)| *time)
+    }
+
+    pub fn is_playing(&self) -> bool {
+        !self.track.is_empty() && self.elapsed < self.duration() + HOLD_TIME
+    }
+
+    /// Where the object was `elapsed` seconds into the replay.
+    fn sample(&self, elapsed: f32) -> Option<Transform> {
+        let last = self.track.len().checked_sub(1)?;
+        let next = self
+            .track
+            .iter()
+            .position(|(time, _)| *time >= elapsed)
+            .unwrap_or(last);
+        let (start, from) = self.track[next.saturating_sub(1)];
+        let (end, to) = self.track[next];
+        let t = if end > start {
+            ((elapsed - start) / (end - start)).clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+
+        let (mut a, mut b) = (Transform::default(), Transform::default());
+        from.apply(&mut a);
+        to.apply(&mut b);
+        Some(Transform {
+            translation: a.translation.lerp(b.translation, t),
+            rotation: a.rotation.slerp(b.rotation, t),
+            ..a
+        })
+    }
+}
+
+#[derive(Component)]
+pub struct PipCamera;
+
+#[derive(Component)]
+pub struct PipStandIn;
+
+#[derive(Component)]
+pub struct PipWindow;
+
+#[derive(Component)]
+pub struct PipLabel;
+
+pub fn setup_pip(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let size = Extent3d {
+        width: PIP_SIZE[0],
+        height: PIP_SIZE[1],
+        ..default()
+    };
+    let mut image = Image {
+        texture_descriptor: TextureDescriptor {
+            label: None,
+            size,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::bevy_default(),
+            mip_level_count: 1,
+            sample_count: 1,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::RENDER_ATTACHMENT,
+        },
+        sampler_descriptor: ImageSampler::Descriptor(SamplerDescriptor {
+            mag_filter: FilterMode::Nearest,
+            min_filter: FilterMode::Nearest,
+            mipmap_filter: FilterMode::Nearest,
+            ..default()
+        }),
+        ..default()
+    };
+    image.resize(size);
+    let image_handle = images.set(PIP_IMAGE_HANDLE, image);
+
+    // Forward rendering like the minimap, and only while a replay runs.
+    commands
+        .spawn_bundle(Camera3dBundle {
+            camera: Camera {
+                priority: -3,
+                target: RenderTarget::Image(image_handle.clone()),
+                is_active: false,
+                ..default()
+            },
+            ..default()
+        })
+        .insert(layers::INSET_VIEW)
+        .insert(PipCamera);
+
+    commands
+        .spawn_bundle(PbrBundle {
+            mesh: meshes.add(shape::Cube::new(CUBE_SIZE).into()),
+            material: materials.add(StandardMaterial {
+                base_color: Color::rgb(1.0, 0.85, 0.4),
+                emissive: Color::rgb(0.5, 0.4, 0.1),
+                ..default()
+            }),
+            visibility: Visibility { is_visible: false },
+            ..default()
+        })
+        .insert(layers::UI_3D)
+        .insert(PipStandIn);
+
+    let half = 0.5 * Vec2::new(RENDER_SIZE[0] as f32, RENDER_SIZE[1] as f32);
+    let window = Vec2::new(PIP_SIZE[0] as f32, PIP_SIZE[1] as f32);
+    let position = -half + 0.5 * window + Vec2::splat(PIP_MARGIN);
+    commands
+        .spawn_on_hud_layer(SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgba(0.0, 0.0, 0.0, 0.6),
+                custom_size: Some(window + 2.0),
+                ..default()
+            },
+            transform: Transform::from_translation(position.extend(0.0)),
+            visibility: Visibility { is_visible: false },
+            ..default()
+        })
+        .insert(PipWindow)
+        .with_children(|parent| {
+            parent.spawn_on_hud_layer(SpriteBundle {
+                texture: image_handle,
+                transform: Transform::from_xyz(0.0, 0.0, 0.1),
+                ..default()
+            });
+            parent
+                .spawn_on_hud_layer(BitmapTextBundle::new(
+                    BitmapText::new("", Color::WHITE, BitmapAlign::Left),
+                    Vec2::new(3.0 - 0.5 * window.x, 0.5 * window.y - 5.0),
+                ))
+                .insert(PipLabel);
+        });
+}
+
+pub fn record_goal_history(
+    time: Res<Time>,
+    mut history: ResMut<GoalHistory>,
+    objects: Query<(Entity, &Transform), With<CatchObject>>,
+) {
+    let now = time.seconds_since_startup();
+    let poses = objects
+        .iter()
+        .map(|(entity, transform)| (entity, BodySnapshot::from(transform)))
+        .collect();
+    history.frames.push_back((now, poses));
+    while let Some((time, _)) = history.frames.front() {
+        if now - time <= HISTORY as f64 {
+            break;
+        }
+        history.frames.pop_front();
+    }
+}
+
+/// Starts a replay of the scoring object, and points the camera at its path from the side.
+pub fn start_goal_replay(
+    locale: Res<Locale>,
+    history: Res<GoalHistory>,
+    mut replay: ResMut<GoalReplay>,
+    mut events: EventReader<GoalReached>,
+    mut cameras: Query<&mut Transform, With<PipCamera>>,
+    mut labels: Query<&mut BitmapText, With<PipLabel>>,
+) {
+    let event = match events.iter().last() {
+        Some(event) => event,
+        None => return,
+    };
+    let track: Vec<_> = history
+        .frames
+        .iter()
+        .filter_map(|(time, poses)| {
+            poses
+                .iter()
+                .find(|(entity, _)| *entity == event.object)
+                .map(|(_, pose)| (*time, *pose))
+        })
+        .collect();
+    if track.len() < 2 {
+        return;
+    }
+    let start = track[0].0;
+    let track: Vec<_> = track
+        .into_iter()
+        .map(|(time, pose)| ((time - start) as f32, pose))
+        .collect();
+
+    let first = Vec3::from_array(track[0].1.translation);
+    let last = Vec3::from_array(track[track.len() - 1].1.translation);
+    let middle = 0.5 * (first + last);
+    let span = Vec3::new(last.x - first.x, 0.0, last.z - first.z);
+    let side = span.normalize_or_zero().cross(Vec3::Y);
+    let side = if side == Vec3::ZERO { Vec3::X } else { side };
+    let distance = span.length().max(MIN_CAMERA_DISTANCE);
+    for mut transform in &mut cameras {
+        *transform = Transform::from_translation(middle + distance * (side + 0.3 * Vec3::Y))
+            .looking_at(middle, Vec3::Y);
+    }
+
+    for mut label in &mut labels {
+        label.value = locale.get("goal-replay");
+    }
+    *replay = GoalReplay {
+        track,
+        elapsed: 0.0,
+    };
+}
+
+#[allow(clippy::type_complexity)]
+pub fn play_goal_replay(
+    time: Res<Time>,
+    mut replay: ResMut<GoalReplay>,
+    mut cameras: Query<&mut Camera, With<PipCamera>>,
+    mut stand_ins: Query<(&mut Transform, &mut Visibility), With<PipStandIn>>,
+    mut windows: Query<&mut Visibility, (With<PipWindow>, Without<PipStandIn>)>,
+) {
+    let was_playing = replay.is_playing();
+    if was_playing {
+        replay.elapsed += time.delta_seconds();
+    }
+    let playing = replay.is_playing();
+    if !playing && !was_playing {
+        return;
+    }
+
+    for mut camera in &mut cameras {
+        camera.is_active = playing;
+    }
+    for mut visibility in &mut windows {
+        visibility.is_visible = playing;
+    }
+    let pose = replay.sample(replay.elapsed.min(replay.duration()));
+    for (mut transform, mut visibility) in &mut stand_ins {
+        visibility.is_visible = playing;
+        if let Some(pose) = pose {
+            *transform = pose;
+        }
+    }
+}
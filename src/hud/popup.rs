@@ -0,0 +1,154 @@
+//! Floating text over the world, for score popups, pickup names and the like.
+//!
+//! Send a [`PopupEvent`] with a world position; the text is drawn with the pixel font where that
+//! position lands on screen, rising and fading out. Popups come from a fixed pool of text
+//! entities, so a busy moment reuses the oldest one instead of spawning more.
+
+use super::{
+    bitmap::{BitmapAlign, BitmapText, BitmapTextBundle},
+    world_to_hud,
+};
+use crate::{
+    accessibility::AccessibilitySettings, layers::SpawnOnLayerExt, level::trigger::GoalReached,
+    locale::Locale, PlayerCamera, RENDER_SIZE,
+};
+use bevy::prelude::*;
+
+const POOL_SIZE: usize = 16;
+/// Seconds a popup stays up.
+const LIFETIME: f32 = 1.2;
+const FADE_TIME: f32 = 0.4;
+/// How fast popups rise, in meters per second.
+const RISE_SPEED: f32 = 1.0;
+
+#[derive(Debug, Clone)]
+pub struct PopupEvent {
+    pub text: String,
+    pub position: Vec3,
+    pub color: Color,
+}
+
+impl PopupEvent {
+    pub fn new(text: impl Into<String>, position: Vec3) -> Self {
+        Self {
+            text: text.into(),
+            position,
+            color: Color::WHITE,
+        }
+    }
+
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+}
+
+#[derive(Default, Component)]
+pub struct Popup {
+    active: bool,
+    position: Vec3,
+    color: Color,
+    age: f32,
+}
+
+pub struct PopupPool(Vec<Entity>);
+
+pub fn setup_popup_pool(mut commands: Commands) {
+    let entities = (0..POOL_SIZE)
+        .map(|_| {
+            commands
+                .spawn_on_hud_layer(BitmapTextBundle::new(
+                    BitmapText::new("", Color::WHITE, BitmapAlign::Center),
+                    Vec2::ZERO,
+                ))
+                .insert(Popup::default())
+                .id()
+        })
+        .collect();
+    commands.insert_resource(PopupPool(entities));
+}
+
+pub fn show_popups(
+    pool: Res<PopupPool>,
+    mut events: EventReader<PopupEvent>,
+    mut popups: Query<(&mut Popup, &mut BitmapText)>,
+) {
+    for event in events.iter() {
+        // A free popup, or else the one closest to fading out anyway.
+        let entity = pool
+            .0
+            .iter()
+            .copied()
+            .filter_map(|entity| popups.get(entity).ok().map(|(popup, _)| (entity, popup)))
+            .max_by(|(_, a), (_, b)| {
+                let age = |popup: &Popup| if popup.active { popup.age } else { f32::MAX };
+                age(a).total_cmp(&age(b))
+            })
+            .map(|(entity, _)| entity);
+
+        if let Some(Ok((mut popup, mut text))) = entity.map(|entity| popups.get_mut(entity)) {
+            *popup = Popup {
+                active: true,
+                position: event.position,
+                color: event.color,
+                age: 0.0,
+            };
+            text.value = event.text.clone();
+            text.color = event.color;
+        }
+    }
+}
+
+pub fn animate_popups(
+    time: Res<Time>,
+    cameras: Query<(&Camera, &GlobalTransform), With<PlayerCamera>>,
+    mut popups: Query<(&mut Popup, &mut BitmapText, &mut Transform, &mut Visibility)>,
+) {
+    let camera = cameras.get_single().ok();
+    let half = 0.5 * Vec2::new(RENDER_SIZE[0] as f32, RENDER_SIZE[1] as f32);
+
+    for (mut popup, mut text, mut transform, mut visibility) in &mut popups {
+        if !popup.active {
+            visibility.is_visible = false;
+            continue;
+        }
+        popup.age += time.delta_seconds();
+        if popup.age >= LIFETIME {
+            popup.active = false;
+            visibility.is_visible = false;
+            continue;
+        }
+
+        let position = popup.position + RISE_SPEED * popup.age * Vec3::Y;
+        let screen = camera
+            .and_then(|(camera, camera_transform)| world_to_hud(camera, camera_transform, position))
+            .filter(|screen| screen.abs().cmple(half).all());
+        visibility.is_visible = screen.is_some();
+        if let Some(screen) = screen {
+            transform.translation = screen.round().extend(0.0);
+        }
+
+        let mut color = popup.color;
+        color.set_a(((LIFETIME - popup.age) / FADE_TIME).min(1.0) * popup.color.a());
+        if text.color != color {
+            text.color = color;
+        }
+    }
+}
+
+pub fn goal_popups(
+    locale: Res<Locale>,
+    settings: Res<AccessibilitySettings>,
+    transforms: Query<&GlobalTransform>,
+    mut goals: EventReader<GoalReached>,
+    mut popups: EventWriter<PopupEvent>,
+) {
+    for event in goals.iter() {
+        if let Ok(transform) = transforms.get(event.goal) {
+            popups.send(
+                PopupEvent::new(locale.get("popup-goal"), transform.translation())
+                    .with_color(settings.palette().goal),
+            );
+        }
+    }
+}
@@ -0,0 +1,165 @@
+//! A ring of choices around the crosshair, picked by pushing Look toward one.
+//!
+//! Whatever wants a choice opens the [`RadialMenu`] with its options and closes it again, usually
+//! when a held key comes up, getting back the option that was picked. While open, Look moves a
+//! cursor instead of the camera.
+
+use super::bitmap::{glyph_index, BitmapAlign, BitmapFont, BitmapText, BitmapTextBundle};
+use crate::{layers::SpawnOnLayerExt, Action, Player};
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::*;
+use std::f32::consts::TAU;
+
+pub const MAX_OPTIONS: usize = 8;
+/// Distance of the options from the center, in render target pixels.
+const RADIUS: f32 = 28.0;
+/// How far Look input moves the cursor, relative to the ring.
+const CURSOR_SCALE: f32 = 0.02;
+/// The cursor must be this far out, relative to the ring, to pick anything.
+const DEADZONE: f32 = 0.3;
+const SELECTED_SCALE: f32 = 2.0;
+const LABEL_OFFSET: f32 = -RADIUS - 12.0;
+
+/// What opened the menu.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RadialKind {
+    Stamp,
+    Ability,
+}
+
+#[derive(Debug, Clone)]
+pub struct RadialOption {
+    pub icon: char,
+    pub color: Color,
+    pub label: String,
+}
+
+#[derive(Default)]
+pub struct RadialMenu {
+    kind: Option<RadialKind>,
+    options: Vec<RadialOption>,
+    cursor: Vec2,
+    selected: Option<usize>,
+}
+
+impl RadialMenu {
+    /// Shows `options`, clockwise from the top; only the first [`MAX_OPTIONS`] fit.
+    pub fn open(&mut self, kind: RadialKind, mut options: Vec<RadialOption>) {
+        options.truncate(MAX_OPTIONS);
+        *self = Self {
+            kind: Some(kind),
+            options,
+            ..default()
+        };
+    }
+
+    /// Hides the menu, returning the index of the picked option.
+    pub fn close(&mut self) -> Option<usize> {
+        let selected = self.selected;
+        *self = Self::default();
+        selected
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.kind.is_some()
+    }
+
+    pub fn is_open_for(&self, kind: RadialKind) -> bool {
+        self.kind == Some(kind)
+    }
+}
+
+#[derive(Component)]
+pub struct RadialIcon(usize);
+
+#[derive(Component)]
+pub struct RadialLabel;
+
+pub fn setup_radial_menu(mut commands: Commands, font: Res<BitmapFont>) {
+    for index in 0..MAX_OPTIONS {
+        commands
+            .spawn_on_hud_layer(SpriteSheetBundle {
+                texture_atlas: font.atlas.clone(),
+                visibility: Visibility { is_visible: false },
+                ..default()
+            })
+            .insert(RadialIcon(index));
+    }
+    commands
+        .spawn_on_hud_layer(BitmapTextBundle::new(
+            BitmapText::new("", Color::WHITE, BitmapAlign::Center),
+            Vec2::new(0.0, LABEL_OFFSET),
+        ))
+        .insert(RadialLabel);
+}
+
+/// Moves the cursor with Look, and picks the option in its direction.
+pub fn steer_radial_menu(
+    mut menu: ResMut<RadialMenu>,
+    players: Query<&ActionState<Action>, With<Player>>,
+) {
+    if !menu.is_open() || menu.options.is_empty() {
+        return;
+    }
+    let action_state = match players.get_single() {
+        Ok(action_state) => action_state,
+        Err(_) => return,
+    };
+    let delta = action_state
+        .axis_pair(Action::Look)
+        .map_or(Vec2::ZERO, |axis| Vec2::new(axis.x(), -axis.y()));
+    menu.cursor = (menu.cursor + CURSOR_SCALE * delta).clamp_length_max(1.0);
+
+    if menu.cursor.length() > DEADZONE {
+        // Clockwise from the top, with each option in the middle of its slice.
+        let count = menu.options.len();
+        let angle = menu.cursor.x.atan2(menu.cursor.y).rem_euclid(TAU);
+        let slice = TAU / count as f32;
+        menu.selected = Some(((angle / slice + 0.5) as usize) % count);
+    }
+}
+
+fn option_position(index: usize, count: usize) -> Vec2 {
+    let angle = TAU * index as f32 / count as f32;
+    (RADIUS * Vec2::new(angle.sin(), angle.cos())).round()
+}
+
+pub fn show_radial_menu(
+    menu: Res<RadialMenu>,
+    mut icons: Query<(
+        &RadialIcon,
+        &mut TextureAtlasSprite,
+        &mut Transform,
+        &mut Visibility,
+    )>,
+    mut labels: Query<&mut BitmapText, With<RadialLabel>>,
+) {
+    if !menu.is_changed() {
+        return;
+    }
+    let count = menu.options.len();
+    for (icon, mut sprite, mut transform, mut visibility) in &mut icons {
+        let option = menu.options.get(icon.0);
+        visibility.is_visible = option.is_some();
+        if let Some(option) = option {
+            sprite.index = glyph_index(option.icon).unwrap_or_default();
+            sprite.color = option.color;
+            transform.translation = option_position(icon.0, count).extend(0.0);
+            transform.scale = if menu.selected == Some(icon.0) {
+                Vec3::splat(SELECTED_SCALE)
+            } else {
+                Vec3::ONE
+            };
+        }
+    }
+
+    let value = menu
+        .selected
+        .and_then(|index| menu.options.get(index))
+        .map_or_else(String::new, |option| option.label.clone());
+    for mut label in &mut labels {
+        if label.value != value {
+            label.value = value.clone();
+        }
+    }
+}
@@ -0,0 +1,69 @@
+//! The stamina meter, a bar under the crosshair that shows up while stamina isn't full.
+
+use crate::{layers::SpawnOnLayerExt, stamina::Stamina, Player, RENDER_SIZE};
+use bevy::prelude::*;
+
+const METER_SIZE: Vec2 = Vec2::new(40.0, 2.0);
+/// Distance of the meter from the bottom of the render target, in pixels.
+const METER_MARGIN: f32 = 12.0;
+
+#[derive(Component)]
+pub struct StaminaMeter;
+
+#[derive(Component)]
+pub struct StaminaFill;
+
+pub fn setup_stamina_meter(mut commands: Commands) {
+    let y = -0.5 * RENDER_SIZE[1] as f32 + METER_MARGIN;
+    commands
+        .spawn_on_hud_layer(SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgba(0.0, 0.0, 0.0, 0.6),
+                custom_size: Some(METER_SIZE + 2.0),
+                ..default()
+            },
+            transform: Transform::from_xyz(0.0, y, 0.0),
+            visibility: Visibility { is_visible: false },
+            ..default()
+        })
+        .insert(StaminaMeter)
+        .with_children(|parent| {
+            parent
+                .spawn_on_hud_layer(SpriteBundle {
+                    sprite: Sprite {
+                        custom_size: Some(METER_SIZE),
+                        ..default()
+                    },
+                    transform: Transform::from_xyz(0.0, 0.0, 0.1),
+                    ..default()
+                })
+                .insert(StaminaFill);
+        });
+}
+
+pub fn update_stamina_meter(
+    players: Query<&Stamina, With<Player>>,
+    mut meters: Query<&mut Visibility, With<StaminaMeter>>,
+    mut fills: Query<(&mut Sprite, &mut Transform), With<StaminaFill>>,
+) {
+    let stamina = match players.get_single() {
+        Ok(stamina) => stamina,
+        Err(_) => return,
+    };
+    let fraction = stamina.fraction();
+
+    for mut visibility in &mut meters {
+        visibility.is_visible = fraction < 1.0;
+    }
+    for (mut sprite, mut transform) in &mut fills {
+        // Whole pixels, shrinking towards the left end.
+        let width = (fraction * METER_SIZE.x).round();
+        sprite.custom_size = Some(Vec2::new(width, METER_SIZE.y));
+        transform.translation.x = 0.5 * (width - METER_SIZE.x);
+        sprite.color = if stamina.is_exhausted() {
+            Color::rgb(0.9, 0.3, 0.2)
+        } else {
+            Color::rgb(0.9, 0.9, 0.6)
+        };
+    }
+}
@@ -0,0 +1,91 @@
+//! The style gauge, a bar on the right edge with the rank letter above it. It shows up while the
+//! multiplier is up.
+
+use crate::{
+    hud::bitmap::{BitmapAlign, BitmapText, BitmapTextBundle},
+    layers::SpawnOnLayerExt,
+    style::StyleMeter,
+    RENDER_SIZE,
+};
+use bevy::prelude::*;
+
+const GAUGE_SIZE: Vec2 = Vec2::new(3.0, 40.0);
+/// Distance of the gauge from the right edge of the render target, in pixels.
+const GAUGE_MARGIN: f32 = 8.0;
+
+#[derive(Component)]
+pub struct StyleGauge;
+
+#[derive(Component)]
+pub struct StyleFill;
+
+#[derive(Component)]
+pub struct StyleRank;
+
+pub fn setup_style_gauge(mut commands: Commands) {
+    let x = 0.5 * RENDER_SIZE[0] as f32 - GAUGE_MARGIN;
+    commands
+        .spawn_on_hud_layer(SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgba(0.0, 0.0, 0.0, 0.6),
+                custom_size: Some(GAUGE_SIZE + 2.0),
+                ..default()
+            },
+            transform: Transform::from_xyz(x, 0.0, 0.0),
+            visibility: Visibility { is_visible: false },
+            ..default()
+        })
+        .insert(StyleGauge)
+        .with_children(|parent| {
+            parent
+                .spawn_on_hud_layer(SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::rgb(1.0, 0.5, 0.9),
+                        custom_size: Some(GAUGE_SIZE),
+                        ..default()
+                    },
+                    transform: Transform::from_xyz(0.0, 0.0, 0.1),
+                    ..default()
+                })
+                .insert(StyleFill);
+        });
+    commands
+        .spawn_bundle(BitmapTextBundle::new(
+            BitmapText::new("", Color::WHITE, BitmapAlign::Center),
+            Vec2::new(x, 0.5 * GAUGE_SIZE.y + 6.0),
+        ))
+        .insert(StyleRank);
+}
+
+pub fn update_style_gauge(
+    meter: Res<StyleMeter>,
+    mut gauges: Query<&mut Visibility, With<StyleGauge>>,
+    mut fills: Query<(&mut Sprite, &mut Transform), With<StyleFill>>,
+    mut ranks: Query<&mut BitmapText, With<StyleRank>>,
+) {
+    if !meter.is_changed() {
+        return;
+    }
+    let fraction = meter.fraction();
+    let shown = fraction > 0.0;
+
+    for mut visibility in &mut gauges {
+        visibility.is_visible = shown;
+    }
+    for (mut sprite, mut transform) in &mut fills {
+        // Whole pixels, filling up from the bottom.
+        let height = (fraction * GAUGE_SIZE.y).round();
+        sprite.custom_size = Some(Vec2::new(GAUGE_SIZE.x, height));
+        transform.translation.y = 0.5 * (height - GAUGE_SIZE.y);
+    }
+    let rank = if shown {
+        meter.rank().to_string()
+    } else {
+        String::new()
+    };
+    for mut text in &mut ranks {
+        if text.value != rank {
+            text.value = rank.clone();
+        }
+    }
+}
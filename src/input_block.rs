@@ -0,0 +1,57 @@
+//! What keeps the player's actions switched off.
+//!
+//! Chat, loading and cutscenes each turn actions off for their own reason, and their spans
+//! overlap: a level can finish loading while chat is open. Each sets or clears its own
+//! [`BlockReason`] on the [`InputBlock`], and actions only come back on once no reason holds.
+//! Hotkeys read straight off the keyboard ask [`InputBlock::typing`] instead, so typing a chat
+//! message never toggles anything.
+
+use crate::Action;
+use bevy::{prelude::*, utils::HashSet};
+use leafwing_input_manager::prelude::*;
+
+pub struct InputBlockPlugin;
+
+impl Plugin for InputBlockPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InputBlock>()
+            .add_system_to_stage(CoreStage::PostUpdate, apply_input_block);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BlockReason {
+    Chat,
+    Loading,
+    Cutscene,
+}
+
+#[derive(Debug, Default)]
+pub struct InputBlock {
+    reasons: HashSet<BlockReason>,
+}
+
+impl InputBlock {
+    pub fn set(&mut self, reason: BlockReason, blocked: bool) {
+        if blocked {
+            self.reasons.insert(reason);
+        } else {
+            self.reasons.remove(&reason);
+        }
+    }
+
+    pub fn is_blocked(&self) -> bool {
+        !self.reasons.is_empty()
+    }
+
+    /// Whether keys are text going into the chat box, which hotkeys should leave alone.
+    pub fn typing(&self) -> bool {
+        self.reasons.contains(&BlockReason::Chat)
+    }
+}
+
+fn apply_input_block(block: Res<InputBlock>, mut toggle_actions: ResMut<ToggleActions<Action>>) {
+    if block.is_changed() {
+        toggle_actions.enabled = !block.is_blocked();
+    }
+}
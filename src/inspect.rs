@@ -0,0 +1,145 @@
+//! Taking a closer look at a held object.
+//!
+//! Pressing Inspect while holding something brings it up in front of the camera and stops it
+//! simulating; Look then turns the object instead of the player. Pressing Inspect again eases it
+//! back to the catch point, where the hold picks it up again. Letting go of the object drops it
+//! right where it floats.
+
+use crate::{
+    hold::{CatchPoint, Held},
+    player_look, Action, Player, PlayerCamera, PlayerCatch,
+};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use leafwing_input_manager::prelude::*;
+
+/// How far in front of the camera an inspected object floats, in meters.
+const INSPECT_DISTANCE: f32 = 1.5;
+/// Time to move between the catch point and the camera, in seconds.
+const TRANSITION_TIME: f32 = 0.25;
+
+pub struct InspectPlugin;
+
+impl Plugin for InspectPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(toggle_inspect.before(player_look))
+            .add_system(update_inspect.after(toggle_inspect));
+    }
+}
+
+/// On the player while they inspect `object`.
+#[derive(Debug, Component)]
+pub struct Inspecting {
+    pub object: Entity,
+    /// How far the object is on its way to the camera, from 0 at the catch point to 1.
+    progress: f32,
+    leaving: bool,
+}
+
+fn toggle_inspect(
+    mut commands: Commands,
+    mut players: Query<
+        (
+            Entity,
+            &ActionState<Action>,
+            &PlayerCatch,
+            Option<&mut Inspecting>,
+        ),
+        With<Player>,
+    >,
+    held: Query<&Held>,
+) {
+    let (player, action_state, catch, inspecting) = match players.get_single_mut() {
+        Ok(player) => player,
+        Err(_) => return,
+    };
+    match inspecting {
+        Some(mut inspecting) => {
+            if catch.target != Some(inspecting.object) {
+                commands
+                    .entity(inspecting.object)
+                    .insert(RigidBody::Dynamic);
+                commands.entity(player).remove::<Inspecting>();
+            } else if action_state.just_pressed(Action::Inspect) {
+                inspecting.leaving = true;
+            }
+        }
+        None => {
+            let object = match catch.target {
+                Some(object) if action_state.just_pressed(Action::Inspect) => object,
+                _ => return,
+            };
+            if held.get(object).map_or(false, |held| held.holder == player) {
+                commands
+                    .entity(object)
+                    .insert(RigidBody::KinematicPositionBased);
+                commands.entity(player).insert(Inspecting {
+                    object,
+                    progress: 0.0,
+                    leaving: false,
+                });
+            }
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn update_inspect(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut players: Query<(Entity, &ActionState<Action>, &Player, &mut Inspecting)>,
+    cameras: Query<&GlobalTransform, With<PlayerCamera>>,
+    catchers: Query<(&GlobalTransform, &CatchPoint)>,
+    mut objects: Query<(&mut Transform, Option<&mut Velocity>)>,
+) {
+    let (player, action_state, settings, mut inspecting) = match players.get_single_mut() {
+        Ok(player) => player,
+        Err(_) => return,
+    };
+    let (camera, catch_point) = match (
+        cameras.get_single(),
+        catchers.iter().find(|(_, point)| point.0 == player),
+    ) {
+        (Ok(camera), Some((catcher, _))) => (camera.compute_transform(), catcher.translation()),
+        _ => return,
+    };
+    let (mut transform, velocity) = match objects.get_mut(inspecting.object) {
+        Ok(object) => object,
+        Err(_) => {
+            commands.entity(player).remove::<Inspecting>();
+            return;
+        }
+    };
+
+    let step = time.delta_seconds() / TRANSITION_TIME;
+    if inspecting.leaving {
+        inspecting.progress = (inspecting.progress - step).max(0.0);
+    } else {
+        inspecting.progress = (inspecting.progress + step).min(1.0);
+    }
+
+    // Look turns the object about the camera's axes, with the player's own sensitivity.
+    if !inspecting.leaving && action_state.pressed(Action::Look) {
+        let delta = action_state
+            .axis_pair(Action::Look)
+            .map_or(Vec2::ZERO, |axis| Vec2::new(axis.x(), axis.y()));
+        let turn = settings.sensitivity * delta;
+        transform.rotation = Quat::from_axis_angle(camera.up(), turn.x.to_radians())
+            * Quat::from_axis_angle(camera.right(), turn.y.to_radians())
+            * transform.rotation;
+    }
+
+    let front = camera.translation + camera.forward() * INSPECT_DISTANCE;
+    let t = inspecting.progress * inspecting.progress * (3.0 - 2.0 * inspecting.progress);
+    transform.translation = catch_point.lerp(front, t);
+
+    if inspecting.leaving && inspecting.progress <= 0.0 {
+        if let Some(mut velocity) = velocity {
+            *velocity = Velocity::default();
+        }
+        commands
+            .entity(inspecting.object)
+            .insert(RigidBody::Dynamic);
+        commands.entity(player).remove::<Inspecting>();
+    }
+}
@@ -0,0 +1,128 @@
+//! Inspector windows for gameplay resources, next to the world inspector.
+//!
+//! Each window edits one resource with sliders over sensible ranges. Types from other crates get
+//! a proxy here, copied over to the real resource whenever the window changes it.
+
+use crate::{
+    activity::ActivitySettings,
+    camera::CameraSettings,
+    debug_draw::DebugDrawSettings,
+    fog::FogSettings,
+    lifetime::CleanupSettings,
+    lighting::LightingController,
+    post::PostChain,
+    presence::SessionScore,
+    sky::SkySettings,
+    surveillance::FeedBudget,
+    teams::TeamSettings,
+    tuning::Tuning,
+    waves::{WaveDirector, WavePhase},
+};
+use bevy::prelude::*;
+#[cfg(not(target_arch = "wasm32"))]
+use bevy_hikari::prelude::*;
+use bevy_inspector_egui::{Inspectable, InspectorPlugin};
+use std::time::Duration;
+
+pub struct InspectorPanelsPlugin;
+
+impl Plugin for InspectorPanelsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(InspectorPlugin::<Tuning>::new())
+            .add_plugin(InspectorPlugin::<LightingController>::new())
+            .add_plugin(InspectorPlugin::<SkySettings>::new())
+            .add_plugin(InspectorPlugin::<FogSettings>::new())
+            .add_plugin(InspectorPlugin::<ActivitySettings>::new())
+            .add_plugin(InspectorPlugin::<CleanupSettings>::new())
+            .add_plugin(InspectorPlugin::<CameraSettings>::new())
+            .add_plugin(InspectorPlugin::<TeamSettings>::new())
+            .add_plugin(InspectorPlugin::<SessionScore>::new())
+            .add_plugin(InspectorPlugin::<WavePanel>::new())
+            .add_plugin(InspectorPlugin::<DebugDrawSettings>::new())
+            .add_plugin(InspectorPlugin::<FeedBudget>::new())
+            .add_plugin(InspectorPlugin::<PostChain>::new())
+            .add_system(sync_wave_panel);
+        #[cfg(not(target_arch = "wasm32"))]
+        app.add_plugin(InspectorPlugin::<HikariSettings>::new())
+            .add_system(apply_hikari_settings);
+    }
+}
+
+/// Editable part of the [`HikariConfig`]. The validation interval belongs to the
+/// [`LightingController`].
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone, Inspectable)]
+pub struct HikariSettings {
+    #[inspectable(min = 0.0, max = 10.0)]
+    pub emissive_scale: f32,
+    #[inspectable(min = 0, max = 4)]
+    pub indirect_bounces: usize,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl FromWorld for HikariSettings {
+    fn from_world(world: &mut World) -> Self {
+        let config = world.get_resource_or_insert_with(HikariConfig::default);
+        Self {
+            emissive_scale: config.emissive_scale,
+            indirect_bounces: config.indirect_bounces,
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn apply_hikari_settings(settings: Res<HikariSettings>, mut config: ResMut<HikariConfig>) {
+    if settings.is_changed() {
+        config.emissive_scale = settings.emissive_scale;
+        config.indirect_bounces = settings.indirect_bounces;
+    }
+}
+
+/// Where the [`WaveDirector`] is. Only the time left can be changed, to hurry a wave along.
+#[derive(Debug, Default, Clone, PartialEq, Inspectable)]
+pub struct WavePanel {
+    /// Wave waiting or running, from 1; 0 before the first.
+    pub wave: usize,
+    pub active: bool,
+    /// Seconds until the wave starts or runs out.
+    #[inspectable(min = 0.0, max = 300.0)]
+    pub time_left: f32,
+}
+
+fn sync_wave_panel(
+    mut synced: Local<WavePanel>,
+    mut director: ResMut<WaveDirector>,
+    mut panel: ResMut<WavePanel>,
+) {
+    let timer = match &mut director.phase {
+        WavePhase::Waiting { timer, .. } => Some(timer),
+        WavePhase::Active { timer, .. } => timer.as_mut(),
+        WavePhase::Idle | WavePhase::Finished => None,
+    };
+    if let Some(timer) = timer {
+        if panel.time_left != synced.time_left {
+            let elapsed = (timer.duration().as_secs_f32() - panel.time_left).max(0.0);
+            timer.set_elapsed(Duration::from_secs_f32(elapsed));
+        }
+    }
+
+    let current = match &director.phase {
+        WavePhase::Waiting { wave, timer } => WavePanel {
+            wave: wave + 1,
+            active: false,
+            time_left: timer.duration().as_secs_f32() - timer.elapsed_secs(),
+        },
+        WavePhase::Active { wave, timer } => WavePanel {
+            wave: wave + 1,
+            active: true,
+            time_left: timer.as_ref().map_or(0.0, |timer| {
+                timer.duration().as_secs_f32() - timer.elapsed_secs()
+            }),
+        },
+        WavePhase::Idle | WavePhase::Finished => WavePanel::default(),
+    };
+    if *panel != current {
+        *panel = current.clone();
+    }
+    *synced = current;
+}
@@ -0,0 +1,164 @@
+//! Using things in the world by looking at them and pressing Interact.
+//!
+//! Anything with an [`Interactable`] shows a prompt on the HUD while the player looks at it from
+//! within its range, and pressing Interact then sends an [`InteractEvent`]. Buttons, levers and
+//! pickups all listen for that event instead of each finding out on their own what the player is
+//! looking at. Interact shares its key with Abilities, which only opens when nothing is in reach.
+
+use crate::{
+    hud::bitmap::{BitmapAlign, BitmapText, BitmapTextBundle},
+    layers::SpawnOnLayerExt,
+    locale::Locale,
+    Action, Player, PlayerCamera,
+};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use leafwing_input_manager::{prelude::*, user_input::InputKind};
+
+/// Farthest anything can be interacted with, in meters.
+const MAX_REACH: f32 = 5.0;
+const PROMPT_POSITION: Vec2 = Vec2::new(0.0, -16.0);
+
+pub struct InteractPlugin;
+
+impl Plugin for InteractPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InteractTarget>()
+            .add_event::<InteractEvent>()
+            .add_startup_system(setup_interact_prompt)
+            .add_system(find_interact_target)
+            .add_system(interact.after(find_interact_target))
+            .add_system(update_interact_prompt.after(find_interact_target));
+    }
+}
+
+#[derive(Debug, Clone, Component)]
+pub struct Interactable {
+    /// Locale key of what interacting does, like `interact-press`.
+    pub prompt: String,
+    /// Farthest the player can be, in meters, up to [`MAX_REACH`].
+    pub range: f32,
+}
+
+impl Interactable {
+    pub fn new(prompt: impl Into<String>) -> Self {
+        Self {
+            prompt: prompt.into(),
+            range: 2.5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct InteractEvent {
+    pub player: Entity,
+    pub target: Entity,
+}
+
+/// The interactable the player looks at and can reach, if any.
+#[derive(Debug, Default)]
+pub struct InteractTarget(pub Option<Entity>);
+
+#[derive(Component)]
+struct InteractPrompt;
+
+fn setup_interact_prompt(mut commands: Commands) {
+    commands
+        .spawn_on_hud_layer(BitmapTextBundle::new(
+            BitmapText::new("", Color::WHITE, BitmapAlign::Center),
+            PROMPT_POSITION,
+        ))
+        .insert(InteractPrompt);
+}
+
+/// Casts along the view; colliders of an interactable may sit on a child of it.
+fn find_interact_target(
+    rapier_context: Res<RapierContext>,
+    mut target: ResMut<InteractTarget>,
+    players: Query<Entity, With<Player>>,
+    cameras: Query<&GlobalTransform, With<PlayerCamera>>,
+    interactables: Query<&Interactable>,
+    parents: Query<&Parent>,
+) {
+    let found = match (players.get_single(), cameras.get_single()) {
+        (Ok(player), Ok(camera)) => {
+            let filter = QueryFilter::default().exclude_rigid_body(player);
+            rapier_context
+                .cast_ray(
+                    camera.translation(),
+                    camera.forward(),
+                    MAX_REACH,
+                    true,
+                    filter,
+                )
+                .and_then(|(hit, toi)| {
+                    let entity = std::iter::successors(Some(hit), |entity| {
+                        parents.get(*entity).ok().map(|parent| parent.get())
+                    })
+                    .find(|entity| interactables.get(*entity).is_ok())?;
+                    let interactable = interactables.get(entity).ok()?;
+                    (toi <= interactable.range).then_some(entity)
+                })
+        }
+        _ => None,
+    };
+    if target.0 != found {
+        target.0 = found;
+    }
+}
+
+fn interact(
+    target: Res<InteractTarget>,
+    players: Query<(Entity, &ActionState<Action>), With<Player>>,
+    mut events: EventWriter<InteractEvent>,
+) {
+    let (player, action_state) = match players.get_single() {
+        Ok(player) => player,
+        Err(_) => return,
+    };
+    if let Some(target) = target.0 {
+        if action_state.just_pressed(Action::Interact) {
+            events.send(InteractEvent { player, target });
+        }
+    }
+}
+
+/// The first key bound to `action`, as the prompt shows it.
+fn key_label(input_map: &InputMap<Action>, action: Action) -> Option<String> {
+    input_map.get(action).iter().find_map(|input| match input {
+        UserInput::Single(InputKind::Keyboard(key)) => Some(format!("{:?}", key)),
+        _ => None,
+    })
+}
+
+fn update_interact_prompt(
+    locale: Res<Locale>,
+    target: Res<InteractTarget>,
+    players: Query<&InputMap<Action>, With<Player>>,
+    interactables: Query<&Interactable>,
+    mut prompts: Query<&mut BitmapText, With<InteractPrompt>>,
+) {
+    let value = target
+        .0
+        .and_then(|entity| interactables.get(entity).ok())
+        .map(|interactable| {
+            let action = locale.get(&interactable.prompt);
+            let key = players
+                .get_single()
+                .ok()
+                .and_then(|input_map| key_label(input_map, Action::Interact));
+            match key {
+                Some(key) => locale.get_args(
+                    "interact-prompt",
+                    &[("key", key.into()), ("action", action.into())],
+                ),
+                None => action,
+            }
+        })
+        .unwrap_or_default();
+    for mut text in &mut prompts {
+        if text.value != value {
+            text.value = value.clone();
+        }
+    }
+}
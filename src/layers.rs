@@ -0,0 +1,101 @@
+//! Every render layer the game uses, in one place.
+//!
+//! Cameras only draw entities that share a layer with them, which is how each pass and inset view
+//! keeps to its own entities. Adding a camera or a layer means looking here rather than auditing
+//! every spawn site. [`SpawnOnLayerExt`] spawns straight onto a layer, for the common cases.
+
+use bevy::{
+    ecs::system::EntityCommands,
+    prelude::*,
+    render::view::{Layer, RenderLayers},
+};
+
+/// The scene, as the player camera and everything else filming the world sees it.
+pub const WORLD: RenderLayers = RenderLayers::layer(1);
+/// The pixel HUD, drawn into its own image at the render size.
+pub const HUD: RenderLayers = RenderLayers::layer(2);
+/// Icons only the minimap camera sees.
+pub const MINIMAP: RenderLayers = RenderLayers::layer(MINIMAP_LAYER);
+/// Debug lines, drawn over the scene into the same image.
+pub const DEBUG: RenderLayers = RenderLayers::layer(4);
+/// Seen by the player's own view but nothing else filming the world. Mirror surfaces and monitors
+/// sit here, so no mirror or feed ever films itself.
+pub const VIEW_MODEL: RenderLayers = RenderLayers::layer(VIEW_MODEL_LAYER);
+/// 3D props only the HUD's inset views film, like the stand-in of the goal replay.
+pub const UI_3D: RenderLayers = RenderLayers::layer(UI_3D_LAYER);
+/// The quads blending scene and HUD, filmed into the first image of the post chain.
+pub const COMPOSITE: RenderLayers = RenderLayers::layer(7);
+/// Translucent effects a forward camera draws over the scene, behind whatever the scene has in
+/// front of them.
+pub const EFFECTS: RenderLayers = RenderLayers::layer(EFFECTS_LAYER);
+
+/// What the player camera films.
+pub const PLAYER_VIEW: RenderLayers = WORLD.with(VIEW_MODEL_LAYER);
+/// What the inset views of the HUD film.
+pub const INSET_VIEW: RenderLayers = WORLD.with(UI_3D_LAYER);
+/// What the minimap camera films.
+pub const MINIMAP_VIEW: RenderLayers = WORLD.with(MINIMAP_LAYER);
+
+/// Layers reserved for the passes of the post chain, one each.
+pub const POST_PASSES: Layer = 4;
+const FIRST_POST_PASS: Layer = 8;
+const EFFECTS_LAYER: Layer = FIRST_POST_PASS + POST_PASSES;
+/// In-world screens take turns on the layers from here to the last one.
+const FIRST_SCREEN: Layer = EFFECTS_LAYER + 1;
+/// How many screens can have a layer of their own.
+pub const SCREENS: Layer = RenderLayers::TOTAL_LAYERS as Layer - FIRST_SCREEN;
+
+const MINIMAP_LAYER: Layer = 3;
+const VIEW_MODEL_LAYER: Layer = 5;
+const UI_3D_LAYER: Layer = 6;
+
+/// The layer of pass `index` of the post chain.
+pub fn post_pass(index: usize) -> RenderLayers {
+    assert!(index < POST_PASSES as usize);
+    RenderLayers::layer(FIRST_POST_PASS + index as Layer)
+}
+
+/// The layer of in-world screen `index`, up to [`SCREENS`].
+pub fn screen(index: Layer) -> RenderLayers {
+    RenderLayers::layer(FIRST_SCREEN + index % SCREENS)
+}
+
+pub trait SpawnOnLayerExt<'w, 's> {
+    fn spawn_on_layer<'a>(
+        &'a mut self,
+        bundle: impl Bundle,
+        layers: RenderLayers,
+    ) -> EntityCommands<'w, 's, 'a>;
+
+    fn spawn_on_world_layer<'a>(&'a mut self, bundle: impl Bundle) -> EntityCommands<'w, 's, 'a> {
+        self.spawn_on_layer(bundle, WORLD)
+    }
+
+    fn spawn_on_hud_layer<'a>(&'a mut self, bundle: impl Bundle) -> EntityCommands<'w, 's, 'a> {
+        self.spawn_on_layer(bundle, HUD)
+    }
+}
+
+impl<'w, 's> SpawnOnLayerExt<'w, 's> for Commands<'w, 's> {
+    fn spawn_on_layer<'a>(
+        &'a mut self,
+        bundle: impl Bundle,
+        layers: RenderLayers,
+    ) -> EntityCommands<'w, 's, 'a> {
+        let mut entity = self.spawn_bundle(bundle);
+        entity.insert(layers);
+        entity
+    }
+}
+
+impl<'w, 's> SpawnOnLayerExt<'w, 's> for ChildBuilder<'w, 's, '_> {
+    fn spawn_on_layer<'a>(
+        &'a mut self,
+        bundle: impl Bundle,
+        layers: RenderLayers,
+    ) -> EntityCommands<'w, 's, 'a> {
+        let mut entity = self.spawn_bundle(bundle);
+        entity.insert(layers);
+        entity
+    }
+}
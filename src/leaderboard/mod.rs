@@ -0,0 +1,220 @@
+//! Best time-attack times, per level.
+//!
+//! Every finished run goes into the local [`Leaderboard`], which keeps the [`TOP_SCORES`] fastest
+//! of each level in [`LEADERBOARD_FILE`]. With the `online-leaderboard` feature runs are also
+//! submitted to a leaderboard server, and its [`GlobalRankings`] show next to the local ones on
+//! the post-match screen.
+
+use crate::{
+    ghost::TimeAttackFinished,
+    hud::bitmap::{BitmapAlign, BitmapText, BitmapTextBundle},
+    layers::SpawnOnLayerExt,
+    level::{CurrentLevel, LevelDef},
+    locale::Locale,
+    match_flow::MatchState,
+    profile::Profile,
+};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fs, path::Path};
+
+#[cfg(feature = "online-leaderboard")]
+mod online;
+
+pub const LEADERBOARD_FILE: &str = "saves/leaderboard.ron";
+pub const TOP_SCORES: usize = 10;
+/// Top left corners of the local and global tables on the HUD, below the match screen.
+const TABLE_POSITIONS: [Vec2; 2] = [Vec2::new(-140.0, 50.0), Vec2::new(44.0, 50.0)];
+
+pub struct LeaderboardPlugin;
+
+impl Plugin for LeaderboardPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Leaderboard::load())
+            .add_system(record_scores)
+            .add_system_set(
+                SystemSet::on_enter(MatchState::PostMatch).with_system(spawn_leaderboard_screen),
+            )
+            .add_system_set(
+                SystemSet::on_update(MatchState::PostMatch).with_system(update_leaderboard_screen),
+            )
+            .add_system_set(
+                SystemSet::on_exit(MatchState::PostMatch).with_system(despawn_leaderboard_screen),
+            );
+
+        #[cfg(feature = "online-leaderboard")]
+        app.add_plugin(online::OnlineLeaderboardPlugin);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Score {
+    pub name: String,
+    /// Run time in seconds.
+    pub time: f32,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Leaderboard {
+    /// Fastest scores first, by level name.
+    pub levels: HashMap<String, Vec<Score>>,
+}
+
+impl Leaderboard {
+    pub fn load() -> Self {
+        let leaderboard = fs::read_to_string(LEADERBOARD_FILE)
+            .map_err(|err| err.to_string())
+            .and_then(|text| ron::from_str(&text).map_err(|err| err.to_string()));
+        match leaderboard {
+            Ok(leaderboard) => leaderboard,
+            Err(err) => {
+                info!("No leaderboard loaded from {}: {}", LEADERBOARD_FILE, err);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let text =
+            ron::ser::to_string_pretty(self, Default::default()).map_err(|err| err.to_string())?;
+        if let Some(parent) = Path::new(LEADERBOARD_FILE).parent() {
+            fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+        }
+        fs::write(LEADERBOARD_FILE, text).map_err(|err| err.to_string())
+    }
+
+    /// Adds `score` if it makes the top of its level, returning its rank from 0.
+    pub fn insert(&mut self, level: &str, score: Score) -> Option<usize> {
+        let scores = self.levels.entry(level.into()).or_default();
+        let rank = scores
+            .iter()
+            .position(|other| score.time < other.time)
+            .unwrap_or(scores.len());
+        if rank >= TOP_SCORES {
+            return None;
+        }
+        scores.insert(rank, score);
+        scores.truncate(TOP_SCORES);
+        Some(rank)
+    }
+
+    pub fn scores(&self, level: &str) -> &[Score] {
+        self.levels.get(level).map_or(&[], Vec::as_slice)
+    }
+}
+
+/// Scores from the leaderboard server for one level.
+#[derive(Debug, Default, Clone)]
+pub struct GlobalRankings {
+    pub level: String,
+    pub scores: Vec<Score>,
+}
+
+/// Name of the current level, if it has loaded.
+pub fn current_level_name(current: &CurrentLevel, levels: &Assets<LevelDef>) -> Option<String> {
+    levels.get(&current.handle).map(|level| level.name.clone())
+}
+
+/// Name that goes with the player's scores.
+pub fn player_name(profile: &Profile) -> String {
+    if profile.name.is_empty() {
+        "PLAYER".into()
+    } else {
+        profile.name.clone()
+    }
+}
+
+fn record_scores(
+    current: Res<CurrentLevel>,
+    levels: Res<Assets<LevelDef>>,
+    profile: Res<Profile>,
+    mut leaderboard: ResMut<Leaderboard>,
+    mut finished: EventReader<TimeAttackFinished>,
+) {
+    let level = match current_level_name(&current, &levels) {
+        Some(level) => level,
+        None => return,
+    };
+    for event in finished.iter() {
+        let score = Score {
+            name: player_name(&profile),
+            time: event.time,
+        };
+        if let Some(rank) = leaderboard.insert(&level, score) {
+            info!("Time attack rank {} on {}", rank + 1, level);
+            if let Err(err) = leaderboard.save() {
+                error!(
+                    "Failed to save leaderboard to {}: {}",
+                    LEADERBOARD_FILE, err
+                );
+            }
+        }
+    }
+}
+
+#[derive(Component)]
+struct LeaderboardScreen;
+
+/// A table of times, the global one if `global`.
+#[derive(Component)]
+struct LeaderboardText {
+    global: bool,
+}
+
+fn spawn_leaderboard_screen(mut commands: Commands) {
+    for (index, position) in TABLE_POSITIONS.into_iter().enumerate() {
+        commands
+            .spawn_on_hud_layer(BitmapTextBundle::new(
+                BitmapText::new("", Color::WHITE, BitmapAlign::Left),
+                position,
+            ))
+            .insert_bundle((LeaderboardScreen, LeaderboardText { global: index > 0 }));
+    }
+}
+
+fn despawn_leaderboard_screen(
+    mut commands: Commands,
+    screens: Query<Entity, With<LeaderboardScreen>>,
+) {
+    for entity in &screens {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn format_scores(title: String, scores: &[Score]) -> String {
+    let mut value = title + "\n";
+    for (rank, score) in scores.iter().enumerate() {
+        value += &format!("{:>2}. {:<12} {:>7.2}\n", rank + 1, score.name, score.time);
+    }
+    if scores.is_empty() {
+        value += "--\n";
+    }
+    value
+}
+
+fn update_leaderboard_screen(
+    current: Res<CurrentLevel>,
+    levels: Res<Assets<LevelDef>>,
+    locale: Res<Locale>,
+    leaderboard: Res<Leaderboard>,
+    global: Option<Res<GlobalRankings>>,
+    mut texts: Query<(&mut BitmapText, &LeaderboardText)>,
+) {
+    let level = match current_level_name(&current, &levels) {
+        Some(level) => level,
+        None => return,
+    };
+    let local = format_scores(locale.get("leaderboard-local"), leaderboard.scores(&level));
+    let global = global
+        .filter(|global| global.level == level)
+        .map(|global| format_scores(locale.get("leaderboard-global"), &global.scores))
+        .unwrap_or_default();
+
+    for (mut text, table) in &mut texts {
+        let value = if table.global { &global } else { &local };
+        if text.value != *value {
+            text.value = value.clone();
+        }
+    }
+}
@@ -0,0 +1,128 @@
+//! Talks to the leaderboard server over HTTP.
+//!
+//! The server keeps scores per level: `POST <url>/scores/<level>` with a [`Score`] submits one, and
+//! `GET <url>/scores/<level>` answers with the best ones as a JSON list. The URL comes from the
+//! `LEADERBOARD_URL` environment variable. Requests run on their own threads, so a slow server
+//! never stalls a frame; their answers are picked up by [`receive_rankings`].
+
+use super::{current_level_name, player_name, GlobalRankings, Score, TOP_SCORES};
+use crate::{
+    ghost::TimeAttackFinished,
+    level::{CurrentLevel, LevelDef},
+    match_flow::MatchState,
+    profile::Profile,
+};
+use bevy::prelude::*;
+use std::{
+    env,
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Mutex,
+    },
+    thread,
+};
+
+const DEFAULT_URL: &str = "http://localhost:8080";
+
+pub struct OnlineLeaderboardPlugin;
+
+impl Plugin for OnlineLeaderboardPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LeaderboardClient>()
+            .init_resource::<GlobalRankings>()
+            .add_system(submit_scores)
+            .add_system(receive_rankings)
+            .add_system_set(SystemSet::on_enter(MatchState::PostMatch).with_system(fetch_rankings));
+    }
+}
+
+type Answer = Result<(String, Vec<Score>), String>;
+
+pub struct LeaderboardClient {
+    url: String,
+    sender: Mutex<Sender<Answer>>,
+    receiver: Mutex<Receiver<Answer>>,
+}
+
+impl Default for LeaderboardClient {
+    fn default() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self {
+            url: env::var("LEADERBOARD_URL").unwrap_or_else(|_| DEFAULT_URL.into()),
+            sender: Mutex::new(sender),
+            receiver: Mutex::new(receiver),
+        }
+    }
+}
+
+impl LeaderboardClient {
+    /// Submits `score` first if there is one, then fetches the rankings of `level`.
+    fn request(&self, level: String, score: Option<Score>) {
+        let url = format!("{}/scores/{}", self.url, level);
+        let sender = match self.sender.lock() {
+            Ok(sender) => sender.clone(),
+            Err(_) => return,
+        };
+        thread::spawn(move || {
+            let answer = (|| {
+                if let Some(score) = score {
+                    ureq::post(&url)
+                        .send_json(&score)
+                        .map_err(|err| err.to_string())?;
+                }
+                let mut scores: Vec<Score> = ureq::get(&url)
+                    .call()
+                    .map_err(|err| err.to_string())?
+                    .into_json()
+                    .map_err(|err| err.to_string())?;
+                scores.truncate(TOP_SCORES);
+                Ok((level, scores))
+            })();
+            // The game may have quit in the meantime.
+            let _ = sender.send(answer);
+        });
+    }
+}
+
+fn submit_scores(
+    current: Res<CurrentLevel>,
+    levels: Res<Assets<LevelDef>>,
+    profile: Res<Profile>,
+    client: Res<LeaderboardClient>,
+    mut finished: EventReader<TimeAttackFinished>,
+) {
+    let level = match current_level_name(&current, &levels) {
+        Some(level) => level,
+        None => return,
+    };
+    for event in finished.iter() {
+        let score = Score {
+            name: player_name(&profile),
+            time: event.time,
+        };
+        client.request(level.clone(), Some(score));
+    }
+}
+
+fn fetch_rankings(
+    current: Res<CurrentLevel>,
+    levels: Res<Assets<LevelDef>>,
+    client: Res<LeaderboardClient>,
+) {
+    if let Some(level) = current_level_name(&current, &levels) {
+        client.request(level, None);
+    }
+}
+
+fn receive_rankings(client: Res<LeaderboardClient>, mut rankings: ResMut<GlobalRankings>) {
+    let receiver = match client.receiver.lock() {
+        Ok(receiver) => receiver,
+        Err(_) => return,
+    };
+    for answer in receiver.try_iter() {
+        match answer {
+            Ok((level, scores)) => *rankings = GlobalRankings { level, scores },
+            Err(err) => warn!("Leaderboard server: {}", err),
+        }
+    }
+}
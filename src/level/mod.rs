@@ -0,0 +1,1189 @@
+//! Levels loaded from RON files.
+//!
+//! [`LevelRegistry`] lists the playable levels from `assets/levels/registry.ron`. The current level
+//! is a [`LevelDef`] asset; once loaded, its objects are spawned tagged with [`LevelEntity`], and
+//! switching levels despawns everything tagged before loading the next one. The host picks the
+//! level from the lobby and clients follow.
+//!
+//! Procedural registry entries have no file; their [`LevelDef`] is generated from the
+//! [`WorldSeed`] instead.
+//!
+//! Objects can be wrapped in [`LevelObject::Tagged`] to give them [`Tags`], which
+//! [`LevelObject::Trigger`] volumes refer to when they fire, and in [`LevelObject::Surface`] to
+//! change what they are made of.
+//!
+//! [`LevelObject::Lod`] gives far geometry coarser meshes, which keeps the path tracer fast.
+//!
+//! Walls, cubes and goals take their materials from the [`MaterialLibrary`] by name.
+//!
+//! Editing the file of the current level patches the world in place: only objects that changed
+//! are respawned, so the player and the cubes already in play stay where they are.
+
+use crate::{
+    carry::{item_collider, CarryItem, ItemKind, Receptacle, ITEM_SIZE},
+    cutscene::Cutscene,
+    dialogue::Conversation,
+    hud::bitmap::{BitmapAlign, BitmapText, BitmapTextBundle},
+    input_block::InputBlock,
+    interact::Interactable,
+    layers::SpawnOnLayerExt,
+    loading::LoadingAssets,
+    locale::Locale,
+    lod::LodRequest,
+    match_flow::{MatchState, MatchUpdate},
+    materials::MaterialLibrary,
+    mirror::MirrorSurface,
+    nav::NavMesh,
+    net::{FromServer, Network, ServerMessage},
+    practice::RangeTarget,
+    prefab::SpawnPrefabExt,
+    props::SpawnPropExt,
+    replay::WorldSeed,
+    rings::TargetRing,
+    rope::{Rope, SpawnRopeExt},
+    screens::{Screen, ScreenContent},
+    socket::{Socket, SocketItem},
+    spawner::CubeSpawner,
+    surface::SurfaceType,
+    surveillance::{Monitor, SurveillanceCamera},
+    tags::Tags,
+    teams::Team,
+    Player, CUBE_SIZE,
+};
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+    utils::BoxedFuture,
+};
+use bevy_rapier3d::prelude::*;
+use rand::Rng;
+use serde::Deserialize;
+use std::{f32::consts::PI, fs};
+use trigger::{GoalReached, Linked, Powered, Trigger, TriggerAction, Triggered, WallButton};
+use walls::{spawn_walls, WallDef, WALL_THICKNESS};
+
+pub mod procedural;
+pub mod trigger;
+pub mod walls;
+
+pub const LEVEL_REGISTRY_PATH: &str = "assets/levels/registry.ron";
+const THUMBNAIL_SIZE: Vec2 = Vec2::new(160.0, 90.0);
+/// Top left corner of the level list on the HUD, in pixels from its center.
+const LEVEL_LIST_POSITION: Vec2 = Vec2::new(-150.0, 50.0);
+const GOAL_SIZE: f32 = 4.0;
+
+pub struct LevelPlugin;
+
+impl Plugin for LevelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<LevelDef>()
+            .init_asset_loader::<LevelLoader>()
+            .init_resource::<LevelRegistry>()
+            .init_resource::<CurrentLevel>()
+            .init_resource::<LevelSelect>()
+            .add_event::<LevelCommand>()
+            .add_event::<LevelLoaded>()
+            .add_event::<Triggered>()
+            .add_event::<Powered>()
+            .add_event::<GoalReached>()
+            .add_startup_system(preload_level)
+            .add_system_set(SystemSet::on_update(MatchState::Lobby).with_system(level_select_keys))
+            .add_system_set(SystemSet::on_exit(MatchState::Lobby).with_system(close_level_select))
+            .add_system(level_select_screen.after(level_select_keys))
+            .add_system(client_level_updates)
+            .add_system(level_commands.after(level_select_keys))
+            .add_system(spawn_level.after(level_commands))
+            .add_system(hot_reload_level.after(spawn_level))
+            .add_system(host_level_sync.after(level_commands))
+            .add_system(trigger::detect_triggers)
+            .add_system(trigger::detect_goals)
+            .add_system(trigger::run_trigger_actions.after(trigger::detect_triggers))
+            .add_system(trigger::press_buttons)
+            .add_system(trigger::fill_receptacles)
+            .add_system(trigger::fill_sockets)
+            .add_system(
+                trigger::run_linked_actions
+                    .after(trigger::press_buttons)
+                    .after(trigger::fill_receptacles)
+                    .after(trigger::fill_sockets),
+            )
+            .add_system(
+                trigger::move_tagged
+                    .after(trigger::run_trigger_actions)
+                    .after(trigger::run_linked_actions),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LevelInfo {
+    pub name: String,
+    /// Level file, relative to the asset folder.
+    #[serde(default)]
+    pub path: String,
+    #[serde(default)]
+    pub thumbnail: Option<String>,
+    /// Generated from the world seed instead of loaded from `path`.
+    #[serde(default)]
+    pub procedural: bool,
+}
+
+pub struct LevelRegistry {
+    pub levels: Vec<LevelInfo>,
+}
+
+impl FromWorld for LevelRegistry {
+    fn from_world(_world: &mut World) -> Self {
+        let levels = fs::read_to_string(LEVEL_REGISTRY_PATH)
+            .map_err(|err| err.to_string())
+            .and_then(|text| ron::from_str(&text).map_err(|err| err.to_string()));
+        let levels = match levels {
+            Ok(levels) => levels,
+            Err(err) => {
+                warn!("Failed to load {}: {}", LEVEL_REGISTRY_PATH, err);
+                vec![LevelInfo {
+                    name: "Arena".into(),
+                    path: "levels/arena.level.ron".into(),
+                    thumbnail: None,
+                    procedural: false,
+                }]
+            }
+        };
+        Self { levels }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub enum LevelObject {
+    /// Square floor with walls and a ceiling.
+    Arena {
+        size: f32,
+        /// Name in the material library; walls by default.
+        #[serde(default)]
+        material: Option<String>,
+    },
+    Block {
+        translation: [f32; 3],
+        size: [f32; 3],
+        /// Name in the material library; walls by default.
+        #[serde(default)]
+        material: Option<String>,
+        /// Rotation around the up axis, in degrees.
+        #[serde(default)]
+        yaw: f32,
+        /// Tilt around the local right axis, in degrees; makes ramps.
+        #[serde(default)]
+        pitch: f32,
+    },
+    /// A button on a wall facing along `yaw` in degrees, which runs `actions` when pressed.
+    Button {
+        translation: [f32; 3],
+        actions: Vec<TriggerAction>,
+        #[serde(default)]
+        yaw: f32,
+    },
+    /// A stack of catchable cubes.
+    Cubes {
+        translation: [f32; 3],
+        count: usize,
+        /// Name in the material library, which sockets go by; cubes by default.
+        #[serde(default)]
+        material: Option<String>,
+    },
+    /// A target pad for game modes, sitting on the floor at `translation`. A goal of a team is
+    /// where the other team scores.
+    Goal {
+        translation: [f32; 3],
+        #[serde(default)]
+        team: Option<Team>,
+    },
+    /// An item lying on the floor at `translation`, to be carried.
+    Item {
+        translation: [f32; 3],
+        kind: ItemKind,
+    },
+    /// A slot on a wall facing along `yaw` in degrees, which runs `actions` once an item of the
+    /// kind it `accepts` is put in.
+    Receptacle {
+        translation: [f32; 3],
+        accepts: ItemKind,
+        actions: Vec<TriggerAction>,
+        #[serde(default)]
+        yaw: f32,
+    },
+    /// A socket on a wall facing along `yaw` in degrees, which runs `actions` once it holds what it
+    /// `accepts`.
+    Socket {
+        translation: [f32; 3],
+        accepts: SocketItem,
+        actions: Vec<TriggerAction>,
+        /// How close an item has to come to snap in, in meters.
+        #[serde(default = "default_socket_tolerance")]
+        tolerance: f32,
+        #[serde(default)]
+        yaw: f32,
+    },
+    /// A security camera filming for the monitors on `channel`.
+    SecurityCamera {
+        translation: [f32; 3],
+        channel: u32,
+        /// Rotation around the up axis, in degrees.
+        #[serde(default)]
+        yaw: f32,
+        /// Tilt around the local right axis, in degrees.
+        #[serde(default)]
+        pitch: f32,
+    },
+    /// A screen of `size` meters showing the feed of the camera on `channel`, standing upright and
+    /// facing along `yaw` in degrees.
+    Monitor {
+        translation: [f32; 3],
+        size: [f32; 2],
+        channel: u32,
+        #[serde(default)]
+        yaw: f32,
+    },
+    /// A mirror of `size` meters, standing upright and facing along `yaw` in degrees.
+    Mirror {
+        translation: [f32; 3],
+        size: [f32; 2],
+        #[serde(default)]
+        yaw: f32,
+    },
+    /// An assembly from `assets/prefabs`, looked up by name.
+    Prefab {
+        name: String,
+        translation: [f32; 3],
+        /// Rotation around the up axis, in degrees.
+        #[serde(default)]
+        yaw: f32,
+    },
+    /// A rope hanging from a fixed point at `translation`.
+    Rope { translation: [f32; 3], rope: Rope },
+    /// A ring of the practice range, standing upright and facing along `yaw` in degrees.
+    RangeTarget {
+        translation: [f32; 3],
+        radius: f32,
+        #[serde(default)]
+        yaw: f32,
+    },
+    /// A point dropping cubes to keep the level stocked.
+    Spawner {
+        translation: [f32; 3],
+        spawner: CubeSpawner,
+    },
+    /// A ring of a throwing challenge, floating upright and facing along `yaw` in degrees. Rings
+    /// light up by `order`, and stay lit for `time_limit` seconds.
+    TargetRing {
+        translation: [f32; 3],
+        radius: f32,
+        order: u32,
+        #[serde(default = "default_ring_time_limit")]
+        time_limit: f32,
+        #[serde(default)]
+        yaw: f32,
+    },
+    /// A screen of `size` meters showing `content`, standing upright and facing along `yaw` in
+    /// degrees.
+    Screen {
+        translation: [f32; 3],
+        size: [f32; 2],
+        content: ScreenContent,
+        #[serde(default)]
+        yaw: f32,
+    },
+    /// An invisible box that runs `actions` when a player or bot walks in.
+    Trigger {
+        translation: [f32; 3],
+        size: [f32; 3],
+        actions: Vec<TriggerAction>,
+        #[serde(default)]
+        repeat: bool,
+    },
+    /// Another object with [`Tags`], so triggers and scripts can find it.
+    Tagged {
+        tags: Vec<String>,
+        object: Box<LevelObject>,
+    },
+    /// Another object made of something other than stone.
+    Surface {
+        surface: SurfaceType,
+        object: Box<LevelObject>,
+    },
+    /// Another object with coarser meshes beyond each of `distances`, in meters.
+    Lod {
+        distances: Vec<f32>,
+        object: Box<LevelObject>,
+    },
+}
+
+fn default_socket_tolerance() -> f32 {
+    0.6
+}
+
+fn default_ring_time_limit() -> f32 {
+    8.0
+}
+
+#[derive(Debug, Clone, Deserialize, TypeUuid)]
+#[uuid = "6f8e2a3c-51d4-4b7e-9a0c-2d1f7c4e8b93"]
+pub struct LevelDef {
+    pub name: String,
+    pub player_spawn: [f32; 3],
+    pub objects: Vec<LevelObject>,
+    /// Rhai script driving the level, relative to the asset folder.
+    #[serde(default)]
+    pub script: Option<String>,
+    #[serde(default)]
+    pub cutscenes: Vec<Cutscene>,
+    #[serde(default)]
+    pub conversations: Vec<Conversation>,
+}
+
+#[derive(Default)]
+pub struct LevelLoader;
+
+impl AssetLoader for LevelLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let level: LevelDef = ron::de::from_bytes(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(level));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["level.ron"]
+    }
+}
+
+pub struct CurrentLevel {
+    pub index: usize,
+    /// Seed the level was generated from, if procedural.
+    pub seed: Option<u64>,
+    pub handle: Handle<LevelDef>,
+    pub spawned: bool,
+    /// Spawned objects with their root entities.
+    pub objects: Vec<(LevelObject, Vec<Entity>)>,
+}
+
+impl FromWorld for CurrentLevel {
+    fn from_world(world: &mut World) -> Self {
+        let level = world.resource::<LevelRegistry>().levels[0].clone();
+        // Levels come up before the replay plugin, which would make the seed otherwise.
+        let seed = world.get_resource_or_insert_with(WorldSeed::default).0;
+        let handle = if level.procedural {
+            world
+                .resource_mut::<Assets<LevelDef>>()
+                .add(procedural::generate(seed))
+        } else {
+            world.resource::<AssetServer>().load(&level.path)
+        };
+        Self {
+            index: 0,
+            seed: level.procedural.then(|| seed),
+            handle,
+            spawned: false,
+            objects: vec![],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum LevelCommand {
+    /// Loads a level of the registry; procedural ones are generated from `seed`.
+    Load { index: usize, seed: u64 },
+}
+
+/// Sent once the objects of a level are spawned.
+#[derive(Debug, Clone, Copy)]
+pub struct LevelLoaded(pub usize);
+
+/// Part of the current level, despawned when switching levels.
+#[derive(Debug, Default, Component)]
+pub struct LevelEntity;
+
+#[derive(Debug, Default, Component)]
+pub struct Goal;
+
+#[derive(Debug, Default)]
+pub struct LevelSelect {
+    pub open: bool,
+    pub selected: usize,
+}
+
+#[derive(Component)]
+struct LevelSelectScreen;
+
+#[derive(Component)]
+struct LevelSelectText;
+
+#[derive(Component)]
+struct LevelSelectThumbnail;
+
+fn preload_level(current: Res<CurrentLevel>, mut loading: ResMut<LoadingAssets>) {
+    loading.add(&current.handle);
+}
+
+#[allow(clippy::too_many_arguments)]
+fn level_select_keys(
+    keys: Res<Input<KeyCode>>,
+    block: Res<InputBlock>,
+    network: Res<Network>,
+    registry: Res<LevelRegistry>,
+    current: Res<CurrentLevel>,
+    mut seed: ResMut<WorldSeed>,
+    mut select: ResMut<LevelSelect>,
+    mut commands: EventWriter<LevelCommand>,
+) {
+    if !network.is_authoritative() || block.typing() {
+        return;
+    }
+
+    if !select.open {
+        if keys.just_pressed(KeyCode::L) {
+            select.open = true;
+            select.selected = current.index;
+        }
+        return;
+    }
+
+    let count = registry.levels.len();
+    if keys.just_pressed(KeyCode::Up) {
+        select.selected = (select.selected + count - 1) % count;
+    }
+    if keys.just_pressed(KeyCode::Down) {
+        select.selected = (select.selected + 1) % count;
+    }
+    // G drops the carried item; no control preset binds the function keys.
+    if keys.just_pressed(KeyCode::F2) {
+        seed.0 = rand::thread_rng().gen();
+        // Show the new seed.
+        select.set_changed();
+    }
+    if keys.just_pressed(KeyCode::L) {
+        commands.send(LevelCommand::Load {
+            index: select.selected,
+            seed: seed.0,
+        });
+        select.open = false;
+    }
+    if keys.just_pressed(KeyCode::Escape) {
+        select.open = false;
+    }
+}
+
+fn close_level_select(mut select: ResMut<LevelSelect>) {
+    select.open = false;
+}
+
+#[allow(clippy::too_many_arguments)]
+fn level_select_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    seed: Res<WorldSeed>,
+    registry: Res<LevelRegistry>,
+    select: Res<LevelSelect>,
+    locale: Res<Locale>,
+    screens: Query<Entity, With<LevelSelectScreen>>,
+    mut text: Query<&mut BitmapText, With<LevelSelectText>>,
+    mut thumbnail: Query<(&mut UiImage, &mut Visibility), With<LevelSelectThumbnail>>,
+) {
+    if !select.is_changed() && !locale.is_changed() {
+        return;
+    }
+
+    if !select.open {
+        for entity in &screens {
+            commands.entity(entity).despawn_recursive();
+        }
+        return;
+    }
+
+    let mut value = locale.get("levels") + "\n";
+    for (index, level) in registry.levels.iter().enumerate() {
+        let marker = if index == select.selected { ">" } else { " " };
+        value += &format!("{} {}", marker, level.name);
+        if level.procedural {
+            value += &format!(" {:016x}", seed.0);
+        }
+        value += "\n";
+    }
+    value += &locale.get("levels-hint");
+    let selected = registry
+        .levels
+        .get(select.selected)
+        .and_then(|level| level.thumbnail.as_ref())
+        .map(|path| asset_server.load::<Image, _>(path.as_str()));
+
+    if screens.is_empty() {
+        commands
+            .spawn_bundle(NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        top: Val::Px(120.0),
+                        right: Val::Px(10.0),
+                        ..default()
+                    },
+                    flex_direction: FlexDirection::ColumnReverse,
+                    padding: UiRect::all(Val::Px(8.0)),
+                    ..default()
+                },
+                color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+                ..default()
+            })
+            .insert(LevelSelectScreen)
+            .with_children(|parent| {
+                parent
+                    .spawn_bundle(ImageBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(THUMBNAIL_SIZE.x), Val::Px(THUMBNAIL_SIZE.y)),
+                            ..default()
+                        },
+                        visibility: Visibility {
+                            is_visible: selected.is_some(),
+                        },
+                        image: selected.clone().map(UiImage).unwrap_or_default(),
+                        ..default()
+                    })
+                    .insert(LevelSelectThumbnail);
+            });
+        // The list is HUD text; only the thumbnail, a picture, stays in the window-sized UI.
+        commands
+            .spawn_on_hud_layer(BitmapTextBundle::new(
+                BitmapText::new(value, Color::WHITE, BitmapAlign::Left),
+                LEVEL_LIST_POSITION,
+            ))
+            .insert_bundle((LevelSelectScreen, LevelSelectText));
+        return;
+    }
+
+    for mut text in &mut text {
+        text.value = value.clone();
+    }
+    for (mut image, mut visibility) in &mut thumbnail {
+        match &selected {
+            Some(handle) => {
+                image.0 = handle.clone();
+                visibility.is_visible = true;
+            }
+            None => visibility.is_visible = false,
+        }
+    }
+}
+
+fn client_level_updates(
+    network: Res<Network>,
+    current: Res<CurrentLevel>,
+    mut updates: EventReader<FromServer<MatchUpdate>>,
+    mut commands: EventWriter<LevelCommand>,
+) {
+    if network.is_authoritative() {
+        return;
+    }
+    for FromServer(update) in updates.iter() {
+        if let MatchUpdate::Level { index, seed } = *update {
+            if index != current.index || seed != current.seed {
+                commands.send(LevelCommand::Load {
+                    index,
+                    seed: seed.unwrap_or_default(),
+                });
+            }
+        }
+    }
+}
+
+/// Tells clients which level to play, now and then so players who just joined catch up.
+fn host_level_sync(
+    time: Res<Time>,
+    network: Res<Network>,
+    current: Res<CurrentLevel>,
+    mut last_sync: Local<f64>,
+) {
+    if !matches!(*network, Network::Host(_)) {
+        return;
+    }
+    let now = time.seconds_since_startup();
+    if current.is_changed() || now - *last_sync > 1.0 {
+        *last_sync = now;
+        network.broadcast(
+            &ServerMessage::Match(MatchUpdate::Level {
+                index: current.index,
+                seed: current.seed,
+            }),
+            None,
+        );
+    }
+}
+
+/// Tears down the current level and starts loading the next.
+fn level_commands(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut levels: ResMut<Assets<LevelDef>>,
+    mut world_seed: ResMut<WorldSeed>,
+    registry: Res<LevelRegistry>,
+    mut current: ResMut<CurrentLevel>,
+    mut events: EventReader<LevelCommand>,
+    entities: Query<Entity, With<LevelEntity>>,
+) {
+    for event in events.iter() {
+        match *event {
+            LevelCommand::Load { index, seed } => {
+                let level = match registry.levels.get(index) {
+                    Some(level) => level,
+                    None => {
+                        warn!("No level {} in the registry", index);
+                        continue;
+                    }
+                };
+
+                for entity in &entities {
+                    commands.entity(entity).despawn_recursive();
+                }
+                info!("Loading level {}", level.name);
+                *current = if level.procedural {
+                    // Clients take the host's seed, so replays and rematches get it too.
+                    world_seed.0 = seed;
+                    CurrentLevel {
+                        index,
+                        seed: Some(seed),
+                        handle: levels.add(procedural::generate(seed)),
+                        spawned: false,
+                        objects: vec![],
+                    }
+                } else {
+                    CurrentLevel {
+                        index,
+                        seed: None,
+                        handle: asset_server.load(&level.path),
+                        spawned: false,
+                        objects: vec![],
+                    }
+                };
+            }
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn spawn_level(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut library: ResMut<MaterialLibrary>,
+    levels: Res<Assets<LevelDef>>,
+    mut current: ResMut<CurrentLevel>,
+    mut nav_mesh: ResMut<NavMesh>,
+    mut loaded: EventWriter<LevelLoaded>,
+    mut player: Query<(&mut Transform, Option<&mut Velocity>), With<Player>>,
+) {
+    if current.spawned {
+        return;
+    }
+    let level = match levels.get(&current.handle) {
+        Some(level) => level,
+        None => return,
+    };
+
+    current.objects = level
+        .objects
+        .iter()
+        .map(|object| {
+            let entities = spawn_level_object(
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                &mut library,
+                object,
+            );
+            (object.clone(), entities)
+        })
+        .collect();
+
+    // Start from a standstill at the level's spawn.
+    if let Ok((mut transform, velocity)) = player.get_single_mut() {
+        transform.translation = Vec3::from(level.player_spawn);
+        if let Some(mut velocity) = velocity {
+            *velocity = Velocity::default();
+        }
+    }
+
+    nav_mesh.rebake();
+    current.spawned = true;
+    loaded.send(LevelLoaded(current.index));
+    info!("Spawned level {}", level.name);
+}
+
+/// Respawns the objects that changed when the current level file is edited.
+#[allow(clippy::too_many_arguments)]
+fn hot_reload_level(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut library: ResMut<MaterialLibrary>,
+    levels: Res<Assets<LevelDef>>,
+    mut current: ResMut<CurrentLevel>,
+    mut nav_mesh: ResMut<NavMesh>,
+    mut events: EventReader<AssetEvent<LevelDef>>,
+    alive: Query<(), With<LevelEntity>>,
+) {
+    let modified = events.iter().any(|event| match event {
+        AssetEvent::Modified { handle } => *handle == current.handle,
+        _ => false,
+    });
+    if !modified || !current.spawned {
+        return;
+    }
+    let level = match levels.get(&current.handle) {
+        Some(level) => level,
+        None => return,
+    };
+
+    // Match every new object with an identical old one; whatever is left over changed.
+    let mut old = std::mem::take(&mut current.objects);
+    let mut kept = 0;
+    for object in &level.objects {
+        let entities = match old.iter().position(|(other, _)| other == object) {
+            Some(index) => {
+                kept += 1;
+                old.swap_remove(index).1
+            }
+            None => spawn_level_object(
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                &mut library,
+                object,
+            ),
+        };
+        current.objects.push((object.clone(), entities));
+    }
+
+    let removed = old.len();
+    for entity in old.into_iter().flat_map(|(_, entities)| entities) {
+        // Cubes may have been removed by gameplay already.
+        if alive.get(entity).is_ok() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+
+    info!(
+        "Reloaded level {}: kept {}, respawned {}, removed {}",
+        level.name,
+        kept,
+        level.objects.len() - kept,
+        removed
+    );
+    nav_mesh.rebake();
+}
+
+/// Spawns an object of a level, and returns its root entities.
+pub fn spawn_level_object(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    library: &mut MaterialLibrary,
+    object: &LevelObject,
+) -> Vec<Entity> {
+    let entities = match object {
+        LevelObject::Arena { size, material } => {
+            let material = library.get(material.as_deref().unwrap_or("wall"), materials);
+            let side = Vec2::splat(*size);
+            let face = 0.5 * size - 0.5 * WALL_THICKNESS;
+
+            // Floor and walls facing in, and the ceiling, which only collides.
+            let walls = [
+                WallDef::new(0.5 * WALL_THICKNESS * Vec3::Y, Vec3::Y, side),
+                WallDef::new(face * Vec3::X, -Vec3::X, side),
+                WallDef::new(-face * Vec3::X, Vec3::X, side),
+                WallDef::new(-face * Vec3::Z, Vec3::Z, side),
+                WallDef::new(face * Vec3::Z, -Vec3::Z, side),
+                WallDef::new(face * Vec3::Y, -Vec3::Y, side).hidden(),
+            ];
+            spawn_walls(commands, meshes, &material, &walls)
+        }
+        LevelObject::Block {
+            translation,
+            size,
+            material,
+            yaw,
+            pitch,
+        } => {
+            let size = Vec3::from(*size);
+            let material = library.get(material.as_deref().unwrap_or("wall"), materials);
+            let rotation =
+                Quat::from_euler(EulerRot::YXZ, yaw.to_radians(), pitch.to_radians(), 0.0);
+            let entity = commands
+                .spawn_static_wall(
+                    meshes.add(shape::Box::new(size.x, size.y, size.z).into()),
+                    material,
+                    Collider::cuboid(0.5 * size.x, 0.5 * size.y, 0.5 * size.z),
+                    Transform::from_translation(Vec3::from(*translation)).with_rotation(rotation),
+                    Vec3::ZERO,
+                )
+                .id();
+            vec![entity]
+        }
+        LevelObject::Button {
+            translation,
+            actions,
+            yaw,
+        } => {
+            let entity = commands
+                .spawn_static_wall(
+                    meshes.add(shape::Box::new(0.3, 0.3, 0.1).into()),
+                    library.get("goal", materials),
+                    Collider::cuboid(0.15, 0.15, 0.05),
+                    Transform::from_translation(Vec3::from(*translation))
+                        .with_rotation(Quat::from_rotation_y(yaw.to_radians())),
+                    Vec3::ZERO,
+                )
+                .insert_bundle((
+                    Interactable::new("interact-press"),
+                    WallButton,
+                    Linked {
+                        actions: actions.clone(),
+                    },
+                ))
+                .id();
+            vec![entity]
+        }
+        LevelObject::Item { translation, kind } => {
+            let entity = commands
+                .spawn_on_world_layer(PbrBundle {
+                    mesh: meshes.add(shape::Cube::new(ITEM_SIZE).into()),
+                    material: library.get(kind.material(), materials),
+                    transform: Transform::from_translation(Vec3::from(*translation)),
+                    ..default()
+                })
+                .insert_bundle(item_collider())
+                .insert_bundle((CarryItem(*kind), Interactable::new("interact-pickup")))
+                .id();
+            if *kind == ItemKind::Battery {
+                commands.entity(entity).insert(SocketItem::Battery);
+            }
+            vec![entity]
+        }
+        LevelObject::Receptacle {
+            translation,
+            accepts,
+            actions,
+            yaw,
+        } => {
+            let size = 1.5 * ITEM_SIZE;
+            let entity = commands
+                .spawn_static_wall(
+                    meshes.add(shape::Box::new(size, size, 0.1).into()),
+                    library.get(accepts.material(), materials),
+                    Collider::cuboid(0.5 * size, 0.5 * size, 0.05),
+                    Transform::from_translation(Vec3::from(*translation))
+                        .with_rotation(Quat::from_rotation_y(yaw.to_radians())),
+                    Vec3::ZERO,
+                )
+                .insert_bundle((
+                    Interactable::new("interact-insert"),
+                    Receptacle::new(*accepts),
+                    Linked {
+                        actions: actions.clone(),
+                    },
+                ))
+                .id();
+            vec![entity]
+        }
+        LevelObject::Cubes {
+            translation,
+            count,
+            material,
+        } => {
+            let mesh = meshes.add(shape::Cube::new(CUBE_SIZE).into());
+            let material = material.as_deref().unwrap_or("cube");
+            let translation = Vec3::from(*translation);
+            (0..*count)
+                .map(|id| {
+                    commands
+                        .spawn_catch_cube(
+                            mesh.clone(),
+                            library.get(material, materials),
+                            material,
+                            Transform::from_translation(
+                                translation + Vec3::Y * CUBE_SIZE * id as f32,
+                            ),
+                        )
+                        .insert(SocketItem::Cube(material.into()))
+                        .id()
+                })
+                .collect()
+        }
+        LevelObject::Goal { translation, team } => {
+            let mut entity = commands
+                .spawn_bundle(SpatialBundle {
+                    transform: Transform::from_translation(Vec3::from(*translation)),
+                    ..default()
+                })
+                .insert_bundle((
+                    Collider::cuboid(0.5 * GOAL_SIZE, 1.0, 0.5 * GOAL_SIZE),
+                    Sensor,
+                    ActiveEvents::COLLISION_EVENTS,
+                    Goal,
+                ))
+                .with_children(|parent| {
+                    parent.spawn_on_world_layer(PbrBundle {
+                        mesh: meshes.add(shape::Plane { size: GOAL_SIZE }.into()),
+                        material: library.get("goal", materials),
+                        transform: Transform::from_xyz(0.0, 0.01, 0.0),
+                        ..default()
+                    });
+                });
+            if let Some(team) = team {
+                entity.insert(*team);
+            }
+            vec![entity.id()]
+        }
+        LevelObject::Prefab {
+            name,
+            translation,
+            yaw,
+        } => {
+            let transform = Transform::from_translation(Vec3::from(*translation))
+                .with_rotation(Quat::from_rotation_y(yaw.to_radians()));
+            vec![commands.spawn_prefab(name, transform)]
+        }
+        LevelObject::Rope { translation, rope } => {
+            let transform = Transform::from_translation(Vec3::from(*translation));
+            vec![commands.spawn_rope(rope.clone(), transform)]
+        }
+        LevelObject::RangeTarget {
+            translation,
+            radius,
+            yaw,
+        } => {
+            let rotation =
+                Quat::from_rotation_y(yaw.to_radians()) * Quat::from_rotation_x(0.5 * PI);
+            let entity = commands
+                .spawn_on_world_layer(PbrBundle {
+                    mesh: meshes.add(
+                        shape::Torus {
+                            radius: *radius,
+                            ring_radius: 0.1,
+                            ..default()
+                        }
+                        .into(),
+                    ),
+                    material: library.get("goal", materials),
+                    transform: Transform::from_translation(Vec3::from(*translation))
+                        .with_rotation(rotation),
+                    ..default()
+                })
+                .insert(RangeTarget { radius: *radius })
+                .id();
+            vec![entity]
+        }
+        LevelObject::Spawner {
+            translation,
+            spawner,
+        } => {
+            let entity = commands
+                .spawn_bundle(TransformBundle::from_transform(
+                    Transform::from_translation(Vec3::from(*translation)),
+                ))
+                .insert(spawner.clone())
+                .id();
+            vec![entity]
+        }
+        LevelObject::Socket {
+            translation,
+            accepts,
+            actions,
+            tolerance,
+            yaw,
+        } => {
+            let size = 1.2 * CUBE_SIZE;
+            let entity = commands
+                .spawn_bundle(SpatialBundle {
+                    transform: Transform::from_translation(Vec3::from(*translation))
+                        .with_rotation(Quat::from_rotation_y(yaw.to_radians())),
+                    ..default()
+                })
+                .insert_bundle((
+                    RigidBody::Fixed,
+                    Socket::new(accepts.clone(), *tolerance),
+                    Linked {
+                        actions: actions.clone(),
+                    },
+                ))
+                .with_children(|parent| {
+                    // A backplate behind the item, so the socket reads as one when empty.
+                    parent.spawn_on_world_layer(PbrBundle {
+                        mesh: meshes.add(shape::Box::new(size, size, 0.1).into()),
+                        material: library.get(accepts.material(), materials),
+                        transform: Transform::from_xyz(0.0, 0.0, -0.5 * size),
+                        ..default()
+                    });
+                })
+                .id();
+            vec![entity]
+        }
+        LevelObject::TargetRing {
+            translation,
+            radius,
+            order,
+            time_limit,
+            yaw,
+        } => {
+            let rotation =
+                Quat::from_rotation_y(yaw.to_radians()) * Quat::from_rotation_x(0.5 * PI);
+            let entity = commands
+                .spawn_on_world_layer(PbrBundle {
+                    mesh: meshes.add(
+                        shape::Torus {
+                            radius: *radius,
+                            ring_radius: 0.15,
+                            ..default()
+                        }
+                        .into(),
+                    ),
+                    material: library.get("wall", materials),
+                    transform: Transform::from_translation(Vec3::from(*translation))
+                        .with_rotation(rotation),
+                    ..default()
+                })
+                .insert(TargetRing {
+                    radius: *radius,
+                    order: *order,
+                    time_limit: *time_limit,
+                })
+                .id();
+            vec![entity]
+        }
+        LevelObject::SecurityCamera {
+            translation,
+            channel,
+            yaw,
+            pitch,
+        } => {
+            let rotation =
+                Quat::from_euler(EulerRot::YXZ, yaw.to_radians(), pitch.to_radians(), 0.0);
+            let entity = commands
+                .spawn_on_world_layer(PbrBundle {
+                    mesh: meshes.add(shape::Box::new(0.3, 0.3, 0.5).into()),
+                    material: library.get("wall", materials),
+                    transform: Transform::from_translation(Vec3::from(*translation))
+                        .with_rotation(rotation),
+                    ..default()
+                })
+                .insert(SurveillanceCamera { channel: *channel })
+                .id();
+            vec![entity]
+        }
+        LevelObject::Monitor {
+            translation,
+            size,
+            channel,
+            yaw,
+        } => {
+            let entity = commands
+                .spawn_bundle(SpatialBundle {
+                    transform: Transform::from_translation(Vec3::from(*translation))
+                        .with_rotation(Quat::from_rotation_y(yaw.to_radians())),
+                    ..default()
+                })
+                .insert(meshes.add(shape::Quad::new(Vec2::from(*size)).into()))
+                .insert(Monitor { channel: *channel })
+                .id();
+            vec![entity]
+        }
+        LevelObject::Mirror {
+            translation,
+            size,
+            yaw,
+        } => {
+            let entity = commands
+                .spawn_bundle(SpatialBundle {
+                    transform: Transform::from_translation(Vec3::from(*translation))
+                        .with_rotation(Quat::from_rotation_y(yaw.to_radians())),
+                    ..default()
+                })
+                .insert(MirrorSurface {
+                    size: Vec2::from(*size),
+                })
+                .id();
+            vec![entity]
+        }
+        LevelObject::Screen {
+            translation,
+            size,
+            content,
+            yaw,
+        } => {
+            let size = Vec2::from(*size);
+            let entity = commands
+                .spawn_on_world_layer(PbrBundle {
+                    mesh: meshes.add(shape::Quad::new(size).into()),
+                    transform: Transform::from_translation(Vec3::from(*translation))
+                        .with_rotation(Quat::from_rotation_y(yaw.to_radians())),
+                    ..default()
+                })
+                .insert(Screen {
+                    size,
+                    content: content.clone(),
+                })
+                .id();
+            vec![entity]
+        }
+        LevelObject::Trigger {
+            translation,
+            size,
+            actions,
+            repeat,
+        } => {
+            let size = Vec3::from(*size);
+            let entity = commands
+                .spawn_bundle(SpatialBundle {
+                    transform: Transform::from_translation(Vec3::from(*translation)),
+                    ..default()
+                })
+                .insert_bundle((
+                    Collider::cuboid(0.5 * size.x, 0.5 * size.y, 0.5 * size.z),
+                    Sensor,
+                    Trigger::new(actions.clone(), *repeat),
+                ))
+                .id();
+            vec![entity]
+        }
+        LevelObject::Tagged { tags, object } => {
+            let entities = spawn_level_object(commands, meshes, materials, library, object);
+            for entity in &entities {
+                commands.entity(*entity).insert(Tags::new(tags.clone()));
+            }
+            entities
+        }
+        LevelObject::Surface { surface, object } => {
+            let entities = spawn_level_object(commands, meshes, materials, library, object);
+            for entity in &entities {
+                commands.entity(*entity).insert(*surface);
+            }
+            entities
+        }
+        LevelObject::Lod { distances, object } => {
+            let entities = spawn_level_object(commands, meshes, materials, library, object);
+            for entity in &entities {
+                commands
+                    .entity(*entity)
+                    .insert(LodRequest(distances.clone()));
+            }
+            entities
+        }
+    };
+
+    for entity in &entities {
+        commands.entity(*entity).insert(LevelEntity);
+    }
+    entities
+}
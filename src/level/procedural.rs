@@ -0,0 +1,145 @@
+//! Arenas generated from a seed.
+//!
+//! Everything is drawn from one RNG seeded with the level seed, in a fixed order, so a seed always
+//! yields the same layout and can be shared with other players.
+
+use super::{LevelDef, LevelObject};
+use crate::{teams::Team, GROUND_SIZE};
+use bevy::prelude::*;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+/// Height of the floor surface.
+const FLOOR: f32 = 1.0;
+/// Keeps geometry away from the arena walls.
+const MARGIN: f32 = 6.0;
+const SPAWN_CLEARANCE: f32 = 6.0;
+const MAX_ATTEMPTS: usize = 32;
+
+/// Places that must stay free: the player spawn and the match spawn points.
+const KEEP_CLEAR: [Vec2; 5] = [
+    Vec2::new(0.0, 30.0),
+    Vec2::new(0.0, -30.0),
+    Vec2::new(30.0, 0.0),
+    Vec2::new(-30.0, 0.0),
+    Vec2::new(0.0, 20.0),
+];
+
+struct Layout {
+    rng: StdRng,
+    /// Footprints taken so far, as center and radius.
+    taken: Vec<(Vec2, f32)>,
+}
+
+impl Layout {
+    /// Finds a free spot for something `radius` wide, or gives up after a few tries.
+    fn place(&mut self, radius: f32) -> Option<Vec2> {
+        let extent = 0.5 * GROUND_SIZE - MARGIN - radius;
+        for _ in 0..MAX_ATTEMPTS {
+            let point = Vec2::new(
+                self.rng.gen_range(-extent..extent),
+                self.rng.gen_range(-extent..extent),
+            );
+            let free = KEEP_CLEAR
+                .iter()
+                .all(|clear| clear.distance(point) > SPAWN_CLEARANCE + radius)
+                && self
+                    .taken
+                    .iter()
+                    .all(|(other, other_radius)| other.distance(point) > other_radius + radius);
+            if free {
+                self.taken.push((point, radius));
+                return Some(point);
+            }
+        }
+        None
+    }
+}
+
+pub fn generate(seed: u64) -> LevelDef {
+    let mut layout = Layout {
+        rng: StdRng::seed_from_u64(seed),
+        taken: vec![],
+    };
+    let mut objects = vec![LevelObject::Arena {
+        size: GROUND_SIZE,
+        material: None,
+    }];
+
+    // Pillars
+    let pillars = layout.rng.gen_range(3..=7);
+    for _ in 0..pillars {
+        let width = layout.rng.gen_range(3.0..8.0);
+        let height = layout.rng.gen_range(4.0..20.0);
+        let yaw = layout.rng.gen_range(0.0..90.0);
+        if let Some(point) = layout.place(width) {
+            objects.push(LevelObject::Block {
+                translation: [point.x, FLOOR + 0.5 * height, point.y],
+                size: [width, height, width],
+                material: None,
+                yaw,
+                pitch: 0.0,
+            });
+        }
+    }
+
+    // Ramps, tilted so their low end sinks into the floor.
+    let ramps = layout.rng.gen_range(2..=4);
+    for _ in 0..ramps {
+        let length = layout.rng.gen_range(8.0..14.0);
+        let pitch: f32 = layout.rng.gen_range(12.0..25.0);
+        let yaw = layout.rng.gen_range(0.0..360.0);
+        if let Some(point) = layout.place(0.5 * length) {
+            let rise = 0.5 * length * pitch.to_radians().sin();
+            objects.push(LevelObject::Block {
+                translation: [point.x, FLOOR + rise - 0.25, point.y],
+                size: [4.0, 0.5, length],
+                material: None,
+                yaw,
+                pitch,
+            });
+        }
+    }
+
+    // Cube clusters
+    let clusters = layout.rng.gen_range(3..=6);
+    for _ in 0..clusters {
+        let stacks = layout.rng.gen_range(1..=3);
+        if let Some(center) = layout.place(3.0) {
+            for stack in 0..stacks {
+                let offset = Vec2::new(2.0 * stack as f32 - 2.0, 0.0);
+                let count = layout.rng.gen_range(2..=5);
+                objects.push(LevelObject::Cubes {
+                    translation: [center.x + offset.x, FLOOR + 1.0, center.y + offset.y],
+                    count,
+                    material: None,
+                });
+            }
+        }
+    }
+
+    // Goals, one in each half of the arena, belonging to the team spawning there.
+    for (side, team) in [(1.0, Team::Red), (-1.0, Team::Blue)] {
+        for _ in 0..MAX_ATTEMPTS {
+            if let Some(point) = layout.place(3.0) {
+                if point.y * side > 0.0 {
+                    objects.push(LevelObject::Goal {
+                        translation: [point.x, FLOOR, point.y],
+                        team: Some(team),
+                    });
+                    break;
+                }
+                // Wrong half, give the spot back.
+                layout.taken.pop();
+            }
+        }
+    }
+
+    LevelDef {
+        name: format!("Seed {:016x}", seed),
+        player_spawn: [0.0, 2.0, 20.0],
+        objects,
+        script: None,
+        cutscenes: vec![],
+        conversations: vec![],
+    }
+}
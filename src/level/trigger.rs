@@ -0,0 +1,298 @@
+//! Sensor volumes that act on tagged entities when someone walks in.
+//!
+//! Every peer sees every player, so triggers run everywhere instead of being replicated.
+//!
+//! Buttons and receptacles run the same actions through [`Linked`] when they are [`Powered`]: a
+//! [`WallButton`] when the player presses it, a [`Receptacle`] when an item goes in, a [`Socket`]
+//! when it is filled.
+
+use super::{Goal, LevelEntity};
+use crate::{
+    bots::Bot,
+    carry::{ItemInserted, Receptacle},
+    interact::InteractEvent,
+    nav::NavMesh,
+    net::RemotePlayer,
+    prefab::SpawnPrefabExt,
+    rope::{Rope, SpawnRopeExt},
+    socket::{Socket, SocketFilled},
+    tags::Tagged,
+    tutorial::TutorialStep,
+    CatchObject, Player,
+};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use serde::Deserialize;
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub enum TriggerAction {
+    /// Slides every entity tagged `tag` by `offset`, e.g. to open doors.
+    Move {
+        tag: String,
+        offset: [f32; 3],
+        #[serde(default)]
+        seconds: f32,
+    },
+    Despawn {
+        tag: String,
+    },
+    SpawnPrefab {
+        name: String,
+        translation: [f32; 3],
+    },
+    /// Hangs a weighted rope of `links` from `offset` on every entity tagged `tag`.
+    HangRope {
+        tag: String,
+        offset: [f32; 3],
+        links: usize,
+    },
+    /// Teaches the local player `steps` they haven't learned yet.
+    Tutorial {
+        steps: Vec<TutorialStep>,
+    },
+}
+
+/// Sent when a catchable object lands in a [`Goal`].
+#[derive(Debug, Clone, Copy)]
+pub struct GoalReached {
+    pub goal: Entity,
+    pub object: Entity,
+}
+
+/// Sent when something enters a [`Trigger`].
+#[derive(Debug, Clone, Copy)]
+pub struct Triggered {
+    pub trigger: Entity,
+    pub activator: Entity,
+}
+
+#[derive(Debug, Component)]
+pub struct Trigger {
+    pub actions: Vec<TriggerAction>,
+    /// Fires every time something enters, instead of only the first time.
+    pub repeat: bool,
+    fired: bool,
+    occupied: bool,
+}
+
+impl Trigger {
+    pub fn new(actions: Vec<TriggerAction>, repeat: bool) -> Self {
+        Self {
+            actions,
+            repeat,
+            fired: false,
+            occupied: false,
+        }
+    }
+}
+
+/// Sent when a button or receptacle is used.
+#[derive(Debug, Clone, Copy)]
+pub struct Powered(pub Entity);
+
+/// Runs `actions` every time the entity is [`Powered`].
+#[derive(Debug, Component)]
+pub struct Linked {
+    pub actions: Vec<TriggerAction>,
+}
+
+/// Powered by pressing it.
+#[derive(Debug, Default, Component)]
+pub struct WallButton;
+
+/// Slides an entity over time; removed once it arrives.
+#[derive(Debug, Component)]
+pub struct Mover {
+    from: Vec3,
+    to: Vec3,
+    timer: Timer,
+}
+
+pub fn detect_triggers(
+    rapier_context: Res<RapierContext>,
+    mut triggers: Query<(Entity, &mut Trigger)>,
+    activators: Query<(), Or<(With<Player>, With<RemotePlayer>, With<Bot>)>>,
+    mut events: EventWriter<Triggered>,
+) {
+    for (entity, mut trigger) in &mut triggers {
+        let activator = rapier_context
+            .intersections_with(entity)
+            .filter(|(_, _, intersecting)| *intersecting)
+            .map(|(a, b, _)| if a == entity { b } else { a })
+            .find(|other| activators.get(*other).is_ok());
+
+        let entered = activator.is_some() && !trigger.occupied;
+        trigger.occupied = activator.is_some();
+        if !entered || (trigger.fired && !trigger.repeat) {
+            continue;
+        }
+
+        trigger.fired = true;
+        if let Some(activator) = activator {
+            events.send(Triggered {
+                trigger: entity,
+                activator,
+            });
+        }
+    }
+}
+
+/// Goals need [`ActiveEvents::COLLISION_EVENTS`] to show up here.
+pub fn detect_goals(
+    mut collisions: EventReader<CollisionEvent>,
+    goals: Query<(), With<Goal>>,
+    objects: Query<(), With<CatchObject>>,
+    mut events: EventWriter<GoalReached>,
+) {
+    for collision in collisions.iter() {
+        if let CollisionEvent::Started(a, b, _) = *collision {
+            for (goal, object) in [(a, b), (b, a)] {
+                if goals.get(goal).is_ok() && objects.get(object).is_ok() {
+                    debug!(target: "goal", ?goal, ?object, "Goal reached");
+                    events.send(GoalReached { goal, object });
+                }
+            }
+        }
+    }
+}
+
+pub fn run_trigger_actions(
+    mut commands: Commands,
+    mut events: EventReader<Triggered>,
+    triggers: Query<&Trigger>,
+    tagged: Tagged,
+    transforms: Query<&Transform>,
+) {
+    for event in events.iter() {
+        let trigger = match triggers.get(event.trigger) {
+            Ok(trigger) => trigger,
+            Err(_) => continue,
+        };
+
+        for action in &trigger.actions {
+            apply_action(&mut commands, &tagged, &transforms, action);
+        }
+    }
+}
+
+pub fn press_buttons(
+    mut events: EventReader<InteractEvent>,
+    buttons: Query<(), With<WallButton>>,
+    mut powered: EventWriter<Powered>,
+) {
+    for event in events.iter() {
+        if buttons.get(event.target).is_ok() {
+            powered.send(Powered(event.target));
+        }
+    }
+}
+
+pub fn fill_receptacles(
+    mut events: EventReader<ItemInserted>,
+    receptacles: Query<(), With<Receptacle>>,
+    mut powered: EventWriter<Powered>,
+) {
+    for event in events.iter() {
+        if receptacles.get(event.receptacle).is_ok() {
+            powered.send(Powered(event.receptacle));
+        }
+    }
+}
+
+pub fn fill_sockets(
+    mut events: EventReader<SocketFilled>,
+    sockets: Query<(), With<Socket>>,
+    mut powered: EventWriter<Powered>,
+) {
+    for event in events.iter() {
+        if sockets.get(event.socket).is_ok() {
+            powered.send(Powered(event.socket));
+        }
+    }
+}
+
+pub fn run_linked_actions(
+    mut commands: Commands,
+    mut events: EventReader<Powered>,
+    linked: Query<&Linked>,
+    tagged: Tagged,
+    transforms: Query<&Transform>,
+) {
+    for event in events.iter() {
+        if let Ok(linked) = linked.get(event.0) {
+            for action in &linked.actions {
+                apply_action(&mut commands, &tagged, &transforms, action);
+            }
+        }
+    }
+}
+
+/// Carries out an action right away; also used by level scripts.
+pub fn apply_action(
+    commands: &mut Commands,
+    tagged: &Tagged,
+    transforms: &Query<&Transform>,
+    action: &TriggerAction,
+) {
+    match action {
+        TriggerAction::Move {
+            tag,
+            offset,
+            seconds,
+        } => {
+            for entity in tagged.find_by_tag(tag) {
+                if let Ok(transform) = transforms.get(entity) {
+                    commands.entity(entity).insert(Mover {
+                        from: transform.translation,
+                        to: transform.translation + Vec3::from(*offset),
+                        timer: Timer::from_seconds(seconds.max(0.0), false),
+                    });
+                }
+            }
+        }
+        TriggerAction::Despawn { tag } => {
+            for entity in tagged.find_by_tag(tag) {
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+        TriggerAction::SpawnPrefab { name, translation } => {
+            let transform = Transform::from_translation(Vec3::from(*translation));
+            let entity = commands.spawn_prefab(name, transform);
+            commands.entity(entity).insert(LevelEntity);
+        }
+        TriggerAction::HangRope { tag, offset, links } => {
+            for entity in tagged.find_by_tag(tag) {
+                if let Ok(transform) = transforms.get(entity) {
+                    let offset = Vec3::from(*offset);
+                    let rope = Rope::new(*links).with_weight().anchored_to(entity, offset);
+                    let at = Transform::from_translation(transform.mul_vec3(offset));
+                    let rope = commands.spawn_rope(rope, at);
+                    commands.entity(rope).insert(LevelEntity);
+                }
+            }
+        }
+        // Only the player who walked in gets the prompts, see the tutorial module.
+        TriggerAction::Tutorial { .. } => {}
+    }
+}
+
+pub fn move_tagged(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut movers: Query<(Entity, &mut Transform, &mut Mover)>,
+    mut nav_mesh: ResMut<NavMesh>,
+) {
+    for (entity, mut transform, mut mover) in &mut movers {
+        mover.timer.tick(time.delta());
+        let t = match mover.timer.duration().as_secs_f32() {
+            duration if duration > 0.0 => mover.timer.elapsed_secs() / duration,
+            _ => 1.0,
+        };
+        transform.translation = mover.from.lerp(mover.to, t.min(1.0));
+
+        if mover.timer.finished() {
+            commands.entity(entity).remove::<Mover>();
+            nav_mesh.rebake();
+        }
+    }
+}
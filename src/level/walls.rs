@@ -0,0 +1,103 @@
+//! Walls described by the face they show, with the collider built behind it.
+//!
+//! A [`WallDef`] gives the center of the face, the way it faces and its size. [`spawn_walls`]
+//! turns each into a slab [`WALL_THICKNESS`] deep behind the face, and a quad on the face itself
+//! unless the wall is hidden, so collider and mesh can't drift apart. Hidden walls show up as a
+//! [`ForceField`] instead.
+
+use crate::{force_field::ForceField, props::SpawnPropExt};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+/// Depth of a wall's collider behind its face, in meters.
+pub const WALL_THICKNESS: f32 = 2.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WallDef {
+    /// Center of the face.
+    pub center: Vec3,
+    /// The way the face looks, into the room.
+    pub normal: Vec3,
+    /// Width and height of the face. Walls standing up have the height along the world Y axis.
+    pub size: Vec2,
+    /// Hidden walls collide, and only show as a force field.
+    pub visible: bool,
+}
+
+impl WallDef {
+    pub fn new(center: Vec3, normal: Vec3, size: Vec2) -> Self {
+        Self {
+            center,
+            normal: normal.normalize(),
+            size,
+            visible: true,
+        }
+    }
+
+    pub fn hidden(self) -> Self {
+        Self {
+            visible: false,
+            ..self
+        }
+    }
+
+    /// Where the collider goes: local Z along the normal, and the slab behind the face.
+    fn transform(&self) -> Transform {
+        Transform {
+            translation: self.center - 0.5 * WALL_THICKNESS * self.normal,
+            rotation: Quat::from_rotation_arc(Vec3::Z, self.normal),
+            ..default()
+        }
+    }
+
+    fn collider(&self) -> Collider {
+        Collider::cuboid(0.5 * self.size.x, 0.5 * self.size.y, 0.5 * WALL_THICKNESS)
+    }
+}
+
+/// Spawns `walls`, all with `material`, and returns their entities. Walls of the same size share a
+/// mesh.
+pub fn spawn_walls(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    material: &Handle<StandardMaterial>,
+    walls: &[WallDef],
+) -> Vec<Entity> {
+    let mut quads: Vec<(Vec2, Handle<Mesh>)> = vec![];
+    walls
+        .iter()
+        .map(|wall| {
+            if !wall.visible {
+                return commands
+                    .spawn_bundle(SpatialBundle {
+                        transform: wall.transform(),
+                        ..default()
+                    })
+                    .insert(wall.collider())
+                    .insert(ForceField {
+                        size: wall.size,
+                        offset: 0.5 * WALL_THICKNESS,
+                    })
+                    .id();
+            }
+
+            let mesh = match quads.iter().find(|(size, _)| *size == wall.size) {
+                Some((_, mesh)) => mesh.clone(),
+                None => {
+                    let mesh = meshes.add(shape::Quad::new(wall.size).into());
+                    quads.push((wall.size, mesh.clone()));
+                    mesh
+                }
+            };
+            commands
+                .spawn_static_wall(
+                    mesh,
+                    material.clone(),
+                    wall.collider(),
+                    wall.transform(),
+                    0.5 * WALL_THICKNESS * Vec3::Z,
+                )
+                .id()
+        })
+        .collect()
+}
@@ -0,0 +1,132 @@
+//! Cleaning up what would otherwise pile up over a long session.
+//!
+//! Entities with a [`Lifetime`] are despawned once it runs out. Bodies marked [`Debris`], like the
+//! cubes of spawners and waves, are collected once more of them sleep than
+//! [`CleanupSettings::max_sleeping`], the longest asleep first, but only after they have slept for
+//! [`CleanupSettings::sleep_limit`]. [`CleanupStats`] counts entities for the perf overlay, so a
+//! leak shows up as a number that keeps going up.
+
+use crate::{hold::Held, net::Network};
+use bevy::{prelude::*, utils::HashMap};
+use bevy_inspector_egui::Inspectable;
+use bevy_rapier3d::prelude::*;
+
+pub struct LifetimePlugin;
+
+impl Plugin for LifetimePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CleanupSettings>()
+            .init_resource::<CleanupStats>()
+            .add_system(expire_lifetimes)
+            .add_system(collect_debris)
+            .add_system(count_entities);
+    }
+}
+
+/// Despawns the entity, with its children, when the timer runs out.
+#[derive(Debug, Component)]
+pub struct Lifetime(pub Timer);
+
+impl Lifetime {
+    pub fn new(seconds: f32) -> Self {
+        Self(Timer::from_seconds(seconds, false))
+    }
+}
+
+/// A body nobody misses once it has been lying around asleep for a while.
+#[derive(Debug, Default, Clone, Copy, Component)]
+pub struct Debris;
+
+#[derive(Debug, Clone, Inspectable)]
+pub struct CleanupSettings {
+    /// Sleeping debris kept around no matter how long it sleeps.
+    #[inspectable(min = 0, max = 500)]
+    pub max_sleeping: usize,
+    /// Seconds debris has to sleep before it can be collected.
+    #[inspectable(min = 1.0, max = 300.0)]
+    pub sleep_limit: f32,
+}
+
+impl Default for CleanupSettings {
+    fn default() -> Self {
+        Self {
+            max_sleeping: 60,
+            sleep_limit: 20.0,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CleanupStats {
+    pub entities: usize,
+    /// Entities waiting on a [`Lifetime`].
+    pub timed: usize,
+    /// Entities despawned by their [`Lifetime`] so far.
+    pub expired: usize,
+    /// Debris collected so far.
+    pub collected: usize,
+}
+
+fn expire_lifetimes(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut stats: ResMut<CleanupStats>,
+    mut lifetimes: Query<(Entity, &mut Lifetime)>,
+) {
+    for (entity, mut lifetime) in &mut lifetimes {
+        if lifetime.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+            stats.expired += 1;
+        }
+    }
+}
+
+/// Remembers since when each piece of debris sleeps, and collects the longest asleep past the cap.
+fn collect_debris(
+    mut commands: Commands,
+    time: Res<Time>,
+    network: Res<Network>,
+    settings: Res<CleanupSettings>,
+    mut stats: ResMut<CleanupStats>,
+    mut asleep_since: Local<HashMap<Entity, f64>>,
+    bodies: Query<(Entity, &Sleeping), (With<Debris>, Without<Held>)>,
+) {
+    if !network.is_authoritative() {
+        return;
+    }
+
+    let now = time.seconds_since_startup();
+    let mut sleeping = HashMap::default();
+    for (entity, body) in &bodies {
+        if body.sleeping {
+            let since = asleep_since.get(&entity).copied().unwrap_or(now);
+            sleeping.insert(entity, since);
+        }
+    }
+    *asleep_since = sleeping;
+
+    let excess = asleep_since.len().saturating_sub(settings.max_sleeping);
+    if excess == 0 {
+        return;
+    }
+    let mut oldest: Vec<_> = asleep_since
+        .iter()
+        .map(|(entity, since)| (*entity, *since))
+        .filter(|(_, since)| now - since > settings.sleep_limit as f64)
+        .collect();
+    oldest.sort_by(|a, b| a.1.total_cmp(&b.1));
+    for (entity, _) in oldest.into_iter().take(excess) {
+        commands.entity(entity).despawn_recursive();
+        asleep_since.remove(&entity);
+        stats.collected += 1;
+    }
+}
+
+fn count_entities(
+    mut stats: ResMut<CleanupStats>,
+    entities: Query<Entity>,
+    lifetimes: Query<(), With<Lifetime>>,
+) {
+    stats.entities = entities.iter().count();
+    stats.timed = lifetimes.iter().count();
+}
@@ -0,0 +1,100 @@
+//! Moves the sun without smearing the path-traced image.
+//!
+//! Hikari reuses light samples over frames, so a sun that turns a little every frame leaves ghosts
+//! and never settles. By default the [`LightingController`] holds the sun still and moves it in
+//! steps of [`LightingController::step`]; after each step it checks reused samples every frame
+//! for a moment, then goes back to [`LightingController::settled_interval`] while the image
+//! converges. Fast GPUs can use [`SunMode::Smooth`] instead, which turns the sun every frame and
+//! always checks. Without hikari, as in the browser, the sun just turns.
+
+use crate::tuning::Tuning;
+use bevy::prelude::*;
+#[cfg(not(target_arch = "wasm32"))]
+use bevy_hikari::prelude::*;
+use bevy_inspector_egui::Inspectable;
+
+/// Frames of checking reused samples every frame after a step.
+const SETTLE_FRAMES: u32 = 8;
+
+pub struct LightingPlugin;
+
+impl Plugin for LightingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LightingController>()
+            .add_system(rotate_sun);
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Inspectable)]
+pub enum SunMode {
+    #[default]
+    Stepped,
+    Smooth,
+}
+
+#[derive(Debug, Clone, Inspectable)]
+pub struct LightingController {
+    pub mode: SunMode,
+    /// Turn of the sun per step, in degrees.
+    #[inspectable(min = 0.1, max = 30.0)]
+    pub step: f32,
+    /// Frames between checks of reused samples while the sun is still.
+    #[inspectable(min = 1, max = 16)]
+    pub settled_interval: usize,
+    /// Turn not yet applied to the sun, in radians.
+    #[inspectable(ignore)]
+    pending: f32,
+    #[inspectable(ignore)]
+    settle_frames: u32,
+}
+
+impl Default for LightingController {
+    fn default() -> Self {
+        Self {
+            mode: SunMode::Stepped,
+            step: 2.0,
+            settled_interval: 4,
+            pending: 0.0,
+            settle_frames: 0,
+        }
+    }
+}
+
+fn rotate_sun(
+    time: Res<Time>,
+    tuning: Res<Tuning>,
+    mut controller: ResMut<LightingController>,
+    #[cfg(not(target_arch = "wasm32"))] mut config: ResMut<HikariConfig>,
+    mut lights: Query<&mut Transform, With<DirectionalLight>>,
+) {
+    controller.pending += tuning.light_rotation_speed * time.delta_seconds();
+
+    let turn = match controller.mode {
+        SunMode::Smooth => controller.pending,
+        SunMode::Stepped if controller.pending.abs() >= controller.step.to_radians() => {
+            controller.pending
+        }
+        SunMode::Stepped => 0.0,
+    };
+    if turn != 0.0 {
+        controller.pending = 0.0;
+        controller.settle_frames = SETTLE_FRAMES;
+        for mut transform in &mut lights {
+            transform.rotate_y(turn);
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let interval = if controller.mode == SunMode::Smooth || controller.settle_frames > 0 {
+            1
+        } else {
+            controller.settled_interval
+        };
+        controller.settle_frames = controller.settle_frames.saturating_sub(1);
+        // Touching the config makes hikari take it up again.
+        if config.validation_interval != interval {
+            config.validation_interval = interval;
+        }
+    }
+}
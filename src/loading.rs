@@ -0,0 +1,235 @@
+//! Loading screen shown until assets are ready.
+//!
+//! The app starts in [`AppState::Loading`] with physics paused. Files listed in [`PRELOAD`] and
+//! anything other modules add to [`LoadingAssets`] are loaded up front, then the meshes in the
+//! scene are waited on. Hikari builds its mesh buffers in the render world after the meshes show
+//! up, so the screen lingers for a few frames before switching to [`AppState::Playing`].
+
+use crate::{
+    hud::bitmap::{BitmapAlign, BitmapText, BitmapTextBundle},
+    input_block::{BlockReason, InputBlock},
+    layers::SpawnOnLayerExt,
+    locale::Locale,
+    RENDER_SIZE,
+};
+use bevy::{asset::LoadState, prelude::*};
+use bevy_rapier3d::prelude::*;
+
+/// Assets to load before playing, relative to the asset folder.
+pub const PRELOAD: &[&str] = &["fonts/DejaVuSansMono.ttf", "earth_daymap.jpg"];
+/// Frames to wait after everything loaded, for the renderer to catch up.
+const SETTLE_FRAMES: u32 = 10;
+
+/// Size of the progress bar, in HUD pixels.
+const BAR_SIZE: Vec2 = Vec2::new(160.0, 4.0);
+const BAR_Z: f32 = 10.0;
+
+pub struct LoadingPlugin;
+
+impl Plugin for LoadingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_state(AppState::Loading)
+            .init_resource::<LoadingAssets>()
+            .init_resource::<LoadingProgress>()
+            .add_system_set(
+                SystemSet::on_enter(AppState::Loading)
+                    .with_system(enter_loading)
+                    .with_system(spawn_loading_screen),
+            )
+            .add_system_set(
+                SystemSet::on_update(AppState::Loading)
+                    .with_system(check_loading)
+                    .with_system(update_loading_screen.after(check_loading)),
+            )
+            .add_system_set(
+                SystemSet::on_exit(AppState::Loading)
+                    .with_system(exit_loading)
+                    .with_system(despawn_loading_screen),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AppState {
+    Loading,
+    Playing,
+}
+
+/// Handles that must finish loading before the game starts.
+pub struct LoadingAssets {
+    pub handles: Vec<HandleUntyped>,
+}
+
+impl FromWorld for LoadingAssets {
+    fn from_world(world: &mut World) -> Self {
+        let asset_server = world.resource::<AssetServer>();
+        let handles = PRELOAD
+            .iter()
+            .map(|path| asset_server.load_untyped(*path))
+            .collect();
+        Self { handles }
+    }
+}
+
+impl LoadingAssets {
+    pub fn add<T: Asset>(&mut self, handle: &Handle<T>) {
+        self.handles.push(handle.clone_untyped());
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct LoadingProgress {
+    pub loaded: usize,
+    pub total: usize,
+    settled_frames: u32,
+}
+
+impl LoadingProgress {
+    pub fn fraction(&self) -> f32 {
+        let settle = self.settled_frames as f32 / SETTLE_FRAMES as f32;
+        match self.total {
+            0 => settle,
+            total => 0.9 * self.loaded as f32 / total as f32 + 0.1 * settle,
+        }
+    }
+}
+
+#[derive(Component)]
+struct LoadingScreen;
+
+#[derive(Component)]
+struct LoadingBar;
+
+#[derive(Component)]
+struct LoadingText;
+
+fn enter_loading(
+    mut rapier_config: ResMut<RapierConfiguration>,
+    mut block: ResMut<InputBlock>,
+    mut progress: ResMut<LoadingProgress>,
+) {
+    rapier_config.physics_pipeline_active = false;
+    block.set(BlockReason::Loading, true);
+    *progress = LoadingProgress::default();
+}
+
+fn exit_loading(mut rapier_config: ResMut<RapierConfiguration>, mut block: ResMut<InputBlock>) {
+    rapier_config.physics_pipeline_active = true;
+    block.set(BlockReason::Loading, false);
+}
+
+fn spawn_loading_screen(mut commands: Commands, locale: Res<Locale>) {
+    // On the HUD, over everything else on it, so the screen has the same pixels as the game.
+    commands
+        .spawn_on_hud_layer(SpriteBundle {
+            sprite: Sprite {
+                color: Color::BLACK,
+                custom_size: Some(Vec2::new(RENDER_SIZE[0] as f32, RENDER_SIZE[1] as f32)),
+                ..default()
+            },
+            transform: Transform::from_xyz(0.0, 0.0, BAR_Z),
+            ..default()
+        })
+        .insert(LoadingScreen)
+        .with_children(|parent| {
+            parent.spawn_on_hud_layer(SpriteBundle {
+                sprite: Sprite {
+                    color: Color::rgb(0.25, 0.25, 0.25),
+                    custom_size: Some(BAR_SIZE),
+                    ..default()
+                },
+                transform: Transform::from_xyz(0.0, 0.0, 0.1),
+                ..default()
+            });
+            parent
+                .spawn_on_hud_layer(SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::rgb(0.8, 0.7, 0.6),
+                        custom_size: Some(BAR_SIZE),
+                        ..default()
+                    },
+                    transform: Transform::from_xyz(0.0, 0.0, 0.2)
+                        .with_scale(Vec3::new(0.0, 1.0, 1.0)),
+                    ..default()
+                })
+                .insert(LoadingBar);
+
+            let mut text = BitmapTextBundle::new(
+                BitmapText::new(locale.get("loading"), Color::WHITE, BitmapAlign::Left),
+                Vec2::new(-0.5 * BAR_SIZE.x, BAR_SIZE.y + 4.0),
+            );
+            text.spatial.transform.translation.z = 0.3;
+            parent.spawn_on_hud_layer(text).insert(LoadingText);
+        });
+}
+
+fn despawn_loading_screen(mut commands: Commands, screens: Query<Entity, With<LoadingScreen>>) {
+    for entity in &screens {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn check_loading(
+    asset_server: Res<AssetServer>,
+    loading: Res<LoadingAssets>,
+    meshes: Res<Assets<Mesh>>,
+    mesh_handles: Query<&Handle<Mesh>>,
+    mut progress: ResMut<LoadingProgress>,
+    mut state: ResMut<State<AppState>>,
+) {
+    let mut loaded = 0;
+    let mut failed = 0;
+    for handle in &loading.handles {
+        match asset_server.get_load_state(handle) {
+            LoadState::Loaded => loaded += 1,
+            // Don't hang on a missing file, the game copes without it.
+            LoadState::Failed | LoadState::Unloaded => failed += 1,
+            LoadState::NotLoaded | LoadState::Loading => {}
+        }
+    }
+    let mesh_count = mesh_handles.iter().count();
+    let meshes_ready = mesh_handles
+        .iter()
+        .filter(|handle| meshes.get(handle).is_some())
+        .count();
+
+    progress.loaded = loaded + failed + meshes_ready;
+    progress.total = loading.handles.len() + mesh_count;
+    if progress.loaded < progress.total {
+        progress.settled_frames = 0;
+        return;
+    }
+
+    progress.settled_frames += 1;
+    if progress.settled_frames >= SETTLE_FRAMES {
+        info!(
+            "Loaded {} assets, {} failed",
+            progress.total - failed,
+            failed
+        );
+        if let Err(err) = state.set(AppState::Playing) {
+            warn!("Failed to start playing: {:?}", err);
+        }
+    }
+}
+
+fn update_loading_screen(
+    progress: Res<LoadingProgress>,
+    locale: Res<Locale>,
+    mut bars: Query<&mut Transform, With<LoadingBar>>,
+    mut text: Query<&mut BitmapText, With<LoadingText>>,
+) {
+    let fraction = progress.fraction().clamp(0.0, 1.0);
+    for mut transform in &mut bars {
+        // Grow from the left edge.
+        transform.scale.x = fraction;
+        transform.translation.x = -0.5 * BAR_SIZE.x * (1.0 - fraction);
+    }
+    for mut text in &mut text {
+        let percent = (100.0 * fraction) as u32;
+        let value = locale.get_args("loading-progress", &[("percent", percent.into())]);
+        if text.value != value {
+            text.value = value;
+        }
+    }
+}
@@ -0,0 +1,215 @@
+//! Translated text with Fluent.
+//!
+//! Languages are listed in `assets/locales/locales.ron`, each with a `<id>.ftl` file next to it.
+//! [`Locale`] looks up messages in the current language, then in English, then gives the key back.
+//! F8 cycles languages at runtime. A language can name its own font for scripts the HUD font
+//! lacks; HUD texts switch over once it has loaded.
+
+use crate::hud::{HudFont, HUD_FONT};
+use bevy::{asset::LoadState, prelude::*};
+use fluent_bundle::{concurrent::FluentBundle, FluentArgs, FluentResource, FluentValue};
+use serde::Deserialize;
+use std::fs;
+use unic_langid::LanguageIdentifier;
+
+pub const LOCALE_FOLDER: &str = "assets/locales";
+const FALLBACK_LANGUAGE: &str = "en-US";
+
+pub struct LocalePlugin;
+
+impl Plugin for LocalePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Locale>()
+            .add_system(switch_language)
+            .add_system(apply_locale_font.after(switch_language));
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LanguageInfo {
+    /// Language tag, also the name of the `.ftl` file.
+    pub id: String,
+    /// Shown in its own language.
+    pub name: String,
+    /// Font covering this language, relative to the asset folder.
+    #[serde(default)]
+    pub font: Option<String>,
+}
+
+type Bundle = FluentBundle<FluentResource>;
+
+fn load_bundle(id: &str) -> anyhow::Result<Bundle> {
+    let language: LanguageIdentifier = id.parse()?;
+    let source = fs::read_to_string(format!("{}/{}.ftl", LOCALE_FOLDER, id))?;
+    let resource =
+        FluentResource::try_new(source).map_err(|(_, errors)| anyhow::anyhow!("{:?}", errors))?;
+
+    let mut bundle = FluentBundle::new_concurrent(vec![language]);
+    // The isolation marks around arguments show up as boxes in the HUD font.
+    bundle.set_use_isolating(false);
+    bundle
+        .add_resource(resource)
+        .map_err(|errors| anyhow::anyhow!("{:?}", errors))?;
+    Ok(bundle)
+}
+
+pub struct Locale {
+    pub languages: Vec<LanguageInfo>,
+    pub current: usize,
+    bundle: Option<Bundle>,
+    fallback: Option<Bundle>,
+    /// Font waiting to load before the HUD switches to it.
+    pending_font: Option<Handle<Font>>,
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        let path = format!("{}/locales.ron", LOCALE_FOLDER);
+        let languages = fs::read_to_string(&path)
+            .map_err(|err| err.to_string())
+            .and_then(|text| ron::from_str(&text).map_err(|err| err.to_string()));
+        let languages = match languages {
+            Ok(languages) => languages,
+            Err(err) => {
+                warn!("Failed to load {}, using English only: {}", path, err);
+                vec![LanguageInfo {
+                    id: FALLBACK_LANGUAGE.into(),
+                    name: "English".into(),
+                    font: None,
+                }]
+            }
+        };
+
+        let fallback = match load_bundle(FALLBACK_LANGUAGE) {
+            Ok(bundle) => Some(bundle),
+            Err(err) => {
+                warn!("Failed to load language {}: {:?}", FALLBACK_LANGUAGE, err);
+                None
+            }
+        };
+        let current = languages
+            .iter()
+            .position(|language| language.id == FALLBACK_LANGUAGE)
+            .unwrap_or(0);
+
+        Self {
+            languages,
+            current,
+            bundle: None,
+            fallback,
+            pending_font: None,
+        }
+    }
+}
+
+impl Locale {
+    pub fn language(&self) -> Option<&LanguageInfo> {
+        self.languages.get(self.current)
+    }
+
+    pub fn set_language(&mut self, index: usize) {
+        let language = match self.languages.get(index) {
+            Some(language) => language,
+            None => return,
+        };
+        self.current = index;
+        self.bundle = if language.id == FALLBACK_LANGUAGE {
+            None
+        } else {
+            match load_bundle(&language.id) {
+                Ok(bundle) => Some(bundle),
+                Err(err) => {
+                    warn!("Failed to load language {}: {:?}", language.id, err);
+                    None
+                }
+            }
+        };
+    }
+
+    fn format(&self, key: &str, args: Option<&FluentArgs>) -> Option<String> {
+        self.bundle
+            .iter()
+            .chain(self.fallback.iter())
+            .find_map(|bundle| {
+                let pattern = bundle.get_message(key)?.value()?;
+                let mut errors = vec![];
+                Some(
+                    bundle
+                        .format_pattern(pattern, args, &mut errors)
+                        .into_owned(),
+                )
+            })
+    }
+
+    /// The message called `key`, or the key itself if there is none.
+    pub fn get(&self, key: &str) -> String {
+        self.format(key, None).unwrap_or_else(|| key.into())
+    }
+
+    pub fn get_args(&self, key: &str, args: &[(&str, FluentValue)]) -> String {
+        let mut fluent_args = FluentArgs::new();
+        for (name, value) in args {
+            fluent_args.set(*name, value.clone());
+        }
+        self.format(key, Some(&fluent_args))
+            .unwrap_or_else(|| key.into())
+    }
+
+    /// Translates content text, like dialogue, that may be a message key or plain text.
+    pub fn translate(&self, text: &str) -> String {
+        self.format(text, None).unwrap_or_else(|| text.into())
+    }
+}
+
+fn switch_language(
+    keys: Res<Input<KeyCode>>,
+    asset_server: Res<AssetServer>,
+    mut locale: ResMut<Locale>,
+) {
+    if !keys.just_pressed(KeyCode::F8) || locale.languages.is_empty() {
+        return;
+    }
+
+    let next = (locale.current + 1) % locale.languages.len();
+    locale.set_language(next);
+    if let Some(language) = locale.language().cloned() {
+        info!("Language: {}", language.name);
+        let font = language.font.as_deref().unwrap_or(HUD_FONT);
+        locale.pending_font = Some(asset_server.load(font));
+    }
+}
+
+/// Moves every text using the HUD font over to the font of the language, once it has loaded.
+fn apply_locale_font(
+    asset_server: Res<AssetServer>,
+    mut locale: ResMut<Locale>,
+    mut hud_font: ResMut<HudFont>,
+    mut texts: Query<&mut Text>,
+) {
+    let font = match &locale.pending_font {
+        Some(font) => font.clone(),
+        None => return,
+    };
+    match asset_server.get_load_state(&font) {
+        LoadState::Loaded => {}
+        LoadState::Failed => {
+            warn!("Failed to load the font of the language, keeping the current one");
+            locale.pending_font = None;
+            return;
+        }
+        _ => return,
+    }
+
+    locale.pending_font = None;
+    if font == hud_font.0 {
+        return;
+    }
+    for mut text in &mut texts {
+        for section in &mut text.sections {
+            if section.style.font == hud_font.0 {
+                section.style.font = font.clone();
+            }
+        }
+    }
+    hud_font.0 = font;
+}
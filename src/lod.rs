@@ -0,0 +1,216 @@
+//! Coarser meshes for far geometry, to keep the path tracer's BVH small.
+//!
+//! [`LodRequest`] on an entity asks for levels of detail for its mesh and the meshes of its
+//! children. Coarser meshes come from [`simplify`], which welds vertices on ever larger grids, and
+//! are shared by every entity with the same mesh. [`update_lod`] picks a level by distance to the
+//! player camera, with some slack so meshes don't flicker at the boundary. Hikari rebuilds its
+//! acceleration structures when a mesh handle changes, so handles are only touched when the level
+//! does.
+
+use crate::PlayerCamera;
+use bevy::{
+    prelude::*,
+    render::mesh::{Indices, PrimitiveTopology, VertexAttributeValues},
+    utils::HashMap,
+};
+
+/// Distance past a boundary, in meters, before the level changes.
+const HYSTERESIS: f32 = 2.0;
+/// Grid cells across the full detail mesh when it's first simplified.
+const BASE_CELLS: f32 = 8.0;
+
+pub struct LodPlugin;
+
+impl Plugin for LodPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LodCache>()
+            .init_resource::<LodStats>()
+            .add_system(build_lods)
+            .add_system(update_lod.after(build_lods));
+    }
+}
+
+/// Asks for levels of detail beyond each of these distances, in meters, nearest first.
+#[derive(Debug, Clone, Component)]
+pub struct LodRequest(pub Vec<f32>);
+
+#[derive(Debug, Clone, Component)]
+pub struct Lod {
+    /// Full detail first, then coarser for each distance.
+    pub meshes: Vec<Handle<Mesh>>,
+    pub distances: Vec<f32>,
+    pub current: usize,
+}
+
+impl Lod {
+    /// Level to show at `distance`, staying at the current one near its boundaries.
+    fn level(&self, distance: f32) -> usize {
+        let mut level = self.current;
+        while level < self.distances.len() && distance > self.distances[level] + HYSTERESIS {
+            level += 1;
+        }
+        while level > 0 && distance < self.distances[level - 1] - HYSTERESIS {
+            level -= 1;
+        }
+        level
+    }
+}
+
+/// Coarser meshes made so far, by the full detail mesh and level.
+#[derive(Default)]
+pub struct LodCache {
+    meshes: HashMap<(Handle<Mesh>, usize), Handle<Mesh>>,
+}
+
+#[derive(Debug, Default)]
+pub struct LodStats {
+    /// Mesh swaps since the start, each one a BVH rebuild for the path tracer.
+    pub swaps: usize,
+}
+
+/// A copy of `mesh` with the vertices in each `cell` wide grid cell welded together.
+///
+/// Vertices only weld when their normals roughly agree, so hard edges stay hard. Triangles that
+/// collapse to a line or a point are dropped.
+pub fn simplify(mesh: &Mesh, cell: f32) -> Option<Mesh> {
+    let positions = mesh.attribute(Mesh::ATTRIBUTE_POSITION)?.as_float3()?;
+    let normals = mesh
+        .attribute(Mesh::ATTRIBUTE_NORMAL)
+        .and_then(VertexAttributeValues::as_float3);
+    let uvs = match mesh.attribute(Mesh::ATTRIBUTE_UV_0) {
+        Some(VertexAttributeValues::Float32x2(uvs)) => Some(uvs),
+        _ => None,
+    };
+    let indices: Vec<u32> = match mesh.indices() {
+        Some(Indices::U32(indices)) => indices.clone(),
+        Some(Indices::U16(indices)) => indices.iter().map(|&index| index as u32).collect(),
+        None => (0..positions.len() as u32).collect(),
+    };
+
+    let mut cells: HashMap<(IVec3, IVec3), u32> = default();
+    let mut sums: Vec<(Vec3, f32)> = vec![];
+    let mut new_normals = vec![];
+    let mut new_uvs = vec![];
+    let remap: Vec<u32> = positions
+        .iter()
+        .enumerate()
+        .map(|(index, position)| {
+            let position = Vec3::from(*position);
+            let normal = normals.map_or(Vec3::Y, |normals| Vec3::from(normals[index]));
+            let key = (
+                (position / cell).floor().as_ivec3(),
+                (normal * 2.0).round().as_ivec3(),
+            );
+            let id = *cells.entry(key).or_insert_with(|| {
+                sums.push((Vec3::ZERO, 0.0));
+                new_normals.push(normal.to_array());
+                new_uvs.push(uvs.map_or([0.0, 0.0], |uvs| uvs[index]));
+                sums.len() as u32 - 1
+            });
+            let sum = &mut sums[id as usize];
+            sum.0 += position;
+            sum.1 += 1.0;
+            id
+        })
+        .collect();
+
+    let new_indices: Vec<u32> = indices
+        .chunks_exact(3)
+        .map(|triangle| {
+            [
+                remap[triangle[0] as usize],
+                remap[triangle[1] as usize],
+                remap[triangle[2] as usize],
+            ]
+        })
+        .filter(|[a, b, c]| a != b && b != c && a != c)
+        .flatten()
+        .collect();
+    let new_positions: Vec<[f32; 3]> = sums
+        .iter()
+        .map(|(sum, count)| (*sum / *count).to_array())
+        .collect();
+
+    let mut simplified = Mesh::new(PrimitiveTopology::TriangleList);
+    simplified.insert_attribute(Mesh::ATTRIBUTE_POSITION, new_positions);
+    simplified.insert_attribute(Mesh::ATTRIBUTE_NORMAL, new_normals);
+    simplified.insert_attribute(Mesh::ATTRIBUTE_UV_0, new_uvs);
+    simplified.set_indices(Some(Indices::U32(new_indices)));
+    Some(simplified)
+}
+
+/// Gives the meshes under each new [`LodRequest`] their levels of detail.
+fn build_lods(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut cache: ResMut<LodCache>,
+    requests: Query<(Entity, &LodRequest, Option<&Children>)>,
+    handles: Query<&Handle<Mesh>>,
+) {
+    for (entity, request, children) in &requests {
+        let entities = std::iter::once(entity).chain(children.into_iter().flatten().copied());
+        let targets: Vec<_> = entities
+            .filter_map(|entity| Some((entity, handles.get(entity).ok()?.clone())))
+            .collect();
+        // Prefabs get their mesh from a command, which may not have run yet, and glTF meshes
+        // load in the background; keep the request until all of them are there.
+        let loaded = targets.iter().all(|(_, handle)| meshes.contains(handle));
+        if targets.is_empty() || !loaded {
+            continue;
+        }
+
+        for (target, handle) in targets {
+            let mesh = match meshes.get(&handle) {
+                Some(mesh) => mesh.clone(),
+                None => continue,
+            };
+            let size = mesh
+                .compute_aabb()
+                .map_or(1.0, |aabb| 2.0 * aabb.half_extents.max_element());
+            let levels: Vec<_> = (1..=request.0.len())
+                .map(|level| {
+                    let key = (handle.clone(), level);
+                    if let Some(cached) = cache.meshes.get(&key) {
+                        return cached.clone();
+                    }
+                    let cell = size * 2f32.powi(level as i32 - 1) / BASE_CELLS;
+                    let simplified = match simplify(&mesh, cell) {
+                        Some(simplified) => meshes.add(simplified),
+                        None => handle.clone(),
+                    };
+                    cache.meshes.insert(key, simplified.clone());
+                    simplified
+                })
+                .collect();
+
+            commands.entity(target).insert(Lod {
+                meshes: std::iter::once(handle).chain(levels).collect(),
+                distances: request.0.clone(),
+                current: 0,
+            });
+        }
+        commands.entity(entity).remove::<LodRequest>();
+    }
+}
+
+/// Swaps meshes whose level of detail changed with the camera's distance.
+fn update_lod(
+    mut stats: ResMut<LodStats>,
+    cameras: Query<&GlobalTransform, With<PlayerCamera>>,
+    mut objects: Query<(&GlobalTransform, &mut Lod, &mut Handle<Mesh>)>,
+) {
+    let camera = match cameras.get_single() {
+        Ok(camera) => camera.translation(),
+        Err(_) => return,
+    };
+    for (transform, mut lod, mut handle) in &mut objects {
+        let level = lod
+            .level(transform.translation().distance(camera))
+            .min(lod.meshes.len() - 1);
+        if level != lod.current {
+            lod.current = level;
+            *handle = lod.meshes[level].clone();
+            stats.swaps += 1;
+        }
+    }
+}
@@ -0,0 +1,207 @@
+//! Logs that can be filtered while the game runs, and a window to read them in.
+//!
+//! This takes the place of bevy's `LogPlugin`: the same terminal output, behind an
+//! [`EnvFilter`] that [`LogFilter`] can swap at any time, plus a layer keeping the last
+//! [`LOG_HISTORY`] lines for the viewer. Gameplay modules log under short targets, so a filter like
+//! `catch=debug,net=trace` turns on just those. The backquote key opens the viewer.
+
+use crate::{input_block::InputBlock, locale::Locale};
+use bevy::{
+    prelude::*,
+    utils::tracing::{
+        self,
+        field::{Field, Visit},
+        Subscriber,
+    },
+};
+use bevy_egui::{egui, EguiContext};
+use std::{collections::VecDeque, fmt::Write as _, sync::Mutex};
+use tracing_subscriber::{
+    layer::{Context, SubscriberExt},
+    reload, EnvFilter, Layer, Registry,
+};
+
+/// Lines kept for the viewer.
+pub const LOG_HISTORY: usize = 500;
+/// Used without a `--log` option or `RUST_LOG`; the same as bevy's.
+const DEFAULT_FILTER: &str = "info,wgpu=error";
+
+static LOG_LINES: Mutex<VecDeque<LogLine>> = Mutex::new(VecDeque::new());
+
+pub struct LoggingPlugin {
+    /// Filter directives to start with, over `RUST_LOG`.
+    pub filter: Option<String>,
+}
+
+impl Plugin for LoggingPlugin {
+    fn build(&self, app: &mut App) {
+        let directives = self
+            .filter
+            .clone()
+            .or_else(|| std::env::var("RUST_LOG").ok())
+            .unwrap_or_else(|| DEFAULT_FILTER.into());
+        let handle = install_subscriber(&directives);
+        app.insert_resource(LogFilter {
+            directives,
+            handle,
+            error: None,
+        })
+        .add_system(log_viewer);
+    }
+}
+
+/// What gets logged. Changing `directives` through [`LogFilter::set`] takes effect right away.
+pub struct LogFilter {
+    pub directives: String,
+    /// Missing when another subscriber was installed first, as in tests building several apps.
+    handle: Option<reload::Handle<EnvFilter, Registry>>,
+    /// Why the last directives were rejected.
+    error: Option<String>,
+}
+
+impl LogFilter {
+    pub fn set(&mut self, directives: &str) {
+        let filter = match EnvFilter::try_new(directives) {
+            Ok(filter) => filter,
+            Err(err) => {
+                self.error = Some(err.to_string());
+                return;
+            }
+        };
+        if let Some(handle) = &self.handle {
+            if let Err(err) = handle.reload(filter) {
+                self.error = Some(err.to_string());
+                return;
+            }
+        }
+        self.directives = directives.into();
+        self.error = None;
+    }
+}
+
+#[derive(Debug, Clone)]
+struct LogLine {
+    level: tracing::Level,
+    target: String,
+    text: String,
+}
+
+/// Keeps what passes the filter for the viewer.
+struct HistoryLayer;
+
+impl<S: Subscriber> Layer<S> for HistoryLayer {
+    fn on_event(&self, event: &tracing::Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = LineVisitor::default();
+        event.record(&mut visitor);
+        let line = LogLine {
+            level: *event.metadata().level(),
+            target: event.metadata().target().into(),
+            text: visitor.message + &visitor.fields,
+        };
+        if let Ok(mut lines) = LOG_LINES.lock() {
+            if lines.len() == LOG_HISTORY {
+                lines.pop_front();
+            }
+            lines.push_back(line);
+        }
+    }
+}
+
+/// The message of an event, followed by its other fields as `name=value`.
+#[derive(Default)]
+struct LineVisitor {
+    message: String,
+    fields: String,
+}
+
+impl Visit for LineVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{:?}", value);
+        } else {
+            let _ = write!(self.fields, " {}={:?}", field.name(), value);
+        }
+    }
+}
+
+fn install_subscriber(directives: &str) -> Option<reload::Handle<EnvFilter, Registry>> {
+    let filter = EnvFilter::try_new(directives).unwrap_or_else(|_| EnvFilter::new(DEFAULT_FILTER));
+    let (filter, handle) = reload::Layer::new(filter);
+    let subscriber = Registry::default().with(filter).with(HistoryLayer);
+
+    #[cfg(not(target_arch = "wasm32"))]
+    let subscriber = subscriber.with(tracing_subscriber::fmt::Layer::default());
+    #[cfg(target_arch = "wasm32")]
+    let subscriber = subscriber.with(tracing_wasm::WASMLayer::new(
+        tracing_wasm::WASMLayerConfig::default(),
+    ));
+
+    match tracing::subscriber::set_global_default(subscriber) {
+        Ok(()) => Some(handle),
+        Err(_) => None,
+    }
+}
+
+fn level_color(level: tracing::Level) -> egui::Color32 {
+    match level {
+        tracing::Level::ERROR => egui::Color32::from_rgb(255, 96, 96),
+        tracing::Level::WARN => egui::Color32::from_rgb(255, 200, 80),
+        tracing::Level::INFO => egui::Color32::from_rgb(200, 200, 200),
+        tracing::Level::DEBUG => egui::Color32::from_rgb(120, 180, 255),
+        _ => egui::Color32::from_rgb(140, 140, 140),
+    }
+}
+
+fn log_viewer(
+    keys: Res<Input<KeyCode>>,
+    block: Res<InputBlock>,
+    locale: Res<Locale>,
+    mut filter: ResMut<LogFilter>,
+    mut open: Local<bool>,
+    mut edited: Local<Option<String>>,
+    mut egui_context: ResMut<EguiContext>,
+) {
+    if keys.just_pressed(KeyCode::Grave) && !block.typing() {
+        *open = !*open;
+    }
+    if !*open {
+        return;
+    }
+
+    let lines: Vec<_> = match LOG_LINES.lock() {
+        Ok(lines) => lines.iter().cloned().collect(),
+        Err(_) => return,
+    };
+    let directives = edited.get_or_insert_with(|| filter.directives.clone());
+    let mut apply = false;
+    egui::Window::new(locale.get("log"))
+        .default_size([480.0, 320.0])
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                ui.label(locale.get("log-filter"));
+                let response = ui.text_edit_singleline(directives);
+                apply = response.lost_focus() && ui.input().key_pressed(egui::Key::Enter);
+                apply |= ui.button(locale.get("log-apply")).clicked();
+            });
+            if let Some(error) = &filter.error {
+                ui.colored_label(level_color(tracing::Level::ERROR), error);
+            }
+            ui.separator();
+            egui::ScrollArea::vertical()
+                .stick_to_bottom()
+                .auto_shrink([false, false])
+                .show(ui, |ui| {
+                    for line in &lines {
+                        ui.colored_label(
+                            level_color(line.level),
+                            format!("{:5} {}: {}", line.level, line.target, line.text),
+                        );
+                    }
+                });
+        });
+
+    if apply {
+        let directives = directives.clone();
+        filter.set(&directives);
+    }
+}
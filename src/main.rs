@@ -1,57 +1,288 @@
+use accessibility::AccessibilitySettings;
 use bevy::{
+    asset::AssetServerSettings,
+    core_pipeline::clear_color::ClearColorConfig,
+    log::LogPlugin,
     pbr::PbrPlugin,
     prelude::*,
     reflect::TypeUuid,
     render::{
         camera::{CameraRenderGraph, RenderTarget},
         render_resource::*,
-        texture::ImageSampler,
-        view::RenderLayers,
+        settings::WgpuSettings,
+        texture::{BevyDefault, ImageSampler},
     },
     sprite::MaterialMesh2dBundle,
+    window::WindowMode,
+    winit::WinitPlugin,
 };
+use bevy_egui::EguiPlugin;
+#[cfg(not(target_arch = "wasm32"))]
 use bevy_hikari::prelude::*;
 use bevy_inspector_egui::WorldInspectorPlugin;
 use bevy_mod_wanderlust::{CharacterControllerBundle, ControllerInput, WanderlustPlugin};
 use bevy_rapier3d::prelude::*;
+use camera::{smoothing, CameraSettings, CameraZoom};
+use catch_class::CatchClass;
+use clap::Parser;
+use death::Dead;
+use debug_draw::{DebugCategory, DebugDraw};
+use feedback::FeedbackEvent;
+use hold::{CatchPoint, Held};
+use hud::{composite::CompositeMaterial, radial::RadialMenu};
+use inspect::Inspecting;
+use layers::SpawnOnLayerExt;
 use leafwing_input_manager::prelude::*;
+use spatial::SpatialIndex;
+use stamina::Stamina;
 use std::f32::consts::PI;
+use tuning::Tuning;
+
+mod ability;
+mod accessibility;
+mod achievements;
+mod activity;
+mod behavior;
+mod bots;
+mod camera;
+mod carry;
+mod catch_class;
+mod cli;
+mod crash;
+mod cues;
+mod cutscene;
+mod death;
+mod debug_draw;
+mod dialogue;
+mod display;
+mod feedback;
+mod fog;
+mod force_field;
+mod game_event;
+mod ghost;
+mod gpu;
+mod headless;
+mod hold;
+mod hud;
+mod input_block;
+mod inspect;
+mod inspector;
+mod interact;
+mod interpolation;
+mod layers;
+mod leaderboard;
+mod level;
+mod lifetime;
+mod lighting;
+mod loading;
+mod locale;
+mod lod;
+mod logging;
+mod manifest;
+mod match_flow;
+mod materials;
+mod merge;
+mod mirror;
+mod nav;
+mod net;
+mod paint;
+mod perception;
+mod placement;
+mod post;
+mod practice;
+mod prefab;
+mod presence;
+mod profile;
+mod progression;
+mod props;
+mod ragdoll;
+mod repel;
+mod replay;
+mod rings;
+mod rope;
+mod rules;
+mod rumble;
+mod screens;
+mod script;
+mod sky;
+#[cfg(test)]
+mod smoke_tests;
+mod socket;
+mod spatial;
+mod spawner;
+mod spectate;
+mod stamina;
+mod stats;
+mod stretch;
+mod style;
+mod surface;
+mod surveillance;
+mod tags;
+mod teams;
+mod touch;
+mod tuning;
+mod tutorial;
+mod waves;
 
 /// This controls the resolution.
 const RENDER_SIZE: [u32; 2] = [320, 180];
-const RENDER_PASS_LAYER: RenderLayers = RenderLayers::layer(1);
+const UI_IMAGE_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Image::TYPE_UUID, 1145141919811);
 const RENDER_IMAGE_HANDLE: HandleUntyped =
     HandleUntyped::weak_from_u64(Image::TYPE_UUID, 1145141919810);
 
 const GROUND_SIZE: f32 = 100.0;
-const CENTER_PILLAR_SIZE: f32 = 20.0;
 const CUBE_SIZE: f32 = 1.0;
 
-const LIGHT_ROTATION_SPEED: f32 = 0.1;
-
 fn main() {
-    App::new()
-        .register_type::<Player>()
+    let args = cli::Args::parse();
+    crash::install_panic_hook(&args);
+    build_app(args).run();
+}
+
+/// The whole game, set up as `args` ask.
+fn build_app(args: cli::Args) -> App {
+    // In the browser, the canvas on the page is the window.
+    let mode = if args.windowed || cfg!(target_arch = "wasm32") {
+        WindowMode::Windowed
+    } else {
+        WindowMode::BorderlessFullscreen
+    };
+
+    let mut app = App::new();
+    app.register_type::<Player>()
         .register_type::<PlayerCamera>()
         .register_type::<PlayerCatcher>()
         .register_type::<CatchObject>()
         .insert_resource(WindowDescriptor {
-            width: 1280.,
-            height: 720.,
+            width: (RENDER_SIZE[0] * args.render_scale) as f32,
+            height: (RENDER_SIZE[1] * args.render_scale) as f32,
+            mode,
+            canvas: cfg!(target_arch = "wasm32").then(|| "#bevy".into()),
+            fit_canvas_to_parent: true,
             ..Default::default()
         })
-        .insert_resource(ClearColor(Color::rgba(0.1, 0.1, 0.1, 1.0)))
-        .insert_resource(HikariConfig {
-            validation_interval: 1,
-            ..Default::default()
+        // Lets level files and shaders reload while the game runs, where there is a file system
+        // to watch.
+        .insert_resource(AssetServerSettings {
+            watch_for_changes: (cfg!(debug_assertions) || args.hot_reload)
+                && cfg!(not(target_arch = "wasm32")),
+            ..default()
         })
-        .add_plugins(DefaultPlugins)
-        .add_plugin(WorldInspectorPlugin::new())
-        .add_plugin(InputManagerPlugin::<Action>::default())
+        .insert_resource(ClearColor(Color::rgba(0.1, 0.1, 0.1, 1.0)));
+    #[cfg(not(target_arch = "wasm32"))]
+    app.insert_resource(HikariConfig {
+        validation_interval: 1,
+        ..Default::default()
+    });
+    // Logging is set up first, so the other plugins can log while they build.
+    app.add_plugin(logging::LoggingPlugin {
+        filter: args.log.clone(),
+    });
+    if args.headless {
+        // No window, and no GPU for the renderer to start on.
+        app.insert_resource(WgpuSettings {
+            backends: None,
+            ..default()
+        })
+        .add_plugins_with(DefaultPlugins, |group| {
+            group.disable::<LogPlugin>().disable::<WinitPlugin>()
+        })
+        // The menus still ask for egui, though they never open.
+        .add_plugin(EguiPlugin)
+        .add_plugin(headless::HeadlessPlugin);
+    } else {
+        app.add_plugins_with(DefaultPlugins, |group| group.disable::<LogPlugin>())
+            .add_plugin(WorldInspectorPlugin::new())
+            .add_plugin(inspector::InspectorPanelsPlugin);
+    }
+    app.add_plugin(InputManagerPlugin::<Action>::default())
         .add_plugin(RapierPhysicsPlugin::<NoUserData>::default())
         .add_plugin(WanderlustPlugin)
-        .add_plugin(PbrPlugin)
-        .add_plugin(HikariPlugin)
+        .add_plugin(PbrPlugin);
+    // Without path tracing, the cameras fall back to the forward renderer.
+    #[cfg(not(target_arch = "wasm32"))]
+    if args.hikari() {
+        app.add_plugin(HikariPlugin);
+    }
+    app.insert_resource(args)
+        .add_plugin(cli::CliPlugin)
+        .add_plugin(crash::CrashPlugin)
+        .add_plugin(input_block::InputBlockPlugin)
+        .add_plugin(accessibility::AccessibilityPlugin)
+        .add_plugin(camera::CameraPlugin)
+        .add_plugin(hud::HudPlugin)
+        .add_plugin(post::PostPlugin)
+        .add_plugin(locale::LocalePlugin)
+        .add_plugin(loading::LoadingPlugin)
+        .add_plugin(tags::TagsPlugin)
+        .add_plugin(tuning::TuningPlugin)
+        .add_plugin(materials::MaterialsPlugin)
+        .add_plugin(debug_draw::DebugDrawPlugin)
+        .add_plugin(lighting::LightingPlugin)
+        .add_plugin(sky::SkyPlugin)
+        .add_plugin(fog::FogPlugin)
+        .add_plugin(force_field::ForceFieldPlugin)
+        .add_plugin(prefab::PrefabPlugin)
+        .add_plugin(level::LevelPlugin)
+        .add_plugin(manifest::AssetManifestPlugin)
+        .add_plugin(lod::LodPlugin)
+        .add_plugin(spatial::SpatialIndexPlugin::<CatchObject>::default())
+        .add_plugin(interpolation::InterpolationPlugin)
+        .add_plugin(activity::PhysicsActivityPlugin)
+        .add_plugin(lifetime::LifetimePlugin)
+        .add_plugin(replay::ReplayPlugin)
+        .add_plugin(ghost::GhostPlugin)
+        .add_plugin(net::NetworkPlugin)
+        .add_plugin(match_flow::MatchFlowPlugin)
+        .add_plugin(rules::RulesPlugin)
+        .add_plugin(behavior::BehaviorPlugin)
+        .add_plugin(nav::NavPlugin)
+        .add_plugin(perception::PerceptionPlugin)
+        .add_plugin(bots::BotPlugin)
+        .add_plugin(waves::WavePlugin)
+        .add_plugin(spawner::SpawnerPlugin)
+        .add_plugin(script::ScriptPlugin)
+        .add_plugin(cutscene::CutscenePlugin)
+        .add_plugin(dialogue::DialoguePlugin)
+        .add_plugin(feedback::FeedbackPlugin)
+        .add_plugin(cues::CuePlugin)
+        .add_plugin(surface::SurfacePlugin)
+        .add_plugin(rumble::RumblePlugin)
+        .add_plugin(placement::PlacementPlugin)
+        .add_plugin(merge::MergePlugin)
+        .add_plugin(repel::RepelPlugin)
+        .add_plugin(ability::AbilityPlugin)
+        .add_plugin(catch_class::CatchClassPlugin)
+        .add_plugin(hold::HoldPlugin)
+        .add_plugin(inspect::InspectPlugin)
+        .add_plugin(interact::InteractPlugin)
+        .add_plugin(carry::CarryPlugin)
+        .add_plugin(socket::SocketPlugin)
+        .add_plugin(paint::PaintPlugin)
+        .add_plugin(death::DeathPlugin)
+        .add_plugin(ragdoll::RagdollPlugin)
+        .add_plugin(stretch::StretchPlugin)
+        .add_plugin(spectate::SpectatePlugin)
+        .add_plugin(teams::TeamPlugin)
+        .add_plugin(tutorial::TutorialPlugin)
+        .add_plugin(touch::TouchPlugin)
+        .add_plugin(practice::PracticePlugin)
+        .add_plugin(rings::RingPlugin)
+        .add_plugin(style::StylePlugin)
+        .add_plugin(screens::ScreenPlugin)
+        .add_plugin(mirror::MirrorPlugin)
+        .add_plugin(surveillance::SurveillancePlugin)
+        .add_plugin(game_event::GameEventPlugin)
+        .add_plugin(stamina::StaminaPlugin)
+        .add_plugin(profile::ProfilePlugin)
+        .add_plugin(gpu::GpuPlugin)
+        .add_plugin(display::DisplayPlugin)
+        .add_plugin(progression::ProgressionPlugin)
+        .add_plugin(stats::StatsPlugin)
+        .add_plugin(achievements::AchievementPlugin)
+        .add_plugin(leaderboard::LeaderboardPlugin)
+        .add_plugin(presence::PresencePlugin)
         .add_startup_system(setup_render.exclusive_system())
         .add_startup_system(lock_release_cursor)
         .add_startup_system(setup_scene)
@@ -59,8 +290,8 @@ fn main() {
         .add_system(player_move)
         .add_system(player_look)
         .add_system(player_catch)
-        .add_system(light_rotate_system)
-        .run();
+        .add_system(draw_catch_target.after(player_catch));
+    app
 }
 
 fn lock_release_cursor(mut windows: ResMut<Windows>) {
@@ -83,7 +314,7 @@ fn setup_render(
     mut commands: Commands,
     windows: Res<Windows>,
     mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut materials: ResMut<Assets<CompositeMaterial>>,
     mut images: ResMut<Assets<Image>>,
 ) {
     let size = Extent3d {
@@ -92,13 +323,13 @@ fn setup_render(
         ..default()
     };
 
-    // This is the texture that will be rendered to.
+    // These are the textures that will be rendered to, one for the scene and one for the HUD.
     let mut image = Image {
         texture_descriptor: TextureDescriptor {
             label: None,
             size,
             dimension: TextureDimension::D2,
-            format: TextureFormat::Bgra8UnormSrgb,
+            format: TextureFormat::bevy_default(),
             mip_level_count: 1,
             sample_count: 1,
             usage: TextureUsages::TEXTURE_BINDING
@@ -115,36 +346,59 @@ fn setup_render(
     };
 
     image.resize(size);
+    let ui_image_handle = images.set(UI_IMAGE_HANDLE, image.clone());
     let image_handle = images.set(RENDER_IMAGE_HANDLE, image);
 
-    let window = windows.primary();
-    let quad_handle = meshes.add(Mesh::from(shape::Quad::new(Vec2::new(
-        window.width(),
-        window.height(),
-    ))));
-
-    let material_handle = materials.add(ColorMaterial {
-        texture: Some(image_handle),
-        ..default()
+    // Without a window there is nothing to show the images on.
+    if windows.get_primary().is_none() {
+        return;
+    }
+    let quad_handle = meshes.add(Mesh::from(shape::Quad::new(post::post_size())));
+
+    let material_handle = materials.add(CompositeMaterial {
+        params: default(),
+        scene: image_handle,
+        ui: ui_image_handle,
+        depth: fog::FOG_DEPTH_HANDLE.typed(),
+        fog: default(),
     });
 
-    commands.spawn_bundle(MaterialMesh2dBundle {
-        material: material_handle,
-        mesh: quad_handle.into(),
-        transform: Transform {
-            translation: Vec3::new(0.0, 0.0, 1.5),
+    commands
+        .spawn_bundle(MaterialMesh2dBundle {
+            material: material_handle,
+            mesh: quad_handle.into(),
+            transform: Transform {
+                translation: Vec3::new(0.0, 0.0, 1.5),
+                ..default()
+            },
             ..default()
-        },
-        ..default()
-    });
+        })
+        .insert(layers::COMPOSITE);
 
-    commands.spawn_bundle(Camera2dBundle {
+    // Pixel HUD, composited over the scene by the quad.
+    commands.spawn_on_hud_layer(Camera2dBundle {
         camera: Camera {
-            priority: 0,
+            priority: -1,
+            target: RenderTarget::Image(UI_IMAGE_HANDLE.typed()),
             ..default()
         },
-        ..Camera2dBundle::default()
+        camera_2d: Camera2d {
+            clear_color: ClearColorConfig::Custom(Color::NONE),
+        },
+        ..default()
     });
+
+    // The composited image heads into the post chain, which takes it to the window.
+    commands
+        .spawn_bundle(Camera2dBundle {
+            camera: Camera {
+                priority: post::COMPOSITE_PRIORITY,
+                target: RenderTarget::Image(post::COMPOSITE_IMAGE_HANDLE.typed()),
+                ..default()
+            },
+            ..default()
+        })
+        .insert(layers::COMPOSITE);
 }
 
 #[derive(Debug, Actionlike, PartialEq, Eq, Clone, Copy, Hash)]
@@ -153,6 +407,14 @@ pub enum Action {
     Look,
     Jump,
     Catch,
+    Repel,
+    Sprint,
+    Inspect,
+    Stamp,
+    Abilities,
+    Zoom,
+    Interact,
+    Drop,
 }
 
 #[derive(Component, Reflect)]
@@ -162,6 +424,12 @@ pub struct Player {
     pub speed: f32,
     pub max_catch_speed: f32,
     pub throw_speed: f32,
+    /// Farthest an object can be to be caught, in meters.
+    pub catch_range: f32,
+    /// Half-angle of the cone in front of the catcher that objects are caught in, in degrees.
+    pub catch_angle: f32,
+    /// Heaviest objects the player can catch.
+    pub max_class: CatchClass,
 }
 
 impl Default for Player {
@@ -171,6 +439,9 @@ impl Default for Player {
             speed: 1.0,
             max_catch_speed: 100.0,
             throw_speed: 200.0,
+            catch_range: 25.0,
+            catch_angle: 45.0,
+            max_class: CatchClass::Medium,
         }
     }
 }
@@ -187,6 +458,12 @@ pub struct PlayerCatcher;
 #[reflect(Component)]
 pub struct CatchObject;
 
+/// The object the player is currently pulling, if any.
+#[derive(Default, Component)]
+pub struct PlayerCatch {
+    pub target: Option<Entity>,
+}
+
 #[derive(Default, Component, Reflect)]
 #[reflect(Component)]
 pub struct EmissiveObject {
@@ -194,200 +471,8 @@ pub struct EmissiveObject {
     emissive: f32,
 }
 
-fn setup_scene(
-    mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
-    _asset_server: Res<AssetServer>,
-) {
-    // Plane
-    commands
-        .spawn_bundle(SpatialBundle::default())
-        .insert(Collider::cuboid(0.5 * GROUND_SIZE, 1.0, 0.5 * GROUND_SIZE))
-        .with_children(|parent| {
-            parent
-                .spawn_bundle(PbrBundle {
-                    mesh: meshes.add(shape::Plane { size: GROUND_SIZE }.into()),
-                    material: materials.add(StandardMaterial {
-                        base_color: Color::rgb(0.8, 0.7, 0.6),
-                        perceptual_roughness: 0.9,
-                        ..default()
-                    }),
-                    transform: Transform::from_xyz(0.0, 1.0, 0.0),
-                    ..default()
-                })
-                .insert(RENDER_PASS_LAYER);
-        });
-
-    // Top
-    commands
-        .spawn_bundle(SpatialBundle {
-            transform: Transform::from_xyz(0.0, GROUND_SIZE * 0.5, 0.0),
-            ..default()
-        })
-        .insert(Collider::cuboid(0.5 * GROUND_SIZE, 1.0, 0.5 * GROUND_SIZE));
-
-    // Right
-    commands
-        .spawn_bundle(SpatialBundle {
-            transform: Transform {
-                translation: Vec3::new(0.5 * GROUND_SIZE, 0.0, 0.0),
-                rotation: Quat::from_rotation_z(PI / 2.0),
-                ..default()
-            },
-            ..default()
-        })
-        .insert(Collider::cuboid(0.5 * GROUND_SIZE, 1.0, 0.5 * GROUND_SIZE))
-        .with_children(|parent| {
-            parent
-                .spawn_bundle(PbrBundle {
-                    mesh: meshes.add(shape::Plane { size: GROUND_SIZE }.into()),
-                    material: materials.add(StandardMaterial {
-                        base_color: Color::rgb(0.8, 0.7, 0.6),
-                        perceptual_roughness: 0.9,
-                        ..default()
-                    }),
-                    transform: Transform::from_xyz(0.0, 1.0, 0.0),
-                    ..default()
-                })
-                .insert(RENDER_PASS_LAYER);
-        });
-
-    // Left
-    commands
-        .spawn_bundle(SpatialBundle {
-            transform: Transform {
-                translation: Vec3::new(-0.5 * GROUND_SIZE, 0.0, 0.0),
-                rotation: Quat::from_rotation_z(-PI / 2.0),
-                ..default()
-            },
-            ..default()
-        })
-        .insert(Collider::cuboid(0.5 * GROUND_SIZE, 1.0, 0.5 * GROUND_SIZE))
-        .with_children(|parent| {
-            parent
-                .spawn_bundle(PbrBundle {
-                    mesh: meshes.add(shape::Plane { size: GROUND_SIZE }.into()),
-                    material: materials.add(StandardMaterial {
-                        base_color: Color::rgb(0.8, 0.7, 0.6),
-                        perceptual_roughness: 0.9,
-                        ..default()
-                    }),
-                    transform: Transform::from_xyz(0.0, 1.0, 0.0),
-                    ..default()
-                })
-                .insert(RENDER_PASS_LAYER);
-        });
-
-    // Forward
-    commands
-        .spawn_bundle(SpatialBundle {
-            transform: Transform {
-                translation: Vec3::new(0.0, 0.0, -0.5 * GROUND_SIZE),
-                rotation: Quat::from_rotation_x(PI / 2.0),
-                ..default()
-            },
-            ..default()
-        })
-        .insert(Collider::cuboid(0.5 * GROUND_SIZE, 1.0, 0.5 * GROUND_SIZE))
-        .with_children(|parent| {
-            parent
-                .spawn_bundle(PbrBundle {
-                    mesh: meshes.add(shape::Plane { size: GROUND_SIZE }.into()),
-                    material: materials.add(StandardMaterial {
-                        base_color: Color::rgb(0.8, 0.7, 0.6),
-                        perceptual_roughness: 0.9,
-                        ..default()
-                    }),
-                    transform: Transform::from_xyz(0.0, 1.0, 0.0),
-                    ..default()
-                })
-                .insert(RENDER_PASS_LAYER);
-        });
-
-    // Backward
-    commands
-        .spawn_bundle(SpatialBundle {
-            transform: Transform {
-                translation: Vec3::new(0.0, 0.0, 0.5 * GROUND_SIZE),
-                rotation: Quat::from_rotation_x(-PI / 2.0),
-                ..default()
-            },
-            ..default()
-        })
-        .insert(Collider::cuboid(0.5 * GROUND_SIZE, 1.0, 0.5 * GROUND_SIZE))
-        .with_children(|parent| {
-            parent
-                .spawn_bundle(PbrBundle {
-                    mesh: meshes.add(shape::Plane { size: GROUND_SIZE }.into()),
-                    material: materials.add(StandardMaterial {
-                        base_color: Color::rgb(0.8, 0.7, 0.6),
-                        perceptual_roughness: 0.9,
-                        ..default()
-                    }),
-                    transform: Transform::from_xyz(0.0, 1.0, 0.0),
-                    ..default()
-                })
-                .insert(RENDER_PASS_LAYER);
-        });
-
-    // Center
-    commands
-        .spawn_bundle(SpatialBundle {
-            transform: Transform {
-                translation: Vec3::new(0.0, 0.25 * GROUND_SIZE, 0.0),
-                ..default()
-            },
-            ..default()
-        })
-        .insert(Collider::cuboid(
-            0.5 * CENTER_PILLAR_SIZE,
-            0.5 * GROUND_SIZE,
-            0.5 * CENTER_PILLAR_SIZE,
-        ))
-        .with_children(|parent| {
-            parent
-                .spawn_bundle(PbrBundle {
-                    mesh: meshes.add(
-                        shape::Box::new(CENTER_PILLAR_SIZE, 0.5 * GROUND_SIZE, CENTER_PILLAR_SIZE)
-                            .into(),
-                    ),
-                    material: materials.add(StandardMaterial {
-                        base_color: Color::rgb(0.8, 0.7, 0.6),
-                        perceptual_roughness: 0.9,
-                        ..default()
-                    }),
-                    ..default()
-                })
-                .insert(RENDER_PASS_LAYER);
-        });
-
-    // Cubes
-    let cube_mesh = meshes.add(shape::Cube::new(CUBE_SIZE).into());
-    for id in 0..10 {
-        commands
-            .spawn_bundle(PbrBundle {
-                mesh: cube_mesh.clone(),
-                material: materials.add(StandardMaterial {
-                    base_color: Color::rgb(0.6, 0.7, 0.8),
-                    emissive: Color::rgba(0.8, 0.7, 0.6, 0.1),
-                    perceptual_roughness: 0.9,
-                    ..default()
-                }),
-                transform: Transform::from_xyz(0.0, 2.0 + CUBE_SIZE * id as f32, 15.0),
-                ..default()
-            })
-            .insert_bundle((
-                RigidBody::Dynamic,
-                Collider::cuboid(CUBE_SIZE * 0.5, CUBE_SIZE * 0.5, CUBE_SIZE * 0.5),
-                ReadMassProperties::default(),
-                Velocity::default(),
-                ExternalImpulse::default(),
-                Ccd::enabled(),
-                CatchObject,
-            ))
-            .insert(RENDER_PASS_LAYER);
-    }
+fn setup_scene(mut commands: Commands, _asset_server: Res<AssetServer>, args: Res<cli::Args>) {
+    // The level itself is spawned from its file, see `level`.
 
     // Sphere
     // commands
@@ -418,7 +503,7 @@ fn setup_scene(
     //         Ccd::enabled(),
     //         CatchObject,
     //     ))
-    //     .insert(RENDER_PASS_LAYER);
+    //     .insert(layers::WORLD);
 
     // Only directional light is supported
     commands.spawn_bundle(DirectionalLightBundle {
@@ -434,6 +519,15 @@ fn setup_scene(
         ..default()
     });
 
+    #[cfg(not(target_arch = "wasm32"))]
+    let render_graph = if args.hikari() {
+        bevy_hikari::graph::NAME
+    } else {
+        bevy::core_pipeline::core_3d::graph::NAME
+    };
+    #[cfg(target_arch = "wasm32")]
+    let render_graph = bevy::core_pipeline::core_3d::graph::NAME;
+
     // Player
     commands
         .spawn_bundle(CharacterControllerBundle {
@@ -441,30 +535,28 @@ fn setup_scene(
             ..default()
         })
         .insert_bundle(InputManagerBundle::<Action> {
-            input_map: InputMap::default()
-                .insert(VirtualDPad::wasd(), Action::Move)
-                .insert(DualAxis::left_stick(), Action::Move)
-                .insert(DualAxis::mouse_motion(), Action::Look)
-                .insert(DualAxis::right_stick(), Action::Look)
-                .insert(KeyCode::Space, Action::Jump)
-                .insert(MouseButton::Right, Action::Catch)
-                .build(),
+            // Laid out by the control preset once the settings are applied.
+            input_map: InputMap::default(),
             ..default()
         })
         .insert(Player::default())
+        .insert(PlayerCatch::default())
+        // Reports objects hitting the player, for feedback.
+        .insert(ActiveEvents::COLLISION_EVENTS)
         .with_children(|parent| {
+            let player = parent.parent_entity();
             // Camera
             parent
                 .spawn_bundle(Camera3dBundle {
                     camera: Camera {
-                        priority: -1,
+                        priority: -2,
                         target: RenderTarget::Image(RENDER_IMAGE_HANDLE.typed()),
                         ..default()
                     },
-                    camera_render_graph: CameraRenderGraph::new(bevy_hikari::graph::NAME),
+                    camera_render_graph: CameraRenderGraph::new(render_graph),
                     ..default()
                 })
-                .insert(RENDER_PASS_LAYER)
+                .insert(layers::PLAYER_VIEW)
                 .insert(PlayerCamera)
                 .with_children(|parent| {
                     parent
@@ -472,16 +564,23 @@ fn setup_scene(
                             local: Transform::from_xyz(1.0, 1.0, -2.0),
                             ..default()
                         })
-                        .insert(PlayerCatcher);
+                        .insert(PlayerCatcher)
+                        .insert(CatchPoint(player));
                 });
         });
 }
 
 fn player_move(
-    mut player: Query<(&ActionState<Action>, &Player, &mut ControllerInput)>,
+    tuning: Res<Tuning>,
+    mut player: Query<(
+        &ActionState<Action>,
+        &Player,
+        Option<&Stamina>,
+        &mut ControllerInput,
+    )>,
     camera: Query<&GlobalTransform, (With<PlayerCamera>, Without<Player>)>,
 ) {
-    let (action_state, player, mut controller) = player.single_mut();
+    let (action_state, player, stamina, mut controller) = player.single_mut();
     let camera = camera.single();
 
     let mut direction = Vec3::ZERO;
@@ -491,78 +590,322 @@ fn player_move(
             .map_or(Vec2::ZERO, |axis| Vec2::new(axis.x(), axis.y()));
         direction = camera.right() * axis.x + camera.forward() * axis.y;
     }
-    controller.movement = player.speed * direction.normalize_or_zero();
+    let speed = match stamina {
+        Some(stamina) if stamina.sprinting => tuning.sprint_scale * player.speed,
+        _ => player.speed,
+    };
+    controller.movement = speed * direction.normalize_or_zero();
     controller.jumping = action_state.pressed(Action::Jump);
 }
 
 fn player_look(
+    time: Res<Time>,
+    settings: Res<CameraSettings>,
+    zoom: Res<CameraZoom>,
+    accessibility: Res<AccessibilitySettings>,
+    radial: Res<RadialMenu>,
+    mut pending: Local<Vec2>,
     mut camera: Query<&mut Transform, (With<PlayerCamera>, Without<Player>)>,
-    mut player: Query<(&ActionState<Action>, &Player, &mut Transform)>,
+    mut player: Query<
+        (&ActionState<Action>, &Player, &mut Transform),
+        (Without<Dead>, Without<Inspecting>),
+    >,
 ) {
     let mut camera = camera.single_mut();
-    let (action_state, player, mut body) = player.single_mut();
+    // Knocked out players look where the ragdoll takes them, and inspecting ones turn the object.
+    let (action_state, player, mut body) = match player.get_single_mut() {
+        Ok(player) => player,
+        Err(_) => return,
+    };
 
+    // An open radial menu takes the Look input for its cursor.
     let mut delta = Vec2::ZERO;
-    if action_state.pressed(Action::Look) {
+    if action_state.pressed(Action::Look) && !radial.is_open() {
         delta = action_state
             .axis_pair(Action::Look)
             .map_or(Vec2::ZERO, |axis| -Vec2::new(axis.x(), axis.y()));
     }
+    if accessibility.invert_y {
+        delta.y = -delta.y;
+    }
+
+    // Turn part of the way each frame; what is left carries over to the next.
+    *pending += player.sensitivity * settings.sensitivity_scale(zoom.0) * delta;
+    let turn = *pending * smoothing(settings.look_smoothing, time.delta_seconds());
+    *pending -= turn;
 
-    camera.rotate_x(player.sensitivity.y * delta.y.to_radians());
-    body.rotate_y(player.sensitivity.x * delta.x.to_radians());
+    camera.rotate_x(turn.y.to_radians());
+    body.rotate_y(turn.x.to_radians());
 }
 
 fn player_catch(
+    settings: Res<AccessibilitySettings>,
+    tuning: Res<Tuning>,
+    index: Res<SpatialIndex<CatchObject>>,
+    mut toggled: Local<bool>,
+    mut feedback: EventWriter<FeedbackEvent>,
     mut queries: ParamSet<(
-        Query<(&ActionState<Action>, &Player)>,
+        Query<
+            (
+                Entity,
+                &ActionState<Action>,
+                &Player,
+                Option<&Stamina>,
+                &mut PlayerCatch,
+            ),
+            Without<Dead>,
+        >,
         Query<&GlobalTransform, With<PlayerCatcher>>,
         Query<
             (
+                Entity,
                 &mut ExternalImpulse,
                 &Velocity,
                 &ReadMassProperties,
                 &GlobalTransform,
+                Option<&CatchClass>,
+                Option<&Held>,
             ),
             With<CatchObject>,
         >,
     )>,
 ) {
     let player_query = queries.p0();
-    let (action_state, player) = player_query.single();
+    let (holder, action_state, player, stamina, _) = match player_query.get_single() {
+        Ok(player) => player,
+        Err(_) => return,
+    };
+    let exhausted = stamina.map_or(false, Stamina::is_exhausted);
 
-    let catch_pressed = action_state.pressed(Action::Catch);
-    let catch_just_released = action_state.just_released(Action::Catch);
+    let (catch_pressed, catch_just_released) = if settings.catch_toggle {
+        let was_toggled = *toggled;
+        if action_state.just_pressed(Action::Catch) {
+            *toggled = !*toggled;
+        }
+        (*toggled, was_toggled && !*toggled)
+    } else {
+        (
+            action_state.pressed(Action::Catch),
+            action_state.just_released(Action::Catch),
+        )
+    };
+    // Running out of stamina drops what is held instead of throwing it.
+    let (catch_pressed, catch_just_released) = if exhausted {
+        *toggled = false;
+        (false, false)
+    } else {
+        (catch_pressed, catch_just_released)
+    };
 
     let max_catch_speed = player.max_catch_speed;
     let throw_speed = player.throw_speed;
+    let catch_range = player.catch_range;
+    let catch_angle = player.catch_angle;
+    let max_class = player.max_class;
 
     let catcher_query = queries.p1();
-    let catcher_transform = catcher_query.single();
+    let catcher_transform = *catcher_query.single();
+    let in_cone = catch_cone_candidates(&index, &catcher_transform, catch_range, catch_angle);
+
+    let target = catch_closest(
+        catch_pressed,
+        catch_just_released,
+        max_catch_speed,
+        throw_speed,
+        catch_range,
+        settings.aim_assist_angle(),
+        max_class,
+        holder,
+        &catcher_transform,
+        // What the player holds stays caught, wherever it swings.
+        queries.p2().iter_mut().filter(|(entity, .., held)| {
+            in_cone.contains(entity) || held.map_or(false, |held| held.holder == holder)
+        }),
+        &tuning,
+    );
+
+    let (_, _, _, _, mut catch) = queries.p0().single_mut();
+    if catch.target.is_some() && catch_just_released {
+        debug!(target: "catch", object = ?catch.target, "Thrown");
+        feedback.send(FeedbackEvent::impact(0.3));
+    } else if catch.target.is_none() && target.is_some() {
+        debug!(target: "catch", object = ?target, "Caught");
+        feedback.send(FeedbackEvent {
+            rumble: 0.2,
+            ..default()
+        });
+    }
+    catch.target = target;
+}
+
+fn draw_catch_target(
+    settings: Res<AccessibilitySettings>,
+    mut draw: ResMut<DebugDraw>,
+    players: Query<(&Player, &PlayerCatch)>,
+    catchers: Query<&GlobalTransform, With<PlayerCatcher>>,
+    objects: Query<&GlobalTransform, With<CatchObject>>,
+) {
+    for ((player, catch), catcher) in players.iter().zip(&catchers) {
+        let (position, direction) = (catcher.translation(), catcher.forward());
+        draw.sphere(DebugCategory::Catch, position, 0.2, Color::YELLOW);
+        draw.cone(
+            DebugCategory::Catch,
+            position,
+            direction,
+            player.catch_range,
+            player.catch_angle.to_radians(),
+            Color::YELLOW,
+        );
+        if let Some(angle) = settings.aim_assist_angle() {
+            draw.cone(
+                DebugCategory::Catch,
+                position,
+                direction,
+                player.catch_range,
+                angle,
+                Color::GREEN,
+            );
+        }
+        if let Some(target) = catch.target.and_then(|target| objects.get(target).ok()) {
+            draw.line(
+                DebugCategory::Catch,
+                position,
+                target.translation(),
+                Color::YELLOW,
+            );
+        }
+    }
+}
+
+/// Widest catch cone, in degrees; any wider and its base would be huge.
+const MAX_CATCH_ANGLE: f32 = 80.0;
+
+/// Catch objects touching the cone of `angle` degrees and `range` meters in front of the
+/// catcher. Big objects count as soon as part of them is inside.
+pub fn catch_cone_candidates(
+    index: &SpatialIndex<CatchObject>,
+    catcher_transform: &GlobalTransform,
+    range: f32,
+    angle: f32,
+) -> Vec<Entity> {
+    index
+        .in_cone(
+            catcher_transform.translation(),
+            catcher_transform.forward(),
+            range,
+            angle.clamp(0.0, MAX_CATCH_ANGLE).to_radians(),
+        )
+        .into_iter()
+        .map(|entry| entry.entity)
+        .collect()
+}
+
+/// Pulls the catch object closest to the catcher while `pressed`, and throws it when `released`.
+/// Shared by everything that catches, so bots play by the same rules as humans.
+/// Only objects within `range` count; the player also narrows `objects` down to a cone, see
+/// [`catch_cone_candidates`]. With an `assist` cone, objects within that angle of where
+/// the catcher faces go first.
+/// Objects above `max_class` are left alone, and the rest are pulled and thrown by their class.
+/// What `holder` already holds is kept, see [`hold`]; what others hold is off limits.
+/// Returns the object being pulled.
+#[allow(clippy::too_many_arguments)]
+pub fn catch_closest<'a>(
+    pressed: bool,
+    released: bool,
+    max_catch_speed: f32,
+    throw_speed: f32,
+    range: f32,
+    assist: Option<f32>,
+    max_class: CatchClass,
+    holder: Entity,
+    catcher_transform: &GlobalTransform,
+    objects: impl Iterator<
+        Item = (
+            Entity,
+            Mut<'a, ExternalImpulse>,
+            &'a Velocity,
+            &'a ReadMassProperties,
+            &'a GlobalTransform,
+            Option<&'a CatchClass>,
+            Option<&'a Held>,
+        ),
+    >,
+    tuning: &Tuning,
+) -> Option<Entity> {
     let catcher_position = catcher_transform.translation();
     let catcher_direction = catcher_transform.forward();
 
     // Find the closest catch object
-    if let Some((mut impulse, velocity, mass, transform)) =
-        queries.p2().iter_mut().min_by_key(|(_, _, _, transform)| {
-            transform.translation().distance_squared(catcher_position) as u32
+    let (entity, mut impulse, velocity, mass, transform, class, held) = objects
+        .filter(|(_, _, _, _, transform, class, held)| {
+            class.copied().unwrap_or_default() <= max_class
+                && held.map_or(true, |held| held.holder == holder)
+                && transform.translation().distance(catcher_position) <= range
         })
-    {
-        let delta_position = catcher_position - transform.translation();
-        if catch_pressed {
-            let speed = (10.0 * delta_position.length_squared()).min(max_catch_speed);
-            let delta_velocity = delta_position.normalize_or_zero() * speed - velocity.linvel;
-            impulse.impulse = delta_velocity * mass.0.mass;
-        } else if catch_just_released {
-            let speed = 1.0 / (delta_position.length_squared() + 1.0) * throw_speed;
-            let delta_velocity = catcher_direction * speed;
-            impulse.impulse = delta_velocity * mass.0.mass;
+        .min_by_key(|(_, _, _, _, transform, _, held)| {
+            let delta = transform.translation() - catcher_position;
+            let outside_cone = assist.map_or(false, |angle| {
+                delta.angle_between(catcher_direction) > angle
+            });
+            (held.is_none(), outside_cone, delta.length_squared() as u32)
+        })?;
+    let class = class.copied().unwrap_or_default();
+
+    let delta_position = catcher_position - transform.translation();
+    if pressed {
+        // A held object follows the catcher on its own.
+        if held.is_none() {
+            impulse.impulse = pull_impulse(
+                delta_position,
+                velocity,
+                mass,
+                class,
+                max_catch_speed,
+                tuning,
+            );
         }
+        Some(entity)
+    } else {
+        if released {
+            impulse.impulse = throw_impulse(
+                delta_position,
+                catcher_direction,
+                mass,
+                class,
+                throw_speed,
+                tuning,
+            );
+        }
+        None
     }
 }
 
-fn light_rotate_system(time: Res<Time>, mut query: Query<&mut Transform, With<DirectionalLight>>) {
-    for mut transform in &mut query {
-        transform.rotate_y(LIGHT_ROTATION_SPEED * time.delta_seconds());
-    }
+/// Impulse that pulls an object `delta_position` away towards the catch point.
+pub fn pull_impulse(
+    delta_position: Vec3,
+    velocity: &Velocity,
+    mass: &ReadMassProperties,
+    class: CatchClass,
+    max_catch_speed: f32,
+    tuning: &Tuning,
+) -> Vec3 {
+    let speed =
+        (tuning.catch_pull * delta_position.length_squared()).min(max_catch_speed * class.pull());
+    let delta_velocity = delta_position.normalize_or_zero() * speed - velocity.linvel;
+    delta_velocity * mass.0.mass * class.stability()
+}
+
+/// Impulse that throws an object `delta_position` away from the catch point along `direction`;
+/// the closer it is, the harder the throw.
+pub fn throw_impulse(
+    delta_position: Vec3,
+    direction: Vec3,
+    mass: &ReadMassProperties,
+    class: CatchClass,
+    throw_speed: f32,
+    tuning: &Tuning,
+) -> Vec3 {
+    let falloff = tuning.throw_falloff * delta_position.length_squared();
+    let speed = 1.0 / (falloff + 1.0) * throw_speed * class.throw();
+    direction * speed * mass.0.mass
 }
@@ -0,0 +1,179 @@
+//! Which assets each level needs, and when to load them.
+//!
+//! [`MANIFEST_PATH`] lists textures, meshes and sounds per level name, plus common ones every
+//! level uses. [`LoadPriority::Required`] assets start loading as soon as the level is picked, and
+//! hold the loading screen if there is one. The rest stream in during play, a few at a time and
+//! higher priority first. Switching levels drops the handles of the previous level once the new
+//! ones are taken, so assets only the old level used are freed while shared ones stay loaded. That
+//! keeps memory bounded on integrated GPUs, which share it with everything else.
+
+use crate::{
+    level::{CurrentLevel, LevelRegistry},
+    loading::{AppState, LoadingAssets},
+};
+use bevy::{asset::LoadState, prelude::*, utils::HashMap};
+use serde::Deserialize;
+use std::{collections::VecDeque, fs};
+
+pub const MANIFEST_PATH: &str = "assets/manifest.ron";
+/// Most background loads running at once, so they don't hitch the frame.
+const MAX_IN_FLIGHT: usize = 4;
+
+pub struct AssetManifestPlugin;
+
+impl Plugin for AssetManifestPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AssetManifest>()
+            .init_resource::<LevelAssets>()
+            .add_startup_system(load_common_assets)
+            .add_system(switch_level_assets)
+            .add_system(stream_level_assets.after(switch_level_assets));
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+pub enum LoadPriority {
+    /// Needed before the level is played.
+    Required,
+    High,
+    #[default]
+    Low,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ManifestEntry {
+    /// Relative to the asset folder.
+    pub path: String,
+    #[serde(default)]
+    pub priority: LoadPriority,
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct AssetManifest {
+    /// Loaded once and kept for the whole session.
+    #[serde(default)]
+    pub common: Vec<ManifestEntry>,
+    /// Keyed by the name of the level in the registry.
+    #[serde(default)]
+    pub levels: HashMap<String, Vec<ManifestEntry>>,
+}
+
+impl FromWorld for AssetManifest {
+    fn from_world(_world: &mut World) -> Self {
+        let manifest = fs::read_to_string(MANIFEST_PATH)
+            .map_err(|err| err.to_string())
+            .and_then(|text| ron::from_str(&text).map_err(|err| err.to_string()));
+        match manifest {
+            Ok(manifest) => manifest,
+            Err(err) => {
+                warn!(
+                    "Failed to load {}, streaming nothing: {}",
+                    MANIFEST_PATH, err
+                );
+                default()
+            }
+        }
+    }
+}
+
+/// Assets of the current level, loaded or on their way.
+#[derive(Default)]
+pub struct LevelAssets {
+    /// Name of the level these belong to.
+    pub level: Option<String>,
+    pub handles: Vec<HandleUntyped>,
+    /// Waiting to start loading, highest priority first.
+    pub queue: VecDeque<String>,
+    /// Assets of every level, never released.
+    common: Vec<HandleUntyped>,
+}
+
+fn load_common_assets(
+    asset_server: Res<AssetServer>,
+    manifest: Res<AssetManifest>,
+    mut assets: ResMut<LevelAssets>,
+    mut loading: ResMut<LoadingAssets>,
+) {
+    for entry in &manifest.common {
+        let handle = asset_server.load_untyped(entry.path.as_str());
+        if entry.priority == LoadPriority::Required {
+            loading.handles.push(handle.clone());
+        }
+        assets.common.push(handle);
+    }
+    info!("Loading {} common assets", assets.common.len());
+}
+
+/// Starts on the assets of a newly picked level and lets go of the previous one's.
+fn switch_level_assets(
+    asset_server: Res<AssetServer>,
+    manifest: Res<AssetManifest>,
+    registry: Res<LevelRegistry>,
+    current: Res<CurrentLevel>,
+    state: Res<State<AppState>>,
+    mut assets: ResMut<LevelAssets>,
+    mut loading: ResMut<LoadingAssets>,
+) {
+    if !current.is_changed() {
+        return;
+    }
+    let name = match registry.levels.get(current.index) {
+        Some(level) => level.name.clone(),
+        None => return,
+    };
+    if assets.level.as_ref() == Some(&name) {
+        return;
+    }
+
+    let mut entries = manifest.levels.get(&name).cloned().unwrap_or_default();
+    entries.sort_by_key(|entry| entry.priority);
+    let (required, background): (Vec<_>, Vec<_>) = entries
+        .into_iter()
+        .partition(|entry| entry.priority == LoadPriority::Required);
+
+    // Take the new handles before dropping the old ones, so shared assets aren't reloaded.
+    let handles: Vec<_> = required
+        .iter()
+        .map(|entry| asset_server.load_untyped(entry.path.as_str()))
+        .collect();
+    // Only the loading screen waits on these; later it would just keep them alive.
+    if *state.current() == AppState::Loading {
+        loading.handles.extend(handles.iter().cloned());
+    }
+
+    let previous = std::mem::replace(&mut assets.handles, handles);
+    assets.queue = background.into_iter().map(|entry| entry.path).collect();
+    info!(
+        "Level {}: {} assets required, {} streaming, {} released",
+        name,
+        assets.handles.len(),
+        assets.queue.len(),
+        previous.len()
+    );
+    assets.level = Some(name);
+}
+
+/// Starts queued loads while few enough are running.
+fn stream_level_assets(asset_server: Res<AssetServer>, mut assets: ResMut<LevelAssets>) {
+    if assets.queue.is_empty() {
+        return;
+    }
+    let in_flight = assets
+        .handles
+        .iter()
+        .filter(|handle| {
+            matches!(
+                asset_server.get_load_state(*handle),
+                LoadState::NotLoaded | LoadState::Loading
+            )
+        })
+        .count();
+    for _ in in_flight..MAX_IN_FLIGHT {
+        let path = match assets.queue.pop_front() {
+            Some(path) => path,
+            None => break,
+        };
+        let handle = asset_server.load_untyped(path.as_str());
+        assets.handles.push(handle);
+    }
+}
@@ -0,0 +1,538 @@
+use crate::{
+    hud::bitmap::{BitmapAlign, BitmapText, BitmapTextBundle},
+    input_block::InputBlock,
+    layers::SpawnOnLayerExt,
+    locale::Locale,
+    net::{ClientMessage, FromClient, FromServer, Network, PeerId, ServerMessage, HOST_PEER},
+    rules::{judge_round, MatchRules, Round, RoundWinner},
+    teams::Team,
+    CatchObject, Player, RENDER_SIZE,
+};
+use bevy::{prelude::*, utils::HashMap};
+use bevy_rapier3d::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const COUNTDOWN_TIME: f32 = 3.0;
+const POST_MATCH_TIME: f32 = 10.0;
+/// Distance of the match screen from the top of the HUD, clear of the team score, in pixels.
+const SCREEN_MARGIN: f32 = 20.0;
+
+const SPAWN_POINTS: [Vec3; 4] = [
+    Vec3::new(0.0, 2.0, 30.0),
+    Vec3::new(0.0, 2.0, -30.0),
+    Vec3::new(30.0, 2.0, 0.0),
+    Vec3::new(-30.0, 2.0, 0.0),
+];
+
+pub struct MatchFlowPlugin;
+
+impl Plugin for MatchFlowPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_state(MatchState::Lobby)
+            .init_resource::<Lobby>()
+            .insert_resource(MatchTimer(Timer::from_seconds(0.0, false)))
+            .add_startup_system(setup_spawn_points)
+            .add_system(record_spawn_transforms)
+            .add_system(match_timer)
+            .add_system(host_match_requests)
+            .add_system(host_lobby_sync.after(host_match_requests))
+            .add_system(
+                host_match_flow
+                    .after(host_lobby_sync)
+                    .after(match_timer)
+                    .after(judge_round),
+            )
+            .add_system(client_match_updates)
+            .add_system_set(
+                SystemSet::on_enter(MatchState::Lobby)
+                    .with_system(enter_lobby)
+                    .with_system(start_timer)
+                    .with_system(spawn_match_screen),
+            )
+            .add_system_set(
+                SystemSet::on_update(MatchState::Lobby)
+                    .with_system(lobby_ready_key)
+                    .with_system(update_match_screen),
+            )
+            .add_system_set(
+                SystemSet::on_enter(MatchState::Countdown)
+                    .with_system(start_timer)
+                    .with_system(move_to_spawn_point)
+                    .with_system(reset_level)
+                    .with_system(spawn_match_screen),
+            )
+            .add_system_set(
+                SystemSet::on_enter(MatchState::InMatch)
+                    .with_system(start_timer)
+                    .with_system(spawn_match_screen),
+            )
+            .add_system_set(
+                SystemSet::on_enter(MatchState::PostMatch)
+                    .with_system(start_timer)
+                    .with_system(spawn_match_screen),
+            )
+            .add_system_set(
+                SystemSet::on_update(MatchState::Countdown).with_system(update_match_screen),
+            )
+            .add_system_set(
+                SystemSet::on_update(MatchState::InMatch).with_system(update_match_screen),
+            )
+            .add_system_set(
+                SystemSet::on_update(MatchState::PostMatch).with_system(update_match_screen),
+            )
+            .add_system_set(SystemSet::on_exit(MatchState::Lobby).with_system(despawn_match_screen))
+            .add_system_set(
+                SystemSet::on_exit(MatchState::Countdown).with_system(despawn_match_screen),
+            )
+            .add_system_set(
+                SystemSet::on_exit(MatchState::InMatch).with_system(despawn_match_screen),
+            )
+            .add_system_set(
+                SystemSet::on_exit(MatchState::PostMatch).with_system(despawn_match_screen),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MatchState {
+    Lobby,
+    Countdown,
+    InMatch,
+    PostMatch,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum MatchRequest {
+    Ready(bool),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum MatchUpdate {
+    Lobby {
+        ready: Vec<(PeerId, bool)>,
+    },
+    State {
+        state: MatchState,
+        spawns: Vec<(PeerId, usize)>,
+        round: u32,
+    },
+    /// Index of the level in the registry, and the seed of procedural ones.
+    Level {
+        index: usize,
+        seed: Option<u64>,
+    },
+}
+
+/// Ready flags and spawn point assignment of everyone in the session.
+#[derive(Debug, Default)]
+pub struct Lobby {
+    pub ready: BTreeMap<PeerId, bool>,
+    pub spawns: HashMap<PeerId, usize>,
+    /// Whether everyone starts at the spawn point opposite their own this round.
+    pub swapped: bool,
+}
+
+impl Lobby {
+    pub fn spawn_index(&self, peer: PeerId) -> usize {
+        let index = self.spawns.get(&peer).copied().unwrap_or_default();
+        // Spawn points come in opposing pairs.
+        if self.swapped {
+            index ^ 1
+        } else {
+            index
+        }
+    }
+
+    pub fn all_ready(&self, participants: &[PeerId]) -> bool {
+        !participants.is_empty()
+            && participants
+                .iter()
+                .all(|peer| self.ready.get(peer).copied().unwrap_or(false))
+    }
+}
+
+pub struct MatchTimer(pub Timer);
+
+/// Where a catch object sits when a match begins.
+#[derive(Debug, Component)]
+pub struct SpawnTransform(pub Transform);
+
+#[derive(Debug, Component)]
+pub struct SpawnPoint(pub usize);
+
+#[derive(Component)]
+struct MatchScreen;
+
+#[derive(Component)]
+struct MatchScreenText;
+
+fn set_state(state: &mut State<MatchState>, next: MatchState) {
+    if state.current() != &next {
+        if let Err(err) = state.set(next) {
+            warn!("Failed to enter {:?}: {:?}", next, err);
+        }
+    }
+}
+
+fn start_timer(
+    state: Res<State<MatchState>>,
+    rules: Res<MatchRules>,
+    round: Res<Round>,
+    mut timer: ResMut<MatchTimer>,
+) {
+    let duration = match state.current() {
+        MatchState::Lobby => 0.0,
+        MatchState::Countdown if round.index == 0 => COUNTDOWN_TIME,
+        MatchState::Countdown => rules.intermission,
+        MatchState::InMatch => rules.round_time,
+        MatchState::PostMatch => POST_MATCH_TIME,
+    };
+    timer.0 = Timer::from_seconds(duration, false);
+}
+
+fn setup_spawn_points(mut commands: Commands) {
+    for (index, position) in SPAWN_POINTS.iter().enumerate() {
+        commands
+            .spawn_bundle(TransformBundle::from_transform(
+                Transform::from_translation(*position)
+                    .looking_at(Vec3::new(0.0, 2.0, 0.0), Vec3::Y),
+            ))
+            .insert(SpawnPoint(index));
+    }
+}
+
+fn record_spawn_transforms(
+    mut commands: Commands,
+    objects: Query<(Entity, &Transform), (With<CatchObject>, Without<SpawnTransform>)>,
+) {
+    for (entity, transform) in &objects {
+        commands.entity(entity).insert(SpawnTransform(*transform));
+    }
+}
+
+fn match_timer(time: Res<Time>, mut timer: ResMut<MatchTimer>) {
+    timer.0.tick(time.delta());
+}
+
+fn enter_lobby(network: Res<Network>, mut lobby: ResMut<Lobby>) {
+    if network.is_authoritative() {
+        lobby.ready.clear();
+        lobby.spawns.clear();
+    }
+}
+
+fn lobby_ready_key(
+    keys: Res<Input<KeyCode>>,
+    block: Res<InputBlock>,
+    network: Res<Network>,
+    mut lobby: ResMut<Lobby>,
+) {
+    if block.typing() || !keys.just_pressed(KeyCode::R) {
+        return;
+    }
+
+    let peer = network.local_peer().unwrap_or(HOST_PEER);
+    let ready = !lobby.ready.get(&peer).copied().unwrap_or(false);
+    lobby.ready.insert(peer, ready);
+    network.send_to_host(&ClientMessage::Match(MatchRequest::Ready(ready)));
+}
+
+fn host_match_requests(
+    network: Res<Network>,
+    state: Res<State<MatchState>>,
+    mut requests: EventReader<FromClient<MatchRequest>>,
+    mut lobby: ResMut<Lobby>,
+) {
+    for FromClient { peer, message } in requests.iter() {
+        match message {
+            // Late ready-ups don't matter once the match is on.
+            MatchRequest::Ready(ready) if *state.current() == MatchState::Lobby => {
+                if network.is_authoritative() {
+                    lobby.ready.insert(*peer, *ready);
+                }
+            }
+            MatchRequest::Ready(_) => {}
+        }
+    }
+}
+
+/// Drops players who left, and tells clients who is ready.
+fn host_lobby_sync(
+    time: Res<Time>,
+    network: Res<Network>,
+    mut last_sync: Local<f64>,
+    mut lobby: ResMut<Lobby>,
+) {
+    if !network.is_authoritative() {
+        return;
+    }
+
+    let participants = network.participants();
+    if lobby.ready.keys().any(|peer| !participants.contains(peer)) {
+        lobby.ready.retain(|peer, _| participants.contains(peer));
+    }
+
+    // Resend now and then so players who just joined catch up.
+    let now = time.seconds_since_startup();
+    if lobby.is_changed() || now - *last_sync > 1.0 {
+        *last_sync = now;
+        let ready = lobby
+            .ready
+            .iter()
+            .map(|(peer, ready)| (*peer, *ready))
+            .collect();
+        network.broadcast(&ServerMessage::Match(MatchUpdate::Lobby { ready }), None);
+    }
+}
+
+fn host_match_flow(
+    network: Res<Network>,
+    timer: Res<MatchTimer>,
+    rules: Res<MatchRules>,
+    mut round: ResMut<Round>,
+    mut lobby: ResMut<Lobby>,
+    mut state: ResMut<State<MatchState>>,
+) {
+    if !network.is_authoritative() {
+        return;
+    }
+
+    let next = match state.current() {
+        MatchState::Lobby => {
+            let participants = network.participants();
+            if !lobby.all_ready(&participants) {
+                return;
+            }
+            lobby.spawns = participants
+                .iter()
+                .enumerate()
+                .map(|(index, peer)| (*peer, index % SPAWN_POINTS.len()))
+                .collect();
+            round.index = 0;
+            MatchState::Countdown
+        }
+        MatchState::Countdown if timer.0.finished() => MatchState::InMatch,
+        // Rounds end when the rules decide them, which includes running out of time.
+        MatchState::InMatch if round.winner.is_some() => {
+            if round.is_last(&rules) {
+                MatchState::PostMatch
+            } else {
+                round.index += 1;
+                MatchState::Countdown
+            }
+        }
+        MatchState::PostMatch if timer.0.finished() => MatchState::Lobby,
+        _ => return,
+    };
+    lobby.swapped = round.swapped(&rules);
+
+    let spawns = lobby
+        .spawns
+        .iter()
+        .map(|(peer, index)| (*peer, *index))
+        .collect();
+    network.broadcast(
+        &ServerMessage::Match(MatchUpdate::State {
+            state: next,
+            spawns,
+            round: round.index,
+        }),
+        None,
+    );
+    set_state(&mut state, next);
+}
+
+fn client_match_updates(
+    mut updates: EventReader<FromServer<MatchUpdate>>,
+    mut lobby: ResMut<Lobby>,
+    rules: Res<MatchRules>,
+    mut round: ResMut<Round>,
+    mut state: ResMut<State<MatchState>>,
+) {
+    for FromServer(update) in updates.iter() {
+        match update {
+            MatchUpdate::Lobby { ready } => lobby.ready = ready.iter().copied().collect(),
+            MatchUpdate::State {
+                state: next,
+                spawns,
+                round: index,
+            } => {
+                lobby.spawns = spawns.iter().copied().collect();
+                round.index = *index;
+                lobby.swapped = round.swapped(&rules);
+                set_state(&mut state, *next);
+            }
+            MatchUpdate::Level { .. } => {}
+        }
+    }
+}
+
+/// Where the local player starts a match, facing along the ground.
+pub fn local_spawn_point(
+    network: &Network,
+    lobby: &Lobby,
+    spawn_points: &Query<(&SpawnPoint, &Transform), Without<Player>>,
+) -> Option<Transform> {
+    let peer = network.local_peer().unwrap_or(HOST_PEER);
+    let index = lobby.spawn_index(peer);
+    let spawn_point = spawn_points
+        .iter()
+        .find(|(point, _)| point.0 == index)
+        .map(|(_, transform)| *transform)?;
+    // The player body only yaws; pitch belongs to the camera.
+    let (yaw, _, _) = spawn_point.rotation.to_euler(EulerRot::YXZ);
+    Some(
+        Transform::from_translation(spawn_point.translation)
+            .with_rotation(Quat::from_rotation_y(yaw)),
+    )
+}
+
+fn move_to_spawn_point(
+    network: Res<Network>,
+    lobby: Res<Lobby>,
+    spawn_points: Query<(&SpawnPoint, &Transform), Without<Player>>,
+    mut player: Query<(&mut Transform, Option<&mut Velocity>), With<Player>>,
+) {
+    if let Some(spawn_point) = local_spawn_point(&network, &lobby, &spawn_points) {
+        let (mut transform, velocity) = player.single_mut();
+        *transform = spawn_point;
+        if let Some(mut velocity) = velocity {
+            *velocity = Velocity::default();
+        }
+    }
+}
+
+/// Puts the catch objects back where they started; clients get them from the host.
+fn reset_level(
+    network: Res<Network>,
+    mut objects: Query<(&SpawnTransform, &mut Transform, &mut Velocity), With<CatchObject>>,
+) {
+    if !network.is_authoritative() {
+        return;
+    }
+    for (spawn, mut transform, mut velocity) in &mut objects {
+        *transform = spawn.0;
+        *velocity = Velocity::default();
+    }
+}
+
+fn spawn_match_screen(mut commands: Commands) {
+    let position = Vec2::new(0.0, 0.5 * RENDER_SIZE[1] as f32 - SCREEN_MARGIN);
+    commands
+        .spawn_on_hud_layer(BitmapTextBundle::new(
+            BitmapText::new("", Color::WHITE, BitmapAlign::Center),
+            position,
+        ))
+        .insert_bundle((MatchScreen, MatchScreenText));
+}
+
+fn despawn_match_screen(mut commands: Commands, screens: Query<Entity, With<MatchScreen>>) {
+    for entity in &screens {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn round_name(locale: &Locale, round: &Round, rules: &MatchRules) -> String {
+    locale.get_args(
+        "round",
+        &[
+            ("round", (round.index + 1).into()),
+            ("total", rules.rounds.into()),
+        ],
+    )
+}
+
+/// Text saying who won, for a round or the whole match.
+fn describe_winner(locale: &Locale, winner: RoundWinner) -> String {
+    match winner {
+        RoundWinner::Team(Team::Red) => locale.get("winner-red"),
+        RoundWinner::Team(Team::Blue) => locale.get("winner-blue"),
+        RoundWinner::Players => locale.get("winner-players"),
+        RoundWinner::Nobody => locale.get("winner-nobody"),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_match_screen(
+    state: Res<State<MatchState>>,
+    network: Res<Network>,
+    lobby: Res<Lobby>,
+    timer: Res<MatchTimer>,
+    rules: Res<MatchRules>,
+    round: Res<Round>,
+    locale: Res<Locale>,
+    mut text: Query<&mut BitmapText, With<MatchScreenText>>,
+) {
+    let remaining = (timer.0.duration() - timer.0.elapsed()).as_secs_f32();
+    let value = match state.current() {
+        MatchState::Lobby => {
+            let local = network.local_peer().unwrap_or(HOST_PEER);
+            let participants = network.participants();
+            let mut value = locale.get("lobby") + "\n";
+            for peer in participants.iter().chain(
+                lobby
+                    .ready
+                    .keys()
+                    .filter(|peer| !participants.contains(peer)),
+            ) {
+                let ready = lobby.ready.get(peer).copied().unwrap_or(false);
+                let name = if *peer == local {
+                    locale.get("lobby-you")
+                } else {
+                    format!("P{}", peer)
+                };
+                let status = locale.get(if ready {
+                    "lobby-ready"
+                } else {
+                    "lobby-waiting"
+                });
+                value += &format!("{} {}\n", name, status);
+            }
+            value + &locale.get("lobby-hint")
+        }
+        MatchState::Countdown => {
+            let mut value = String::new();
+            if let Some(winner) = round.results.last().filter(|_| round.index > 0) {
+                value += &(describe_winner(&locale, *winner) + "\n");
+            }
+            if rules.rounds > 1 {
+                value += &(round_name(&locale, &round, &rules) + "\n");
+            }
+            value + &format!("{}", remaining.ceil() as u32)
+        }
+        MatchState::InMatch => {
+            let seconds = remaining.ceil() as u32;
+            let clock = format!("{}:{:02}", seconds / 60, seconds % 60);
+            if rules.rounds > 1 {
+                round_name(&locale, &round, &rules) + "\n" + &clock
+            } else {
+                clock
+            }
+        }
+        MatchState::PostMatch => {
+            // The match goes to whoever won the most rounds.
+            let winners = [
+                RoundWinner::Team(Team::Red),
+                RoundWinner::Team(Team::Blue),
+                RoundWinner::Players,
+            ];
+            let best = winners.iter().map(|winner| round.wins(*winner)).max();
+            let leaders: Vec<_> = winners
+                .iter()
+                .filter(|winner| Some(round.wins(**winner)) == best && best > Some(0))
+                .collect();
+            let winner = match leaders[..] {
+                [winner] => *winner,
+                _ => RoundWinner::Nobody,
+            };
+            locale.get("match-over") + "\n" + &describe_winner(&locale, winner)
+        }
+    };
+
+    for mut text in &mut text {
+        if text.value != value {
+            text.value = value.clone();
+        }
+    }
+}
@@ -0,0 +1,226 @@
+//! Named material presets, kept out of the code.
+//!
+//! [`MATERIALS_FILE`] maps names like `"wall"`, `"cube"` and `"goal"` to [`MaterialPreset`]s. Levels
+//! and prefabs ask the [`MaterialLibrary`] for a material by name and get one shared handle per
+//! name, so saving the file repaints everything that uses it. Names the file leaves out keep the
+//! built-in presets.
+
+use crate::loading::LoadingAssets;
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+    utils::{BoxedFuture, HashMap},
+};
+use serde::Deserialize;
+
+pub const MATERIALS_FILE: &str = "default.materials.ron";
+
+pub struct MaterialsPlugin;
+
+impl Plugin for MaterialsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<MaterialPresets>()
+            .init_asset_loader::<MaterialPresetsLoader>()
+            .init_resource::<MaterialLibrary>()
+            .add_startup_system(load_materials)
+            .add_system(reload_materials);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct MaterialPreset {
+    pub color: [f32; 3],
+    pub emissive: [f32; 4],
+    pub roughness: f32,
+    pub metallic: f32,
+    /// Ignores lighting, for signs and markers.
+    pub unlit: bool,
+}
+
+impl Default for MaterialPreset {
+    fn default() -> Self {
+        Self {
+            color: [1.0, 1.0, 1.0],
+            emissive: [0.0, 0.0, 0.0, 0.0],
+            roughness: 0.9,
+            metallic: 0.0,
+            unlit: false,
+        }
+    }
+}
+
+impl MaterialPreset {
+    pub fn material(&self) -> StandardMaterial {
+        let [red, green, blue] = self.color;
+        let [emissive_red, emissive_green, emissive_blue, emissive_alpha] = self.emissive;
+        StandardMaterial {
+            base_color: Color::rgb(red, green, blue),
+            emissive: Color::rgba(emissive_red, emissive_green, emissive_blue, emissive_alpha),
+            perceptual_roughness: self.roughness,
+            metallic: self.metallic,
+            unlit: self.unlit,
+            ..default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, TypeUuid)]
+#[uuid = "c2e47a90-6d1b-4f38-b5a2-9e0d3f7c1a68"]
+pub struct MaterialPresets {
+    pub presets: HashMap<String, MaterialPreset>,
+}
+
+#[derive(Default)]
+pub struct MaterialPresetsLoader;
+
+impl AssetLoader for MaterialPresetsLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let presets: MaterialPresets = ron::de::from_bytes(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(presets));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["materials.ron"]
+    }
+}
+
+pub struct MaterialLibrary {
+    presets: HashMap<String, MaterialPreset>,
+    /// One shared material per name, made the first time it is asked for.
+    handles: HashMap<String, Handle<StandardMaterial>>,
+    /// Keeps the file loaded, so it is watched.
+    file: Option<Handle<MaterialPresets>>,
+}
+
+impl Default for MaterialLibrary {
+    fn default() -> Self {
+        let presets = [
+            (
+                "wall",
+                MaterialPreset {
+                    color: [0.8, 0.7, 0.6],
+                    ..default()
+                },
+            ),
+            (
+                "cube",
+                MaterialPreset {
+                    color: [0.6, 0.7, 0.8],
+                    emissive: [0.8, 0.7, 0.6, 0.1],
+                    ..default()
+                },
+            ),
+            (
+                "goal",
+                MaterialPreset {
+                    color: [0.9, 0.6, 0.2],
+                    emissive: [0.9, 0.6, 0.2, 0.5],
+                    ..default()
+                },
+            ),
+        ];
+        Self {
+            presets: presets
+                .into_iter()
+                .map(|(name, preset)| (name.to_string(), preset))
+                .collect(),
+            handles: default(),
+            file: None,
+        }
+    }
+}
+
+impl MaterialLibrary {
+    /// The preset called `name`, or plain white if there is none.
+    pub fn preset(&self, name: &str) -> MaterialPreset {
+        match self.presets.get(name) {
+            Some(preset) => *preset,
+            None => {
+                warn!("No material called {}", name);
+                default()
+            }
+        }
+    }
+
+    /// The shared material called `name`.
+    pub fn get(
+        &mut self,
+        name: &str,
+        materials: &mut Assets<StandardMaterial>,
+    ) -> Handle<StandardMaterial> {
+        if let Some(handle) = self.handles.get(name) {
+            return handle.clone();
+        }
+        let handle = materials.add(self.preset(name).material());
+        self.handles.insert(name.into(), handle.clone());
+        handle
+    }
+
+    /// A copy of the material called `name`, for objects that change their material one by one.
+    /// Copies don't follow edits of the file.
+    pub fn unique(
+        &self,
+        name: &str,
+        materials: &mut Assets<StandardMaterial>,
+    ) -> Handle<StandardMaterial> {
+        materials.add(self.preset(name).material())
+    }
+}
+
+fn load_materials(
+    asset_server: Res<AssetServer>,
+    mut library: ResMut<MaterialLibrary>,
+    mut loading: ResMut<LoadingAssets>,
+) {
+    let handle = asset_server.load(MATERIALS_FILE);
+    loading.add(&handle);
+    library.file = Some(handle);
+}
+
+/// Takes the presets from the file, and repaints the shared materials of the ones that changed.
+fn reload_materials(
+    assets: Res<Assets<MaterialPresets>>,
+    mut library: ResMut<MaterialLibrary>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut events: EventReader<AssetEvent<MaterialPresets>>,
+) {
+    let library = &mut *library;
+    let file = match &library.file {
+        Some(file) => file,
+        None => return,
+    };
+    for event in events.iter() {
+        match event {
+            AssetEvent::Created { handle } | AssetEvent::Modified { handle } if handle == file => {
+                let loaded = match assets.get(file) {
+                    Some(loaded) => loaded,
+                    None => continue,
+                };
+                info!("Materials loaded from {}", MATERIALS_FILE);
+                for (name, preset) in &loaded.presets {
+                    if library.presets.get(name) == Some(preset) {
+                        continue;
+                    }
+                    library.presets.insert(name.clone(), *preset);
+                    if let Some(material) = library
+                        .handles
+                        .get(name)
+                        .and_then(|handle| materials.get_mut(handle))
+                    {
+                        *material = preset.material();
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,118 @@
+//! Cubes that hit each other hard enough become one bigger cube.
+//!
+//! Only cubes with [`Mergeable`] take part, which is every cube from [`spawn_catch_cube`]. A
+//! merged cube keeps the volume, mass and momentum of both, and glows brighter the more cubes went
+//! into it, until [`MergeRules::max_units`] stops it from growing further.
+//!
+//! [`spawn_catch_cube`]: crate::props::SpawnPropExt::spawn_catch_cube
+
+use crate::materials::MaterialLibrary;
+use bevy::{prelude::*, utils::HashSet};
+use bevy_rapier3d::prelude::*;
+
+/// Extra glow per cube merged in, relative to a plain cube.
+const GLOW_PER_UNIT: f32 = 0.5;
+
+pub struct MergePlugin;
+
+impl Plugin for MergePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MergeRules>().add_system(merge_cubes);
+    }
+}
+
+pub struct MergeRules {
+    /// Slowest closing speed, in meters per second, at which two cubes merge.
+    pub min_speed: f32,
+    /// Most plain cubes a merged cube can be made of.
+    pub max_units: u32,
+}
+
+impl Default for MergeRules {
+    fn default() -> Self {
+        Self {
+            min_speed: 6.0,
+            max_units: 8,
+        }
+    }
+}
+
+/// A cube made of `units` plain cubes.
+#[derive(Debug, Clone, Component)]
+pub struct Mergeable {
+    pub units: u32,
+    /// Name of the preset in the material library it is drawn with.
+    pub material: String,
+}
+
+impl Mergeable {
+    pub fn new(material: &str) -> Self {
+        Self {
+            units: 1,
+            material: material.into(),
+        }
+    }
+
+    /// Edge length relative to a plain cube, so that volume adds up.
+    pub fn scale(&self) -> f32 {
+        (self.units as f32).cbrt()
+    }
+}
+
+fn merge_cubes(
+    mut commands: Commands,
+    rules: Res<MergeRules>,
+    library: Res<MaterialLibrary>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut collisions: EventReader<CollisionEvent>,
+    mut cubes: Query<(
+        &mut Mergeable,
+        &mut Transform,
+        &mut Velocity,
+        &mut Handle<StandardMaterial>,
+    )>,
+) {
+    let mut merged = HashSet::default();
+    for event in collisions.iter() {
+        let (a, b) = match event {
+            CollisionEvent::Started(a, b, _) => (*a, *b),
+            _ => continue,
+        };
+        if merged.contains(&a) || merged.contains(&b) {
+            continue;
+        }
+        let (other_units, other_translation, other_velocity) = match cubes.get(b) {
+            Ok((cube, transform, velocity, _)) => (cube.units, transform.translation, *velocity),
+            Err(_) => continue,
+        };
+        let (mut cube, mut transform, mut velocity, mut material) = match cubes.get_mut(a) {
+            Ok(cube) => cube,
+            Err(_) => continue,
+        };
+
+        let units = cube.units + other_units;
+        let closing = (velocity.linvel - other_velocity.linvel).length();
+        if units > rules.max_units || closing < rules.min_speed {
+            continue;
+        }
+
+        // Same density throughout, so units weigh the mass center and momentum.
+        let (weight, other_weight) = (cube.units as f32, other_units as f32);
+        let total = weight + other_weight;
+        transform.translation =
+            (weight * transform.translation + other_weight * other_translation) / total;
+        velocity.linvel = (weight * velocity.linvel + other_weight * other_velocity.linvel) / total;
+        velocity.angvel = (weight * velocity.angvel + other_weight * other_velocity.angvel) / total;
+
+        cube.units = units;
+        transform.scale = Vec3::splat(cube.scale());
+        // Its own material, so the glow stays off the cubes sharing the preset.
+        let mut glowing = library.preset(&cube.material).material();
+        glowing.emissive = glowing.emissive * (1.0 + GLOW_PER_UNIT * (units - 1) as f32);
+        *material = materials.add(glowing);
+
+        commands.entity(b).despawn_recursive();
+        merged.insert(a);
+        merged.insert(b);
+    }
+}
@@ -0,0 +1,253 @@
+//! Mirrors that don't rely on the path tracer.
+//!
+//! Every [`MirrorSurface`] gets a forward camera of its own, placed at the reflection of the
+//! player's eye behind the mirror and looking through it. Its projection is cut to the exact
+//! outline of the mirror, so the image it renders lines up with the quad it is shown on, and
+//! nothing behind the mirror gets in the way. Mirrors sit on [`layers::VIEW_MODEL`], which
+//! mirror cameras leave out, so they never draw into the texture they show.
+
+use crate::{layers, PlayerCamera};
+use bevy::{
+    core_pipeline::core_3d,
+    prelude::*,
+    render::{
+        camera::{
+            CameraProjection, CameraProjectionPlugin, CameraRenderGraph, CameraUpdateSystem,
+            DepthCalculation, RenderTarget,
+        },
+        primitives::Frustum,
+        render_resource::*,
+        texture::{BevyDefault, ImageSampler},
+        view::{update_frusta, VisibleEntities},
+    },
+    transform::TransformSystem,
+};
+
+/// Texture pixels per meter of mirror.
+const MIRROR_DENSITY: f32 = 32.0;
+const MIRROR_FAR: f32 = 1000.0;
+
+pub struct MirrorPlugin;
+
+impl Plugin for MirrorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(CameraProjectionPlugin::<MirrorProjection>::default())
+            .add_system(setup_mirrors)
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                reflect_cameras
+                    .after(TransformSystem::TransformPropagate)
+                    .before(CameraUpdateSystem),
+            )
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                update_frusta::<MirrorProjection>.after(reflect_cameras),
+            );
+    }
+}
+
+/// A mirror of `size` meters, facing along its local z axis.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct MirrorSurface {
+    pub size: Vec2,
+}
+
+/// The camera drawing what `0` reflects.
+#[derive(Component)]
+struct MirrorCamera(Entity);
+
+/// A perspective projection whose frustum goes through the mirror outline, measured on the near
+/// plane, which lies in the mirror itself.
+#[derive(Debug, Clone, Component, Reflect)]
+#[reflect(Component)]
+pub struct MirrorProjection {
+    left: f32,
+    right: f32,
+    bottom: f32,
+    top: f32,
+    near: f32,
+}
+
+impl Default for MirrorProjection {
+    fn default() -> Self {
+        Self {
+            left: -1.0,
+            right: 1.0,
+            bottom: -1.0,
+            top: 1.0,
+            near: 1.0,
+        }
+    }
+}
+
+impl CameraProjection for MirrorProjection {
+    /// Off-axis, with reversed infinite depth like the usual perspective projection.
+    fn get_projection_matrix(&self) -> Mat4 {
+        let width = self.right - self.left;
+        let height = self.top - self.bottom;
+        Mat4::from_cols(
+            Vec4::new(2.0 * self.near / width, 0.0, 0.0, 0.0),
+            Vec4::new(0.0, 2.0 * self.near / height, 0.0, 0.0),
+            Vec4::new(
+                (self.right + self.left) / width,
+                (self.top + self.bottom) / height,
+                0.0,
+                -1.0,
+            ),
+            Vec4::new(0.0, 0.0, self.near, 0.0),
+        )
+    }
+
+    /// The outline of the mirror decides the frustum, not the size of the texture.
+    fn update(&mut self, _width: f32, _height: f32) {}
+
+    fn depth_calculation(&self) -> DepthCalculation {
+        DepthCalculation::Distance
+    }
+
+    fn far(&self) -> f32 {
+        MIRROR_FAR
+    }
+}
+
+fn setup_mirrors(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut images: ResMut<Assets<Image>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mirrors: Query<(Entity, &MirrorSurface), Added<MirrorSurface>>,
+) {
+    for (entity, mirror) in &mirrors {
+        let size = Extent3d {
+            width: (mirror.size.x * MIRROR_DENSITY).round().max(1.0) as u32,
+            height: (mirror.size.y * MIRROR_DENSITY).round().max(1.0) as u32,
+            ..default()
+        };
+        let mut image = Image {
+            texture_descriptor: TextureDescriptor {
+                label: None,
+                size,
+                dimension: TextureDimension::D2,
+                format: TextureFormat::bevy_default(),
+                mip_level_count: 1,
+                sample_count: 1,
+                usage: TextureUsages::TEXTURE_BINDING
+                    | TextureUsages::COPY_DST
+                    | TextureUsages::RENDER_ATTACHMENT,
+            },
+            sampler_descriptor: ImageSampler::Descriptor(SamplerDescriptor {
+                mag_filter: FilterMode::Nearest,
+                min_filter: FilterMode::Nearest,
+                mipmap_filter: FilterMode::Nearest,
+                ..default()
+            }),
+            ..default()
+        };
+        image.resize(size);
+        let image = images.add(image);
+
+        // The path tracer takes the picture as light coming off the mirror.
+        let material = materials.add(StandardMaterial {
+            base_color_texture: Some(image.clone()),
+            emissive: Color::WHITE,
+            emissive_texture: Some(image.clone()),
+            unlit: true,
+            ..default()
+        });
+        commands
+            .entity(entity)
+            .insert(
+                meshes.add(
+                    shape::Quad {
+                        size: mirror.size,
+                        flip: true,
+                    }
+                    .into(),
+                ),
+            )
+            .insert(material)
+            .insert(layers::VIEW_MODEL);
+
+        commands
+            .spawn_bundle((
+                Camera {
+                    priority: -6,
+                    target: RenderTarget::Image(image),
+                    ..default()
+                },
+                CameraRenderGraph::new(core_3d::graph::NAME),
+                Camera3d::default(),
+                MirrorProjection::default(),
+                VisibleEntities::default(),
+                Frustum::default(),
+            ))
+            .insert_bundle(TransformBundle::default())
+            .insert(layers::WORLD)
+            .insert(MirrorCamera(entity));
+    }
+}
+
+/// Puts each mirror camera at the reflection of the player's eye, and fits its frustum to the
+/// mirror; mirrors seen from behind stop rendering.
+#[allow(clippy::type_complexity)]
+fn reflect_cameras(
+    mut commands: Commands,
+    viewers: Query<&GlobalTransform, With<PlayerCamera>>,
+    mirrors: Query<(&GlobalTransform, &MirrorSurface)>,
+    mut cameras: Query<
+        (
+            Entity,
+            &MirrorCamera,
+            &mut Camera,
+            &mut Transform,
+            &mut GlobalTransform,
+            &mut MirrorProjection,
+        ),
+        Without<PlayerCamera>,
+    >,
+) {
+    let eye = match viewers.get_single() {
+        Ok(viewer) => viewer.translation(),
+        Err(_) => return,
+    };
+    for (entity, mirror_camera, mut camera, mut transform, mut global, mut projection) in
+        &mut cameras
+    {
+        let (mirror, surface) = match mirrors.get(mirror_camera.0) {
+            Ok(mirror) => mirror,
+            Err(_) => {
+                commands.entity(entity).despawn_recursive();
+                continue;
+            }
+        };
+        let center = mirror.translation();
+        let normal = mirror.back();
+        let up = mirror.up();
+
+        let distance = (eye - center).dot(normal);
+        let active = distance > 0.01;
+        if camera.is_active != active {
+            camera.is_active = active;
+        }
+        if !active {
+            continue;
+        }
+
+        let reflected = eye - 2.0 * distance * normal;
+        *transform = Transform::from_translation(reflected).looking_at(reflected + normal, up);
+        *global = GlobalTransform::from(*transform);
+
+        // The mirror outline, seen from the reflected eye, on a near plane through the mirror.
+        let offset = center - reflected;
+        let x = offset.dot(transform.right());
+        let y = offset.dot(up);
+        let half = 0.5 * surface.size;
+        *projection = MirrorProjection {
+            left: x - half.x,
+            right: x + half.x,
+            bottom: y - half.y,
+            top: y + half.y,
+            near: distance,
+        };
+    }
+}
@@ -0,0 +1,381 @@
+//! Navigation for AI agents.
+//!
+//! The level is baked into a walkable grid by probing the static colliders: a cell is walkable if
+//! there is ground under it and an agent-sized cylinder fits on top. Paths are found with A* over
+//! the grid and shortened by skipping waypoints in line of sight.
+
+use crate::{
+    debug_draw::{DebugCategory, DebugDraw},
+    GROUND_SIZE,
+};
+use bevy::prelude::*;
+use bevy_mod_wanderlust::ControllerInput;
+use bevy_rapier3d::prelude::*;
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap},
+};
+
+pub const CELL_SIZE: f32 = 1.0;
+const AGENT_RADIUS: f32 = 0.5;
+const AGENT_HEIGHT: f32 = 1.6;
+/// Leaves room for small bumps between the ground and the clearance probe.
+const STEP_HEIGHT: f32 = 0.2;
+const PROBE_HEIGHT: f32 = 0.5 * GROUND_SIZE - 2.0;
+const WAYPOINT_RADIUS: f32 = 0.75;
+const REPATH_INTERVAL: f32 = 0.5;
+
+pub struct NavPlugin;
+
+impl Plugin for NavPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<NavMesh>()
+            .add_system(bake_nav_mesh)
+            .add_system(nav_agent_follow.after(bake_nav_mesh))
+            .add_system(draw_nav_paths.after(nav_agent_follow));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Cell(pub i32, pub i32);
+
+pub struct NavMesh {
+    /// Set to rebake after the level changes.
+    pub dirty: bool,
+    /// Frames to wait before baking, so new colliders reach the physics world.
+    delay: u32,
+    pub origin: Vec2,
+    pub size: UVec2,
+    /// Ground height of each cell, `None` if unwalkable.
+    cells: Vec<Option<f32>>,
+}
+
+impl Default for NavMesh {
+    fn default() -> Self {
+        let size = (GROUND_SIZE / CELL_SIZE) as u32;
+        Self {
+            dirty: true,
+            delay: 0,
+            origin: Vec2::splat(-0.5 * GROUND_SIZE),
+            size: UVec2::splat(size),
+            cells: vec![],
+        }
+    }
+}
+
+impl NavMesh {
+    /// Bakes again once the colliders spawned this frame are in the physics world.
+    pub fn rebake(&mut self) {
+        self.dirty = true;
+        self.delay = 2;
+    }
+
+    pub fn is_baked(&self) -> bool {
+        !self.cells.is_empty()
+    }
+
+    pub fn cell(&self, position: Vec3) -> Cell {
+        let local = (Vec2::new(position.x, position.z) - self.origin) / CELL_SIZE;
+        Cell(local.x.floor() as i32, local.y.floor() as i32)
+    }
+
+    fn index(&self, cell: Cell) -> Option<usize> {
+        let Cell(x, z) = cell;
+        (x >= 0 && z >= 0 && (x as u32) < self.size.x && (z as u32) < self.size.y)
+            .then(|| z as usize * self.size.x as usize + x as usize)
+    }
+
+    /// Ground height of a walkable cell.
+    pub fn height(&self, cell: Cell) -> Option<f32> {
+        self.index(cell)
+            .and_then(|index| self.cells.get(index).copied().flatten())
+    }
+
+    pub fn is_walkable(&self, cell: Cell) -> bool {
+        self.height(cell).is_some()
+    }
+
+    pub fn center(&self, cell: Cell) -> Vec3 {
+        let Cell(x, z) = cell;
+        let xz = self.origin + (Vec2::new(x as f32, z as f32) + 0.5) * CELL_SIZE;
+        Vec3::new(xz.x, self.height(cell).unwrap_or_default(), xz.y)
+    }
+
+    /// The walkable cell closest to `cell`, searching outwards a few rings.
+    fn nearest_walkable(&self, cell: Cell) -> Option<Cell> {
+        if self.is_walkable(cell) {
+            return Some(cell);
+        }
+        (1..8).find_map(|ring| {
+            let Cell(x, z) = cell;
+            (-ring..=ring)
+                .flat_map(|dx| (-ring..=ring).map(move |dz| Cell(x + dx, z + dz)))
+                .filter(|other| self.is_walkable(*other))
+                .min_by_key(|Cell(ox, oz)| (ox - x).pow(2) + (oz - z).pow(2))
+        })
+    }
+
+    fn neighbors(&self, cell: Cell) -> impl Iterator<Item = (Cell, f32)> + '_ {
+        const OFFSETS: [(i32, i32); 8] = [
+            (1, 0),
+            (-1, 0),
+            (0, 1),
+            (0, -1),
+            (1, 1),
+            (1, -1),
+            (-1, 1),
+            (-1, -1),
+        ];
+        let Cell(x, z) = cell;
+        OFFSETS.iter().filter_map(move |(dx, dz)| {
+            let next = Cell(x + dx, z + dz);
+            let diagonal = *dx != 0 && *dz != 0;
+            // Don't cut corners: both sides of a diagonal step must be free.
+            let open = self.is_walkable(next)
+                && (!diagonal
+                    || (self.is_walkable(Cell(x + dx, z)) && self.is_walkable(Cell(x, z + dz))));
+            open.then(|| {
+                (
+                    next,
+                    if diagonal {
+                        std::f32::consts::SQRT_2
+                    } else {
+                        1.0
+                    },
+                )
+            })
+        })
+    }
+
+    /// Whether a straight walk between two cells stays on walkable ground.
+    fn line_of_sight(&self, from: Cell, to: Cell) -> bool {
+        let (a, b) = (self.center(from), self.center(to));
+        let steps = (a.distance(b) / (0.25 * CELL_SIZE)).ceil().max(1.0) as usize;
+        (0..=steps).all(|step| {
+            let point = a.lerp(b, step as f32 / steps as f32);
+            // Check the agent's width on both sides of the line.
+            let side = (b - a).cross(Vec3::Y).normalize_or_zero() * AGENT_RADIUS;
+            [point, point + side, point - side]
+                .iter()
+                .all(|point| self.is_walkable(self.cell(*point)))
+        })
+    }
+
+    /// Finds a walkable path between two points; the result starts after `from` and ends at `to`.
+    pub fn find_path(&self, from: Vec3, to: Vec3) -> Option<Vec<Vec3>> {
+        let start = self.nearest_walkable(self.cell(from))?;
+        let goal = self.nearest_walkable(self.cell(to))?;
+
+        #[derive(PartialEq)]
+        struct Open(f32, Cell);
+        impl Eq for Open {}
+        impl Ord for Open {
+            fn cmp(&self, other: &Self) -> Ordering {
+                other
+                    .0
+                    .total_cmp(&self.0)
+                    .then_with(|| self.1.cmp(&other.1))
+            }
+        }
+        impl PartialOrd for Open {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        let heuristic = |Cell(x, z): Cell| {
+            let Cell(gx, gz) = goal;
+            (((gx - x).pow(2) + (gz - z).pow(2)) as f32).sqrt()
+        };
+
+        let mut open = BinaryHeap::new();
+        let mut came_from = HashMap::new();
+        let mut cost = HashMap::new();
+        open.push(Open(heuristic(start), start));
+        cost.insert(start, 0.0);
+
+        while let Some(Open(_, current)) = open.pop() {
+            if current == goal {
+                break;
+            }
+            let current_cost = cost[&current];
+            for (next, step) in self.neighbors(current) {
+                let next_cost = current_cost + step;
+                if cost.get(&next).map_or(true, |&known| next_cost < known) {
+                    cost.insert(next, next_cost);
+                    came_from.insert(next, current);
+                    open.push(Open(next_cost + heuristic(next), next));
+                }
+            }
+        }
+
+        if start != goal && !came_from.contains_key(&goal) {
+            return None;
+        }
+
+        let mut cells = vec![goal];
+        while let Some(previous) = came_from.get(cells.last()?) {
+            cells.push(*previous);
+        }
+        cells.reverse();
+
+        // String pulling: keep only the cells where the straight line breaks.
+        let mut waypoints = vec![];
+        let mut anchor = start;
+        for window in cells.windows(2) {
+            if !self.line_of_sight(anchor, window[1]) {
+                waypoints.push(self.center(window[0]));
+                anchor = window[0];
+            }
+        }
+        waypoints.push(Vec3::new(to.x, self.center(goal).y, to.z));
+        Some(waypoints)
+    }
+}
+
+/// Walks its entity towards `destination` along navmesh paths by driving its [`ControllerInput`].
+#[derive(Debug, Component)]
+pub struct NavAgent {
+    pub destination: Option<Vec3>,
+    pub speed: f32,
+    pub path: Vec<Vec3>,
+    path_destination: Option<Vec3>,
+    repath_timer: Timer,
+}
+
+impl NavAgent {
+    pub fn new(speed: f32) -> Self {
+        Self {
+            destination: None,
+            speed,
+            path: vec![],
+            path_destination: None,
+            repath_timer: Timer::from_seconds(REPATH_INTERVAL, true),
+        }
+    }
+
+    /// Sets the destination, or stops with `None`.
+    pub fn go_to(&mut self, destination: Option<Vec3>) {
+        self.destination = destination;
+    }
+}
+
+fn bake_nav_mesh(mut nav_mesh: ResMut<NavMesh>, rapier_context: Res<RapierContext>) {
+    // Wait until the level colliders made it into the physics world.
+    if !nav_mesh.dirty || rapier_context.colliders.is_empty() {
+        return;
+    }
+    if nav_mesh.delay > 0 {
+        nav_mesh.delay -= 1;
+        return;
+    }
+
+    let filter = QueryFilter::default()
+        .exclude_dynamic()
+        .exclude_kinematic()
+        .exclude_sensors();
+    let probe = Collider::cylinder(0.5 * AGENT_HEIGHT, AGENT_RADIUS);
+
+    let size = nav_mesh.size;
+    let mut cells = Vec::with_capacity((size.x * size.y) as usize);
+    for z in 0..size.y as i32 {
+        for x in 0..size.x as i32 {
+            let center = nav_mesh.center(Cell(x, z));
+            let top = Vec3::new(center.x, PROBE_HEIGHT, center.z);
+
+            let height = rapier_context
+                .cast_ray(top, -Vec3::Y, PROBE_HEIGHT * 2.0, true, filter)
+                .map(|(_, toi)| top.y - toi)
+                .filter(|height| {
+                    let probe_center = Vec3::new(
+                        center.x,
+                        height + STEP_HEIGHT + 0.5 * AGENT_HEIGHT,
+                        center.z,
+                    );
+                    rapier_context
+                        .intersection_with_shape(probe_center, Quat::IDENTITY, &probe, filter)
+                        .is_none()
+                });
+            cells.push(height);
+        }
+    }
+
+    let walkable = cells.iter().filter(|cell| cell.is_some()).count();
+    info!(
+        "Baked navmesh: {} of {} cells walkable",
+        walkable,
+        cells.len()
+    );
+
+    nav_mesh.cells = cells;
+    nav_mesh.dirty = false;
+}
+
+fn draw_nav_paths(mut draw: ResMut<DebugDraw>, agents: Query<(&NavAgent, &GlobalTransform)>) {
+    if !draw.shows(DebugCategory::Ai) {
+        return;
+    }
+    for (agent, transform) in &agents {
+        let mut points = vec![transform.translation()];
+        points.extend_from_slice(&agent.path);
+        draw.path(DebugCategory::Ai, &points, Color::CYAN);
+        if let Some(destination) = agent.destination {
+            draw.sphere(DebugCategory::Ai, destination, 0.3, Color::CYAN);
+        }
+    }
+}
+
+pub fn nav_agent_follow(
+    time: Res<Time>,
+    nav_mesh: Res<NavMesh>,
+    mut agents: Query<(&mut NavAgent, &mut ControllerInput, &GlobalTransform)>,
+) {
+    for (mut agent, mut controller, transform) in &mut agents {
+        let destination = match agent.destination {
+            Some(destination) => destination,
+            None => {
+                agent.path.clear();
+                agent.path_destination = None;
+                continue;
+            }
+        };
+        let position = transform.translation();
+
+        let moved = agent
+            .path_destination
+            .map_or(true, |previous| previous.distance(destination) > CELL_SIZE);
+        let repath = agent.repath_timer.tick(time.delta()).just_finished();
+        if moved || repath {
+            agent.path = match nav_mesh.is_baked() {
+                true => nav_mesh
+                    .find_path(position, destination)
+                    .unwrap_or_default(),
+                // Walk straight until there is a navmesh to plan with.
+                false => vec![destination],
+            };
+            agent.path_destination = Some(destination);
+        }
+
+        while let Some(waypoint) = agent.path.first() {
+            let offset = *waypoint - position;
+            if Vec2::new(offset.x, offset.z).length() > WAYPOINT_RADIUS || agent.path.len() == 1 {
+                break;
+            }
+            agent.path.remove(0);
+        }
+
+        let movement = match agent.path.first() {
+            Some(waypoint) => {
+                let offset = *waypoint - position;
+                let offset = Vec3::new(offset.x, 0.0, offset.z);
+                if offset.length() < WAYPOINT_RADIUS {
+                    Vec3::ZERO
+                } else {
+                    offset.normalize()
+                }
+            }
+            None => Vec3::ZERO,
+        };
+        controller.movement = agent.speed * movement;
+    }
+}
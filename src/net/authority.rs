@@ -0,0 +1,184 @@
+//! Simulation authority of catch objects.
+//!
+//! The host owns every object by default. A client that starts catching an object asks the host
+//! for it and, once granted, simulates it locally and streams its state back, so held and thrown
+//! objects don't rubber-band. The host arbitrates: an owned object stays with its owner until it
+//! is released or its lease runs out, and the host's own player wins ties since it has no latency.
+
+use super::{send, BodyState, ClientMessage, NetBuffer, NetId, Network, PeerId, HOST_PEER};
+use crate::{CatchObject, Player, PlayerCatch};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+/// The host takes objects back if their owner stops sending updates for this long.
+pub const AUTHORITY_LEASE: f64 = 2.0;
+/// Clients keep simulating a released object this long, so throws fly smoothly.
+const RELEASE_DELAY: f64 = 1.0;
+const REQUEST_INTERVAL: f64 = 0.1;
+
+/// Peer simulating an object. Everyone else follows its states through a [`NetBuffer`].
+#[derive(Debug, Component)]
+pub struct NetAuthority {
+    pub owner: PeerId,
+    pub last_update: f64,
+    idle_since: Option<f64>,
+}
+
+impl Default for NetAuthority {
+    fn default() -> Self {
+        Self {
+            owner: HOST_PEER,
+            last_update: 0.0,
+            idle_since: None,
+        }
+    }
+}
+
+impl NetAuthority {
+    /// Grants the object to `peer` if nobody else holds it.
+    pub fn request(&mut self, peer: PeerId, host_holding: bool, now: f64) -> bool {
+        let free = self.owner == HOST_PEER && !host_holding;
+        if free || self.owner == peer {
+            self.owner = peer;
+            self.last_update = now;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn set_owner(&mut self, owner: PeerId) {
+        self.owner = owner;
+        self.idle_since = None;
+    }
+}
+
+/// Makes objects dynamic where they are simulated, and kinematic followers everywhere else.
+#[allow(clippy::type_complexity)]
+pub(super) fn authority_apply(
+    mut commands: Commands,
+    network: Res<Network>,
+    mut objects: Query<
+        (
+            Entity,
+            &NetAuthority,
+            ChangeTrackers<NetAuthority>,
+            &mut RigidBody,
+            Option<&NetBuffer>,
+        ),
+        With<CatchObject>,
+    >,
+) {
+    let local_peer = network.local_peer();
+    for (entity, authority, tracker, mut rigid_body, buffer) in &mut objects {
+        if !network.is_changed() && !tracker.is_changed() {
+            continue;
+        }
+
+        let simulated = local_peer.map_or(true, |peer| authority.owner == peer);
+        if simulated {
+            if *rigid_body != RigidBody::Dynamic {
+                *rigid_body = RigidBody::Dynamic;
+            }
+            if buffer.is_some() {
+                commands.entity(entity).remove::<NetBuffer>();
+            }
+        } else {
+            if *rigid_body != RigidBody::KinematicPositionBased {
+                *rigid_body = RigidBody::KinematicPositionBased;
+            }
+            if buffer.is_none() {
+                commands.entity(entity).insert(NetBuffer::default());
+            }
+        }
+    }
+}
+
+/// Asks for the object being caught, and hands back owned objects once they have settled.
+#[allow(clippy::type_complexity)]
+pub(super) fn authority_client(
+    time: Res<Time>,
+    network: Res<Network>,
+    mut last_request: Local<f64>,
+    player: Query<&PlayerCatch, With<Player>>,
+    mut objects: Query<
+        (
+            Entity,
+            &NetId,
+            &mut NetAuthority,
+            &Transform,
+            Option<&Velocity>,
+        ),
+        With<CatchObject>,
+    >,
+) {
+    let client = match &*network {
+        Network::Client(client) => client,
+        _ => return,
+    };
+    let peer = match client.peer {
+        Some(peer) => peer,
+        None => return,
+    };
+
+    let now = time.seconds_since_startup();
+    let target = player.single().target;
+
+    for (entity, id, mut authority, transform, velocity) in &mut objects {
+        let targeted = target == Some(entity);
+        if authority.owner == peer {
+            if targeted {
+                if authority.idle_since.is_some() {
+                    authority.idle_since = None;
+                }
+                continue;
+            }
+
+            let idle_since = match authority.idle_since {
+                Some(idle_since) => idle_since,
+                None => {
+                    authority.idle_since = Some(now);
+                    now
+                }
+            };
+            if now - idle_since > RELEASE_DELAY {
+                let state = BodyState::new(transform, velocity);
+                send(
+                    &client.socket,
+                    client.server,
+                    &ClientMessage::ReleaseAuthority(*id, state),
+                );
+                authority.set_owner(HOST_PEER);
+            }
+        } else if targeted && now - *last_request > REQUEST_INTERVAL {
+            send(
+                &client.socket,
+                client.server,
+                &ClientMessage::RequestAuthority(*id),
+            );
+            *last_request = now;
+        }
+    }
+}
+
+/// Takes back objects whose owner went silent.
+pub(super) fn authority_lease(
+    time: Res<Time>,
+    network: Res<Network>,
+    mut objects: Query<(&NetId, &mut NetAuthority)>,
+) {
+    if !matches!(*network, Network::Host(_)) {
+        return;
+    }
+
+    let now = time.seconds_since_startup();
+    for (id, mut authority) in &mut objects {
+        if authority.owner != HOST_PEER && now - authority.last_update > AUTHORITY_LEASE {
+            warn!(
+                "Authority lease of object {} held by peer {} expired",
+                id.0, authority.owner
+            );
+            authority.set_owner(HOST_PEER);
+        }
+    }
+}
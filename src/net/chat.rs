@@ -0,0 +1,420 @@
+use super::{ClientMessage, Network, PeerId, RemotePlayer, ServerMessage};
+use crate::{
+    hud::bitmap::{BitmapAlign, BitmapText, BitmapTextBundle, GLYPH_ADVANCE},
+    input_block::{BlockReason, InputBlock},
+    layers::SpawnOnLayerExt,
+    Player, PlayerCamera, RENDER_SIZE,
+};
+use bevy::{
+    prelude::*,
+    render::{render_resource::*, texture::ImageSampler},
+    window::ReceivedCharacter,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+const MAX_MESSAGE_LEN: usize = 120;
+const MAX_LOG_LINES: usize = 32;
+const VISIBLE_LINES: usize = 6;
+/// Closed chat only shows lines younger than this.
+const LINE_DISPLAY_TIME: f64 = 10.0;
+/// Distance of the chat from the bottom left corner of the HUD, in pixels.
+const CHAT_MARGIN: f32 = 4.0;
+const EMOTE_LIFETIME: f32 = 2.0;
+const EMOTE_SIZE: f32 = 0.6;
+const EMOTE_HEIGHT: f32 = 2.5;
+
+pub struct ChatPlugin;
+
+impl Plugin for ChatPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ChatLog>()
+            .init_resource::<ChatInput>()
+            .init_resource::<EmoteAssets>()
+            .add_event::<ChatEvent>()
+            .add_startup_system(setup_chat)
+            .add_system(chat_input)
+            .add_system(chat_events.after(chat_input))
+            .add_system(chat_display.after(chat_events))
+            .add_system(emote_billboard.after(chat_events));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Emote {
+    Heart,
+    Smile,
+    Exclaim,
+    Question,
+}
+
+impl Emote {
+    pub const ALL: [Emote; 4] = [Emote::Heart, Emote::Smile, Emote::Exclaim, Emote::Question];
+
+    fn key(self) -> KeyCode {
+        match self {
+            Emote::Heart => KeyCode::Key1,
+            Emote::Smile => KeyCode::Key2,
+            Emote::Exclaim => KeyCode::Key3,
+            Emote::Question => KeyCode::Key4,
+        }
+    }
+
+    fn color(self) -> [u8; 4] {
+        match self {
+            Emote::Heart => [230, 60, 80, 255],
+            Emote::Smile => [250, 210, 60, 255],
+            Emote::Exclaim => [250, 140, 40, 255],
+            Emote::Question => [90, 160, 250, 255],
+        }
+    }
+
+    /// 8×8 pixel art of the icon.
+    fn pattern(self) -> [&'static str; 8] {
+        match self {
+            Emote::Heart => [
+                "........", ".XX..XX.", "XXXXXXXX", "XXXXXXXX", ".XXXXXX.", "..XXXX..", "...XX...",
+                "........",
+            ],
+            Emote::Smile => [
+                "..XXXX..", ".XXXXXX.", "XX.XX.XX", "XXXXXXXX", "X.XXXX.X", "XX....XX", ".XXXXXX.",
+                "..XXXX..",
+            ],
+            Emote::Exclaim => [
+                "...XX...", "...XX...", "...XX...", "...XX...", "...XX...", "........", "...XX...",
+                "...XX...",
+            ],
+            Emote::Question => [
+                "..XXXX..", ".XX..XX.", ".....XX.", "....XX..", "...XX...", "........", "...XX...",
+                "...XX...",
+            ],
+        }
+    }
+
+    fn image(self) -> Image {
+        let color = self.color();
+        let data = self
+            .pattern()
+            .iter()
+            .flat_map(|row| row.chars())
+            .flat_map(|pixel| match pixel {
+                'X' => color,
+                _ => [0, 0, 0, 0],
+            })
+            .collect();
+
+        let mut image = Image::new(
+            Extent3d {
+                width: 8,
+                height: 8,
+                ..default()
+            },
+            TextureDimension::D2,
+            data,
+            TextureFormat::Rgba8UnormSrgb,
+        );
+        image.sampler_descriptor = ImageSampler::Descriptor(SamplerDescriptor {
+            mag_filter: FilterMode::Nearest,
+            min_filter: FilterMode::Nearest,
+            mipmap_filter: FilterMode::Nearest,
+            ..default()
+        });
+        image
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ChatPayload {
+    Text(String),
+    Emote(Emote),
+}
+
+impl ChatPayload {
+    /// Strips control characters and over-long text; `None` if nothing is left to show.
+    pub fn sanitized(self) -> Option<Self> {
+        match self {
+            ChatPayload::Text(text) => {
+                let text: String = text
+                    .chars()
+                    .filter(|c| !c.is_control())
+                    .take(MAX_MESSAGE_LEN)
+                    .collect();
+                let text = text.trim();
+                (!text.is_empty()).then(|| ChatPayload::Text(text.into()))
+            }
+            emote => Some(emote),
+        }
+    }
+}
+
+/// A chat message or emote to display, local ones included. `peer` is `None` when offline.
+#[derive(Debug, Clone)]
+pub struct ChatEvent {
+    pub peer: Option<PeerId>,
+    pub payload: ChatPayload,
+}
+
+pub struct ChatLine {
+    pub sender: String,
+    pub text: String,
+    pub time: f64,
+}
+
+#[derive(Default)]
+pub struct ChatLog {
+    pub lines: VecDeque<ChatLine>,
+}
+
+#[derive(Default)]
+pub struct ChatInput {
+    pub open: bool,
+    pub text: String,
+}
+
+pub struct EmoteAssets {
+    mesh: Handle<Mesh>,
+    materials: Vec<(Emote, Handle<StandardMaterial>)>,
+}
+
+impl FromWorld for EmoteAssets {
+    fn from_world(world: &mut World) -> Self {
+        let mesh = world
+            .resource_mut::<Assets<Mesh>>()
+            .add(shape::Quad::new(Vec2::splat(EMOTE_SIZE)).into());
+
+        let images: Vec<_> = {
+            let mut images = world.resource_mut::<Assets<Image>>();
+            Emote::ALL
+                .iter()
+                .map(|emote| images.add(emote.image()))
+                .collect()
+        };
+        let mut materials = world.resource_mut::<Assets<StandardMaterial>>();
+        let materials = Emote::ALL
+            .iter()
+            .zip(images)
+            .map(|(emote, image)| {
+                let material = materials.add(StandardMaterial {
+                    base_color_texture: Some(image),
+                    alpha_mode: AlphaMode::Mask(0.5),
+                    unlit: true,
+                    ..default()
+                });
+                (*emote, material)
+            })
+            .collect();
+
+        Self { mesh, materials }
+    }
+}
+
+#[derive(Component)]
+struct ChatText;
+
+#[derive(Component)]
+struct EmoteBillboard {
+    follow: Entity,
+    timer: Timer,
+}
+
+/// Bottom left corner of the chat, where the last line goes.
+fn chat_corner() -> Vec2 {
+    let half = 0.5 * Vec2::new(RENDER_SIZE[0] as f32, RENDER_SIZE[1] as f32);
+    Vec2::new(-half.x + CHAT_MARGIN, -half.y + CHAT_MARGIN)
+}
+
+fn setup_chat(mut commands: Commands) {
+    commands
+        .spawn_on_hud_layer(BitmapTextBundle::new(
+            BitmapText::new("", Color::WHITE, BitmapAlign::Left),
+            chat_corner(),
+        ))
+        .insert(ChatText);
+}
+
+fn chat_input(
+    keys: Res<Input<KeyCode>>,
+    network: Res<Network>,
+    mut characters: EventReader<ReceivedCharacter>,
+    mut input: ResMut<ChatInput>,
+    mut block: ResMut<InputBlock>,
+    mut events: EventWriter<ChatEvent>,
+) {
+    let mut submit = |payload: ChatPayload| {
+        let payload = match payload.sanitized() {
+            Some(payload) => payload,
+            None => return,
+        };
+        let peer = network.local_peer();
+        match *network {
+            Network::Offline => {}
+            Network::Host(_) => network.broadcast(
+                &ServerMessage::Chat {
+                    peer: super::HOST_PEER,
+                    payload: payload.clone(),
+                },
+                None,
+            ),
+            Network::Client(_) => network.send_to_host(&ClientMessage::Chat(payload.clone())),
+        }
+        events.send(ChatEvent { peer, payload });
+    };
+
+    if !input.open {
+        // Drop the characters typed while playing.
+        for _ in characters.iter() {}
+
+        if keys.just_pressed(KeyCode::T) || keys.just_pressed(KeyCode::Return) {
+            input.open = true;
+            block.set(BlockReason::Chat, true);
+        }
+        for emote in Emote::ALL {
+            if keys.just_pressed(emote.key()) {
+                submit(ChatPayload::Emote(emote));
+            }
+        }
+        return;
+    }
+
+    for character in characters.iter() {
+        match character.char {
+            '\u{8}' => {
+                input.text.pop();
+            }
+            c if c.is_control() => {}
+            c if input.text.chars().count() < MAX_MESSAGE_LEN => input.text.push(c),
+            _ => {}
+        }
+    }
+
+    if keys.just_pressed(KeyCode::Return) {
+        let text = std::mem::take(&mut input.text);
+        submit(ChatPayload::Text(text));
+    }
+    if keys.just_pressed(KeyCode::Return) || keys.just_pressed(KeyCode::Escape) {
+        input.open = false;
+        input.text.clear();
+        block.set(BlockReason::Chat, false);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn chat_events(
+    mut commands: Commands,
+    time: Res<Time>,
+    assets: Res<EmoteAssets>,
+    mut events: EventReader<ChatEvent>,
+    mut log: ResMut<ChatLog>,
+    network: Res<Network>,
+    player: Query<Entity, With<Player>>,
+    remote_players: Query<(Entity, &RemotePlayer)>,
+) {
+    for event in events.iter() {
+        let sender = match event.peer {
+            Some(peer) if Some(peer) == network.local_peer() => "You".into(),
+            Some(peer) => format!("P{}", peer),
+            None => "You".into(),
+        };
+
+        match &event.payload {
+            ChatPayload::Text(text) => {
+                log.lines.push_back(ChatLine {
+                    sender,
+                    text: text.clone(),
+                    time: time.seconds_since_startup(),
+                });
+                while log.lines.len() > MAX_LOG_LINES {
+                    log.lines.pop_front();
+                }
+            }
+            ChatPayload::Emote(emote) => {
+                let follow = match event.peer {
+                    Some(peer) if Some(peer) != network.local_peer() => remote_players
+                        .iter()
+                        .find(|(_, remote)| remote.peer == peer)
+                        .map(|(entity, _)| entity),
+                    _ => player.get_single().ok(),
+                };
+                let material = assets
+                    .materials
+                    .iter()
+                    .find(|(other, _)| other == emote)
+                    .map(|(_, material)| material.clone());
+
+                if let (Some(follow), Some(material)) = (follow, material) {
+                    commands
+                        .spawn_on_world_layer(PbrBundle {
+                            mesh: assets.mesh.clone(),
+                            material,
+                            ..default()
+                        })
+                        .insert(EmoteBillboard {
+                            follow,
+                            timer: Timer::from_seconds(EMOTE_LIFETIME, false),
+                        });
+                }
+            }
+        }
+    }
+}
+
+fn chat_display(
+    time: Res<Time>,
+    log: Res<ChatLog>,
+    input: Res<ChatInput>,
+    mut texts: Query<(&mut BitmapText, &mut Transform), With<ChatText>>,
+) {
+    let now = time.seconds_since_startup();
+    let mut lines: Vec<_> = log
+        .lines
+        .iter()
+        .rev()
+        .take(VISIBLE_LINES)
+        .filter(|line| input.open || now - line.time < LINE_DISPLAY_TIME)
+        .map(|line| format!("{}: {}", line.sender, line.text))
+        .collect();
+    lines.reverse();
+    if input.open {
+        lines.push(format!("> {}_", input.text));
+    }
+
+    let value = lines.join("\n");
+    // Lines run down from the text's position, so move it up to keep the last line at the bottom.
+    let bottom = chat_corner().y;
+    for (mut text, mut transform) in &mut texts {
+        transform.translation.y = bottom + GLYPH_ADVANCE.y * lines.len().saturating_sub(1) as f32;
+        if text.value != value {
+            text.value = value.clone();
+        }
+    }
+}
+
+fn emote_billboard(
+    mut commands: Commands,
+    time: Res<Time>,
+    camera: Query<&GlobalTransform, With<PlayerCamera>>,
+    targets: Query<&GlobalTransform, Without<EmoteBillboard>>,
+    mut billboards: Query<(Entity, &mut EmoteBillboard, &mut Transform)>,
+) {
+    let camera_position = camera.single().translation();
+    for (entity, mut billboard, mut transform) in &mut billboards {
+        let target = match targets.get(billboard.follow) {
+            Ok(target) => target,
+            Err(_) => {
+                commands.entity(entity).despawn_recursive();
+                continue;
+            }
+        };
+        if billboard.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+
+        // Bob up a little while fading in.
+        let rise = 0.2 * (billboard.timer.percent() * 4.0).min(1.0);
+        transform.translation = target.translation() + Vec3::Y * (EMOTE_HEIGHT + rise);
+        // Quads face +Z, so look away from the camera to show the front.
+        let away = 2.0 * transform.translation - camera_position;
+        transform.look_at(away, Vec3::Y);
+    }
+}
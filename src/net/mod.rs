@@ -0,0 +1,894 @@
+use crate::{
+    bots::{Bot, BOT_PEER_BASE},
+    layers::SpawnOnLayerExt,
+    level::LevelLoaded,
+    match_flow::{MatchRequest, MatchUpdate},
+    paint::{Emblem, StampEvent},
+    replay::BodySnapshot,
+    CatchObject, Player, PlayerCamera, PlayerCatch,
+};
+use bevy::{prelude::*, utils::HashMap};
+use bevy_rapier3d::prelude::*;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    collections::VecDeque,
+    io::ErrorKind,
+    net::{SocketAddr, UdpSocket},
+};
+
+mod authority;
+mod chat;
+
+pub use authority::NetAuthority;
+pub use chat::{ChatEvent, ChatPayload, Emote};
+
+pub const DEFAULT_PORT: u16 = 40000;
+
+/// Snapshots sent per second.
+const TICK_RATE: f32 = 30.0;
+/// Remote entities are drawn this far in the past, so there are always two samples to blend.
+const INTERPOLATION_DELAY: f64 = 0.1;
+const TIMEOUT: f64 = 5.0;
+const MAX_PACKET_SIZE: usize = 64 * 1024;
+const MAX_BUFFERED_SAMPLES: usize = 32;
+
+pub type PeerId = u16;
+pub const HOST_PEER: PeerId = 0;
+
+pub struct NetworkPlugin;
+
+impl Plugin for NetworkPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Network>()
+            .init_resource::<RemotePlayerAssets>()
+            .insert_resource(NetTimer(Timer::from_seconds(1.0 / TICK_RATE, true)))
+            .add_event::<NetCommand>()
+            .add_event::<FromClient<MatchRequest>>()
+            .add_event::<FromServer<MatchUpdate>>()
+            .add_plugin(chat::ChatPlugin)
+            .add_system(assign_net_ids)
+            .add_system(network_keys)
+            .add_system(network_commands.after(network_keys))
+            .add_system(network_receive.after(network_commands))
+            .add_system(network_send.after(network_receive))
+            .add_system(network_interpolate.after(network_receive))
+            .add_system(network_timeout)
+            .add_system(authority::authority_client.after(network_receive))
+            .add_system(authority::authority_lease.after(network_receive))
+            .add_system(
+                authority::authority_apply
+                    .after(authority::authority_client)
+                    .after(authority::authority_lease),
+            );
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum NetCommand {
+    Host(u16),
+    Connect(SocketAddr),
+    Disconnect,
+}
+
+#[derive(Default)]
+pub enum Network {
+    #[default]
+    Offline,
+    Host(Host),
+    Client(Client),
+}
+
+impl Network {
+    /// Id of the local player, if connected.
+    pub fn local_peer(&self) -> Option<PeerId> {
+        match self {
+            Network::Offline => None,
+            Network::Host(_) => Some(HOST_PEER),
+            Network::Client(client) => client.peer,
+        }
+    }
+
+    /// Sends a message to every connected client, except `skip`.
+    pub fn broadcast(&self, message: &ServerMessage, skip: Option<PeerId>) {
+        if let Network::Host(host) = self {
+            for (addr, peer) in &host.peers {
+                if Some(peer.id) != skip {
+                    send(&host.socket, *addr, message);
+                }
+            }
+        }
+    }
+
+    pub fn send_to_host(&self, message: &ClientMessage) {
+        if let Network::Client(client) = self {
+            send(&client.socket, client.server, message);
+        }
+    }
+
+    /// Ids of everyone in the session, the local player included.
+    pub fn participants(&self) -> Vec<PeerId> {
+        match self {
+            Network::Offline => vec![HOST_PEER],
+            Network::Host(host) => std::iter::once(HOST_PEER)
+                .chain(host.peers.values().map(|peer| peer.id))
+                .collect(),
+            Network::Client(client) => client.peer.into_iter().collect(),
+        }
+    }
+
+    /// Whether this instance decides the game rules, i.e. it is the host or plays offline.
+    pub fn is_authoritative(&self) -> bool {
+        !matches!(self, Network::Client(_))
+    }
+}
+
+pub struct Host {
+    socket: UdpSocket,
+    peers: HashMap<SocketAddr, Peer>,
+    next_peer: PeerId,
+    tick: u32,
+}
+
+impl Host {
+    /// A peer id nobody has, going round below the ids of bots so a left peer's id isn't handed
+    /// out again right away. `None` once every id is taken.
+    fn allocate_peer(&mut self) -> Option<PeerId> {
+        let first = HOST_PEER + 1;
+        let count = BOT_PEER_BASE - first;
+        let id = (0..count)
+            .map(|offset| first + (self.next_peer - first + offset) % count)
+            .find(|id| self.peers.values().all(|peer| peer.id != *id))?;
+        self.next_peer = first + (id + 1 - first) % count;
+        Some(id)
+    }
+}
+
+pub struct Peer {
+    pub id: PeerId,
+    pub last_seen: f64,
+    pub state: Option<PlayerState>,
+}
+
+pub struct Client {
+    socket: UdpSocket,
+    server: SocketAddr,
+    peer: Option<PeerId>,
+    last_seen: f64,
+    tick: u32,
+}
+
+struct NetTimer(Timer);
+
+/// Identifies replicated entities across the network.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Component, Serialize, Deserialize)]
+pub struct NetId(pub u32);
+
+#[derive(Debug, Component)]
+pub struct RemotePlayer {
+    pub peer: PeerId,
+    pub pitch: f32,
+}
+
+/// Received states of a replicated entity, blended with [`INTERPOLATION_DELAY`].
+#[derive(Debug, Default, Component)]
+pub struct NetBuffer {
+    samples: VecDeque<(f64, BodyState)>,
+}
+
+impl NetBuffer {
+    pub fn push(&mut self, time: f64, state: BodyState) {
+        self.samples.push_back((time, state));
+        while self.samples.len() > MAX_BUFFERED_SAMPLES {
+            self.samples.pop_front();
+        }
+    }
+
+    pub fn latest(&self) -> Option<&BodyState> {
+        self.samples.back().map(|(_, state)| state)
+    }
+
+    pub fn sample(&self, time: f64) -> Option<Transform> {
+        let to_transform = |state: &BodyState| {
+            let mut transform = Transform::default();
+            state.pose.apply(&mut transform);
+            transform
+        };
+
+        let next = self.samples.iter().position(|(t, _)| *t >= time);
+        match next {
+            Some(0) => self.samples.front().map(|(_, state)| to_transform(state)),
+            Some(index) => {
+                let (t0, s0) = &self.samples[index - 1];
+                let (t1, s1) = &self.samples[index];
+                let t = ((time - t0) / (t1 - t0).max(f64::EPSILON)) as f32;
+                let (a, b) = (to_transform(s0), to_transform(s1));
+                Some(Transform {
+                    translation: a.translation.lerp(b.translation, t),
+                    rotation: a.rotation.slerp(b.rotation, t),
+                    ..default()
+                })
+            }
+            None => self.latest().map(to_transform),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct BodyState {
+    pub pose: BodySnapshot,
+    pub linvel: [f32; 3],
+    pub angvel: [f32; 3],
+}
+
+impl BodyState {
+    pub fn new(transform: &Transform, velocity: Option<&Velocity>) -> Self {
+        let velocity = velocity.copied().unwrap_or_default();
+        Self {
+            pose: transform.into(),
+            linvel: velocity.linvel.to_array(),
+            angvel: velocity.angvel.to_array(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct PlayerState {
+    pub peer: PeerId,
+    pub body: BodyState,
+    pub pitch: f32,
+}
+
+/// A gameplay message a client sent to the host.
+#[derive(Debug, Clone)]
+pub struct FromClient<T> {
+    pub peer: PeerId,
+    pub message: T,
+}
+
+/// A gameplay message the host sent to this client.
+#[derive(Debug, Clone)]
+pub struct FromServer<T>(pub T);
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ObjectState {
+    pub id: NetId,
+    pub owner: PeerId,
+    pub body: BodyState,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum ClientMessage {
+    Hello,
+    State {
+        tick: u32,
+        player: PlayerState,
+        /// Objects this client has authority over.
+        owned: Vec<(NetId, BodyState)>,
+    },
+    RequestAuthority(NetId),
+    ReleaseAuthority(NetId, BodyState),
+    Chat(ChatPayload),
+    Match(MatchRequest),
+    Stamp(NetId, Emblem),
+    Bye,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum ServerMessage {
+    Welcome {
+        peer: PeerId,
+    },
+    Snapshot {
+        tick: u32,
+        players: Vec<PlayerState>,
+        objects: Vec<ObjectState>,
+    },
+    Leave {
+        peer: PeerId,
+    },
+    Chat {
+        peer: PeerId,
+        payload: ChatPayload,
+    },
+    Match(MatchUpdate),
+    Stamp(StampEvent),
+    Shutdown,
+}
+
+pub struct RemotePlayerAssets {
+    mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+}
+
+impl FromWorld for RemotePlayerAssets {
+    fn from_world(world: &mut World) -> Self {
+        let mesh = world.resource_mut::<Assets<Mesh>>().add(
+            shape::Capsule {
+                radius: 0.5,
+                depth: 1.0,
+                ..default()
+            }
+            .into(),
+        );
+        let material = world
+            .resource_mut::<Assets<StandardMaterial>>()
+            .add(StandardMaterial {
+                base_color: Color::rgb(0.8, 0.4, 0.3),
+                perceptual_roughness: 0.9,
+                ..default()
+            });
+        Self { mesh, material }
+    }
+}
+
+fn send<T: Serialize>(socket: &UdpSocket, addr: SocketAddr, message: &T) {
+    match bincode::serialize(message) {
+        Ok(bytes) => {
+            if let Err(err) = socket.send_to(&bytes, addr) {
+                warn!("Failed to send packet to {}: {}", addr, err);
+            }
+        }
+        Err(err) => error!("Failed to serialize packet: {}", err),
+    }
+}
+
+fn receive<T: DeserializeOwned>(socket: &UdpSocket) -> Vec<(SocketAddr, T)> {
+    let mut messages = vec![];
+    let mut buffer = vec![0; MAX_PACKET_SIZE];
+    loop {
+        match socket.recv_from(&mut buffer) {
+            Ok((len, addr)) => match bincode::deserialize(&buffer[..len]) {
+                Ok(message) => messages.push((addr, message)),
+                Err(err) => warn!("Dropped malformed packet from {}: {}", addr, err),
+            },
+            Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+            Err(err) => {
+                // On some platforms an unreachable peer shows up as a receive error.
+                warn!("Failed to receive packet: {}", err);
+                break;
+            }
+        }
+    }
+    messages
+}
+
+fn bind(port: u16) -> std::io::Result<UdpSocket> {
+    let socket = UdpSocket::bind(("0.0.0.0", port))?;
+    socket.set_nonblocking(true)?;
+    Ok(socket)
+}
+
+fn spawn_remote_player(
+    commands: &mut Commands,
+    assets: &RemotePlayerAssets,
+    peer: PeerId,
+) -> Entity {
+    info!(target: "net", %peer, "Peer joined");
+    commands
+        .spawn_bundle(SpatialBundle::default())
+        .insert_bundle((
+            RemotePlayer { peer, pitch: 0.0 },
+            NetBuffer::default(),
+            RigidBody::KinematicPositionBased,
+            Collider::capsule(Vec3::Y * 0.5, Vec3::Y * 1.5, 0.5),
+        ))
+        .with_children(|parent| {
+            parent.spawn_on_world_layer(PbrBundle {
+                mesh: assets.mesh.clone(),
+                material: assets.material.clone(),
+                transform: Transform::from_xyz(0.0, 1.0, 0.0),
+                ..default()
+            });
+        })
+        .id()
+}
+
+/// Both sides spawn the same level, so numbering catch objects by where they spawned matches them
+/// up. Entity ids aren't used since despawned slots get reused in any order.
+fn assign_net_ids(
+    mut commands: Commands,
+    mut next_id: Local<u32>,
+    mut level_loaded: EventReader<LevelLoaded>,
+    objects: Query<(Entity, &Transform), (With<CatchObject>, Without<NetId>)>,
+) {
+    // Every level numbers its objects from scratch, so late joiners agree with the host.
+    if level_loaded.iter().count() > 0 {
+        *next_id = 0;
+    }
+
+    let mut entities: Vec<_> = objects.iter().collect();
+    entities.sort_by(|(_, a), (_, b)| {
+        let (a, b) = (a.translation, b.translation);
+        a.x.total_cmp(&b.x)
+            .then(a.y.total_cmp(&b.y))
+            .then(a.z.total_cmp(&b.z))
+    });
+    for (entity, _) in entities {
+        commands
+            .entity(entity)
+            .insert_bundle((NetId(*next_id), NetAuthority::default()));
+        *next_id += 1;
+    }
+}
+
+fn network_keys(
+    keys: Res<Input<KeyCode>>,
+    network: Res<Network>,
+    mut commands: EventWriter<NetCommand>,
+) {
+    if keys.just_pressed(KeyCode::F9) {
+        commands.send(match *network {
+            Network::Offline => NetCommand::Host(DEFAULT_PORT),
+            _ => NetCommand::Disconnect,
+        });
+    }
+    if keys.just_pressed(KeyCode::F10) {
+        commands.send(match *network {
+            Network::Offline => {
+                NetCommand::Connect(SocketAddr::from(([127, 0, 0, 1], DEFAULT_PORT)))
+            }
+            _ => NetCommand::Disconnect,
+        });
+    }
+}
+
+fn network_commands(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut events: EventReader<NetCommand>,
+    mut network: ResMut<Network>,
+    remote_players: Query<Entity, With<RemotePlayer>>,
+    mut objects: Query<&mut NetAuthority>,
+) {
+    for event in events.iter() {
+        // Leave the current session first.
+        match std::mem::take(&mut *network) {
+            Network::Offline => {}
+            Network::Host(host) => {
+                for addr in host.peers.keys() {
+                    send(&host.socket, *addr, &ServerMessage::Shutdown);
+                }
+            }
+            Network::Client(client) => send(&client.socket, client.server, &ClientMessage::Bye),
+        }
+        for entity in &remote_players {
+            commands.entity(entity).despawn_recursive();
+        }
+        for mut authority in &mut objects {
+            authority.set_owner(HOST_PEER);
+        }
+
+        match event {
+            NetCommand::Host(port) => match bind(*port) {
+                Ok(socket) => {
+                    info!("Hosting on port {}", port);
+                    *network = Network::Host(Host {
+                        socket,
+                        peers: default(),
+                        next_peer: HOST_PEER + 1,
+                        tick: 0,
+                    });
+                }
+                Err(err) => error!("Failed to host on port {}: {}", port, err),
+            },
+            NetCommand::Connect(server) => match bind(0) {
+                Ok(socket) => {
+                    info!("Connecting to {}", server);
+                    send(&socket, *server, &ClientMessage::Hello);
+                    *network = Network::Client(Client {
+                        socket,
+                        server: *server,
+                        peer: None,
+                        last_seen: time.seconds_since_startup(),
+                        tick: 0,
+                    });
+                }
+                Err(err) => error!("Failed to open client socket: {}", err),
+            },
+            NetCommand::Disconnect => info!("Disconnected"),
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn network_receive(
+    mut commands: Commands,
+    time: Res<Time>,
+    assets: Res<RemotePlayerAssets>,
+    mut network: ResMut<Network>,
+    mut net_commands: EventWriter<NetCommand>,
+    mut chat_events: EventWriter<ChatEvent>,
+    mut match_requests: EventWriter<FromClient<MatchRequest>>,
+    mut match_updates: EventWriter<FromServer<MatchUpdate>>,
+    mut stamps: EventWriter<StampEvent>,
+    mut remote_players: Query<(Entity, &mut RemotePlayer, &mut NetBuffer), Without<CatchObject>>,
+    player: Query<&PlayerCatch, With<Player>>,
+    mut objects: Query<
+        (
+            Entity,
+            &NetId,
+            &mut NetAuthority,
+            Option<&mut NetBuffer>,
+            &mut Transform,
+            &mut Velocity,
+        ),
+        With<CatchObject>,
+    >,
+) {
+    let now = time.seconds_since_startup();
+
+    match &mut *network {
+        Network::Offline => {}
+        Network::Host(host) => {
+            for (addr, message) in receive::<ClientMessage>(&host.socket) {
+                match message {
+                    ClientMessage::Hello => {
+                        let peer = match host.peers.get(&addr) {
+                            Some(peer) => peer.id,
+                            None => {
+                                let id = match host.allocate_peer() {
+                                    Some(id) => id,
+                                    None => {
+                                        warn!("No peer id left for {}", addr);
+                                        continue;
+                                    }
+                                };
+                                host.peers.insert(
+                                    addr,
+                                    Peer {
+                                        id,
+                                        last_seen: now,
+                                        state: None,
+                                    },
+                                );
+                                spawn_remote_player(&mut commands, &assets, id);
+                                id
+                            }
+                        };
+                        send(&host.socket, addr, &ServerMessage::Welcome { peer });
+                    }
+                    ClientMessage::State { player, owned, .. } => {
+                        let peer = match host.peers.get_mut(&addr) {
+                            Some(peer) => peer,
+                            None => continue,
+                        };
+                        peer.last_seen = now;
+                        // Never trust the peer id claimed by the packet.
+                        let state = PlayerState {
+                            peer: peer.id,
+                            ..player
+                        };
+                        peer.state = Some(state);
+
+                        if let Some((_, mut remote, mut buffer)) = remote_players
+                            .iter_mut()
+                            .find(|(_, remote, _)| remote.peer == state.peer)
+                        {
+                            remote.pitch = state.pitch;
+                            buffer.push(now, state.body);
+                        }
+
+                        let owned: HashMap<_, _> = owned.into_iter().collect();
+                        for (_, id, mut authority, buffer, _, _) in &mut objects {
+                            if authority.owner != state.peer {
+                                continue;
+                            }
+                            if let Some(body) = owned.get(id) {
+                                authority.last_update = now;
+                                if let Some(mut buffer) = buffer {
+                                    buffer.push(now, *body);
+                                }
+                            }
+                        }
+                    }
+                    ClientMessage::RequestAuthority(id) => {
+                        let peer = match host.peers.get(&addr) {
+                            Some(peer) => peer.id,
+                            None => continue,
+                        };
+                        let host_target = player.single().target;
+                        if let Some((entity, _, mut authority, ..)) =
+                            objects.iter_mut().find(|(_, other, ..)| **other == id)
+                        {
+                            let host_holding = host_target == Some(entity);
+                            if authority.request(peer, host_holding, now) {
+                                debug!(target: "net", object = id.0, %peer, "Granted object");
+                            }
+                        }
+                    }
+                    ClientMessage::ReleaseAuthority(id, body) => {
+                        let peer = match host.peers.get(&addr) {
+                            Some(peer) => peer.id,
+                            None => continue,
+                        };
+                        if let Some((_, _, mut authority, _, mut transform, mut velocity)) =
+                            objects.iter_mut().find(|(_, other, ..)| **other == id)
+                        {
+                            if authority.owner != peer {
+                                continue;
+                            }
+                            // Continue from where the owner left off, momentum included.
+                            authority.set_owner(HOST_PEER);
+                            body.pose.apply(&mut transform);
+                            velocity.linvel = Vec3::from_array(body.linvel);
+                            velocity.angvel = Vec3::from_array(body.angvel);
+                        }
+                    }
+                    ClientMessage::Match(message) => {
+                        if let Some(peer) = host.peers.get(&addr) {
+                            match_requests.send(FromClient {
+                                peer: peer.id,
+                                message,
+                            });
+                        }
+                    }
+                    ClientMessage::Stamp(object, emblem) => {
+                        let peer = match host.peers.get(&addr) {
+                            Some(peer) => peer.id,
+                            None => continue,
+                        };
+                        let event = StampEvent {
+                            peer,
+                            object,
+                            emblem,
+                        };
+                        for (addr, other) in &host.peers {
+                            if other.id != peer {
+                                send(&host.socket, *addr, &ServerMessage::Stamp(event));
+                            }
+                        }
+                        stamps.send(event);
+                    }
+                    ClientMessage::Chat(payload) => {
+                        let peer = match host.peers.get(&addr) {
+                            Some(peer) => peer.id,
+                            None => continue,
+                        };
+                        let payload = match payload.sanitized() {
+                            Some(payload) => payload,
+                            None => continue,
+                        };
+                        for (addr, other) in &host.peers {
+                            if other.id != peer {
+                                let message = ServerMessage::Chat {
+                                    peer,
+                                    payload: payload.clone(),
+                                };
+                                send(&host.socket, *addr, &message);
+                            }
+                        }
+                        chat_events.send(ChatEvent {
+                            peer: Some(peer),
+                            payload,
+                        });
+                    }
+                    ClientMessage::Bye => {
+                        if let Some(peer) = host.peers.remove(&addr) {
+                            info!("Peer {} left", peer.id);
+                            for (entity, remote, _) in &remote_players {
+                                if remote.peer == peer.id {
+                                    commands.entity(entity).despawn_recursive();
+                                }
+                            }
+                            for addr in host.peers.keys() {
+                                send(&host.socket, *addr, &ServerMessage::Leave { peer: peer.id });
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Network::Client(client) => {
+            for (addr, message) in receive::<ServerMessage>(&client.socket) {
+                if addr != client.server {
+                    continue;
+                }
+                client.last_seen = now;
+
+                match message {
+                    ServerMessage::Welcome { peer } => {
+                        info!("Joined as peer {}", peer);
+                        client.peer = Some(peer);
+                    }
+                    ServerMessage::Snapshot {
+                        players,
+                        objects: states,
+                        ..
+                    } => {
+                        for state in players {
+                            if Some(state.peer) == client.peer {
+                                continue;
+                            }
+                            match remote_players
+                                .iter_mut()
+                                .find(|(_, remote, _)| remote.peer == state.peer)
+                            {
+                                Some((_, mut remote, mut buffer)) => {
+                                    remote.pitch = state.pitch;
+                                    buffer.push(now, state.body);
+                                }
+                                None => {
+                                    let entity =
+                                        spawn_remote_player(&mut commands, &assets, state.peer);
+                                    let mut buffer = NetBuffer::default();
+                                    buffer.push(now, state.body);
+                                    commands.entity(entity).insert(buffer);
+                                }
+                            }
+                        }
+
+                        let states: HashMap<_, _> =
+                            states.into_iter().map(|state| (state.id, state)).collect();
+                        for (_, id, mut authority, buffer, _, _) in &mut objects {
+                            let state = match states.get(id) {
+                                Some(state) => state,
+                                None => continue,
+                            };
+                            if authority.owner != state.owner {
+                                authority.set_owner(state.owner);
+                            }
+                            // Objects we own are simulated here; the host only echoes them.
+                            if Some(state.owner) == client.peer {
+                                continue;
+                            }
+                            if let Some(mut buffer) = buffer {
+                                buffer.push(now, state.body);
+                            }
+                        }
+                    }
+                    ServerMessage::Leave { peer } => {
+                        info!("Peer {} left", peer);
+                        for (entity, remote, _) in &remote_players {
+                            if remote.peer == peer {
+                                commands.entity(entity).despawn_recursive();
+                            }
+                        }
+                    }
+                    ServerMessage::Match(message) => match_updates.send(FromServer(message)),
+                    ServerMessage::Stamp(event) => stamps.send(event),
+                    ServerMessage::Chat { peer, payload } => {
+                        if let Some(payload) = payload.sanitized() {
+                            chat_events.send(ChatEvent {
+                                peer: Some(peer),
+                                payload,
+                            });
+                        }
+                    }
+                    ServerMessage::Shutdown => {
+                        info!("Host shut down the session");
+                        net_commands.send(NetCommand::Disconnect);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn network_send(
+    time: Res<Time>,
+    mut timer: ResMut<NetTimer>,
+    mut network: ResMut<Network>,
+    player: Query<(&Transform, Option<&Velocity>), With<Player>>,
+    camera: Query<&Transform, (With<PlayerCamera>, Without<Player>)>,
+    bots: Query<(&Bot, &Transform, Option<&Velocity>)>,
+    objects: Query<(&NetId, &NetAuthority, &Transform, Option<&Velocity>), With<CatchObject>>,
+) {
+    if !timer.0.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let local_player = |peer| {
+        let (transform, velocity) = player.single();
+        let (pitch, _, _) = camera.single().rotation.to_euler(EulerRot::XYZ);
+        PlayerState {
+            peer,
+            body: BodyState::new(transform, velocity),
+            pitch,
+        }
+    };
+
+    match &mut *network {
+        Network::Offline => {}
+        Network::Host(host) => {
+            host.tick += 1;
+
+            // Bots are simulated here and show up as ordinary players to clients.
+            let bots = bots.iter().map(|(bot, transform, velocity)| PlayerState {
+                peer: bot.peer,
+                body: BodyState::new(transform, velocity),
+                pitch: 0.0,
+            });
+            let players = std::iter::once(local_player(HOST_PEER))
+                .chain(host.peers.values().filter_map(|peer| peer.state))
+                .chain(bots)
+                .collect();
+            let objects = objects
+                .iter()
+                .map(|(id, authority, transform, velocity)| ObjectState {
+                    id: *id,
+                    owner: authority.owner,
+                    body: BodyState::new(transform, velocity),
+                })
+                .collect();
+            let snapshot = ServerMessage::Snapshot {
+                tick: host.tick,
+                players,
+                objects,
+            };
+            for addr in host.peers.keys() {
+                send(&host.socket, *addr, &snapshot);
+            }
+        }
+        Network::Client(client) => {
+            client.tick += 1;
+            match client.peer {
+                Some(peer) => {
+                    let owned = objects
+                        .iter()
+                        .filter(|(_, authority, _, _)| authority.owner == peer)
+                        .map(|(id, _, transform, velocity)| {
+                            (*id, BodyState::new(transform, velocity))
+                        })
+                        .collect();
+                    let message = ClientMessage::State {
+                        tick: client.tick,
+                        player: local_player(peer),
+                        owned,
+                    };
+                    send(&client.socket, client.server, &message);
+                }
+                // Keep knocking until the host answers.
+                None => send(&client.socket, client.server, &ClientMessage::Hello),
+            }
+        }
+    }
+}
+
+fn network_interpolate(time: Res<Time>, mut query: Query<(&NetBuffer, &mut Transform)>) {
+    let render_time = time.seconds_since_startup() - INTERPOLATION_DELAY;
+    for (buffer, mut transform) in &mut query {
+        if let Some(sample) = buffer.sample(render_time) {
+            *transform = sample;
+        }
+    }
+}
+
+fn network_timeout(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut network: ResMut<Network>,
+    mut net_commands: EventWriter<NetCommand>,
+    remote_players: Query<(Entity, &RemotePlayer)>,
+) {
+    let now = time.seconds_since_startup();
+    match &mut *network {
+        Network::Offline => {}
+        Network::Host(host) => {
+            let timed_out: Vec<_> = host
+                .peers
+                .iter()
+                .filter(|(_, peer)| now - peer.last_seen > TIMEOUT)
+                .map(|(addr, peer)| (*addr, peer.id))
+                .collect();
+            for (addr, id) in timed_out {
+                warn!("Peer {} timed out", id);
+                host.peers.remove(&addr);
+                for (entity, remote) in &remote_players {
+                    if remote.peer == id {
+                        commands.entity(entity).despawn_recursive();
+                    }
+                }
+                for addr in host.peers.keys() {
+                    send(&host.socket, *addr, &ServerMessage::Leave { peer: id });
+                }
+            }
+        }
+        Network::Client(client) => {
+            if now - client.last_seen > TIMEOUT {
+                warn!("Connection to {} timed out", client.server);
+                net_commands.send(NetCommand::Disconnect);
+            }
+        }
+    }
+}
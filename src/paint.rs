@@ -0,0 +1,182 @@
+//! Stamping held cubes with an emblem.
+//!
+//! Holding Stamp while carrying something opens a [`RadialMenu`] of emblems; letting go of the key
+//! paints the object in the picked emblem's color and marks it with a [`Stamp`] naming the local
+//! peer. Stamps go through the host to everyone, so they can stand for who owns what, as in the
+//! possession win condition of the match rules.
+
+use crate::{
+    hold::Held,
+    hud::radial::{RadialKind, RadialMenu, RadialOption},
+    locale::Locale,
+    net::{ClientMessage, NetId, Network, PeerId, ServerMessage, HOST_PEER},
+    Action, Player, PlayerCatch,
+};
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub struct PaintPlugin;
+
+impl Plugin for PaintPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<StampEvent>()
+            .add_system(stamp_menu)
+            .add_system(apply_stamps.after(stamp_menu));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Emblem {
+    Star,
+    Cross,
+    Grid,
+    Ring,
+    Arrow,
+    Bolt,
+}
+
+impl Emblem {
+    pub const ALL: [Emblem; 6] = [
+        Emblem::Star,
+        Emblem::Cross,
+        Emblem::Grid,
+        Emblem::Ring,
+        Emblem::Arrow,
+        Emblem::Bolt,
+    ];
+
+    /// Glyph of the pixel font drawn for the emblem.
+    pub fn icon(self) -> char {
+        match self {
+            Emblem::Star => '*',
+            Emblem::Cross => '+',
+            Emblem::Grid => '#',
+            Emblem::Ring => 'O',
+            Emblem::Arrow => '>',
+            Emblem::Bolt => '!',
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            Emblem::Star => Color::rgb(1.0, 0.8, 0.2),
+            Emblem::Cross => Color::rgb(0.9, 0.25, 0.25),
+            Emblem::Grid => Color::rgb(0.3, 0.5, 1.0),
+            Emblem::Ring => Color::rgb(0.3, 0.9, 0.4),
+            Emblem::Arrow => Color::rgb(0.8, 0.4, 1.0),
+            Emblem::Bolt => Color::rgb(1.0, 0.55, 0.15),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Emblem::Star => "emblem-star",
+            Emblem::Cross => "emblem-cross",
+            Emblem::Grid => "emblem-grid",
+            Emblem::Ring => "emblem-ring",
+            Emblem::Arrow => "emblem-arrow",
+            Emblem::Bolt => "emblem-bolt",
+        }
+    }
+}
+
+/// Who stamped an object last, and with what.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct Stamp {
+    pub emblem: Emblem,
+    pub owner: PeerId,
+}
+
+/// A stamp put on the object with `object` as its network id, locally or by another peer.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct StampEvent {
+    pub peer: PeerId,
+    pub object: NetId,
+    pub emblem: Emblem,
+}
+
+/// Opens the emblems while Stamp is held with something caught, and stamps the pick on release.
+fn stamp_menu(
+    locale: Res<Locale>,
+    network: Res<Network>,
+    mut menu: ResMut<RadialMenu>,
+    mut events: EventWriter<StampEvent>,
+    players: Query<(Entity, &ActionState<Action>, &PlayerCatch), With<Player>>,
+    objects: Query<(&Held, &NetId)>,
+) {
+    let (player, action_state, catch) = match players.get_single() {
+        Ok(player) => player,
+        Err(_) => return,
+    };
+    let held = catch
+        .target
+        .and_then(|target| objects.get(target).ok())
+        .filter(|(held, _)| held.holder == player);
+
+    if action_state.just_pressed(Action::Stamp) && held.is_some() && !menu.is_open() {
+        let options = Emblem::ALL
+            .iter()
+            .map(|emblem| RadialOption {
+                icon: emblem.icon(),
+                color: emblem.color(),
+                label: locale.get(emblem.name()),
+            })
+            .collect();
+        menu.open(RadialKind::Stamp, options);
+    }
+    if !menu.is_open_for(RadialKind::Stamp) {
+        return;
+    }
+    // Dropping the object cancels the stamp.
+    let id = match held {
+        Some((_, id)) => *id,
+        None => {
+            menu.close();
+            return;
+        }
+    };
+    if !action_state.just_released(Action::Stamp) {
+        return;
+    }
+    let emblem = match menu.close().and_then(|index| Emblem::ALL.get(index)) {
+        Some(emblem) => *emblem,
+        None => return,
+    };
+
+    let event = StampEvent {
+        peer: network.local_peer().unwrap_or(HOST_PEER),
+        object: id,
+        emblem,
+    };
+    network.send_to_host(&ClientMessage::Stamp(id, emblem));
+    network.broadcast(&ServerMessage::Stamp(event), None);
+    events.send(event);
+}
+
+/// Paints stamped objects, whoever stamped them.
+fn apply_stamps(
+    mut commands: Commands,
+    mut events: EventReader<StampEvent>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    objects: Query<(Entity, &NetId, &Handle<StandardMaterial>)>,
+) {
+    for event in events.iter() {
+        let (entity, material) = match objects.iter().find(|(_, id, _)| **id == event.object) {
+            Some((entity, _, material)) => (entity, material),
+            None => continue,
+        };
+        // Objects may share their material; stamped ones get their own.
+        let mut painted = materials.get(material).cloned().unwrap_or_default();
+        let color = event.emblem.color();
+        painted.base_color = color;
+        painted.emissive = color * 0.3;
+        commands
+            .entity(entity)
+            .insert(materials.add(painted))
+            .insert(Stamp {
+                emblem: event.emblem,
+                owner: event.peer,
+            });
+    }
+}
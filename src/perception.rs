@@ -0,0 +1,233 @@
+//! What AI agents notice.
+//!
+//! Agents see targets inside a view cone when a ray from their eyes reaches them, and hear
+//! [`NoiseEvent`]s from loud impacts and fast running. Both fill an [`Awareness`] meter that drains
+//! over time; agent behaviors read the meter instead of peeking at every target in range.
+
+use crate::{net::RemotePlayer, CatchObject, Player};
+use bevy::{prelude::*, utils::HashMap};
+use bevy_rapier3d::prelude::*;
+
+const EYE_HEIGHT: f32 = 1.5;
+/// Aim rays at the chest of a target rather than its feet.
+const TARGET_HEIGHT: f32 = 1.0;
+
+/// Objects slowing down this much in one frame made a loud impact.
+const IMPACT_SPEED: f32 = 8.0;
+const IMPACT_LOUDNESS: f32 = 1.5;
+/// Players moving faster than this are heard.
+const RUN_SPEED: f32 = 6.0;
+const RUN_LOUDNESS: f32 = 10.0;
+const FOOTSTEP_INTERVAL: f32 = 0.4;
+
+/// Awareness gained per second when a target is seen up close.
+const SIGHT_GAIN: f32 = 2.0;
+const NOISE_GAIN: f32 = 0.5;
+const AWARENESS_DECAY: f32 = 0.15;
+const SUSPICIOUS_LEVEL: f32 = 0.3;
+/// Alerted agents calm down only once awareness drops below this.
+const CALM_LEVEL: f32 = 0.5;
+
+pub struct PerceptionPlugin;
+
+impl Plugin for PerceptionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<NoiseEvent>()
+            .add_system(impact_noise)
+            .add_system(footstep_noise)
+            .add_system(perceive.after(impact_noise).after(footstep_noise));
+    }
+}
+
+/// A sound that agents within `radius` can hear.
+#[derive(Debug, Clone, Copy)]
+pub struct NoiseEvent {
+    pub position: Vec3,
+    pub radius: f32,
+    /// The player that made it, if any.
+    pub source: Option<Entity>,
+}
+
+/// Senses of an agent.
+#[derive(Debug, Clone, Component)]
+pub struct Perception {
+    pub view_distance: f32,
+    /// Half angle of the view cone, in radians.
+    pub view_angle: f32,
+    /// Scales the radius of heard noises.
+    pub hearing: f32,
+}
+
+impl Default for Perception {
+    fn default() -> Self {
+        Self {
+            view_distance: 40.0,
+            view_angle: 60.0_f32.to_radians(),
+            hearing: 1.0,
+        }
+    }
+}
+
+/// How sure an agent is that someone is around, from 0 to 1.
+#[derive(Debug, Default, Clone, Component)]
+pub struct Awareness {
+    pub level: f32,
+    /// The target currently in sight, or the source of the last noise.
+    pub target: Option<Entity>,
+    pub last_known: Option<Vec3>,
+    pub in_sight: bool,
+    alerted: bool,
+}
+
+impl Awareness {
+    /// Fully aware of a target; the agent should engage.
+    pub fn is_alerted(&self) -> bool {
+        self.alerted
+    }
+
+    /// Noticed something but isn't sure; the agent should look around `last_known`.
+    pub fn is_suspicious(&self) -> bool {
+        !self.alerted && self.level >= SUSPICIOUS_LEVEL
+    }
+
+    fn raise(&mut self, amount: f32) {
+        self.level = (self.level + amount).min(1.0);
+        if self.level >= 1.0 {
+            self.alerted = true;
+        }
+    }
+
+    fn decay(&mut self, amount: f32) {
+        self.level = (self.level - amount).max(0.0);
+        if self.level < CALM_LEVEL {
+            self.alerted = false;
+        }
+        if self.level <= 0.0 {
+            self.target = None;
+            self.last_known = None;
+        }
+    }
+}
+
+fn impact_noise(
+    mut previous: Local<HashMap<Entity, Vec3>>,
+    mut noises: EventWriter<NoiseEvent>,
+    objects: Query<(Entity, &GlobalTransform, &Velocity), With<CatchObject>>,
+) {
+    let mut current = HashMap::default();
+    for (entity, transform, velocity) in &objects {
+        if let Some(linvel) = previous.get(&entity) {
+            let change = (*linvel - velocity.linvel).length();
+            if change > IMPACT_SPEED {
+                noises.send(NoiseEvent {
+                    position: transform.translation(),
+                    radius: IMPACT_LOUDNESS * change,
+                    source: None,
+                });
+            }
+        }
+        current.insert(entity, velocity.linvel);
+    }
+    *previous = current;
+}
+
+#[allow(clippy::type_complexity)]
+fn footstep_noise(
+    time: Res<Time>,
+    mut timer: Local<Option<Timer>>,
+    mut previous: Local<HashMap<Entity, Vec3>>,
+    mut noises: EventWriter<NoiseEvent>,
+    players: Query<(Entity, &GlobalTransform), Or<(With<Player>, With<RemotePlayer>)>>,
+) {
+    let timer = timer.get_or_insert_with(|| Timer::from_seconds(FOOTSTEP_INTERVAL, true));
+    if !timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let mut current = HashMap::default();
+    for (entity, transform) in &players {
+        let position = transform.translation();
+        if let Some(last) = previous.get(&entity) {
+            let offset = position - *last;
+            let speed = Vec2::new(offset.x, offset.z).length() / FOOTSTEP_INTERVAL;
+            if speed > RUN_SPEED {
+                noises.send(NoiseEvent {
+                    position,
+                    radius: RUN_LOUDNESS,
+                    source: Some(entity),
+                });
+            }
+        }
+        current.insert(entity, position);
+    }
+    *previous = current;
+}
+
+#[allow(clippy::type_complexity)]
+pub fn perceive(
+    time: Res<Time>,
+    rapier_context: Res<RapierContext>,
+    mut noises: EventReader<NoiseEvent>,
+    mut agents: Query<(Entity, &Perception, &mut Awareness, &GlobalTransform)>,
+    targets: Query<(Entity, &GlobalTransform), Or<(With<Player>, With<RemotePlayer>)>>,
+) {
+    let delta = time.delta_seconds();
+    let noises: Vec<_> = noises.iter().copied().collect();
+
+    for (entity, perception, mut awareness, transform) in &mut agents {
+        let eye = transform.translation() + Vec3::Y * EYE_HEIGHT;
+        let forward = transform.forward();
+        let filter = QueryFilter::default()
+            .exclude_rigid_body(entity)
+            .exclude_sensors();
+
+        let visible = targets
+            .iter()
+            .filter_map(|(target, target_transform)| {
+                let point = target_transform.translation() + Vec3::Y * TARGET_HEIGHT;
+                let offset = point - eye;
+                let distance = offset.length();
+                if distance > perception.view_distance
+                    || forward.angle_between(offset) > perception.view_angle
+                {
+                    return None;
+                }
+                // Anything else in the way blocks the view.
+                let blocked = rapier_context
+                    .cast_ray(eye, offset / distance, distance, true, filter)
+                    .map_or(false, |(hit, _)| hit != target);
+                (!blocked).then(|| (target, point, distance))
+            })
+            .min_by(|(_, _, a), (_, _, b)| a.total_cmp(b));
+
+        awareness.in_sight = visible.is_some();
+        if let Some((target, point, distance)) = visible {
+            // Closer targets are noticed faster.
+            let closeness = 1.0 - distance / perception.view_distance;
+            awareness.raise(SIGHT_GAIN * (0.25 + closeness) * delta);
+            awareness.target = Some(target);
+            awareness.last_known = Some(point);
+            continue;
+        }
+
+        let heard = noises
+            .iter()
+            .filter(|noise| noise.source != Some(entity))
+            .filter(|noise| noise.position.distance(eye) < noise.radius * perception.hearing)
+            .min_by(|a, b| {
+                a.position
+                    .distance(eye)
+                    .total_cmp(&b.position.distance(eye))
+            });
+        match heard {
+            Some(noise) => {
+                awareness.raise(NOISE_GAIN);
+                if noise.source.is_some() {
+                    awareness.target = noise.source;
+                }
+                awareness.last_known = Some(noise.position);
+            }
+            None => awareness.decay(AWARENESS_DECAY * delta),
+        }
+    }
+}
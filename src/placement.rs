@@ -0,0 +1,181 @@
+//! Precision placing, for building towers out of cubes.
+//!
+//! Thrown cubes rarely land square on each other. With precision placing on (toggled with P), a
+//! held cube above the floor or another cube shows a ghost where it would go, snapped to the cube
+//! below or to the floor grid. Letting go then sets the cube down there instead of throwing it.
+
+use crate::{
+    input_block::InputBlock, layers::SpawnOnLayerExt, player_catch, CatchObject, Player,
+    PlayerCatch, CUBE_SIZE,
+};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+/// How far below a held cube a surface to place on can be.
+const PLACE_REACH: f32 = 3.0;
+/// Steepest surface normal, as its up component, that a cube can stand on.
+const MIN_UP: f32 = 0.8;
+const SNAP_TIME: f32 = 0.4;
+/// How fast a snapping cube closes the distance, per second.
+const SNAP_RATE: f32 = 12.0;
+
+pub struct PlacementPlugin;
+
+impl Plugin for PlacementPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PrecisionPlace>()
+            .add_startup_system(setup_ghost)
+            .add_system(toggle_precision_place)
+            .add_system(preview_placement.after(player_catch))
+            .add_system(place_on_release.after(preview_placement))
+            .add_system(snap_placed);
+    }
+}
+
+#[derive(Default)]
+pub struct PrecisionPlace {
+    pub enabled: bool,
+    /// The held cube with where it would go if let go now.
+    preview: Option<(Entity, Transform)>,
+}
+
+/// The translucent cube showing the preview.
+#[derive(Component)]
+struct PlacementGhost;
+
+/// Eases a released cube onto its placed transform.
+#[derive(Component)]
+struct Snapping {
+    target: Transform,
+    timer: Timer,
+}
+
+fn setup_ghost(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands
+        .spawn_on_world_layer(PbrBundle {
+            mesh: meshes.add(shape::Cube::new(CUBE_SIZE).into()),
+            material: materials.add(StandardMaterial {
+                base_color: Color::rgba(0.6, 0.8, 1.0, 0.35),
+                alpha_mode: AlphaMode::Blend,
+                unlit: true,
+                ..default()
+            }),
+            visibility: Visibility { is_visible: false },
+            ..default()
+        })
+        .insert(PlacementGhost);
+}
+
+fn toggle_precision_place(
+    keys: Res<Input<KeyCode>>,
+    block: Res<InputBlock>,
+    mut place: ResMut<PrecisionPlace>,
+) {
+    if keys.just_pressed(KeyCode::P) && !block.typing() {
+        place.enabled = !place.enabled;
+        info!("Precision placing: {}", place.enabled);
+    }
+}
+
+/// Finds where the held cube would stand: on top of the cube below, lined up with it, or on the
+/// floor grid.
+fn preview_placement(
+    rapier_context: Res<RapierContext>,
+    mut place: ResMut<PrecisionPlace>,
+    players: Query<(Entity, &PlayerCatch), With<Player>>,
+    objects: Query<&GlobalTransform, With<CatchObject>>,
+    mut ghosts: Query<(&mut Transform, &mut Visibility), With<PlacementGhost>>,
+) {
+    place.preview = None;
+    let held = players
+        .get_single()
+        .ok()
+        .and_then(|(player, catch)| Some((player, catch.target?)))
+        .filter(|_| place.enabled);
+
+    if let Some((player, held)) = held {
+        if let Ok(transform) = objects.get(held) {
+            let filter = QueryFilter::default()
+                .exclude_rigid_body(player)
+                .exclude_rigid_body(held)
+                .exclude_sensors();
+            let hit = rapier_context.cast_ray_and_get_normal(
+                transform.translation(),
+                Vec3::NEG_Y,
+                PLACE_REACH,
+                true,
+                filter,
+            );
+
+            place.preview = hit
+                .filter(|(_, hit)| hit.normal.y >= MIN_UP)
+                .map(|(entity, hit)| match objects.get(entity) {
+                    Ok(below) => {
+                        let (_, rotation, translation) = below.to_scale_rotation_translation();
+                        let translation = translation + rotation * Vec3::Y * CUBE_SIZE;
+                        Transform::from_translation(translation).with_rotation(rotation)
+                    }
+                    Err(_) => {
+                        let snap = |value: f32| (value / CUBE_SIZE).round() * CUBE_SIZE;
+                        let center = hit.point + Vec3::Y * 0.5 * CUBE_SIZE;
+                        Transform::from_xyz(snap(center.x), center.y, snap(center.z))
+                    }
+                })
+                .map(|target| (held, target));
+        }
+    }
+
+    for (mut transform, mut visibility) in &mut ghosts {
+        visibility.is_visible = place.preview.is_some();
+        if let Some((_, target)) = place.preview {
+            *transform = target;
+        }
+    }
+}
+
+/// Sets the cube down at the preview when it is let go, instead of throwing it.
+fn place_on_release(
+    mut commands: Commands,
+    place: Res<PrecisionPlace>,
+    mut previous: Local<Option<(Entity, Transform)>>,
+    players: Query<&PlayerCatch, With<Player>>,
+    mut objects: Query<(&mut ExternalImpulse, &mut Transform), With<CatchObject>>,
+) {
+    let released = players
+        .get_single()
+        .map_or(true, |catch| catch.target.is_none());
+    if let (true, Some((entity, target))) = (released, *previous) {
+        if let Ok((mut impulse, mut transform)) = objects.get_mut(entity) {
+            impulse.impulse = Vec3::ZERO;
+            impulse.torque_impulse = Vec3::ZERO;
+            transform.rotation = target.rotation;
+            commands.entity(entity).insert(Snapping {
+                target,
+                timer: Timer::from_seconds(SNAP_TIME, false),
+            });
+        }
+    }
+    *previous = place.preview;
+}
+
+fn snap_placed(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut cubes: Query<(Entity, &mut Snapping, &mut Transform, &mut Velocity)>,
+) {
+    for (entity, mut snapping, mut transform, mut velocity) in &mut cubes {
+        let offset = snapping.target.translation - transform.translation;
+        velocity.angvel = Vec3::ZERO;
+        if snapping.timer.tick(time.delta()).finished() || offset.length() < 0.01 {
+            *transform = snapping.target;
+            velocity.linvel = Vec3::ZERO;
+            commands.entity(entity).remove::<Snapping>();
+        } else {
+            velocity.linvel = offset * SNAP_RATE;
+        }
+    }
+}
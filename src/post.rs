@@ -0,0 +1,326 @@
+//! The post-process chain between the composited image and the window.
+//!
+//! The composite quad renders into [`COMPOSITE_IMAGE_HANDLE`], at [`POST_SCALE`] times the render
+//! size so effects have pixels to work with between the chunky ones. Each enabled entry of the
+//! [`PostChain`] then takes a pass of its own: a quad with a [`PostMaterial`] reading the image of
+//! the pass before, drawn by a camera into an image of its own. Passes run by priority, lowest
+//! first, and the window shows whichever image came last. Entries can be added, reordered and
+//! switched at runtime; the passes follow on the next frame.
+
+use crate::{
+    accessibility::AccessibilitySettings, hud::composite::WatchedShaders, layers, RENDER_SIZE,
+};
+use bevy::{
+    core_pipeline::clear_color::ClearColorConfig,
+    prelude::*,
+    reflect::TypeUuid,
+    render::{
+        camera::RenderTarget,
+        render_resource::*,
+        texture::{BevyDefault, ImageSampler},
+    },
+    sprite::{Material2d, Material2dPlugin, MaterialMesh2dBundle},
+};
+use bevy_inspector_egui::Inspectable;
+
+pub const COMPOSITE_IMAGE_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Image::TYPE_UUID, 1145141919814);
+/// Composite camera priority; the passes come right after it.
+pub const COMPOSITE_PRIORITY: isize = 1;
+/// Each pass takes a layer of its own.
+pub const MAX_PASSES: usize = layers::POST_PASSES as usize;
+/// Size of the chain's images, relative to the render size.
+pub const POST_SCALE: u32 = 4;
+const POST_SHADER: &str = "shaders/post.wgsl";
+/// Shaders the post shader imports, one per effect.
+const EFFECT_SHADERS: [&str; 4] = [
+    "shaders/palette.wgsl",
+    "shaders/vignette.wgsl",
+    "shaders/chromatic.wgsl",
+    "shaders/crt.wgsl",
+];
+
+pub struct PostPlugin;
+
+impl Plugin for PostPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(Material2dPlugin::<PostMaterial>::default())
+            .init_resource::<PostChain>()
+            .add_startup_system(watch_post_shaders)
+            .add_startup_system(setup_post_chain)
+            .add_system(apply_post_chain);
+    }
+}
+
+/// Numbered as the post shader tells them apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Inspectable)]
+pub enum PostEffect {
+    /// Color correction for colorblind players, from the accessibility settings.
+    Palette,
+    Vignette,
+    ChromaticAberration,
+    Crt,
+}
+
+#[derive(Debug, Clone, PartialEq, Inspectable)]
+pub struct PostEntry {
+    pub effect: PostEffect,
+    /// Lower runs earlier.
+    pub priority: i32,
+    pub enabled: bool,
+    #[inspectable(min = 0.0, max = 1.0)]
+    pub strength: f32,
+}
+
+#[derive(Debug, Clone, Inspectable)]
+pub struct PostChain {
+    pub entries: Vec<PostEntry>,
+}
+
+impl Default for PostChain {
+    fn default() -> Self {
+        let mut chain = Self { entries: vec![] };
+        chain.insert(PostEffect::Palette, 0, 1.0);
+        chain.insert(PostEffect::Vignette, 10, 0.5);
+        chain.insert(PostEffect::ChromaticAberration, 20, 0.5);
+        chain.insert(PostEffect::Crt, 30, 0.5);
+        chain.set_enabled(PostEffect::Vignette, false);
+        chain.set_enabled(PostEffect::ChromaticAberration, false);
+        chain.set_enabled(PostEffect::Crt, false);
+        chain
+    }
+}
+
+impl PostChain {
+    /// Adds `effect`, enabled, or moves it to `priority` if it is already in.
+    pub fn insert(&mut self, effect: PostEffect, priority: i32, strength: f32) {
+        match self.get_mut(effect) {
+            Some(entry) => {
+                entry.priority = priority;
+                entry.strength = strength;
+                entry.enabled = true;
+            }
+            None => self.entries.push(PostEntry {
+                effect,
+                priority,
+                enabled: true,
+                strength,
+            }),
+        }
+    }
+
+    pub fn get_mut(&mut self, effect: PostEffect) -> Option<&mut PostEntry> {
+        self.entries.iter_mut().find(|entry| entry.effect == effect)
+    }
+
+    pub fn set_enabled(&mut self, effect: PostEffect, enabled: bool) {
+        if let Some(entry) = self.get_mut(effect) {
+            entry.enabled = enabled;
+        }
+    }
+
+    /// The enabled entries in the order they run; those past [`MAX_PASSES`] are left out.
+    pub fn passes(&self) -> Vec<&PostEntry> {
+        let mut passes: Vec<_> = self.entries.iter().filter(|entry| entry.enabled).collect();
+        passes.sort_by_key(|entry| entry.priority);
+        passes.truncate(MAX_PASSES);
+        passes
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, ShaderType)]
+pub struct PostParams {
+    pub palette: Mat3,
+    pub effect: u32,
+    pub strength: f32,
+    pub lines: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, AsBindGroup, TypeUuid)]
+#[uuid = "8f4d2c61-0a9e-4b37-b6d1-2e7c9a5f3b18"]
+pub struct PostMaterial {
+    #[uniform(0)]
+    pub params: PostParams,
+    #[texture(1)]
+    #[sampler(2)]
+    pub source: Handle<Image>,
+}
+
+impl Material2d for PostMaterial {
+    fn fragment_shader() -> ShaderRef {
+        POST_SHADER.into()
+    }
+}
+
+/// The cameras and materials of the passes, and what the window shows.
+struct PostPasses {
+    passes: Vec<(Entity, Handle<PostMaterial>, Handle<Image>)>,
+    display: Handle<ColorMaterial>,
+}
+
+/// Size of the chain's images, in pixels.
+pub fn post_size() -> Vec2 {
+    Vec2::new(
+        (RENDER_SIZE[0] * POST_SCALE) as f32,
+        (RENDER_SIZE[1] * POST_SCALE) as f32,
+    )
+}
+
+fn post_image() -> Image {
+    let size = Extent3d {
+        width: RENDER_SIZE[0] * POST_SCALE,
+        height: RENDER_SIZE[1] * POST_SCALE,
+        ..default()
+    };
+    let mut image = Image {
+        texture_descriptor: TextureDescriptor {
+            label: None,
+            size,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::bevy_default(),
+            mip_level_count: 1,
+            sample_count: 1,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::RENDER_ATTACHMENT,
+        },
+        sampler_descriptor: ImageSampler::Descriptor(SamplerDescriptor {
+            mag_filter: FilterMode::Nearest,
+            min_filter: FilterMode::Nearest,
+            mipmap_filter: FilterMode::Nearest,
+            ..default()
+        }),
+        ..default()
+    };
+    image.resize(size);
+    image
+}
+
+fn watch_post_shaders(asset_server: Res<AssetServer>, mut shaders: ResMut<WatchedShaders>) {
+    let paths = std::iter::once(POST_SHADER).chain(EFFECT_SHADERS);
+    shaders
+        .0
+        .extend(paths.map(|path| asset_server.load::<Shader, _>(path)));
+}
+
+fn setup_post_chain(
+    mut commands: Commands,
+    windows: Res<Windows>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut images: ResMut<Assets<Image>>,
+    mut post_materials: ResMut<Assets<PostMaterial>>,
+    mut color_materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let composite = images.set(COMPOSITE_IMAGE_HANDLE, post_image());
+
+    // Without a window there is nothing to show the chain on.
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+
+    let quad = meshes.add(shape::Quad::new(post_size()).into());
+    let mut passes = vec![];
+    for index in 0..MAX_PASSES {
+        let image = images.add(post_image());
+        let material = post_materials.add(PostMaterial {
+            params: default(),
+            source: composite.clone(),
+        });
+        let layer = layers::post_pass(index);
+        commands
+            .spawn_bundle(MaterialMesh2dBundle {
+                mesh: quad.clone().into(),
+                material: material.clone(),
+                ..default()
+            })
+            .insert(layer);
+        let camera = commands
+            .spawn_bundle(Camera2dBundle {
+                camera: Camera {
+                    priority: COMPOSITE_PRIORITY + 1 + index as isize,
+                    target: RenderTarget::Image(image.clone()),
+                    is_active: false,
+                    ..default()
+                },
+                camera_2d: Camera2d {
+                    clear_color: ClearColorConfig::None,
+                },
+                ..default()
+            })
+            .insert(layer)
+            .id();
+        passes.push((camera, material, image));
+    }
+
+    let display = color_materials.add(ColorMaterial::from(composite));
+    commands.spawn_bundle(MaterialMesh2dBundle {
+        mesh: meshes
+            .add(shape::Quad::new(Vec2::new(window.width(), window.height())).into())
+            .into(),
+        material: display.clone(),
+        ..default()
+    });
+    commands.spawn_bundle(Camera2dBundle {
+        camera: Camera {
+            priority: COMPOSITE_PRIORITY + 1 + MAX_PASSES as isize,
+            ..default()
+        },
+        ..default()
+    });
+
+    commands.insert_resource(PostPasses { passes, display });
+}
+
+/// Hands each enabled entry a pass reading the one before, and shows the last on the window.
+fn apply_post_chain(
+    chain: Res<PostChain>,
+    settings: Res<AccessibilitySettings>,
+    passes: Option<Res<PostPasses>>,
+    mut post_materials: ResMut<Assets<PostMaterial>>,
+    mut color_materials: ResMut<Assets<ColorMaterial>>,
+    mut cameras: Query<&mut Camera>,
+) {
+    let passes = match passes {
+        Some(passes) => passes,
+        None => return,
+    };
+    if !chain.is_changed() && !settings.is_changed() && !passes.is_added() {
+        return;
+    }
+
+    let entries = chain.passes();
+    let mut source = COMPOSITE_IMAGE_HANDLE.typed();
+    for (index, (camera, material, image)) in passes.passes.iter().enumerate() {
+        let entry = entries.get(index);
+        if let Ok(mut camera) = cameras.get_mut(*camera) {
+            camera.is_active = entry.is_some();
+        }
+        let entry = match entry {
+            Some(entry) => entry,
+            None => continue,
+        };
+        let params = PostParams {
+            palette: settings.palette.correction(),
+            effect: entry.effect as u32,
+            strength: entry.strength,
+            lines: RENDER_SIZE[1] as f32,
+        };
+        let changed = post_materials.get(material).map_or(false, |material| {
+            material.params != params || material.source != source
+        });
+        if changed {
+            if let Some(material) = post_materials.get_mut(material) {
+                material.params = params;
+                material.source = source.clone();
+            }
+        }
+        source = image.clone();
+    }
+
+    if let Some(display) = color_materials.get_mut(&passes.display) {
+        if display.texture.as_ref() != Some(&source) {
+            display.texture = Some(source);
+        }
+    }
+}
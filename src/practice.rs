@@ -0,0 +1,167 @@
+//! Throw metrics for the practice range.
+//!
+//! In a level with [`RangeTarget`] rings, every throw of the local player is followed until the
+//! object comes to rest: how far it landed from where it was let go, how high it rose, and how
+//! close to the center it passed through any ring. The numbers float up where they happened, and
+//! records go into the [`Profile`] stats.
+
+use crate::{
+    hud::popup::PopupEvent, locale::Locale, profile::Profile, CatchObject, Player, PlayerCatch,
+};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+/// A throw is over once the object is slower than this, in meters per second.
+const REST_SPEED: f32 = 1.0;
+/// Longest a throw is followed, in seconds.
+const MAX_FLIGHT: f32 = 8.0;
+/// Grace period before a throw can count as at rest, in seconds.
+const MIN_FLIGHT: f32 = 0.3;
+const RESULT_COLOR: Color = Color::rgb(0.6, 0.9, 1.0);
+const BEST_COLOR: Color = Color::rgb(1.0, 0.85, 0.2);
+
+pub struct PracticePlugin;
+
+impl Plugin for PracticePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(track_throws)
+            .add_system(measure_throws.after(track_throws));
+    }
+}
+
+/// A ring to throw through, with its hole along its local y axis.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct RangeTarget {
+    pub radius: f32,
+}
+
+/// How far from the center of the ring placed at `ring` something moving from `from` to `to` passes
+/// through the plane of the ring, if it does.
+pub fn ring_crossing(ring: &GlobalTransform, from: Vec3, to: Vec3) -> Option<f32> {
+    let center = ring.translation();
+    let normal = ring.up();
+    let before = (from - center).dot(normal);
+    let after = (to - center).dot(normal);
+    if before * after > 0.0 || before == after {
+        return None;
+    }
+    let crossing = from.lerp(to, before / (before - after));
+    Some(crossing.distance(center))
+}
+
+/// A throw being measured.
+#[derive(Debug, Component)]
+struct Throw {
+    origin: Vec3,
+    last: Vec3,
+    height: f32,
+    /// Best pass through a ring, as a share of its radius from the rim to the center.
+    accuracy: Option<f32>,
+    flight: f32,
+}
+
+/// Starts measuring whatever the local player lets go of, if there are targets around.
+fn track_throws(
+    mut commands: Commands,
+    mut holding: Local<Option<Entity>>,
+    targets: Query<(), With<RangeTarget>>,
+    players: Query<&PlayerCatch, With<Player>>,
+    objects: Query<&GlobalTransform, With<CatchObject>>,
+) {
+    let catch = match players.get_single() {
+        Ok(catch) => catch,
+        Err(_) => return,
+    };
+    if let (Some(object), None) = (*holding, catch.target) {
+        if let (false, Ok(transform)) = (targets.is_empty(), objects.get(object)) {
+            let origin = transform.translation();
+            commands.entity(object).insert(Throw {
+                origin,
+                last: origin,
+                height: 0.0,
+                accuracy: None,
+                flight: 0.0,
+            });
+        }
+    }
+    // Catching a thrown object again ends its throw without a result.
+    if let Some(object) = catch.target {
+        commands.entity(object).remove::<Throw>();
+    }
+    *holding = catch.target;
+}
+
+fn measure_throws(
+    mut commands: Commands,
+    time: Res<Time>,
+    locale: Res<Locale>,
+    mut profile: ResMut<Profile>,
+    mut popups: EventWriter<PopupEvent>,
+    targets: Query<(&RangeTarget, &GlobalTransform)>,
+    mut throws: Query<(Entity, &mut Throw, &GlobalTransform, &Velocity)>,
+) {
+    for (entity, mut throw, transform, velocity) in &mut throws {
+        let position = transform.translation();
+        throw.flight += time.delta_seconds();
+        throw.height = throw.height.max(position.y - throw.origin.y);
+
+        for (target, target_transform) in &targets {
+            let miss = match ring_crossing(target_transform, throw.last, position) {
+                Some(miss) => miss,
+                None => continue,
+            };
+            if miss < target.radius {
+                let accuracy = 1.0 - miss / target.radius;
+                throw.accuracy = Some(throw.accuracy.map_or(accuracy, |a| a.max(accuracy)));
+                let percent = (accuracy * 100.0).round() as u32;
+                popups.send(
+                    PopupEvent::new(
+                        locale.get_args("practice-accuracy", &[("percent", percent.into())]),
+                        target_transform.translation(),
+                    )
+                    .with_color(RESULT_COLOR),
+                );
+            }
+        }
+        throw.last = position;
+
+        let resting = throw.flight > MIN_FLIGHT && velocity.linvel.length() < REST_SPEED;
+        if !resting && throw.flight < MAX_FLIGHT {
+            continue;
+        }
+        commands.entity(entity).remove::<Throw>();
+
+        let offset = position - throw.origin;
+        let distance = Vec2::new(offset.x, offset.z).length();
+        let stats = &mut profile.stats;
+        let mut best = false;
+        if distance > stats.longest_throw {
+            stats.longest_throw = distance;
+            best = true;
+        }
+        if throw.height > stats.highest_throw {
+            stats.highest_throw = throw.height;
+            best = true;
+        }
+        if let Some(accuracy) = throw.accuracy {
+            if accuracy > stats.best_accuracy {
+                stats.best_accuracy = accuracy;
+                best = true;
+            }
+        }
+
+        let mut text = locale.get_args(
+            "practice-throw",
+            &[
+                ("distance", format!("{:.1}", distance).into()),
+                ("height", format!("{:.1}", throw.height).into()),
+            ],
+        );
+        if best {
+            text += "\n";
+            text += &locale.get("practice-best");
+        }
+        let color = if best { BEST_COLOR } else { RESULT_COLOR };
+        popups.send(PopupEvent::new(text, position + Vec3::Y).with_color(color));
+    }
+}
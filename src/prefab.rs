@@ -0,0 +1,327 @@
+//! Reusable entity assemblies described in RON.
+//!
+//! Every `*.prefab.ron` file under `assets/prefabs` is a [`Prefab`]: a name and a list of
+//! [`PrefabComponent`]s. `commands.spawn_prefab("crate", transform)` spawns one by name, so levels
+//! can place assemblies without repeating their components. Materials are either spelled out or
+//! named from the material library.
+
+use crate::{
+    catch_class::{CatchClass, CatcherUpgrade},
+    layers,
+    level::Goal,
+    loading::LoadingAssets,
+    lod::LodRequest,
+    materials::MaterialLibrary,
+    props::DynamicBodyBundle,
+    surface::SurfaceType,
+    teams::Team,
+    CatchObject,
+};
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    ecs::system::Command,
+    prelude::*,
+    reflect::TypeUuid,
+    utils::BoxedFuture,
+};
+use bevy_rapier3d::prelude::*;
+use serde::Deserialize;
+
+pub const PREFAB_FOLDER: &str = "prefabs";
+
+pub struct PrefabPlugin;
+
+impl Plugin for PrefabPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<Prefab>()
+            .init_asset_loader::<PrefabLoader>()
+            .init_resource::<PrefabLibrary>()
+            .add_startup_system(preload_prefabs);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub enum PrefabShape {
+    Box {
+        size: [f32; 3],
+    },
+    Ball {
+        radius: f32,
+    },
+    /// Upright capsule, `height` from end to end.
+    Capsule {
+        radius: f32,
+        height: f32,
+    },
+    Torus {
+        radius: f32,
+        ring_radius: f32,
+    },
+    Plane {
+        size: f32,
+    },
+}
+
+impl PrefabShape {
+    pub fn mesh(&self) -> Mesh {
+        match *self {
+            PrefabShape::Box { size } => shape::Box::new(size[0], size[1], size[2]).into(),
+            PrefabShape::Ball { radius } => shape::Icosphere {
+                radius,
+                subdivisions: 3,
+            }
+            .into(),
+            PrefabShape::Capsule { radius, height } => shape::Capsule {
+                radius,
+                depth: (height - 2.0 * radius).max(0.0),
+                ..default()
+            }
+            .into(),
+            PrefabShape::Torus {
+                radius,
+                ring_radius,
+            } => shape::Torus {
+                radius,
+                ring_radius,
+                ..default()
+            }
+            .into(),
+            PrefabShape::Plane { size } => shape::Plane { size }.into(),
+        }
+    }
+
+    pub fn collider(&self) -> Collider {
+        match *self {
+            PrefabShape::Box { size } => {
+                Collider::cuboid(0.5 * size[0], 0.5 * size[1], 0.5 * size[2])
+            }
+            PrefabShape::Ball { radius } => Collider::ball(radius),
+            PrefabShape::Capsule { radius, height } => {
+                Collider::capsule_y((0.5 * height - radius).max(0.0), radius)
+            }
+            // A flat disc is close enough for rings, which are mostly sensors.
+            PrefabShape::Torus {
+                radius,
+                ring_radius,
+            } => Collider::cylinder(ring_radius, radius + ring_radius),
+            PrefabShape::Plane { size } => Collider::cuboid(0.5 * size, 0.01, 0.5 * size),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum PrefabBody {
+    Dynamic,
+    Fixed,
+    Kinematic,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub enum PrefabComponent {
+    Mesh(PrefabShape),
+    Material {
+        color: [f32; 3],
+        #[serde(default)]
+        emissive: [f32; 4],
+        #[serde(default = "default_roughness")]
+        roughness: f32,
+    },
+    /// A material from the material library, shared with everything else using it.
+    NamedMaterial(String),
+    Collider(PrefabShape),
+    /// Coarser meshes beyond each of these distances, in meters.
+    Lod(Vec<f32>),
+    RigidBody(PrefabBody),
+    Sensor,
+    Surface(SurfaceType),
+    /// Can be caught and thrown, like the cubes.
+    Catchable,
+    CatchClass(CatchClass),
+    /// Lets the player catch heavy objects once touched.
+    CatcherUpgrade,
+    Goal,
+    /// Belongs to a team; on a goal, the other team scores in it.
+    Team(Team),
+}
+
+fn default_roughness() -> f32 {
+    0.9
+}
+
+#[derive(Debug, Clone, Deserialize, TypeUuid)]
+#[uuid = "0b7d3c9e-2f6a-4e51-8d14-6a9c3e7f2b05"]
+pub struct Prefab {
+    pub name: String,
+    pub components: Vec<PrefabComponent>,
+}
+
+#[derive(Default)]
+pub struct PrefabLoader;
+
+impl AssetLoader for PrefabLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let prefab: Prefab = ron::de::from_bytes(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(prefab));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["prefab.ron"]
+    }
+}
+
+/// Keeps every prefab loaded.
+pub struct PrefabLibrary {
+    pub handles: Vec<Handle<Prefab>>,
+}
+
+impl FromWorld for PrefabLibrary {
+    fn from_world(world: &mut World) -> Self {
+        let asset_server = world.resource::<AssetServer>();
+        let handles = match asset_server.load_folder(PREFAB_FOLDER) {
+            Ok(handles) => handles
+                .into_iter()
+                .map(|handle| handle.typed::<Prefab>())
+                .collect(),
+            Err(err) => {
+                warn!("Failed to load prefabs: {:?}", err);
+                vec![]
+            }
+        };
+        Self { handles }
+    }
+}
+
+fn preload_prefabs(library: Res<PrefabLibrary>, mut loading: ResMut<LoadingAssets>) {
+    for handle in &library.handles {
+        loading.add(handle);
+    }
+}
+
+pub trait SpawnPrefabExt {
+    /// Spawns the prefab called `name`; the entity stays empty if there is no such prefab.
+    fn spawn_prefab(&mut self, name: &str, transform: Transform) -> Entity;
+}
+
+impl SpawnPrefabExt for Commands<'_, '_> {
+    fn spawn_prefab(&mut self, name: &str, transform: Transform) -> Entity {
+        let entity = self
+            .spawn_bundle(SpatialBundle {
+                transform,
+                ..default()
+            })
+            .id();
+        self.add(InsertPrefab {
+            entity,
+            name: name.into(),
+        });
+        entity
+    }
+}
+
+struct InsertPrefab {
+    entity: Entity,
+    name: String,
+}
+
+impl Command for InsertPrefab {
+    fn write(self, world: &mut World) {
+        let components = world
+            .resource::<Assets<Prefab>>()
+            .iter()
+            .find(|(_, prefab)| prefab.name == self.name)
+            .map(|(_, prefab)| prefab.components.clone());
+        let components = match components {
+            Some(components) => components,
+            None => {
+                warn!("No prefab called {}", self.name);
+                return;
+            }
+        };
+
+        for component in components {
+            match component {
+                PrefabComponent::Mesh(shape) => {
+                    let mesh = world.resource_mut::<Assets<Mesh>>().add(shape.mesh());
+                    world
+                        .entity_mut(self.entity)
+                        .insert(mesh)
+                        .insert(layers::WORLD);
+                }
+                PrefabComponent::Material {
+                    color,
+                    emissive,
+                    roughness,
+                } => {
+                    let material =
+                        world
+                            .resource_mut::<Assets<StandardMaterial>>()
+                            .add(StandardMaterial {
+                                base_color: Color::rgb(color[0], color[1], color[2]),
+                                emissive: Color::rgba(
+                                    emissive[0],
+                                    emissive[1],
+                                    emissive[2],
+                                    emissive[3],
+                                ),
+                                perceptual_roughness: roughness,
+                                ..default()
+                            });
+                    world.entity_mut(self.entity).insert(material);
+                }
+                PrefabComponent::NamedMaterial(name) => {
+                    let material =
+                        world.resource_scope(|world, mut library: Mut<MaterialLibrary>| {
+                            library
+                                .get(&name, &mut world.resource_mut::<Assets<StandardMaterial>>())
+                        });
+                    world.entity_mut(self.entity).insert(material);
+                }
+                PrefabComponent::Collider(shape) => {
+                    world.entity_mut(self.entity).insert(shape.collider());
+                }
+                PrefabComponent::Lod(distances) => {
+                    world.entity_mut(self.entity).insert(LodRequest(distances));
+                }
+                PrefabComponent::RigidBody(body) => {
+                    world.entity_mut(self.entity).insert(match body {
+                        PrefabBody::Dynamic => RigidBody::Dynamic,
+                        PrefabBody::Fixed => RigidBody::Fixed,
+                        PrefabBody::Kinematic => RigidBody::KinematicPositionBased,
+                    });
+                }
+                PrefabComponent::Sensor => {
+                    world.entity_mut(self.entity).insert(Sensor);
+                }
+                PrefabComponent::Surface(surface) => {
+                    world.entity_mut(self.entity).insert(surface);
+                }
+                PrefabComponent::Catchable => {
+                    world
+                        .entity_mut(self.entity)
+                        .insert_bundle((DynamicBodyBundle::default(), CatchObject));
+                }
+                PrefabComponent::CatchClass(class) => {
+                    world.entity_mut(self.entity).insert(class);
+                }
+                PrefabComponent::CatcherUpgrade => {
+                    world.entity_mut(self.entity).insert(CatcherUpgrade);
+                }
+                PrefabComponent::Goal => {
+                    world
+                        .entity_mut(self.entity)
+                        .insert_bundle((Goal, ActiveEvents::COLLISION_EVENTS));
+                }
+                PrefabComponent::Team(team) => {
+                    world.entity_mut(self.entity).insert(team);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,42 @@
+//! Sends the [`Presence`] to a running Discord client.
+
+use super::Presence;
+use bevy::prelude::*;
+use discord_rich_presence::{activity::Activity, DiscordIpc, DiscordIpcClient};
+
+/// Application id from the Discord developer portal.
+const DISCORD_APP_ID: &str = "1031520713564180480";
+
+pub struct DiscordPresencePlugin;
+
+impl Plugin for DiscordPresencePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(connect_discord.exclusive_system())
+            .add_system(send_discord_presence);
+    }
+}
+
+fn connect_discord(world: &mut World) {
+    let client = DiscordIpcClient::new(DISCORD_APP_ID).and_then(|mut client| {
+        client.connect()?;
+        Ok(client)
+    });
+    match client {
+        Ok(client) => world.insert_non_send_resource(client),
+        // Discord just isn't running, most of the time.
+        Err(err) => info!("No Discord presence: {}", err),
+    }
+}
+
+fn send_discord_presence(presence: Res<Presence>, client: Option<NonSendMut<DiscordIpcClient>>) {
+    let mut client = match client {
+        Some(client) if presence.is_changed() => client,
+        _ => return,
+    };
+    let activity = Activity::new()
+        .details(&presence.details)
+        .state(&presence.state);
+    if let Err(err) = client.set_activity(activity) {
+        warn!("Failed to update Discord presence: {}", err);
+    }
+}
@@ -0,0 +1,126 @@
+//! What the player is up to, for Discord and Steam friends lists.
+//!
+//! [`Presence`] sums up the current level, game mode and score in two lines of text. It only
+//! changes when one of those does, and the backends behind the `discord` and `steam` features
+//! send it on whenever it changes. Without either feature it is just kept for nobody.
+
+use crate::{
+    ghost::TimeAttack,
+    level::{trigger::GoalReached, CurrentLevel, LevelDef},
+    locale::Locale,
+    match_flow::MatchState,
+    waves::{WaveDirector, WavePhase},
+};
+use bevy::prelude::*;
+use bevy_inspector_egui::Inspectable;
+use fluent_bundle::FluentValue;
+
+#[cfg(feature = "discord")]
+mod discord;
+#[cfg(feature = "steam")]
+mod steam;
+
+pub struct PresencePlugin;
+
+impl Plugin for PresencePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Presence>()
+            .init_resource::<SessionScore>()
+            .add_system(count_score)
+            .add_system(update_presence.after(count_score))
+            .add_system_set(SystemSet::on_enter(MatchState::Countdown).with_system(reset_score));
+
+        #[cfg(feature = "discord")]
+        app.add_plugin(discord::DiscordPresencePlugin);
+        #[cfg(feature = "steam")]
+        app.add_plugin(steam::SteamPresencePlugin);
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum GameMode {
+    #[default]
+    FreePlay,
+    Match,
+    Waves(usize),
+    TimeAttack,
+}
+
+/// The text shown to friends.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Presence {
+    pub level: String,
+    pub mode: GameMode,
+    pub score: u32,
+    /// First line, the level.
+    pub details: String,
+    /// Second line, the mode and score.
+    pub state: String,
+}
+
+/// Goals scored since the last match began.
+#[derive(Debug, Default, Inspectable)]
+pub struct SessionScore(pub u32);
+
+fn count_score(mut score: ResMut<SessionScore>, mut goals: EventReader<GoalReached>) {
+    score.0 += goals.iter().count() as u32;
+}
+
+fn reset_score(mut score: ResMut<SessionScore>) {
+    score.0 = 0;
+}
+
+fn game_mode(state: &MatchState, waves: &WaveDirector, time_attack: &TimeAttack) -> GameMode {
+    if time_attack.running {
+        return GameMode::TimeAttack;
+    }
+    if let WavePhase::Waiting { wave, .. } | WavePhase::Active { wave, .. } = waves.phase {
+        return GameMode::Waves(wave + 1);
+    }
+    match state {
+        MatchState::Countdown | MatchState::InMatch | MatchState::PostMatch => GameMode::Match,
+        MatchState::Lobby => GameMode::FreePlay,
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_presence(
+    current: Res<CurrentLevel>,
+    levels: Res<Assets<LevelDef>>,
+    locale: Res<Locale>,
+    state: Res<State<MatchState>>,
+    waves: Res<WaveDirector>,
+    time_attack: Res<TimeAttack>,
+    score: Res<SessionScore>,
+    mut presence: ResMut<Presence>,
+) {
+    let level = levels
+        .get(&current.handle)
+        .map(|level| level.name.clone())
+        .unwrap_or_default();
+    let mode = game_mode(state.current(), &waves, &time_attack);
+    if presence.level == level
+        && presence.mode == mode
+        && presence.score == score.0
+        && !locale.is_changed()
+    {
+        return;
+    }
+
+    let mode_text = match mode {
+        GameMode::FreePlay => locale.get("presence-free-play"),
+        GameMode::Match => locale.get("presence-match"),
+        GameMode::Waves(wave) => {
+            locale.get_args("presence-waves", &[("wave", FluentValue::from(wave))])
+        }
+        GameMode::TimeAttack => locale.get("presence-time-attack"),
+    };
+    let score_text = locale.get_args("presence-score", &[("goals", FluentValue::from(score.0))]);
+    *presence = Presence {
+        details: locale.translate(&level),
+        state: format!("{} · {}", mode_text, score_text),
+        level,
+        mode,
+        score: score.0,
+    };
+}
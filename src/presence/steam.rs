@@ -0,0 +1,45 @@
+//! Sends the [`Presence`] to Steam friends.
+//!
+//! Steam shows rich presence through the `#Status` localization token of the app, which should
+//! read `{%level%}: {%state%}`.
+
+use super::Presence;
+use bevy::prelude::*;
+use steamworks::{Client, SingleClient};
+
+pub struct SteamPresencePlugin;
+
+impl Plugin for SteamPresencePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(connect_steam.exclusive_system())
+            .add_system(run_steam_callbacks)
+            .add_system(send_steam_presence);
+    }
+}
+
+fn connect_steam(world: &mut World) {
+    match Client::init() {
+        Ok((client, single)) => {
+            world.insert_non_send_resource(client);
+            world.insert_non_send_resource(single);
+        }
+        Err(err) => info!("No Steam presence: {}", err),
+    }
+}
+
+fn run_steam_callbacks(single: Option<NonSend<SingleClient>>) {
+    if let Some(single) = single {
+        single.run_callbacks();
+    }
+}
+
+fn send_steam_presence(presence: Res<Presence>, client: Option<NonSend<Client>>) {
+    let client = match client {
+        Some(client) if presence.is_changed() => client,
+        _ => return,
+    };
+    let friends = client.friends();
+    friends.set_rich_presence("level", Some(&presence.details));
+    friends.set_rich_presence("state", Some(&presence.state));
+    friends.set_rich_presence("steam_display", Some("#Status"));
+}
@@ -0,0 +1,108 @@
+//! The player's save profile, kept in [`PROFILE_FILE`] between sessions.
+//!
+//! The [`Profile`] is read at startup, with a fresh one for a first run or an unreadable file.
+//! Changes are written back every few seconds at most, since [`Stats`] change all the time, and
+//! once more on exit.
+
+use crate::{
+    achievements::Achievement, display::DisplaySettings, progression::Skill, tutorial::TutorialStep,
+};
+use bevy::{app::AppExit, prelude::*};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fs, path::Path};
+
+pub const PROFILE_FILE: &str = "saves/profile.ron";
+const SAVE_INTERVAL: f32 = 5.0;
+
+pub struct ProfilePlugin;
+
+impl Plugin for ProfilePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Profile::load())
+            .add_system(save_profile);
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Profile {
+    /// Shown with the player's scores.
+    pub name: String,
+    /// Currency for skills.
+    pub coins: u32,
+    pub skills: Vec<Skill>,
+    pub stats: Stats,
+    pub achievements: Vec<Achievement>,
+    /// Tutorial steps done.
+    pub tutorial: Vec<TutorialStep>,
+    pub tutorial_skipped: bool,
+    pub display: DisplaySettings,
+}
+
+/// Lifetime statistics.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Stats {
+    pub cubes_thrown: u32,
+    /// Distance the player has moved, in meters.
+    pub distance: f32,
+    pub goals: u32,
+    /// Best time-attack time per level name, in seconds.
+    pub best_times: HashMap<String, f32>,
+    /// Farthest a throw on the practice range landed, in meters.
+    pub longest_throw: f32,
+    /// Highest a throw on the practice range rose above where it was let go, in meters.
+    pub highest_throw: f32,
+    /// Closest pass through a practice ring, from 0 at the rim to 1 dead center.
+    pub best_accuracy: f32,
+}
+
+impl Profile {
+    pub fn load() -> Self {
+        let profile = fs::read_to_string(PROFILE_FILE)
+            .map_err(|err| err.to_string())
+            .and_then(|text| ron::from_str(&text).map_err(|err| err.to_string()));
+        match profile {
+            Ok(profile) => profile,
+            Err(err) => {
+                info!(
+                    "No profile loaded from {}, starting fresh: {}",
+                    PROFILE_FILE, err
+                );
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let text =
+            ron::ser::to_string_pretty(self, Default::default()).map_err(|err| err.to_string())?;
+        if let Some(parent) = Path::new(PROFILE_FILE).parent() {
+            fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+        }
+        fs::write(PROFILE_FILE, text).map_err(|err| err.to_string())
+    }
+
+    pub fn has_skill(&self, skill: Skill) -> bool {
+        self.skills.contains(&skill)
+    }
+}
+
+fn save_profile(
+    time: Res<Time>,
+    profile: Res<Profile>,
+    mut dirty: Local<bool>,
+    mut since_save: Local<f32>,
+    mut exits: EventReader<AppExit>,
+) {
+    *dirty |= profile.is_changed() && !profile.is_added();
+    *since_save += time.delta_seconds();
+    let exiting = exits.iter().count() > 0;
+    if *dirty && (*since_save > SAVE_INTERVAL || exiting) {
+        if let Err(err) = profile.save() {
+            error!("Failed to save profile to {}: {}", PROFILE_FILE, err);
+        }
+        *dirty = false;
+        *since_save = 0.0;
+    }
+}
@@ -0,0 +1,314 @@
+//! Coins earned in play, spent on [`Skill`]s in the skill menu.
+//!
+//! Goals and cleared waves pay out coins into the [`Profile`], and K opens the menu to unlock
+//! skills with them. Some skills need another one first, which makes up the tree. Unlocked skills
+//! are stored in the profile and apply to the player from then on.
+
+use crate::{
+    catch_class::CatchClass, hold::Held, input_block::InputBlock, level::trigger::GoalReached,
+    locale::Locale, player_catch, profile::Profile, pull_impulse, stamina::Stamina, throw_impulse,
+    tuning::Tuning, waves::WaveCleared, Action, CatchObject, Player, PlayerCatch, PlayerCatcher,
+};
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use bevy_rapier3d::prelude::*;
+use leafwing_input_manager::prelude::*;
+use serde::{Deserialize, Serialize};
+
+const GOAL_REWARD: u32 = 10;
+const WAVE_REWARD: u32 = 25;
+
+/// Upwards speed of the second jump.
+const DOUBLE_JUMP_SPEED: f32 = 8.0;
+/// Farthest the ground can be below the player to count as standing on it.
+const GROUND_REACH: f32 = 1.5;
+const LONG_REACH_SCALE: f32 = 1.6;
+const FAST_THROW_SCALE: f32 = 1.3;
+/// Where the second object of a multi-catch is pulled, to the right of the catch point.
+const MULTI_CATCH_OFFSET: f32 = 1.2;
+
+pub struct ProgressionPlugin;
+
+impl Plugin for ProgressionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SkillMenu>()
+            .add_system(earn_coins)
+            .add_system(skill_menu)
+            .add_system(apply_skills)
+            .add_system(double_jump)
+            .add_system(multi_catch.after(player_catch));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Skill {
+    DoubleJump,
+    LongReach,
+    /// Pulls a second object along with the first.
+    MultiCatch,
+    FastThrow,
+}
+
+impl Skill {
+    pub const ALL: [Skill; 4] = [
+        Skill::DoubleJump,
+        Skill::LongReach,
+        Skill::MultiCatch,
+        Skill::FastThrow,
+    ];
+
+    pub fn cost(&self) -> u32 {
+        match self {
+            Skill::DoubleJump => 30,
+            Skill::LongReach => 20,
+            Skill::MultiCatch => 60,
+            Skill::FastThrow => 40,
+        }
+    }
+
+    /// The skill to unlock before this one.
+    pub fn requires(&self) -> Option<Skill> {
+        match self {
+            Skill::MultiCatch => Some(Skill::LongReach),
+            _ => None,
+        }
+    }
+
+    /// Locale key of the name.
+    pub fn key(&self) -> &'static str {
+        match self {
+            Skill::DoubleJump => "skill-double-jump",
+            Skill::LongReach => "skill-long-reach",
+            Skill::MultiCatch => "skill-multi-catch",
+            Skill::FastThrow => "skill-fast-throw",
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct SkillMenu {
+    pub open: bool,
+}
+
+fn earn_coins(
+    mut profile: ResMut<Profile>,
+    mut goals: EventReader<GoalReached>,
+    mut waves: EventReader<WaveCleared>,
+) {
+    let coins =
+        GOAL_REWARD * goals.iter().count() as u32 + WAVE_REWARD * waves.iter().count() as u32;
+    if coins > 0 {
+        profile.coins += coins;
+    }
+}
+
+fn skill_menu(
+    keys: Res<Input<KeyCode>>,
+    block: Res<InputBlock>,
+    locale: Res<Locale>,
+    mut menu: ResMut<SkillMenu>,
+    mut profile: ResMut<Profile>,
+    mut egui_context: ResMut<EguiContext>,
+) {
+    if keys.just_pressed(KeyCode::K) && !block.typing() {
+        menu.open = !menu.open;
+    }
+    if !menu.open {
+        return;
+    }
+
+    let mut unlock = None;
+    egui::Window::new(locale.get("skills"))
+        .collapsible(false)
+        .resizable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.label(locale.get_args("skills-coins", &[("coins", profile.coins.into())]));
+            ui.separator();
+            for skill in Skill::ALL {
+                ui.horizontal(|ui| {
+                    ui.label(locale.get(skill.key()));
+                    if profile.has_skill(skill) {
+                        ui.label(locale.get("skills-unlocked"));
+                        return;
+                    }
+                    let ready = skill
+                        .requires()
+                        .map_or(true, |skill| profile.has_skill(skill));
+                    let affordable = profile.coins >= skill.cost();
+                    let label = locale.get_args("skills-unlock", &[("cost", skill.cost().into())]);
+                    if ui
+                        .add_enabled(ready && affordable, egui::Button::new(label))
+                        .clicked()
+                    {
+                        unlock = Some(skill);
+                    }
+                });
+            }
+        });
+
+    if let Some(skill) = unlock {
+        profile.coins -= skill.cost();
+        profile.skills.push(skill);
+    }
+}
+
+/// Scales the player's catching by the skills in the profile, starting from the defaults.
+fn apply_skills(
+    profile: Res<Profile>,
+    tuning: Res<Tuning>,
+    mut applied: Local<usize>,
+    mut players: Query<(&mut Player, ChangeTrackers<Player>)>,
+) {
+    // Skills are only ever added, so a new count means new skills.
+    let unlocked = profile.skills.len() != *applied;
+    *applied = profile.skills.len();
+    let base = Player::default();
+    for (mut player, tracker) in &mut players {
+        if !unlocked && !tuning.is_changed() && !tracker.is_added() {
+            continue;
+        }
+        player.catch_range = base.catch_range;
+        player.throw_speed = tuning.throw_speed;
+        if profile.has_skill(Skill::LongReach) {
+            player.catch_range *= LONG_REACH_SCALE;
+        }
+        if profile.has_skill(Skill::FastThrow) {
+            player.throw_speed *= FAST_THROW_SCALE;
+        }
+    }
+}
+
+/// One more jump in the air, reset on landing.
+fn double_jump(
+    profile: Res<Profile>,
+    rapier_context: Res<RapierContext>,
+    mut used: Local<bool>,
+    mut players: Query<
+        (
+            Entity,
+            &ActionState<Action>,
+            &GlobalTransform,
+            &mut Velocity,
+        ),
+        With<Player>,
+    >,
+) {
+    if !profile.has_skill(Skill::DoubleJump) {
+        return;
+    }
+    for (entity, action_state, transform, mut velocity) in &mut players {
+        let filter = QueryFilter::default()
+            .exclude_rigid_body(entity)
+            .exclude_sensors();
+        let grounded = rapier_context
+            .cast_ray(
+                transform.translation(),
+                Vec3::NEG_Y,
+                GROUND_REACH,
+                true,
+                filter,
+            )
+            .is_some();
+        if grounded {
+            *used = false;
+        } else if !*used && action_state.just_pressed(Action::Jump) {
+            velocity.linvel.y = DOUBLE_JUMP_SPEED;
+            *used = true;
+        }
+    }
+}
+
+/// Pulls a second object beside the first one, and throws it along when the first is thrown.
+#[allow(clippy::type_complexity)]
+fn multi_catch(
+    profile: Res<Profile>,
+    tuning: Res<Tuning>,
+    mut extra: Local<Option<Entity>>,
+    players: Query<(&Player, &PlayerCatch, Option<&Stamina>)>,
+    catchers: Query<&GlobalTransform, With<PlayerCatcher>>,
+    mut objects: Query<
+        (
+            Entity,
+            &mut ExternalImpulse,
+            &Velocity,
+            &ReadMassProperties,
+            &GlobalTransform,
+            Option<&CatchClass>,
+        ),
+        (With<CatchObject>, Without<Held>),
+    >,
+) {
+    let (player, catch, stamina) = match players.get_single() {
+        Ok(player) => player,
+        Err(_) => return,
+    };
+    let catcher = match catchers.get_single() {
+        Ok(catcher) => catcher,
+        Err(_) => return,
+    };
+    if !profile.has_skill(Skill::MultiCatch) {
+        *extra = None;
+        return;
+    }
+    let point = catcher.translation() + MULTI_CATCH_OFFSET * catcher.right();
+
+    let primary = match catch.target {
+        Some(primary) => primary,
+        None => {
+            // Thrown along with the first, unless that was dropped for lack of stamina.
+            let thrown = !stamina.map_or(false, Stamina::is_exhausted);
+            if let (Some(entity), true) = (extra.take(), thrown) {
+                if let Ok((_, mut impulse, _, mass, transform, class)) = objects.get_mut(entity) {
+                    let class = class.copied().unwrap_or_default();
+                    let delta_position = point - transform.translation();
+                    impulse.impulse = throw_impulse(
+                        delta_position,
+                        catcher.forward(),
+                        mass,
+                        class,
+                        player.throw_speed,
+                        &tuning,
+                    );
+                }
+            }
+            return;
+        }
+    };
+
+    let in_reach = |transform: &GlobalTransform, class: Option<&CatchClass>| {
+        transform.translation().distance(point) <= player.catch_range
+            && class.copied().unwrap_or_default() <= player.max_class
+    };
+    let current = extra
+        .and_then(|entity| objects.get(entity).ok())
+        .filter(|(entity, _, _, _, transform, class)| {
+            *entity != primary && in_reach(transform, *class)
+        })
+        .map(|(entity, ..)| entity);
+    *extra = current.or_else(|| {
+        objects
+            .iter()
+            .filter(|(entity, _, _, _, transform, class)| {
+                *entity != primary && in_reach(transform, *class)
+            })
+            .min_by_key(|(_, _, _, _, transform, _)| {
+                transform.translation().distance_squared(point) as u32
+            })
+            .map(|(entity, ..)| entity)
+    });
+
+    if let Some(Ok((_, mut impulse, velocity, mass, transform, class))) =
+        extra.map(|entity| objects.get_mut(entity))
+    {
+        let class = class.copied().unwrap_or_default();
+        let delta_position = point - transform.translation();
+        impulse.impulse = pull_impulse(
+            delta_position,
+            velocity,
+            mass,
+            class,
+            player.max_catch_speed,
+            &tuning,
+        );
+    }
+}
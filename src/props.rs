@@ -0,0 +1,103 @@
+//! Spawning the physics props every level is built from.
+//!
+//! [`SpawnPropExt`] puts the rigid body, collider and render layer of a prop together in one
+//! call, so a cube spawned by a wave is the same as one placed in a level. Code working on the
+//! `World` directly, like prefabs and ropes, gets the body part from [`DynamicBodyBundle`].
+
+use crate::{layers::SpawnOnLayerExt, merge::Mergeable, CatchObject, CUBE_SIZE};
+use bevy::{ecs::system::EntityCommands, prelude::*};
+use bevy_rapier3d::prelude::*;
+
+/// A body that moves under physics and can be pushed around.
+#[derive(Bundle)]
+pub struct DynamicBodyBundle {
+    pub rigid_body: RigidBody,
+    pub mass: ReadMassProperties,
+    pub velocity: Velocity,
+    pub impulse: ExternalImpulse,
+    /// Thrown props are fast enough to tunnel through walls without it.
+    pub ccd: Ccd,
+}
+
+impl Default for DynamicBodyBundle {
+    fn default() -> Self {
+        Self {
+            rigid_body: RigidBody::Dynamic,
+            mass: default(),
+            velocity: default(),
+            impulse: default(),
+            ccd: Ccd::enabled(),
+        }
+    }
+}
+
+pub trait SpawnPropExt<'w, 's>: SpawnOnLayerExt<'w, 's> {
+    /// A dynamic body with `collider`, drawn as `mesh`.
+    fn spawn_prop<'a>(
+        &'a mut self,
+        mesh: Handle<Mesh>,
+        material: Handle<StandardMaterial>,
+        collider: Collider,
+        transform: Transform,
+    ) -> EntityCommands<'w, 's, 'a> {
+        let mut entity = self.spawn_on_world_layer(PbrBundle {
+            mesh,
+            material,
+            transform,
+            ..default()
+        });
+        entity.insert_bundle((DynamicBodyBundle::default(), collider));
+        entity
+    }
+
+    /// A catchable cube drawn with the `preset` material, which merges with others.
+    fn spawn_catch_cube<'a>(
+        &'a mut self,
+        mesh: Handle<Mesh>,
+        material: Handle<StandardMaterial>,
+        preset: &str,
+        transform: Transform,
+    ) -> EntityCommands<'w, 's, 'a> {
+        let half = 0.5 * CUBE_SIZE;
+        let mut entity = self.spawn_prop(
+            mesh,
+            material,
+            Collider::cuboid(half, half, half),
+            transform,
+        );
+        entity.insert_bundle((
+            ActiveEvents::COLLISION_EVENTS,
+            CatchObject,
+            Mergeable::new(preset),
+        ));
+        entity
+    }
+
+    /// A fixed collider, drawn as `mesh` at `offset` from it.
+    fn spawn_static_wall<'a>(
+        &'a mut self,
+        mesh: Handle<Mesh>,
+        material: Handle<StandardMaterial>,
+        collider: Collider,
+        transform: Transform,
+        offset: Vec3,
+    ) -> EntityCommands<'w, 's, 'a> {
+        let mut entity = self.spawn_on_world_layer(SpatialBundle {
+            transform,
+            ..default()
+        });
+        entity.insert(collider).with_children(|parent| {
+            parent.spawn_on_world_layer(PbrBundle {
+                mesh,
+                material,
+                transform: Transform::from_translation(offset),
+                ..default()
+            });
+        });
+        entity
+    }
+}
+
+impl<'w, 's> SpawnPropExt<'w, 's> for Commands<'w, 's> {}
+
+impl<'w, 's> SpawnPropExt<'w, 's> for ChildBuilder<'w, 's, '_> {}
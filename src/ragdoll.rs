@@ -0,0 +1,284 @@
+//! A floppy body for the player while knocked out.
+//!
+//! On a [`DeathEvent`] the player's pose is copied onto a ragdoll of capsules held together with
+//! spherical joints, which takes over the player's velocity and falls where it was hit. The camera
+//! leaves the player's eyes and follows the torso from behind, while not spectating. On the [`RespawnEvent`] the parts
+//! stop simulating and fly back together at the spawn point, then make way for the player again.
+
+use crate::{
+    accessibility::AccessibilitySettings,
+    camera::smoothing,
+    death::{DeathEvent, RespawnEvent},
+    layers::SpawnOnLayerExt,
+    spectate::Spectate,
+    PlayerCamera,
+};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+/// Time constant of the camera following the torso, in seconds.
+const FOLLOW_SMOOTHING: f32 = 0.15;
+/// Where the camera looks at the torso from, relative to the player facing when it died.
+const FOLLOW_OFFSET: Vec3 = Vec3::new(0.0, 2.5, 4.0);
+/// Time for the parts to fly back together, in seconds.
+const REASSEMBLE_TIME: f32 = 0.5;
+
+pub struct RagdollPlugin;
+
+impl Plugin for RagdollPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(spawn_ragdolls)
+            .add_system(follow_torso.after(spawn_ragdolls))
+            .add_system(reassemble_ragdolls)
+            .add_system(update_reassembly.after(reassemble_ragdolls));
+    }
+}
+
+/// One capsule of the ragdoll, relative to the player's origin.
+struct Part {
+    center: Vec3,
+    half_height: f32,
+    radius: f32,
+    /// Index of the part it hangs from, and the joint between them relative to the player.
+    joint: Option<(usize, Vec3)>,
+}
+
+const TORSO: usize = 0;
+
+fn parts() -> [Part; 6] {
+    [
+        Part {
+            center: Vec3::new(0.0, 0.1, 0.0),
+            half_height: 0.25,
+            radius: 0.2,
+            joint: None,
+        },
+        // Head
+        Part {
+            center: Vec3::new(0.0, 0.75, 0.0),
+            half_height: 0.0,
+            radius: 0.18,
+            joint: Some((TORSO, Vec3::new(0.0, 0.55, 0.0))),
+        },
+        // Arms
+        Part {
+            center: Vec3::new(-0.35, 0.15, 0.0),
+            half_height: 0.2,
+            radius: 0.08,
+            joint: Some((TORSO, Vec3::new(-0.3, 0.45, 0.0))),
+        },
+        Part {
+            center: Vec3::new(0.35, 0.15, 0.0),
+            half_height: 0.2,
+            radius: 0.08,
+            joint: Some((TORSO, Vec3::new(0.3, 0.45, 0.0))),
+        },
+        // Legs
+        Part {
+            center: Vec3::new(-0.12, -0.65, 0.0),
+            half_height: 0.25,
+            radius: 0.1,
+            joint: Some((TORSO, Vec3::new(-0.12, -0.35, 0.0))),
+        },
+        Part {
+            center: Vec3::new(0.12, -0.65, 0.0),
+            half_height: 0.25,
+            radius: 0.1,
+            joint: Some((TORSO, Vec3::new(0.12, -0.35, 0.0))),
+        },
+    ]
+}
+
+#[derive(Debug, Component)]
+pub struct Ragdoll {
+    pub player: Entity,
+    pub torso: Entity,
+    /// Where the camera sits relative to the torso.
+    view_offset: Vec3,
+}
+
+/// A part of a ragdoll, with where it sits on the player.
+#[derive(Debug, Component)]
+struct RagdollPart {
+    local: Transform,
+}
+
+/// Parts flying back onto the player.
+#[derive(Debug, Component)]
+struct Reassembling {
+    timer: Timer,
+    from: Vec<(Entity, Transform)>,
+}
+
+fn spawn_ragdolls(
+    mut commands: Commands,
+    settings: Res<AccessibilitySettings>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut deaths: EventReader<DeathEvent>,
+    players: Query<(&GlobalTransform, Option<&Velocity>)>,
+) {
+    for event in deaths.iter() {
+        let (transform, velocity) = match players.get(event.player) {
+            Ok(player) => player,
+            Err(_) => continue,
+        };
+        let transform = transform.compute_transform();
+        let velocity = velocity.copied().unwrap_or_default();
+        let material = materials.add(StandardMaterial {
+            base_color: settings.palette().player,
+            perceptual_roughness: 0.8,
+            ..default()
+        });
+
+        let ragdoll = commands.spawn_bundle(SpatialBundle::default()).id();
+        let mut entities: Vec<Entity> = vec![];
+        for part in parts() {
+            let local = Transform::from_translation(part.center);
+            let mesh = meshes.add(
+                shape::Capsule {
+                    radius: part.radius,
+                    depth: 2.0 * part.half_height,
+                    ..default()
+                }
+                .into(),
+            );
+            let mut entity = commands.spawn_on_world_layer(PbrBundle {
+                mesh,
+                material: material.clone(),
+                transform: transform * local,
+                ..default()
+            });
+            entity.insert_bundle((
+                RigidBody::Dynamic,
+                Collider::capsule_y(part.half_height, part.radius),
+                velocity,
+                RagdollPart { local },
+            ));
+            if let Some((parent, anchor)) = part.joint {
+                let parent_center = parts()[parent].center;
+                let mut joint: GenericJoint = SphericalJointBuilder::new()
+                    .local_anchor1(anchor - parent_center)
+                    .local_anchor2(anchor - part.center)
+                    .into();
+                joint.set_contacts_enabled(false);
+                entity.insert(ImpulseJoint::new(entities[parent], joint));
+            }
+            entities.push(entity.id());
+        }
+
+        commands
+            .entity(ragdoll)
+            .insert(Ragdoll {
+                player: event.player,
+                torso: entities[TORSO],
+                view_offset: transform.rotation * FOLLOW_OFFSET,
+            })
+            .push_children(&entities);
+    }
+}
+
+/// Keeps the camera of a knocked out player on their torso, unless they are watching something
+/// else.
+pub fn follow_torso(
+    time: Res<Time>,
+    spectate: Res<Spectate>,
+    ragdolls: Query<&Ragdoll, Without<Reassembling>>,
+    transforms: Query<&GlobalTransform, Without<PlayerCamera>>,
+    mut cameras: Query<(&Parent, &mut Transform), With<PlayerCamera>>,
+) {
+    if spectate.target.is_some() {
+        return;
+    }
+    let blend = smoothing(FOLLOW_SMOOTHING, time.delta_seconds());
+    for ragdoll in &ragdolls {
+        let (player, torso) = match (
+            transforms.get(ragdoll.player),
+            transforms.get(ragdoll.torso),
+        ) {
+            (Ok(player), Ok(torso)) => (player, torso.translation()),
+            _ => continue,
+        };
+        for (parent, mut camera) in &mut cameras {
+            if parent.get() != ragdoll.player {
+                continue;
+            }
+            // The player body stands still while knocked out; place the camera relative to it.
+            let target =
+                Transform::from_translation(torso + ragdoll.view_offset).looking_at(torso, Vec3::Y);
+            let target =
+                Transform::from_matrix(player.compute_matrix().inverse() * target.compute_matrix());
+            camera.translation = camera.translation.lerp(target.translation, blend);
+            camera.rotation = camera.rotation.slerp(target.rotation, blend);
+        }
+    }
+}
+
+/// Pins the parts of respawned players' ragdolls, to bring them back together.
+fn reassemble_ragdolls(
+    mut commands: Commands,
+    mut respawns: EventReader<RespawnEvent>,
+    ragdolls: Query<(Entity, &Ragdoll, &Children)>,
+    parts: Query<&Transform, With<RagdollPart>>,
+    mut cameras: Query<(&Parent, &mut Transform), (With<PlayerCamera>, Without<RagdollPart>)>,
+) {
+    for event in respawns.iter() {
+        for (parent, mut camera) in &mut cameras {
+            if parent.get() == event.player {
+                *camera = Transform::IDENTITY;
+            }
+        }
+        for (entity, _, children) in ragdolls
+            .iter()
+            .filter(|(_, ragdoll, _)| ragdoll.player == event.player)
+        {
+            let from = children
+                .iter()
+                .filter_map(|&part| Some((part, *parts.get(part).ok()?)))
+                .collect();
+            for &part in children.iter() {
+                commands
+                    .entity(part)
+                    .remove::<ImpulseJoint>()
+                    .insert(RigidBody::KinematicPositionBased)
+                    .insert(CollisionGroups::new(0, 0));
+            }
+            commands.entity(entity).insert(Reassembling {
+                timer: Timer::from_seconds(REASSEMBLE_TIME, false),
+                from,
+            });
+        }
+    }
+}
+
+/// Moves the parts onto the player, and removes the ragdoll once they are there.
+fn update_reassembly(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut ragdolls: Query<(Entity, &Ragdoll, &mut Reassembling)>,
+    players: Query<&GlobalTransform>,
+    mut parts: Query<(&RagdollPart, &mut Transform)>,
+) {
+    for (entity, ragdoll, mut reassembling) in &mut ragdolls {
+        let player = match players.get(ragdoll.player) {
+            Ok(player) => player.compute_transform(),
+            Err(_) => {
+                commands.entity(entity).despawn_recursive();
+                continue;
+            }
+        };
+        if reassembling.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        // Ease out, so the parts snap together at the end.
+        let t = 1.0 - (1.0 - reassembling.timer.percent()).powi(3);
+        for (part, from) in &reassembling.from {
+            if let Ok((local, mut transform)) = parts.get_mut(*part) {
+                let target = player * local.local;
+                transform.translation = from.translation.lerp(target.translation, t);
+                transform.rotation = from.rotation.slerp(target.rotation, t);
+            }
+        }
+    }
+}
@@ -0,0 +1,88 @@
+//! The alt-fire of the catcher: a blast that shoves everything in front of it away.
+//!
+//! Where catching pulls one object in, repelling pushes every dynamic body within a cone in front
+//! of the catcher, harder the closer it is. It then needs [`COOLDOWN`] seconds to charge again.
+//! Which way the blast pushes depends on the [`ActiveAbility`].
+
+use crate::{
+    ability::{Ability, ActiveAbility},
+    feedback::FeedbackEvent,
+    Action, Player, PlayerCatcher,
+};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use leafwing_input_manager::prelude::*;
+
+const COOLDOWN: f32 = 1.0;
+const RANGE: f32 = 8.0;
+/// Half angle of the blast cone, in degrees.
+const CONE_ANGLE: f32 = 50.0;
+/// Velocity given to a body right at the catcher, in meters per second.
+const BLAST_SPEED: f32 = 20.0;
+/// Bodies are also thrown a bit upwards, so they clear the floor.
+const LIFT: f32 = 0.3;
+
+pub struct RepelPlugin;
+
+impl Plugin for RepelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(repel_blast);
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn repel_blast(
+    time: Res<Time>,
+    ability: Res<ActiveAbility>,
+    mut cooldown: Local<f32>,
+    players: Query<&ActionState<Action>, With<Player>>,
+    catchers: Query<&GlobalTransform, With<PlayerCatcher>>,
+    mut bodies: Query<(
+        &RigidBody,
+        &GlobalTransform,
+        &ReadMassProperties,
+        &mut ExternalImpulse,
+    )>,
+    mut feedback: EventWriter<FeedbackEvent>,
+) {
+    *cooldown = (*cooldown - time.delta_seconds()).max(0.0);
+    let pressed = players.get_single().map_or(false, |action_state| {
+        action_state.just_pressed(Action::Repel)
+    });
+    if !pressed || *cooldown > 0.0 {
+        return;
+    }
+    let catcher = match catchers.get_single() {
+        Ok(catcher) => catcher,
+        Err(_) => return,
+    };
+    *cooldown = COOLDOWN;
+
+    let origin = catcher.translation();
+    let forward = catcher.forward();
+    let min_cos = CONE_ANGLE.to_radians().cos();
+    for (body, transform, mass, mut impulse) in &mut bodies {
+        if *body != RigidBody::Dynamic {
+            continue;
+        }
+        let offset = transform.translation() - origin;
+        let distance = offset.length();
+        let direction = offset.normalize_or_zero();
+        if distance > RANGE || direction.dot(forward) < min_cos {
+            continue;
+        }
+        let falloff = 1.0 - distance / RANGE;
+        let push = match ability.0 {
+            Ability::Repel => (direction + LIFT * Vec3::Y).normalize(),
+            Ability::Attract => (LIFT * Vec3::Y - direction).normalize(),
+            Ability::Lift => Vec3::Y,
+        };
+        impulse.impulse += push * BLAST_SPEED * falloff * mass.0.mass;
+    }
+
+    feedback.send(FeedbackEvent {
+        trauma: 0.4,
+        rumble: 0.5,
+        ..default()
+    });
+}
@@ -0,0 +1,373 @@
+use crate::{Action, CatchObject, Player, PlayerCamera};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use leafwing_input_manager::{
+    action_state::ActionData, axislike::DualAxisData, buttonlike::ButtonState,
+    plugin::InputManagerSystem, prelude::*,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+pub const REPLAY_DIR: &str = "replays";
+pub const LAST_REPLAY_FILE: &str = "replays/last.replay";
+/// Written ahead of every replay; files of any other version are refused instead of misread.
+const REPLAY_VERSION: u32 = 2;
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<WorldSeed>()
+            .init_resource::<ReplayState>()
+            .add_event::<ReplayCommand>()
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                replay_record.after(InputManagerSystem::Update),
+            )
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                replay_playback.after(InputManagerSystem::Update),
+            )
+            .add_system(replay_keys)
+            .add_system(replay_commands.after(replay_keys));
+    }
+}
+
+/// Seed of everything random in the world, stored in replays so playback starts identically.
+#[derive(Debug, Clone, Copy)]
+pub struct WorldSeed(pub u64);
+
+impl Default for WorldSeed {
+    fn default() -> Self {
+        Self(unix_time().as_nanos() as u64)
+    }
+}
+
+/// Time since the Unix epoch. The browser has no system clock for std to read, so ask it instead.
+pub fn unix_time() -> Duration {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        Duration::from_secs_f64(js_sys::Date::now() / 1000.0)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ReplayCommand {
+    StartRecording,
+    StopRecording,
+    Play(PathBuf),
+    StopPlayback,
+}
+
+#[derive(Default)]
+pub enum ReplayState {
+    #[default]
+    Idle,
+    Recording(Replay),
+    Playback {
+        replay: Replay,
+        cursor: usize,
+        timestep_mode: TimestepMode,
+    },
+}
+
+impl ReplayState {
+    pub fn is_playing(&self) -> bool {
+        matches!(self, ReplayState::Playback { .. })
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Replay {
+    pub seed: u64,
+    pub initial: WorldSnapshot,
+    pub frames: Vec<ReplayFrame>,
+}
+
+impl Replay {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, bincode::Error> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let version: u32 = bincode::deserialize_from(&mut reader)?;
+        if version != REPLAY_VERSION {
+            return Err(Box::new(bincode::ErrorKind::Custom(format!(
+                "replay version {} is not supported, expected {}",
+                version, REPLAY_VERSION
+            ))));
+        }
+        bincode::deserialize_from(reader)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), bincode::Error> {
+        if let Some(parent) = path.as_ref().parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut writer = BufWriter::new(File::create(path)?);
+        bincode::serialize_into(&mut writer, &REPLAY_VERSION)?;
+        bincode::serialize_into(writer, self)
+    }
+
+    /// Total recorded time in seconds.
+    pub fn duration(&self) -> f32 {
+        self.frames.iter().map(|frame| frame.delta).sum()
+    }
+}
+
+/// Input of a single tick.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ReplayFrame {
+    pub delta: f32,
+    /// Bit `i` is set if the action with index `i` is pressed.
+    pub pressed: u32,
+    /// Axis pairs of pressed dual-axis actions, keyed by action index.
+    pub axis_pairs: Vec<(u8, [f32; 2])>,
+    /// Pose of the player at the start of the tick, used to draw ghosts without re-simulating.
+    pub pose: BodySnapshot,
+}
+
+impl ReplayFrame {
+    fn is_pressed(&self, action: Action) -> bool {
+        self.pressed & (1 << action.index()) != 0
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct BodySnapshot {
+    pub translation: [f32; 3],
+    pub rotation: [f32; 4],
+}
+
+impl From<&Transform> for BodySnapshot {
+    fn from(transform: &Transform) -> Self {
+        Self {
+            translation: transform.translation.to_array(),
+            rotation: transform.rotation.to_array(),
+        }
+    }
+}
+
+impl BodySnapshot {
+    pub fn apply(&self, transform: &mut Transform) {
+        transform.translation = Vec3::from_array(self.translation);
+        transform.rotation = Quat::from_array(self.rotation);
+    }
+}
+
+/// Poses of the player, its camera and every catch object (in spawn order).
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct WorldSnapshot {
+    pub player: BodySnapshot,
+    pub camera: BodySnapshot,
+    pub objects: Vec<BodySnapshot>,
+}
+
+fn replay_keys(
+    keys: Res<Input<KeyCode>>,
+    state: Res<ReplayState>,
+    mut commands: EventWriter<ReplayCommand>,
+) {
+    if keys.just_pressed(KeyCode::F5) {
+        match *state {
+            ReplayState::Idle => commands.send(ReplayCommand::StartRecording),
+            ReplayState::Recording(_) => commands.send(ReplayCommand::StopRecording),
+            ReplayState::Playback { .. } => {}
+        }
+    }
+    if keys.just_pressed(KeyCode::F6) {
+        match *state {
+            ReplayState::Playback { .. } => commands.send(ReplayCommand::StopPlayback),
+            _ => commands.send(ReplayCommand::Play(LAST_REPLAY_FILE.into())),
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn replay_commands(
+    mut events: EventReader<ReplayCommand>,
+    mut state: ResMut<ReplayState>,
+    mut seed: ResMut<WorldSeed>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+    mut player: Query<(&mut Transform, Option<&mut Velocity>), With<Player>>,
+    mut camera: Query<&mut Transform, (With<PlayerCamera>, Without<Player>)>,
+    mut objects: Query<
+        (Entity, &mut Transform, &mut Velocity),
+        (With<CatchObject>, Without<Player>, Without<PlayerCamera>),
+    >,
+) {
+    for event in events.iter() {
+        match event {
+            ReplayCommand::StartRecording => {
+                let (player, _) = player.single();
+                let mut objects: Vec<_> = objects.iter().collect();
+                objects.sort_by_key(|(entity, _, _)| *entity);
+
+                let initial = WorldSnapshot {
+                    player: player.into(),
+                    camera: camera.single().into(),
+                    objects: objects
+                        .iter()
+                        .map(|(_, transform, _)| (*transform).into())
+                        .collect(),
+                };
+                *state = ReplayState::Recording(Replay {
+                    seed: seed.0,
+                    initial,
+                    frames: vec![],
+                });
+                info!("Replay recording started");
+            }
+            ReplayCommand::StopRecording => {
+                if let ReplayState::Recording(replay) = std::mem::take(&mut *state) {
+                    let timestamp = unix_time().as_secs();
+                    let path = Path::new(REPLAY_DIR).join(format!("{timestamp}.replay"));
+                    for path in [path.as_path(), Path::new(LAST_REPLAY_FILE)] {
+                        match replay.save(path) {
+                            Ok(()) => info!("Replay saved to {}", path.display()),
+                            Err(err) => error!("Failed to save replay {}: {}", path.display(), err),
+                        }
+                    }
+                }
+            }
+            ReplayCommand::Play(path) => {
+                let replay = match Replay::load(path) {
+                    Ok(replay) => replay,
+                    Err(err) => {
+                        error!("Failed to load replay {}: {}", path.display(), err);
+                        continue;
+                    }
+                };
+
+                seed.0 = replay.seed;
+
+                let (mut transform, velocity) = player.single_mut();
+                replay.initial.player.apply(&mut transform);
+                if let Some(mut velocity) = velocity {
+                    *velocity = Velocity::default();
+                }
+                replay.initial.camera.apply(&mut camera.single_mut());
+
+                let mut objects: Vec<_> = objects.iter_mut().collect();
+                objects.sort_by_key(|(entity, _, _)| *entity);
+                for ((_, transform, velocity), snapshot) in
+                    objects.iter_mut().zip(replay.initial.objects.iter())
+                {
+                    snapshot.apply(transform);
+                    **velocity = Velocity::default();
+                }
+
+                info!(
+                    "Replay playback started: {} frames, {:.1} s",
+                    replay.frames.len(),
+                    replay.duration()
+                );
+                *state = ReplayState::Playback {
+                    replay,
+                    cursor: 0,
+                    timestep_mode: rapier_config.timestep_mode,
+                };
+            }
+            ReplayCommand::StopPlayback => {
+                if let ReplayState::Playback { timestep_mode, .. } = *state {
+                    rapier_config.timestep_mode = timestep_mode;
+                    *state = ReplayState::Idle;
+                }
+            }
+        }
+    }
+}
+
+fn replay_record(
+    time: Res<Time>,
+    mut state: ResMut<ReplayState>,
+    player: Query<(&ActionState<Action>, &Transform), With<Player>>,
+) {
+    let replay = match &mut *state {
+        ReplayState::Recording(replay) => replay,
+        _ => return,
+    };
+    let (action_state, transform) = player.single();
+
+    let mut frame = ReplayFrame {
+        delta: time.delta_seconds(),
+        pose: transform.into(),
+        ..default()
+    };
+    for action in Action::variants() {
+        if !action_state.pressed(action) {
+            continue;
+        }
+        frame.pressed |= 1 << action.index();
+        if let Some(axis) = action_state.axis_pair(action) {
+            frame
+                .axis_pairs
+                .push((action.index() as u8, [axis.x(), axis.y()]));
+        }
+    }
+    replay.frames.push(frame);
+}
+
+/// Overwrites the live input with the recorded one, and steps physics with the recorded delta.
+fn replay_playback(
+    mut state: ResMut<ReplayState>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+    mut player: Query<&mut ActionState<Action>, With<Player>>,
+) {
+    let (replay, cursor, timestep_mode) = match &mut *state {
+        ReplayState::Playback {
+            replay,
+            cursor,
+            timestep_mode,
+        } => (replay, cursor, *timestep_mode),
+        _ => return,
+    };
+
+    let frame = match replay.frames.get(*cursor) {
+        Some(frame) => frame,
+        None => {
+            info!("Replay playback finished");
+            rapier_config.timestep_mode = timestep_mode;
+            *state = ReplayState::Idle;
+            return;
+        }
+    };
+    let previous = cursor
+        .checked_sub(1)
+        .and_then(|index| replay.frames.get(index));
+
+    let mut action_state = player.single_mut();
+    for action in Action::variants() {
+        let was_pressed = previous.map_or(false, |frame| frame.is_pressed(action));
+        let pressed = frame.is_pressed(action);
+
+        let mut data: ActionData = action_state.action_data(action);
+        data.state = match (was_pressed, pressed) {
+            (false, true) => ButtonState::JustPressed,
+            (true, true) => ButtonState::Pressed,
+            (true, false) => ButtonState::JustReleased,
+            (false, false) => ButtonState::Released,
+        };
+        data.value = if pressed { 1.0 } else { 0.0 };
+        data.axis_pair = frame
+            .axis_pairs
+            .iter()
+            .find(|(index, _)| *index as usize == action.index())
+            .map(|(_, [x, y])| DualAxisData::new(*x, *y));
+        action_state.set_action_data(action, data);
+    }
+
+    rapier_config.timestep_mode = TimestepMode::Fixed {
+        dt: frame.delta,
+        substeps: 1,
+    };
+    *cursor += 1;
+}
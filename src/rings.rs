@@ -0,0 +1,206 @@
+//! Rings to throw cubes through, one after another against the clock.
+//!
+//! The [`TargetRing`]s of a level light up one at a time in their `order`. Getting a cube through
+//! the lit ring before its time runs out scores points and lights the next one, and every ring hit
+//! in a row raises the combo multiplying those points. Letting the time run out breaks the combo
+//! and moves on. The combo and score sit at the top of the HUD while a level has rings.
+
+use crate::{
+    hud::{
+        bitmap::{BitmapAlign, BitmapText, BitmapTextBundle},
+        popup::PopupEvent,
+    },
+    layers::SpawnOnLayerExt,
+    level::LevelLoaded,
+    locale::Locale,
+    materials::MaterialLibrary,
+    practice::ring_crossing,
+    CatchObject,
+};
+use bevy::{prelude::*, utils::HashMap};
+
+/// Points for a ring hit at a combo of one.
+const RING_POINTS: u32 = 10;
+const COMBO_POSITION: Vec2 = Vec2::new(0.0, 48.0);
+const COMBO_COLOR: Color = Color::rgb(1.0, 0.85, 0.2);
+
+pub struct RingPlugin;
+
+impl Plugin for RingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RingChallenge>()
+            .add_startup_system(setup_combo_text)
+            .add_system(reset_rings)
+            .add_system(run_rings.after(reset_rings))
+            .add_system(light_rings.after(run_rings))
+            .add_system(update_combo_text.after(run_rings));
+    }
+}
+
+/// A ring with its hole along its local y axis.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct TargetRing {
+    pub radius: f32,
+    /// Rings light up from the lowest order up, then start over.
+    pub order: u32,
+    /// Seconds to get a cube through once it lights up.
+    pub time_limit: f32,
+}
+
+#[derive(Debug, Default)]
+pub struct RingChallenge {
+    /// The lit ring.
+    pub active: Option<Entity>,
+    pub timer: Timer,
+    /// Rings hit in a row.
+    pub combo: u32,
+    pub score: u32,
+}
+
+#[derive(Component)]
+struct ComboText;
+
+fn setup_combo_text(mut commands: Commands) {
+    commands
+        .spawn_on_hud_layer(BitmapTextBundle::new(
+            BitmapText::new("", COMBO_COLOR, BitmapAlign::Center),
+            COMBO_POSITION,
+        ))
+        .insert(ComboText);
+}
+
+fn reset_rings(mut events: EventReader<LevelLoaded>, mut challenge: ResMut<RingChallenge>) {
+    if events.iter().last().is_some() {
+        *challenge = default();
+    }
+}
+
+/// The ring after `current` in order, going back to the first after the last.
+fn next_ring(
+    rings: &Query<(Entity, &TargetRing, &GlobalTransform)>,
+    current: Option<Entity>,
+) -> Option<Entity> {
+    let mut sorted: Vec<_> = rings
+        .iter()
+        .map(|(entity, ring, _)| (ring.order, entity))
+        .collect();
+    sorted.sort();
+    let index = current
+        .and_then(|current| sorted.iter().position(|(_, entity)| *entity == current))
+        .map_or(0, |index| (index + 1) % sorted.len().max(1));
+    sorted.get(index).map(|(_, entity)| *entity)
+}
+
+fn light(challenge: &mut RingChallenge, entity: Entity, ring: &TargetRing) {
+    challenge.active = Some(entity);
+    challenge.timer = Timer::from_seconds(ring.time_limit, false);
+}
+
+fn run_rings(
+    time: Res<Time>,
+    locale: Res<Locale>,
+    mut challenge: ResMut<RingChallenge>,
+    mut popups: EventWriter<PopupEvent>,
+    mut last_positions: Local<HashMap<Entity, Vec3>>,
+    rings: Query<(Entity, &TargetRing, &GlobalTransform)>,
+    objects: Query<(Entity, &GlobalTransform), With<CatchObject>>,
+) {
+    let positions: HashMap<_, _> = objects
+        .iter()
+        .map(|(entity, transform)| (entity, transform.translation()))
+        .collect();
+    let last = std::mem::replace(&mut *last_positions, positions);
+
+    let active = challenge.active.and_then(|entity| rings.get(entity).ok());
+    let (entity, ring, transform) = match active {
+        Some(active) => active,
+        None => {
+            let first = next_ring(&rings, None).and_then(|entity| rings.get(entity).ok());
+            if let Some((entity, ring, _)) = first {
+                light(&mut challenge, entity, ring);
+            }
+            return;
+        }
+    };
+
+    let center = transform.translation();
+    let hit = last_positions.iter().any(|(object, position)| {
+        last.get(object)
+            .and_then(|previous| ring_crossing(transform, *previous, *position))
+            .map_or(false, |miss| miss < ring.radius)
+    });
+
+    if hit {
+        challenge.combo += 1;
+        let points = RING_POINTS * challenge.combo;
+        challenge.score += points;
+        popups.send(
+            PopupEvent::new(
+                locale.get_args(
+                    "rings-hit",
+                    &[("points", points.into()), ("combo", challenge.combo.into())],
+                ),
+                center,
+            )
+            .with_color(COMBO_COLOR),
+        );
+    } else if challenge.timer.tick(time.delta()).finished() {
+        challenge.combo = 0;
+    } else {
+        return;
+    }
+    let next = next_ring(&rings, Some(entity)).and_then(|entity| rings.get(entity).ok());
+    if let Some((entity, ring, _)) = next {
+        light(&mut challenge, entity, ring);
+    }
+}
+
+/// Lit rings glow like goals; the rest are drawn like walls.
+fn light_rings(
+    challenge: Res<RingChallenge>,
+    mut library: ResMut<MaterialLibrary>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut rings: Query<(Entity, &mut Handle<StandardMaterial>), With<TargetRing>>,
+) {
+    if !challenge.is_changed() {
+        return;
+    }
+    let lit = library.get("goal", &mut materials);
+    let unlit = library.get("wall", &mut materials);
+    for (entity, mut material) in &mut rings {
+        let wanted = if challenge.active == Some(entity) {
+            &lit
+        } else {
+            &unlit
+        };
+        if *material != *wanted {
+            *material = wanted.clone();
+        }
+    }
+}
+
+fn update_combo_text(
+    locale: Res<Locale>,
+    challenge: Res<RingChallenge>,
+    mut texts: Query<&mut BitmapText, With<ComboText>>,
+) {
+    let value = match challenge.active {
+        Some(_) => locale.get_args(
+            "rings-combo",
+            &[
+                ("combo", challenge.combo.max(1).into()),
+                ("score", challenge.score.into()),
+                (
+                    "seconds",
+                    format!("{:.1}", challenge.timer.remaining_secs()).into(),
+                ),
+            ],
+        ),
+        None => String::new(),
+    };
+    for mut text in &mut texts {
+        if text.value != value {
+            text.value = value.clone();
+        }
+    }
+}
@@ -0,0 +1,200 @@
+//! Ropes and chains.
+//!
+//! A rope is a line of capsule links hanging down from where it is spawned, each joined to the one
+//! above with a spherical joint. The top link hangs from a fixed point, or from another body when
+//! anchored to one. The last link can be a cube weight, which can be caught and swung around like
+//! any other object. Links are children of the rope entity, so despawning it takes them along.
+
+use crate::{layers, materials::MaterialLibrary, props::DynamicBodyBundle, CatchObject, CUBE_SIZE};
+use bevy::{ecs::system::Command, prelude::*};
+use bevy_rapier3d::prelude::*;
+use serde::Deserialize;
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Rope {
+    pub links: usize,
+    #[serde(default = "default_link_length")]
+    pub link_length: f32,
+    #[serde(default = "default_link_radius")]
+    pub link_radius: f32,
+    /// Hangs a catchable cube from the end.
+    #[serde(default)]
+    pub weight: bool,
+    /// Body to hang from, with the point on it in its local space; a fixed point if `None`.
+    #[serde(skip)]
+    pub anchor: Option<(Entity, Vec3)>,
+}
+
+fn default_link_length() -> f32 {
+    0.5
+}
+
+fn default_link_radius() -> f32 {
+    0.08
+}
+
+impl Rope {
+    pub fn new(links: usize) -> Self {
+        Self {
+            links,
+            link_length: default_link_length(),
+            link_radius: default_link_radius(),
+            weight: false,
+            anchor: None,
+        }
+    }
+
+    pub fn with_weight(mut self) -> Self {
+        self.weight = true;
+        self
+    }
+
+    pub fn anchored_to(mut self, entity: Entity, point: Vec3) -> Self {
+        self.anchor = Some((entity, point));
+        self
+    }
+}
+
+/// The links of a rope, top first.
+#[derive(Debug, Default, Component)]
+pub struct RopeLinks(pub Vec<Entity>);
+
+pub trait SpawnRopeExt {
+    /// Spawns `rope` hanging from `transform`, returning the rope entity.
+    fn spawn_rope(&mut self, rope: Rope, transform: Transform) -> Entity;
+}
+
+impl<'w, 's> SpawnRopeExt for Commands<'w, 's> {
+    fn spawn_rope(&mut self, rope: Rope, transform: Transform) -> Entity {
+        let entity = self
+            .spawn_bundle(SpatialBundle {
+                transform,
+                ..default()
+            })
+            .id();
+        self.add(BuildRope { entity, rope });
+        entity
+    }
+}
+
+struct BuildRope {
+    entity: Entity,
+    rope: Rope,
+}
+
+impl Command for BuildRope {
+    fn write(self, world: &mut World) {
+        let rope = self.rope;
+        let half = 0.5 * rope.link_length;
+        let link_mesh = world.resource_mut::<Assets<Mesh>>().add(
+            shape::Capsule {
+                radius: rope.link_radius,
+                depth: rope.link_length - 2.0 * rope.link_radius,
+                ..default()
+            }
+            .into(),
+        );
+        let link_material =
+            world
+                .resource_mut::<Assets<StandardMaterial>>()
+                .add(StandardMaterial {
+                    base_color: Color::rgb(0.5, 0.45, 0.4),
+                    perceptual_roughness: 0.9,
+                    ..default()
+                });
+
+        let (mut parent, mut parent_anchor) = match rope.anchor {
+            // Joints need a body on both ends; level geometry has none of its own.
+            Some((entity, point)) => {
+                let mut anchor = match world.get_entity_mut(entity) {
+                    Some(anchor) => anchor,
+                    None => {
+                        warn!("Rope anchor {:?} is gone", entity);
+                        return;
+                    }
+                };
+                if !anchor.contains::<RigidBody>() {
+                    anchor.insert(RigidBody::Fixed);
+                }
+                (entity, point)
+            }
+            None => {
+                world.entity_mut(self.entity).insert(RigidBody::Fixed);
+                (self.entity, Vec3::ZERO)
+            }
+        };
+
+        let mut links = vec![];
+        for index in 0..rope.links {
+            let mut joint: GenericJoint = SphericalJointBuilder::new()
+                .local_anchor1(parent_anchor)
+                .local_anchor2(Vec3::Y * half)
+                .into();
+            // Neighbors overlap where they bend.
+            joint.set_contacts_enabled(false);
+
+            let link = world
+                .spawn()
+                .insert_bundle(PbrBundle {
+                    mesh: link_mesh.clone(),
+                    material: link_material.clone(),
+                    transform: Transform::from_xyz(
+                        0.0,
+                        -rope.link_length * (index as f32 + 0.5),
+                        0.0,
+                    ),
+                    ..default()
+                })
+                .insert_bundle((
+                    RigidBody::Dynamic,
+                    Collider::capsule_y(half - rope.link_radius, rope.link_radius),
+                    ImpulseJoint::new(parent, joint),
+                    layers::WORLD,
+                ))
+                .id();
+            links.push(link);
+            parent = link;
+            parent_anchor = -Vec3::Y * half;
+        }
+
+        if rope.weight {
+            let mesh = world
+                .resource_mut::<Assets<Mesh>>()
+                .add(shape::Cube::new(CUBE_SIZE).into());
+            let material = world.resource_scope(|world, library: Mut<MaterialLibrary>| {
+                library.unique(
+                    "cube",
+                    &mut world.resource_mut::<Assets<StandardMaterial>>(),
+                )
+            });
+            let mut joint: GenericJoint = SphericalJointBuilder::new()
+                .local_anchor1(parent_anchor)
+                .local_anchor2(Vec3::Y * 0.5 * CUBE_SIZE)
+                .into();
+            joint.set_contacts_enabled(false);
+            let depth = rope.link_length * rope.links as f32 + 0.5 * CUBE_SIZE;
+
+            let weight = world
+                .spawn()
+                .insert_bundle(PbrBundle {
+                    mesh,
+                    material,
+                    transform: Transform::from_xyz(0.0, -depth, 0.0),
+                    ..default()
+                })
+                .insert_bundle((
+                    DynamicBodyBundle::default(),
+                    Collider::cuboid(CUBE_SIZE * 0.5, CUBE_SIZE * 0.5, CUBE_SIZE * 0.5),
+                    CatchObject,
+                ))
+                .insert_bundle((ImpulseJoint::new(parent, joint), layers::WORLD))
+                .id();
+            links.push(weight);
+        }
+
+        world
+            .entity_mut(self.entity)
+            .push_children(&links)
+            .insert(RopeLinks(links));
+    }
+}
@@ -0,0 +1,312 @@
+//! How a match is won, round by round.
+//!
+//! [`MatchRules`] come from `assets/rules.ron`: how many rounds, how long each lasts, the pause in
+//! between and what wins a round. A match plays its rounds back to back, each one starting with
+//! the countdown, which doubles as the intermission after the first. With side swaps on, players
+//! start every other round at the opposite spawn point and the goals change hands with them.
+//!
+//! Every peer judges rounds on its own from the same rules file; the host only decides when a
+//! round ends and tells clients which round is next.
+
+use crate::{
+    level::{trigger::GoalReached, Goal},
+    match_flow::{Lobby, MatchState, MatchTimer},
+    paint::Stamp,
+    teams::{Team, TeamScores, TeamSettings},
+    waves::{WaveDirector, WavePhase},
+    CatchObject, CUBE_SIZE,
+};
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::fs;
+
+pub const RULES_PATH: &str = "assets/rules.ron";
+
+pub struct RulesPlugin;
+
+impl Plugin for RulesPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(MatchRules::load())
+            .init_resource::<Round>()
+            .add_system_set(
+                SystemSet::on_enter(MatchState::Countdown)
+                    .with_system(start_round)
+                    .with_system(swap_goals),
+            )
+            .add_system_set(SystemSet::on_update(MatchState::InMatch).with_system(judge_round))
+            .add_system_set(SystemSet::on_exit(MatchState::InMatch).with_system(finish_round));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum WinCondition {
+    /// The first to score `target` goals wins the round.
+    Goals { target: u32 },
+    /// The players win the round by clearing every wave before time runs out.
+    SurviveWaves,
+    /// When time runs out, whoever has more stacks of at least `height` catch objects on their
+    /// half of the arena wins the round.
+    MostStacks { height: usize },
+    /// When time runs out, whoever has stamped more catch objects wins the round.
+    Possession,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct MatchRules {
+    pub win: WinCondition,
+    pub rounds: u32,
+    /// Length of a round, in seconds.
+    pub round_time: f32,
+    /// Pause between rounds, in seconds.
+    pub intermission: f32,
+    pub swap_sides: bool,
+}
+
+impl Default for MatchRules {
+    fn default() -> Self {
+        Self {
+            win: WinCondition::Goals { target: 5 },
+            rounds: 1,
+            round_time: 180.0,
+            intermission: 8.0,
+            swap_sides: false,
+        }
+    }
+}
+
+impl MatchRules {
+    pub fn load() -> Self {
+        let rules = fs::read_to_string(RULES_PATH)
+            .map_err(|err| err.to_string())
+            .and_then(|text| ron::from_str(&text).map_err(|err| err.to_string()));
+        match rules {
+            Ok(rules) => rules,
+            Err(err) => {
+                warn!(
+                    "Failed to load {}, using the default rules: {}",
+                    RULES_PATH, err
+                );
+                Self::default()
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoundWinner {
+    Team(Team),
+    /// Everyone, in matches without teams.
+    Players,
+    Nobody,
+}
+
+/// Progress through the rounds of the current match.
+#[derive(Debug, Default)]
+pub struct Round {
+    /// Index of the round being played, or about to be.
+    pub index: u32,
+    /// Set once the round is decided, which ends it.
+    pub winner: Option<RoundWinner>,
+    /// Winners of the rounds played so far.
+    pub results: Vec<RoundWinner>,
+    /// Goals scored this round, for matches without teams.
+    goals: u32,
+}
+
+impl Round {
+    /// Whether players start at the opposite spawn points this round.
+    pub fn swapped(&self, rules: &MatchRules) -> bool {
+        rules.swap_sides && self.index % 2 == 1
+    }
+
+    fn decide(&mut self, winner: RoundWinner) {
+        self.winner = Some(winner);
+        self.results.push(winner);
+    }
+
+    pub fn wins(&self, winner: RoundWinner) -> usize {
+        self.results
+            .iter()
+            .filter(|result| **result == winner)
+            .count()
+    }
+
+    /// Whether the match ends after the decided rounds, because they were all of them or a team
+    /// can no longer be caught up with.
+    pub fn is_last(&self, rules: &MatchRules) -> bool {
+        let played = self.results.len() as u32;
+        let needed = rules.rounds / 2 + 1;
+        let clinched = [Team::Red, Team::Blue]
+            .iter()
+            .any(|team| self.wins(RoundWinner::Team(*team)) as u32 >= needed);
+        played >= rules.rounds || clinched
+    }
+}
+
+/// Clears the round on every countdown, and the whole match on the first.
+fn start_round(mut round: ResMut<Round>) {
+    if round.index == 0 {
+        round.results.clear();
+    }
+    round.winner = None;
+    round.goals = 0;
+}
+
+/// Hands the goals of the two teams over to each other whenever the sides swap.
+fn swap_goals(
+    mut commands: Commands,
+    lobby: Res<Lobby>,
+    mut swapped: Local<bool>,
+    goals: Query<(Entity, &Team), With<Goal>>,
+) {
+    if lobby.swapped == *swapped {
+        return;
+    }
+    *swapped = lobby.swapped;
+    for (entity, team) in &goals {
+        commands.entity(entity).insert(team.other());
+    }
+}
+
+/// Decides the round as soon as its win condition is met, or its time is up.
+#[allow(clippy::too_many_arguments)]
+pub fn judge_round(
+    rules: Res<MatchRules>,
+    teams: Res<TeamSettings>,
+    scores: Res<TeamScores>,
+    waves: Res<WaveDirector>,
+    timer: Res<MatchTimer>,
+    lobby: Res<Lobby>,
+    mut round: ResMut<Round>,
+    mut events: EventReader<GoalReached>,
+    objects: Query<(&GlobalTransform, Option<&Stamp>), With<CatchObject>>,
+) {
+    round.goals += events.iter().count() as u32;
+    if round.winner.is_some() {
+        return;
+    }
+    let winner = match rules.win {
+        WinCondition::Goals { target } if teams.enabled => [Team::Red, Team::Blue]
+            .into_iter()
+            .find(|team| scores.0[team.index()] >= target)
+            .map(RoundWinner::Team),
+        WinCondition::Goals { target } if round.goals >= target => Some(RoundWinner::Players),
+        WinCondition::SurviveWaves if matches!(waves.phase, WavePhase::Finished) => {
+            Some(RoundWinner::Players)
+        }
+        _ => None,
+    };
+    if let Some(winner) = winner {
+        round.decide(winner);
+    } else if timer.0.finished() {
+        round.decide(time_up(&rules, &teams, &scores, &lobby, &objects));
+    }
+}
+
+/// Settles the round if the host ended it before this peer did.
+fn finish_round(
+    rules: Res<MatchRules>,
+    teams: Res<TeamSettings>,
+    scores: Res<TeamScores>,
+    lobby: Res<Lobby>,
+    mut round: ResMut<Round>,
+    objects: Query<(&GlobalTransform, Option<&Stamp>), With<CatchObject>>,
+) {
+    if round.winner.is_none() {
+        round.decide(time_up(&rules, &teams, &scores, &lobby, &objects));
+    }
+}
+
+/// Who wins a round that ran out of time.
+fn time_up(
+    rules: &MatchRules,
+    teams: &TeamSettings,
+    scores: &TeamScores,
+    lobby: &Lobby,
+    objects: &Query<(&GlobalTransform, Option<&Stamp>), With<CatchObject>>,
+) -> RoundWinner {
+    match rules.win {
+        WinCondition::Goals { .. } if teams.enabled => leader(scores.0),
+        WinCondition::MostStacks { height } => {
+            let positions: Vec<_> = objects
+                .iter()
+                .map(|(transform, _)| transform.translation())
+                .collect();
+            if teams.enabled {
+                leader([Team::Red, Team::Blue].map(|team| {
+                    let sign = home_side(team, lobby.swapped);
+                    count_stacks(&positions, height, |position| position.z * sign > 0.0)
+                }))
+            } else if count_stacks(&positions, height, |_| true) > 0 {
+                RoundWinner::Players
+            } else {
+                RoundWinner::Nobody
+            }
+        }
+        WinCondition::Possession => {
+            let owners: Vec<_> = objects
+                .iter()
+                .filter_map(|(_, stamp)| stamp.map(|stamp| stamp.owner))
+                .collect();
+            if teams.enabled {
+                let mut counts = [0; 2];
+                for owner in owners {
+                    counts[Team::for_peer(owner, lobby).index()] += 1;
+                }
+                leader(counts)
+            } else if !owners.is_empty() {
+                RoundWinner::Players
+            } else {
+                RoundWinner::Nobody
+            }
+        }
+        _ => RoundWinner::Nobody,
+    }
+}
+
+fn leader(counts: [u32; 2]) -> RoundWinner {
+    match counts[0].cmp(&counts[1]) {
+        std::cmp::Ordering::Greater => RoundWinner::Team(Team::Red),
+        std::cmp::Ordering::Less => RoundWinner::Team(Team::Blue),
+        std::cmp::Ordering::Equal => RoundWinner::Nobody,
+    }
+}
+
+/// Sign of the z coordinate on the half of the arena where `team` starts.
+fn home_side(team: Team, swapped: bool) -> f32 {
+    let sign = match team {
+        Team::Red => 1.0,
+        Team::Blue => -1.0,
+    };
+    if swapped {
+        -sign
+    } else {
+        sign
+    }
+}
+
+/// Counts columns of at least `height` objects resting on each other, among those `inside`.
+fn count_stacks(positions: &[Vec3], height: usize, inside: impl Fn(Vec3) -> bool) -> u32 {
+    let mut positions: Vec<_> = positions.iter().copied().filter(|p| inside(*p)).collect();
+    positions.sort_by(|a, b| a.y.total_cmp(&b.y));
+
+    // How many objects each one sits on top of, itself included.
+    let mut levels = vec![1; positions.len()];
+    for upper in 0..positions.len() {
+        for lower in 0..upper {
+            let offset = positions[upper] - positions[lower];
+            let resting = offset.y > 0.5 * CUBE_SIZE
+                && offset.y < 1.5 * CUBE_SIZE
+                && Vec2::new(offset.x, offset.z).length() < 0.5 * CUBE_SIZE;
+            if resting {
+                levels[upper] = levels[upper].max(levels[lower] + 1);
+            }
+        }
+    }
+    // Each tall enough column has exactly one object at the wanted height.
+    levels
+        .iter()
+        .filter(|level| **level == height.max(1))
+        .count() as u32
+}
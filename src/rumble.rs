@@ -0,0 +1,103 @@
+//! Gamepad vibration.
+//!
+//! Bevy has no rumble of its own yet, so this drives force feedback through the gilrs context
+//! that bevy_gilrs keeps. Every [`FeedbackEvent`] with some rumble plays a short pulse on every
+//! gamepad that supports it; stronger ones are longer as well as stronger.
+
+use crate::feedback::FeedbackEvent;
+use bevy::prelude::*;
+use gilrs::{
+    ff::{BaseEffect, BaseEffectType, Effect, EffectBuilder, Replay, Ticks},
+    Gilrs,
+};
+
+/// Length of a pulse at no and at full strength, in milliseconds.
+const PULSE_MS: [u32; 2] = [60, 250];
+
+pub struct RumblePlugin;
+
+impl Plugin for RumblePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RumbleSettings>()
+            .init_non_send_resource::<Pulses>()
+            .add_system(play_rumble);
+    }
+}
+
+pub struct RumbleSettings {
+    pub enabled: bool,
+}
+
+impl Default for RumbleSettings {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+/// Effects stop when dropped, so they are kept until their pulse is over.
+#[derive(Default)]
+struct Pulses(Vec<(Effect, Timer)>);
+
+fn play_rumble(
+    time: Res<Time>,
+    settings: Res<RumbleSettings>,
+    gilrs: Option<NonSendMut<Gilrs>>,
+    mut pulses: NonSendMut<Pulses>,
+    mut events: EventReader<FeedbackEvent>,
+) {
+    pulses
+        .0
+        .retain_mut(|(_, timer)| !timer.tick(time.delta()).finished());
+
+    // Read every event, even with nothing to rumble, so none play late.
+    let requests: Vec<_> = events
+        .iter()
+        .map(|event| event.rumble.min(1.0))
+        .filter(|strength| *strength > 0.0)
+        .collect();
+    let mut gilrs = match gilrs {
+        Some(gilrs) if settings.enabled => gilrs,
+        _ => {
+            pulses.0.clear();
+            return;
+        }
+    };
+    let gamepads: Vec<_> = gilrs
+        .gamepads()
+        .filter(|(_, gamepad)| gamepad.is_ff_supported())
+        .map(|(id, _)| id)
+        .collect();
+    if gamepads.is_empty() {
+        return;
+    }
+
+    for strength in requests {
+        let ms = PULSE_MS[0] + ((PULSE_MS[1] - PULSE_MS[0]) as f32 * strength) as u32;
+        let magnitude = (strength * u16::MAX as f32) as u16;
+        let effect = EffectBuilder::new()
+            .add_effect(BaseEffect {
+                kind: BaseEffectType::Strong { magnitude },
+                scheduling: Replay {
+                    play_for: Ticks::from_ms(ms),
+                    ..default()
+                },
+                ..default()
+            })
+            .add_effect(BaseEffect {
+                kind: BaseEffectType::Weak { magnitude },
+                scheduling: Replay {
+                    play_for: Ticks::from_ms(ms),
+                    ..default()
+                },
+                ..default()
+            })
+            .gamepads(&gamepads)
+            .finish(&mut gilrs);
+        match effect.and_then(|effect| effect.play().map(|_| effect)) {
+            Ok(effect) => pulses
+                .0
+                .push((effect, Timer::from_seconds(ms as f32 / 1000.0, false))),
+            Err(err) => warn!("Failed to rumble: {}", err),
+        }
+    }
+}
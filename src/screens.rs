@@ -0,0 +1,196 @@
+//! Screens standing in the level, like a scoreboard on the wall or a floor display.
+//!
+//! A [`Screen`] is a quad whose texture is drawn by a camera of its own: pixel text on a render
+//! layer only that camera sees, so it comes out in the same font as the HUD but lit up in the
+//! scene. What it shows is picked by its [`ScreenContent`] and kept up to date every frame.
+
+use crate::{
+    hud::bitmap::{BitmapAlign, BitmapText, BitmapTextBundle, GLYPH_ADVANCE},
+    layers,
+    locale::Locale,
+    match_flow::{MatchState, MatchTimer},
+    rules::{MatchRules, Round},
+    teams::{TeamScores, TeamSettings},
+};
+use bevy::{
+    core_pipeline::clear_color::ClearColorConfig,
+    prelude::*,
+    render::{
+        camera::RenderTarget,
+        render_resource::*,
+        texture::{BevyDefault, ImageSampler},
+    },
+};
+use serde::Deserialize;
+
+/// Texture pixels per meter of screen.
+pub const SCREEN_DENSITY: f32 = 24.0;
+const BACKGROUND: Color = Color::rgb(0.02, 0.03, 0.05);
+const TEXT_COLOR: Color = Color::rgb(1.0, 0.75, 0.3);
+
+pub struct ScreenPlugin;
+
+impl Plugin for ScreenPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(setup_screens)
+            .add_system(update_screens.after(setup_screens));
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub enum ScreenContent {
+    /// Round, team scores and time left in the match.
+    Scoreboard,
+    /// Time left in the match.
+    Clock,
+    /// Fixed text, looked up in the locale by this key.
+    Text(String),
+}
+
+/// A quad of `size` meters, facing along its local z axis, showing `content`.
+#[derive(Debug, Clone, Component)]
+pub struct Screen {
+    pub size: Vec2,
+    pub content: ScreenContent,
+}
+
+#[derive(Component)]
+struct ScreenText;
+
+/// Gives new screens their texture, their camera and the text it looks at.
+fn setup_screens(
+    mut commands: Commands,
+    mut next_layer: Local<u8>,
+    mut images: ResMut<Assets<Image>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    screens: Query<(Entity, &Screen), Added<Screen>>,
+) {
+    for (entity, screen) in &screens {
+        let size = Extent3d {
+            width: (screen.size.x * SCREEN_DENSITY).round().max(1.0) as u32,
+            height: (screen.size.y * SCREEN_DENSITY).round().max(1.0) as u32,
+            ..default()
+        };
+        let mut image = Image {
+            texture_descriptor: TextureDescriptor {
+                label: None,
+                size,
+                dimension: TextureDimension::D2,
+                format: TextureFormat::bevy_default(),
+                mip_level_count: 1,
+                sample_count: 1,
+                usage: TextureUsages::TEXTURE_BINDING
+                    | TextureUsages::COPY_DST
+                    | TextureUsages::RENDER_ATTACHMENT,
+            },
+            sampler_descriptor: ImageSampler::Descriptor(SamplerDescriptor {
+                mag_filter: FilterMode::Nearest,
+                min_filter: FilterMode::Nearest,
+                mipmap_filter: FilterMode::Nearest,
+                ..default()
+            }),
+            ..default()
+        };
+        image.resize(size);
+        let image = images.add(image);
+
+        // Beyond the last layer, screens share them and may show each other's text.
+        let layer = layers::screen(*next_layer);
+        *next_layer = (*next_layer + 1) % layers::SCREENS;
+
+        let material = materials.add(StandardMaterial {
+            base_color_texture: Some(image.clone()),
+            unlit: true,
+            ..default()
+        });
+        // Camera and text move along with the screen, so they always line up with each other.
+        commands
+            .entity(entity)
+            .insert(material)
+            .with_children(|parent| {
+                parent
+                    .spawn_bundle(Camera2dBundle {
+                        camera: Camera {
+                            priority: -5,
+                            target: RenderTarget::Image(image),
+                            ..default()
+                        },
+                        camera_2d: Camera2d {
+                            clear_color: ClearColorConfig::Custom(BACKGROUND),
+                        },
+                        ..default()
+                    })
+                    .insert(layer);
+                parent
+                    .spawn_bundle(BitmapTextBundle::new(
+                        BitmapText::new("", TEXT_COLOR, BitmapAlign::Center),
+                        Vec2::ZERO,
+                    ))
+                    .insert(layer)
+                    .insert(ScreenText);
+            });
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_screens(
+    locale: Res<Locale>,
+    state: Res<State<MatchState>>,
+    timer: Res<MatchTimer>,
+    rules: Res<MatchRules>,
+    round: Res<Round>,
+    teams: Res<TeamSettings>,
+    scores: Res<TeamScores>,
+    screens: Query<(&Screen, &Children)>,
+    mut texts: Query<(&mut BitmapText, &mut Transform), With<ScreenText>>,
+) {
+    let clock = match state.current() {
+        MatchState::Countdown | MatchState::InMatch => {
+            let remaining = (timer.0.duration() - timer.0.elapsed()).as_secs_f32();
+            let seconds = remaining.ceil() as u32;
+            format!("{}:{:02}", seconds / 60, seconds % 60)
+        }
+        _ => "-:--".into(),
+    };
+
+    for (screen, children) in &screens {
+        let lines = match &screen.content {
+            ScreenContent::Scoreboard => {
+                let mut lines = vec![];
+                if rules.rounds > 1 {
+                    lines.push(locale.get_args(
+                        "round",
+                        &[
+                            ("round", (round.index + 1).into()),
+                            ("total", rules.rounds.into()),
+                        ],
+                    ));
+                }
+                if teams.enabled {
+                    lines.push(locale.get_args(
+                        "team-score",
+                        &[("red", scores.0[0].into()), ("blue", scores.0[1].into())],
+                    ));
+                }
+                lines.push(clock.clone());
+                lines
+            }
+            ScreenContent::Clock => vec![clock.clone()],
+            ScreenContent::Text(key) => locale.get(key).lines().map(String::from).collect(),
+        };
+        let value = lines.join("\n");
+
+        // Text runs down from its first line, so that one goes above the middle.
+        let top = (0.5 * GLYPH_ADVANCE.y * (lines.len() as f32 - 1.0)).round();
+        for child in children {
+            if let Ok((mut text, mut transform)) = texts.get_mut(*child) {
+                if text.value != value {
+                    text.value = value.clone();
+                }
+                if transform.translation.y != top {
+                    transform.translation.y = top;
+                }
+            }
+        }
+    }
+}
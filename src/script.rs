@@ -0,0 +1,292 @@
+//! Level logic written in Rhai.
+//!
+//! A level names its script in [`LevelDef::script`]. The script is run once when the level loads,
+//! then its hooks are called as things happen, if it defines them:
+//!
+//! - `on_level_loaded()`
+//! - `on_trigger(tags)`, with the tags of the trigger volume
+//! - `on_goal(tags)`, with the tags of the goal
+//! - `on_wave_started(wave, total)` and `on_wave_cleared(wave, total)`
+//! - `on_cutscene_finished(name)` and `on_dialogue_finished(name)`
+//!
+//! Scripts can't touch the world directly; they call `spawn_prefab(name, x, y, z)`,
+//! `move_tagged(tag, dx, dy, dz, seconds)`, `despawn_tagged(tag)`, `hang_rope(tag, dx, dy, dz,
+//! links)`, `set_light(r, g, b, illuminance)`, `play_sound(path)`, `play_cutscene(name)` and
+//! `start_dialogue(name)`, which are queued and carried out afterwards. Like triggers, scripts run
+//! on every peer.
+
+use crate::{
+    cutscene::{CutsceneFinished, PlayCutscene},
+    dialogue::{DialogueFinished, StartDialogue},
+    level::{
+        trigger::{apply_action, GoalReached, TriggerAction, Triggered},
+        CurrentLevel, LevelDef, LevelLoaded,
+    },
+    tags::Tagged,
+    waves::{WaveCleared, WaveStarted},
+};
+use bevy::prelude::*;
+use rhai::{Array, Dynamic, Engine, Scope, AST, FLOAT, INT};
+use std::{
+    fs,
+    sync::{Arc, Mutex},
+};
+
+/// Keeps a runaway script from freezing the game.
+const MAX_OPERATIONS: u64 = 100_000;
+
+pub struct ScriptPlugin;
+
+impl Plugin for ScriptPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LevelScript>()
+            .add_system(load_level_script)
+            .add_system(run_script_hooks.after(load_level_script))
+            .add_system(apply_script_commands.after(run_script_hooks));
+    }
+}
+
+#[derive(Debug, Clone)]
+enum ScriptCommand {
+    Action(TriggerAction),
+    SetLight { color: Color, illuminance: f32 },
+    PlaySound(String),
+    PlayCutscene(String),
+    StartDialogue(String),
+}
+
+type CommandQueue = Arc<Mutex<Vec<ScriptCommand>>>;
+
+pub struct LevelScript {
+    engine: Engine,
+    queue: CommandQueue,
+    loaded: Option<(AST, Scope<'static>)>,
+}
+
+impl Default for LevelScript {
+    fn default() -> Self {
+        let queue = CommandQueue::default();
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        engine.on_print(|text| info!("Script: {}", text));
+
+        let push = |queue: &CommandQueue, command| queue.lock().unwrap().push(command);
+
+        let q = queue.clone();
+        engine.register_fn(
+            "spawn_prefab",
+            move |name: &str, x: FLOAT, y: FLOAT, z: FLOAT| {
+                push(
+                    &q,
+                    ScriptCommand::Action(TriggerAction::SpawnPrefab {
+                        name: name.into(),
+                        translation: [x as f32, y as f32, z as f32],
+                    }),
+                )
+            },
+        );
+        let q = queue.clone();
+        engine.register_fn(
+            "move_tagged",
+            move |tag: &str, x: FLOAT, y: FLOAT, z: FLOAT, seconds: FLOAT| {
+                push(
+                    &q,
+                    ScriptCommand::Action(TriggerAction::Move {
+                        tag: tag.into(),
+                        offset: [x as f32, y as f32, z as f32],
+                        seconds: seconds as f32,
+                    }),
+                )
+            },
+        );
+        let q = queue.clone();
+        engine.register_fn("despawn_tagged", move |tag: &str| {
+            push(
+                &q,
+                ScriptCommand::Action(TriggerAction::Despawn { tag: tag.into() }),
+            )
+        });
+        let q = queue.clone();
+        engine.register_fn(
+            "hang_rope",
+            move |tag: &str, x: FLOAT, y: FLOAT, z: FLOAT, links: INT| {
+                push(
+                    &q,
+                    ScriptCommand::Action(TriggerAction::HangRope {
+                        tag: tag.into(),
+                        offset: [x as f32, y as f32, z as f32],
+                        links: links.max(0) as usize,
+                    }),
+                )
+            },
+        );
+        let q = queue.clone();
+        engine.register_fn(
+            "set_light",
+            move |r: FLOAT, g: FLOAT, b: FLOAT, illuminance: FLOAT| {
+                push(
+                    &q,
+                    ScriptCommand::SetLight {
+                        color: Color::rgb(r as f32, g as f32, b as f32),
+                        illuminance: illuminance as f32,
+                    },
+                )
+            },
+        );
+        let q = queue.clone();
+        engine.register_fn("play_sound", move |path: &str| {
+            push(&q, ScriptCommand::PlaySound(path.into()))
+        });
+        let q = queue.clone();
+        engine.register_fn("play_cutscene", move |name: &str| {
+            push(&q, ScriptCommand::PlayCutscene(name.into()))
+        });
+        let q = queue.clone();
+        engine.register_fn("start_dialogue", move |name: &str| {
+            push(&q, ScriptCommand::StartDialogue(name.into()))
+        });
+
+        Self {
+            engine,
+            queue,
+            loaded: None,
+        }
+    }
+}
+
+impl LevelScript {
+    fn load(&mut self, path: &str) -> anyhow::Result<()> {
+        let source = fs::read_to_string(format!("assets/{}", path))?;
+        let ast = self.engine.compile(&source)?;
+        let mut scope = Scope::new();
+        self.engine.run_ast_with_scope(&mut scope, &ast)?;
+        self.loaded = Some((ast, scope));
+        Ok(())
+    }
+
+    /// Calls a hook if the script defines it.
+    fn call(&mut self, hook: &str, mut args: Vec<Dynamic>) {
+        let (ast, scope) = match &mut self.loaded {
+            Some(loaded) => loaded,
+            None => return,
+        };
+        let defined = ast
+            .iter_functions()
+            .any(|function| function.name == hook && function.params.len() == args.len());
+        if !defined {
+            return;
+        }
+        if let Err(err) = self
+            .engine
+            .call_fn_raw(scope, ast, false, true, hook, None, &mut args)
+        {
+            warn!("Script hook {} failed: {}", hook, err);
+        }
+    }
+}
+
+fn tag_array(tagged: &Tagged, entity: Entity) -> Dynamic {
+    let tags: Array = tagged
+        .tags(entity)
+        .map(|tags| {
+            tags.iter()
+                .map(|tag| Dynamic::from(tag.to_string()))
+                .collect()
+        })
+        .unwrap_or_default();
+    Dynamic::from_array(tags)
+}
+
+fn load_level_script(
+    mut script: ResMut<LevelScript>,
+    current: Res<CurrentLevel>,
+    levels: Res<Assets<LevelDef>>,
+    mut events: EventReader<LevelLoaded>,
+) {
+    if events.iter().last().is_none() {
+        return;
+    }
+
+    script.loaded = None;
+    script.queue.lock().unwrap().clear();
+    let path = match levels
+        .get(&current.handle)
+        .and_then(|level| level.script.clone())
+    {
+        Some(path) => path,
+        None => return,
+    };
+    match script.load(&path) {
+        Ok(()) => {
+            info!("Loaded level script {}", path);
+            script.call("on_level_loaded", vec![]);
+        }
+        Err(err) => warn!("Failed to load level script {}: {}", path, err),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn run_script_hooks(
+    mut script: ResMut<LevelScript>,
+    tagged: Tagged,
+    mut triggered: EventReader<Triggered>,
+    mut goals: EventReader<GoalReached>,
+    mut wave_started: EventReader<WaveStarted>,
+    mut wave_cleared: EventReader<WaveCleared>,
+    mut cutscenes: EventReader<CutsceneFinished>,
+    mut dialogues: EventReader<DialogueFinished>,
+) {
+    for event in triggered.iter() {
+        script.call("on_trigger", vec![tag_array(&tagged, event.trigger)]);
+    }
+    for event in goals.iter() {
+        script.call("on_goal", vec![tag_array(&tagged, event.goal)]);
+    }
+    for event in wave_started.iter() {
+        let args = vec![(event.wave as INT).into(), (event.total as INT).into()];
+        script.call("on_wave_started", args);
+    }
+    for event in wave_cleared.iter() {
+        let args = vec![(event.wave as INT).into(), (event.total as INT).into()];
+        script.call("on_wave_cleared", args);
+    }
+    for CutsceneFinished(name) in cutscenes.iter() {
+        script.call("on_cutscene_finished", vec![name.clone().into()]);
+    }
+    for DialogueFinished(name) in dialogues.iter() {
+        script.call("on_dialogue_finished", vec![name.clone().into()]);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn apply_script_commands(
+    mut commands: Commands,
+    script: Res<LevelScript>,
+    tagged: Tagged,
+    transforms: Query<&Transform>,
+    mut lights: Query<&mut DirectionalLight>,
+    asset_server: Res<AssetServer>,
+    audio: Res<Audio>,
+    mut cutscenes: EventWriter<PlayCutscene>,
+    mut dialogues: EventWriter<StartDialogue>,
+) {
+    let queue = std::mem::take(&mut *script.queue.lock().unwrap());
+    for command in queue {
+        match command {
+            ScriptCommand::Action(action) => {
+                apply_action(&mut commands, &tagged, &transforms, &action);
+            }
+            ScriptCommand::SetLight { color, illuminance } => {
+                for mut light in &mut lights {
+                    light.color = color;
+                    light.illuminance = illuminance;
+                }
+            }
+            ScriptCommand::PlaySound(path) => {
+                audio.play(asset_server.load(&path));
+            }
+            ScriptCommand::PlayCutscene(name) => cutscenes.send(PlayCutscene(name)),
+            ScriptCommand::StartDialogue(name) => dialogues.send(StartDialogue(name)),
+        }
+    }
+}
@@ -0,0 +1,225 @@
+//! A sky above the walls instead of the void.
+//!
+//! A large dome around the player camera is painted with a gradient from the horizon to the
+//! zenith, and a glowing disc marks the sun. Both are emissive, so the path tracer also takes them
+//! as light from the environment. [`SkySettings`] holds the day and night colors; the sky blends
+//! between them by how high the sun stands, so it follows the sun as it moves.
+
+use crate::{layers::SpawnOnLayerExt, PlayerCamera};
+use bevy::{prelude::*, render::mesh::Indices};
+use bevy_inspector_egui::Inspectable;
+
+const DOME_RADIUS: f32 = 400.0;
+/// The sun disc sits just inside the dome.
+const SUN_DISTANCE: f32 = 0.95 * DOME_RADIUS;
+/// How much light the sky gives off, relative to its color.
+const SKY_EMISSION: f32 = 0.3;
+const SUN_EMISSION: f32 = 8.0;
+
+pub struct SkyPlugin;
+
+impl Plugin for SkyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SkySettings>()
+            .init_resource::<SkyColors>()
+            .add_startup_system(spawn_sky)
+            .add_system(follow_camera)
+            .add_system(update_sky);
+    }
+}
+
+#[derive(Debug, Clone, Inspectable)]
+pub struct SkySettings {
+    pub day_horizon: Color,
+    pub day_zenith: Color,
+    pub night_horizon: Color,
+    pub night_zenith: Color,
+    pub sun_color: Color,
+    /// Angular size of the sun disc, in degrees.
+    #[inspectable(min = 0.0, max = 20.0)]
+    pub sun_size: f32,
+}
+
+impl Default for SkySettings {
+    fn default() -> Self {
+        Self {
+            day_horizon: Color::rgb(0.75, 0.82, 0.9),
+            day_zenith: Color::rgb(0.25, 0.45, 0.8),
+            night_horizon: Color::rgb(0.08, 0.08, 0.14),
+            night_zenith: Color::rgb(0.01, 0.01, 0.04),
+            sun_color: Color::rgb(1.0, 0.9, 0.7),
+            sun_size: 5.0,
+        }
+    }
+}
+
+impl SkySettings {
+    /// Colors at the horizon and the zenith with the sun at `elevation` radians.
+    pub fn colors(&self, elevation: f32) -> (Color, Color) {
+        // Dusk runs from a little below the horizon to a little above.
+        let day = ((elevation.sin() + 0.1) / 0.3).clamp(0.0, 1.0);
+        let blend = |night: Color, day_color: Color| {
+            let color = Vec4::from(night.as_linear_rgba_f32())
+                .lerp(Vec4::from(day_color.as_linear_rgba_f32()), day);
+            Color::rgba_linear(color.x, color.y, color.z, 1.0)
+        };
+        (
+            blend(self.night_horizon, self.day_horizon),
+            blend(self.night_zenith, self.day_zenith),
+        )
+    }
+}
+
+/// Colors the dome is painted with right now, for whatever else follows the time of day.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct SkyColors {
+    pub horizon: Color,
+    pub zenith: Color,
+}
+
+#[derive(Component)]
+struct SkyDome;
+
+#[derive(Component)]
+struct SunDisc;
+
+/// A sphere seen from inside.
+fn dome_mesh() -> Mesh {
+    let mut mesh = Mesh::from(shape::UVSphere {
+        radius: DOME_RADIUS,
+        sectors: 32,
+        stacks: 16,
+    });
+    if let Some(Indices::U32(indices)) = mesh.indices_mut() {
+        for triangle in indices.chunks_exact_mut(3) {
+            triangle.swap(1, 2);
+        }
+    }
+    if let Some(normals) = mesh.attribute(Mesh::ATTRIBUTE_NORMAL) {
+        let flipped: Vec<[f32; 3]> = normals
+            .as_float3()
+            .unwrap_or_default()
+            .iter()
+            .map(|[x, y, z]| [-x, -y, -z])
+            .collect();
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, flipped);
+    }
+    mesh
+}
+
+fn spawn_sky(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands
+        .spawn_on_world_layer(PbrBundle {
+            mesh: meshes.add(dome_mesh()),
+            material: materials.add(StandardMaterial {
+                base_color: Color::WHITE,
+                unlit: true,
+                ..default()
+            }),
+            ..default()
+        })
+        .insert(SkyDome);
+
+    commands
+        .spawn_on_world_layer(PbrBundle {
+            mesh: meshes.add(Mesh::from(shape::Icosphere {
+                radius: 1.0,
+                subdivisions: 2,
+            })),
+            material: materials.add(StandardMaterial {
+                unlit: true,
+                ..default()
+            }),
+            ..default()
+        })
+        .insert(SunDisc);
+}
+
+fn follow_camera(
+    cameras: Query<&GlobalTransform, With<PlayerCamera>>,
+    mut domes: Query<&mut Transform, With<SkyDome>>,
+) {
+    let camera = match cameras.get_single() {
+        Ok(camera) => camera.translation(),
+        Err(_) => return,
+    };
+    for mut transform in &mut domes {
+        transform.translation = camera;
+    }
+}
+
+/// Repaints the dome and moves the sun disc once the sun or the settings change.
+#[allow(clippy::type_complexity)]
+fn update_sky(
+    settings: Res<SkySettings>,
+    mut painted: ResMut<SkyColors>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut clear_color: ResMut<ClearColor>,
+    cameras: Query<&GlobalTransform, With<PlayerCamera>>,
+    lights: Query<&GlobalTransform, With<DirectionalLight>>,
+    domes: Query<(&Handle<Mesh>, &Handle<StandardMaterial>), With<SkyDome>>,
+    mut suns: Query<(&mut Transform, &Handle<StandardMaterial>), (With<SunDisc>, Without<SkyDome>)>,
+) {
+    let light = match lights.iter().next() {
+        Some(light) => light,
+        None => return,
+    };
+    // Light shines along its forward, so the sun is the other way.
+    let to_sun = -light.forward();
+    let elevation = to_sun.y.clamp(-1.0, 1.0).asin();
+    let camera = cameras
+        .get_single()
+        .map_or(Vec3::ZERO, |camera| camera.translation());
+
+    for (mut transform, material) in &mut suns {
+        let size = SUN_DISTANCE * (0.5 * settings.sun_size).to_radians().tan();
+        *transform = Transform::from_translation(camera + SUN_DISTANCE * to_sun)
+            .with_scale(Vec3::splat(size));
+        if settings.is_changed() {
+            if let Some(material) = materials.get_mut(material) {
+                material.base_color = settings.sun_color;
+                material.emissive = settings.sun_color * SUN_EMISSION;
+            }
+        }
+    }
+
+    let (horizon, zenith) = settings.colors(elevation);
+    let colors = SkyColors { horizon, zenith };
+    // Compare first, so that the resource only reports a change when the colors do.
+    if *painted == colors {
+        return;
+    }
+    *painted = colors;
+    clear_color.0 = horizon;
+
+    for (mesh, material) in &domes {
+        if let Some(mesh) = meshes.get_mut(mesh) {
+            let colors: Vec<[f32; 4]> = match mesh.attribute(Mesh::ATTRIBUTE_POSITION) {
+                Some(positions) => positions
+                    .as_float3()
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|[_, y, _]| {
+                        let height = (y / DOME_RADIUS).max(0.0);
+                        let color = Vec4::from(horizon.as_linear_rgba_f32())
+                            .lerp(Vec4::from(zenith.as_linear_rgba_f32()), height);
+                        color.to_array()
+                    })
+                    .collect(),
+                None => continue,
+            };
+            mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+        }
+        if let Some(material) = materials.get_mut(material) {
+            let glow = Vec4::from(horizon.as_linear_rgba_f32())
+                .lerp(Vec4::from(zenith.as_linear_rgba_f32()), 0.5)
+                * SKY_EMISSION;
+            material.emissive = Color::rgba_linear(glow.x, glow.y, glow.z, 1.0);
+        }
+    }
+}
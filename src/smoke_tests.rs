@@ -0,0 +1,154 @@
+//! Gameplay smoke tests.
+//!
+//! Each test boots the whole game headless, waits for the first level, then plays scripted input
+//! through [`ReplayState::Playback`] (the same way recorded replays drive the player) and checks
+//! what happened to the world.
+
+use crate::{
+    build_app,
+    cli::Args,
+    headless::TICK_RATE,
+    level::Goal,
+    loading::AppState,
+    presence::SessionScore,
+    replay::{Replay, ReplayFrame, ReplayState},
+    Action, CatchObject, Player,
+};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use clap::Parser;
+use leafwing_input_manager::Actionlike;
+use std::{thread, time::Duration};
+
+/// Updates allowed for the first level to load.
+const MAX_LOADING_UPDATES: usize = 10_000;
+
+fn boot() -> App {
+    let mut app = build_app(Args::parse_from(["pumpkin-jam", "--headless"]));
+    for _ in 0..MAX_LOADING_UPDATES {
+        app.update();
+        let playing = *app.world.resource::<State<AppState>>().current() == AppState::Playing;
+        let spawned = app
+            .world
+            .query_filtered::<(), With<CatchObject>>()
+            .iter(&app.world)
+            .next()
+            .is_some();
+        if playing && spawned {
+            return app;
+        }
+        // Assets load on other threads.
+        thread::sleep(Duration::from_millis(1));
+    }
+    panic!("The first level did not load");
+}
+
+/// One tick of input, holding `actions` and walking along `movement`.
+fn frame(actions: &[Action], movement: Vec2) -> ReplayFrame {
+    let mut pressed = actions
+        .iter()
+        .fold(0, |pressed, action| pressed | 1 << action.index());
+    let mut axis_pairs = vec![];
+    if movement != Vec2::ZERO {
+        pressed |= 1 << Action::Move.index();
+        axis_pairs.push((Action::Move.index() as u8, movement.to_array()));
+    }
+    ReplayFrame {
+        delta: (1.0 / TICK_RATE) as f32,
+        pressed,
+        axis_pairs,
+        pose: default(),
+    }
+}
+
+/// Plays `frames` as the player's input, then lets the world settle for as long again.
+fn play(app: &mut App, frames: Vec<ReplayFrame>) {
+    let ticks = frames.len();
+    let timestep_mode = app.world.resource::<RapierConfiguration>().timestep_mode;
+    *app.world.resource_mut::<ReplayState>() = ReplayState::Playback {
+        replay: Replay {
+            frames,
+            ..default()
+        },
+        cursor: 0,
+        timestep_mode,
+    };
+    for _ in 0..2 * ticks {
+        app.update();
+    }
+}
+
+fn player_position(app: &mut App) -> Vec3 {
+    app.world
+        .query_filtered::<&GlobalTransform, With<Player>>()
+        .single(&app.world)
+        .translation()
+}
+
+fn object_positions(app: &mut App) -> Vec<(Entity, Vec3)> {
+    app.world
+        .query_filtered::<(Entity, &GlobalTransform), With<CatchObject>>()
+        .iter(&app.world)
+        .map(|(entity, transform)| (entity, transform.translation()))
+        .collect()
+}
+
+#[test]
+fn walk_forward() {
+    let mut app = boot();
+    let start = player_position(&mut app);
+
+    play(&mut app, vec![frame(&[], Vec2::Y); 120]);
+
+    let distance = player_position(&mut app).distance(start);
+    assert!(distance > 1.0, "the player moved {} m", distance);
+}
+
+#[test]
+fn catch_and_throw() {
+    let mut app = boot();
+    let start = object_positions(&mut app);
+
+    let mut frames = vec![frame(&[Action::Catch], Vec2::ZERO); 90];
+    frames.extend(vec![frame(&[], Vec2::ZERO); 30]);
+    play(&mut app, frames);
+
+    let end = object_positions(&mut app);
+    let moved = start
+        .iter()
+        .filter_map(|(entity, start)| {
+            let (_, end) = end.iter().find(|(other, _)| other == entity)?;
+            Some(end.distance(*start))
+        })
+        .fold(0.0, f32::max);
+    assert!(moved > 3.0, "no object moved more than {} m", moved);
+}
+
+#[test]
+fn goal_scores() {
+    let mut app = boot();
+    let (object, position) = object_positions(&mut app)[0];
+
+    // A goal on the floor next to the object, which then drops into it.
+    let goal = Vec3::new(position.x + 5.0, 1.0, position.z);
+    app.world
+        .spawn()
+        .insert_bundle(SpatialBundle {
+            transform: Transform::from_translation(goal),
+            ..default()
+        })
+        .insert_bundle((
+            Collider::cuboid(2.0, 1.0, 2.0),
+            Sensor,
+            ActiveEvents::COLLISION_EVENTS,
+            Goal,
+        ));
+    app.world
+        .entity_mut(object)
+        .insert(Transform::from_translation(goal + 3.0 * Vec3::Y))
+        .insert(Velocity::linear(-10.0 * Vec3::Y));
+
+    play(&mut app, vec![frame(&[], Vec2::ZERO); 60]);
+
+    assert!(app.world.resource::<SessionScore>().0 > 0, "no goal scored");
+}
@@ -0,0 +1,111 @@
+//! Sockets that hold the right object once it lands in them, for puzzles.
+//!
+//! A [`Socket`] takes the first [`SocketItem`] matching what it accepts that comes within its
+//! tolerance, whether thrown there or put down. Physics bodies are held with a fixed joint, so a
+//! socketed cube can still be seen wobbling in place; carried items lying still are simply moved
+//! in. Either way a [`SocketFilled`] goes out, which powers the devices linked to the socket.
+
+use crate::{hold::Held, interact::Interactable, merge::Mergeable, CatchObject};
+use bevy::{prelude::*, utils::HashSet};
+use bevy_rapier3d::prelude::*;
+use serde::Deserialize;
+
+pub struct SocketPlugin;
+
+impl Plugin for SocketPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SocketFilled>().add_system(fill_sockets);
+    }
+}
+
+/// What an object counts as when it comes to sockets.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Component)]
+pub enum SocketItem {
+    /// A cube with the material of this name in the material library.
+    Cube(String),
+    Battery,
+}
+
+impl SocketItem {
+    /// Name of the material sockets for it are drawn with.
+    pub fn material(&self) -> &str {
+        match self {
+            SocketItem::Cube(material) => material,
+            SocketItem::Battery => "battery",
+        }
+    }
+}
+
+#[derive(Debug, Component)]
+pub struct Socket {
+    pub accepts: SocketItem,
+    /// Farthest the center of an item can be from the socket to snap in, in meters.
+    pub tolerance: f32,
+    pub item: Option<Entity>,
+}
+
+impl Socket {
+    pub fn new(accepts: SocketItem, tolerance: f32) -> Self {
+        Self {
+            accepts,
+            tolerance,
+            item: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SocketFilled {
+    pub socket: Entity,
+    pub item: Entity,
+}
+
+/// Items in a hand or already socketed have a parent, and held cubes are still on their way.
+#[allow(clippy::type_complexity)]
+fn fill_sockets(
+    mut commands: Commands,
+    mut events: EventWriter<SocketFilled>,
+    mut sockets: Query<(Entity, &mut Socket, &GlobalTransform)>,
+    items: Query<
+        (Entity, &SocketItem, &GlobalTransform, Option<&RigidBody>),
+        (Without<Parent>, Without<Held>),
+    >,
+) {
+    // Taking an item out only happens at the end of the stage, so two sockets could both find it.
+    let mut claimed = HashSet::default();
+    for (socket_entity, mut socket, socket_transform) in &mut sockets {
+        if socket.item.is_some() {
+            continue;
+        }
+        let center = socket_transform.translation();
+        let found = items.iter().find(|(entity, item, transform, _)| {
+            **item == socket.accepts
+                && transform.translation().distance(center) <= socket.tolerance
+                && !claimed.contains(entity)
+        });
+        let (item, _, _, body) = match found {
+            Some(found) => found,
+            None => continue,
+        };
+
+        claimed.insert(item);
+        let mut entity = commands.entity(item);
+        entity.remove::<SocketItem>();
+        if body == Some(&RigidBody::Dynamic) {
+            // Taken out of play: no catching it back out, and no merging into it.
+            entity
+                .remove_bundle::<(CatchObject, Mergeable)>()
+                .insert(ImpulseJoint::new(socket_entity, FixedJointBuilder::new()));
+        } else {
+            entity
+                .remove_bundle::<(Collider, Sensor, Interactable)>()
+                .insert(Transform::identity());
+            commands.entity(socket_entity).add_child(item);
+        }
+        socket.item = Some(item);
+        events.send(SocketFilled {
+            socket: socket_entity,
+            item,
+        });
+    }
+}
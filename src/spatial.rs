@@ -0,0 +1,154 @@
+//! A grid of who is where, for proximity queries that would otherwise walk every entity.
+//!
+//! [`SpatialIndex<T>`] buckets the entities with component `T` into cubic cells of [`CELL_SIZE`]
+//! meters, each with a bounding radius from its collider. It is rebuilt from transforms at the
+//! start of every frame, so queries during the frame see where things were after the last one.
+//! Catching, bots and the physics activity manager ask it for what is near a point or inside a
+//! cone; whatever else needs the same should add a [`SpatialIndexPlugin`] for its component.
+
+use bevy::{prelude::*, utils::HashMap};
+use bevy_rapier3d::prelude::*;
+use std::marker::PhantomData;
+
+pub const CELL_SIZE: f32 = 4.0;
+
+pub struct SpatialIndexPlugin<T>(PhantomData<T>);
+
+impl<T> Default for SpatialIndexPlugin<T> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<T: Component> Plugin for SpatialIndexPlugin<T> {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SpatialIndex<T>>()
+            .add_system_to_stage(CoreStage::PreUpdate, rebuild_index::<T>);
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SpatialEntry {
+    pub entity: Entity,
+    pub position: Vec3,
+    /// Radius of a sphere around `position` holding the whole collider.
+    pub radius: f32,
+}
+
+pub struct SpatialIndex<T> {
+    cells: HashMap<IVec3, Vec<SpatialEntry>>,
+    /// Largest radius of any entry, so queries know how far around them to look.
+    max_radius: f32,
+    marker: PhantomData<T>,
+}
+
+impl<T> Default for SpatialIndex<T> {
+    fn default() -> Self {
+        Self {
+            cells: default(),
+            max_radius: 0.0,
+            marker: PhantomData,
+        }
+    }
+}
+
+fn cell(position: Vec3) -> IVec3 {
+    (position / CELL_SIZE).floor().as_ivec3()
+}
+
+impl<T> SpatialIndex<T> {
+    /// Entries in the cells overlapping a box `extent` around `center`.
+    fn around(&self, center: Vec3, extent: f32) -> Box<dyn Iterator<Item = &SpatialEntry> + '_> {
+        let extent = extent + self.max_radius;
+        // Past a point, looking the cells up one by one is slower than going through them all.
+        let span = 2.0 * extent / CELL_SIZE + 1.0;
+        if span * span * span > self.cells.len() as f32 {
+            return Box::new(self.cells.values().flatten());
+        }
+        let (min, max) = (cell(center - extent), cell(center + extent));
+        Box::new(
+            (min.x..=max.x)
+                .flat_map(move |x| (min.y..=max.y).map(move |y| (x, y)))
+                .flat_map(move |(x, y)| (min.z..=max.z).map(move |z| IVec3::new(x, y, z)))
+                .filter_map(|key| self.cells.get(&key))
+                .flatten(),
+        )
+    }
+
+    /// Entries touching the sphere of `radius` around `center`.
+    pub fn within(&self, center: Vec3, radius: f32) -> Vec<SpatialEntry> {
+        self.around(center, radius)
+            .filter(|entry| entry.position.distance(center) <= radius + entry.radius)
+            .copied()
+            .collect()
+    }
+
+    /// Entries touching the cone from `apex` along `direction`, `range` long and `angle` radians
+    /// from its axis to its side.
+    pub fn in_cone(
+        &self,
+        apex: Vec3,
+        direction: Vec3,
+        range: f32,
+        angle: f32,
+    ) -> Vec<SpatialEntry> {
+        let direction = direction.normalize_or_zero();
+        let (sin, cos) = angle.sin_cos();
+        // A sphere around the middle of the axis that holds the rim of the base.
+        let extent = range * (0.25 + angle.tan().powi(2)).sqrt();
+        self.around(apex + 0.5 * range * direction, extent)
+            .filter(|entry| {
+                let offset = entry.position - apex;
+                let along = offset.dot(direction);
+                if along < -entry.radius || along > range + entry.radius {
+                    return false;
+                }
+                // Distance from the side of the cone, outwards.
+                let across = (offset - along * direction).length();
+                across * cos - along * sin <= entry.radius
+            })
+            .copied()
+            .collect()
+    }
+
+    /// The entry closest to `point`, no farther than `max_distance`.
+    pub fn nearest(&self, point: Vec3, max_distance: f32) -> Option<SpatialEntry> {
+        self.around(point, max_distance)
+            .filter(|entry| entry.position.distance(point) <= max_distance)
+            .min_by(|a, b| {
+                a.position
+                    .distance_squared(point)
+                    .total_cmp(&b.position.distance_squared(point))
+            })
+            .copied()
+    }
+}
+
+fn rebuild_index<T: Component>(
+    mut index: ResMut<SpatialIndex<T>>,
+    entities: Query<(Entity, &GlobalTransform, Option<&Collider>), With<T>>,
+) {
+    let index = &mut *index;
+    // Keep the buckets, so a steady crowd doesn't allocate every frame.
+    for entries in index.cells.values_mut() {
+        entries.clear();
+    }
+    index.max_radius = 0.0;
+    for (entity, transform, collider) in &entities {
+        let (scale, _, position) = transform.to_scale_rotation_translation();
+        let radius = collider.map_or(0.0, |collider| {
+            collider.raw.compute_local_aabb().half_extents().norm() * scale.max_element()
+        });
+        index.max_radius = index.max_radius.max(radius);
+        index
+            .cells
+            .entry(cell(position))
+            .or_default()
+            .push(SpatialEntry {
+                entity,
+                position,
+                radius,
+            });
+    }
+    index.cells.retain(|_, entries| !entries.is_empty());
+}
@@ -0,0 +1,144 @@
+//! Spawners that keep an arena stocked with cubes.
+//!
+//! A [`CubeSpawner`] drops a cube every so often while fewer than its cap of the cubes it made are
+//! still around, so cubes lost to merging or falling out of the world come back. Levels place them
+//! with [`LevelObject::Spawner`], and the waves with [`SpawnKind::Spawner`]. Only the host spawns.
+//!
+//! [`LevelObject::Spawner`]: crate::level::LevelObject::Spawner
+//! [`SpawnKind::Spawner`]: crate::waves::SpawnKind::Spawner
+
+use crate::{
+    level::LevelEntity, lifetime::Debris, materials::MaterialLibrary, net::Network,
+    props::SpawnPropExt, waves::WaveMember, CUBE_SIZE,
+};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use rand::Rng;
+use serde::Deserialize;
+
+pub struct SpawnerPlugin;
+
+impl Plugin for SpawnerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SpawnerAssets>()
+            .add_system(add_spawner_timers)
+            .add_system(run_spawners.after(add_spawner_timers));
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Component)]
+pub struct CubeSpawner {
+    /// Seconds between cubes.
+    #[serde(default = "default_interval")]
+    pub interval: f32,
+    /// Most cubes from this spawner around at once.
+    #[serde(default = "default_max_alive")]
+    pub max_alive: usize,
+    /// Largest speed, in meters per second, a cube leaves the spawner with, in a random direction.
+    #[serde(default)]
+    pub jitter: f32,
+}
+
+fn default_interval() -> f32 {
+    2.0
+}
+
+fn default_max_alive() -> usize {
+    6
+}
+
+impl Default for CubeSpawner {
+    fn default() -> Self {
+        Self {
+            interval: default_interval(),
+            max_alive: default_max_alive(),
+            jitter: 0.0,
+        }
+    }
+}
+
+/// A cube from the spawner it points to.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct Spawned(pub Entity);
+
+#[derive(Component)]
+struct SpawnerTimer(Timer);
+
+struct SpawnerAssets {
+    cube_mesh: Handle<Mesh>,
+}
+
+impl FromWorld for SpawnerAssets {
+    fn from_world(world: &mut World) -> Self {
+        let cube_mesh = world
+            .resource_mut::<Assets<Mesh>>()
+            .add(shape::Cube::new(CUBE_SIZE).into());
+        Self { cube_mesh }
+    }
+}
+
+fn add_spawner_timers(
+    mut commands: Commands,
+    spawners: Query<(Entity, &CubeSpawner), Changed<CubeSpawner>>,
+) {
+    for (entity, spawner) in &spawners {
+        let timer = Timer::from_seconds(spawner.interval.max(0.1), true);
+        commands.entity(entity).insert(SpawnerTimer(timer));
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn run_spawners(
+    mut commands: Commands,
+    time: Res<Time>,
+    network: Res<Network>,
+    assets: Res<SpawnerAssets>,
+    mut library: ResMut<MaterialLibrary>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut spawners: Query<(
+        Entity,
+        &CubeSpawner,
+        &mut SpawnerTimer,
+        &GlobalTransform,
+        Option<&WaveMember>,
+    )>,
+    cubes: Query<&Spawned>,
+) {
+    if !network.is_authoritative() {
+        return;
+    }
+
+    let mut rng = rand::thread_rng();
+    for (entity, spawner, mut timer, transform, member) in &mut spawners {
+        if !timer.0.tick(time.delta()).just_finished() {
+            continue;
+        }
+        let alive = cubes.iter().filter(|cube| cube.0 == entity).count();
+        if alive >= spawner.max_alive {
+            continue;
+        }
+
+        let direction = Vec3::new(
+            rng.gen_range(-1.0..=1.0),
+            rng.gen_range(-1.0..=1.0),
+            rng.gen_range(-1.0..=1.0),
+        )
+        .normalize_or_zero();
+        let speed = rng.gen_range(0.0..=spawner.jitter.max(0.0));
+        let mut cube = commands.spawn_catch_cube(
+            assets.cube_mesh.clone(),
+            library.get("cube", &mut materials),
+            "cube",
+            Transform::from_translation(transform.translation()),
+        );
+        cube.insert_bundle((
+            Velocity::linear(speed * direction),
+            Spawned(entity),
+            LevelEntity,
+            Debris,
+        ));
+        if let Some(member) = member {
+            cube.insert(WaveMember(member.0));
+        }
+    }
+}
@@ -0,0 +1,213 @@
+//! Watching the match while knocked out.
+//!
+//! Right after a [`DeathEvent`] the camera orbits the object that knocked the player out, if any,
+//! and otherwise stays on the ragdoll. After [`WATCH_TIME`] it moves on to the other players, if
+//! there are any, and Repel and Catch cycle through them. A line of text at the bottom of the HUD
+//! says who is being watched and how long until respawn.
+
+use crate::{
+    death::{Dead, DeathEvent, RespawnEvent},
+    hud::bitmap::{BitmapAlign, BitmapText, BitmapTextBundle},
+    layers::SpawnOnLayerExt,
+    locale::Locale,
+    net::RemotePlayer,
+    ragdoll::follow_torso,
+    Action, Player, PlayerCamera, RENDER_SIZE,
+};
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::*;
+
+/// Time spent on the object or ragdoll before switching to other players, in seconds.
+const WATCH_TIME: f32 = 1.5;
+/// Where the camera watches a player from, in their space.
+const PLAYER_VIEW: Vec3 = Vec3::new(0.0, 2.0, 4.0);
+/// Distance and height of the camera orbiting an object, in meters.
+const ORBIT_RADIUS: f32 = 5.0;
+const ORBIT_HEIGHT: f32 = 2.0;
+/// Radians per second.
+const ORBIT_SPEED: f32 = 0.6;
+const TEXT_MARGIN: f32 = 24.0;
+
+pub struct SpectatePlugin;
+
+impl Plugin for SpectatePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Spectate>()
+            .add_startup_system(setup_spectate_text)
+            .add_system(start_spectating)
+            .add_system(pick_spectate_target.after(start_spectating))
+            .add_system(
+                spectate_camera
+                    .after(pick_spectate_target)
+                    .after(follow_torso),
+            )
+            .add_system(update_spectate_text.after(pick_spectate_target));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpectateTarget {
+    Player(Entity),
+    /// The object that knocked the player out.
+    Object(Entity),
+}
+
+#[derive(Debug, Default)]
+pub struct Spectate {
+    pub target: Option<SpectateTarget>,
+    /// Seconds since the player was knocked out.
+    elapsed: f32,
+}
+
+#[derive(Component)]
+struct SpectateText;
+
+fn setup_spectate_text(mut commands: Commands) {
+    let position = Vec2::new(0.0, -0.5 * RENDER_SIZE[1] as f32 + TEXT_MARGIN);
+    commands
+        .spawn_on_hud_layer(BitmapTextBundle::new(
+            BitmapText::new("", Color::WHITE, BitmapAlign::Center),
+            position,
+        ))
+        .insert(SpectateText);
+}
+
+fn start_spectating(
+    mut spectate: ResMut<Spectate>,
+    mut deaths: EventReader<DeathEvent>,
+    mut respawns: EventReader<RespawnEvent>,
+    players: Query<(), With<Player>>,
+) {
+    for event in deaths.iter().filter(|event| players.contains(event.player)) {
+        spectate.target = event.object.map(SpectateTarget::Object);
+        spectate.elapsed = 0.0;
+    }
+    if respawns.iter().any(|event| players.contains(event.player)) {
+        spectate.target = None;
+    }
+}
+
+/// Moves on to the other players, and cycles through them on input.
+fn pick_spectate_target(
+    time: Res<Time>,
+    mut spectate: ResMut<Spectate>,
+    player: Query<&ActionState<Action>, (With<Player>, With<Dead>)>,
+    others: Query<(Entity, &RemotePlayer)>,
+    objects: Query<(), With<GlobalTransform>>,
+) {
+    let action_state = match player.get_single() {
+        Ok(action_state) => action_state,
+        Err(_) => return,
+    };
+    spectate.elapsed += time.delta_seconds();
+
+    // Objects merged away or despawned leave the camera on the ragdoll.
+    if let Some(SpectateTarget::Object(object)) = spectate.target {
+        if !objects.contains(object) {
+            spectate.target = None;
+        }
+    }
+
+    let mut others: Vec<_> = others.iter().collect();
+    if others.is_empty() || spectate.elapsed < WATCH_TIME {
+        return;
+    }
+    others.sort_by_key(|(_, remote)| remote.peer);
+    let current = others
+        .iter()
+        .position(|(entity, _)| spectate.target == Some(SpectateTarget::Player(*entity)));
+    let index = match current {
+        None => 0,
+        Some(index) if action_state.just_pressed(Action::Repel) => (index + 1) % others.len(),
+        Some(index) if action_state.just_pressed(Action::Catch) => {
+            (index + others.len() - 1) % others.len()
+        }
+        Some(index) => index,
+    };
+    spectate.target = Some(SpectateTarget::Player(others[index].0));
+}
+
+/// Puts the camera behind the watched player, or circling the watched object.
+fn spectate_camera(
+    spectate: Res<Spectate>,
+    transforms: Query<&GlobalTransform, Without<PlayerCamera>>,
+    players: Query<&GlobalTransform, (With<Player>, With<Dead>)>,
+    mut cameras: Query<&mut Transform, With<PlayerCamera>>,
+) {
+    let target = match spectate.target {
+        Some(target) => target,
+        None => return,
+    };
+    let player = match players.get_single() {
+        Ok(player) => player,
+        Err(_) => return,
+    };
+    let view = match target {
+        SpectateTarget::Player(entity) => match transforms.get(entity) {
+            Ok(transform) => {
+                let transform = transform.compute_transform();
+                let focus = transform.translation + Vec3::Y;
+                Transform::from_translation(
+                    transform.translation + transform.rotation * PLAYER_VIEW,
+                )
+                .looking_at(focus, Vec3::Y)
+            }
+            Err(_) => return,
+        },
+        SpectateTarget::Object(entity) => match transforms.get(entity) {
+            Ok(transform) => {
+                let focus = transform.translation();
+                let angle = ORBIT_SPEED * spectate.elapsed;
+                let offset =
+                    Quat::from_rotation_y(angle) * Vec3::new(0.0, ORBIT_HEIGHT, ORBIT_RADIUS);
+                Transform::from_translation(focus + offset).looking_at(focus, Vec3::Y)
+            }
+            Err(_) => return,
+        },
+    };
+    // The camera hangs off the player body, which stands still while knocked out.
+    let local = Transform::from_matrix(player.compute_matrix().inverse() * view.compute_matrix());
+    for mut camera in &mut cameras {
+        *camera = local;
+    }
+}
+
+fn update_spectate_text(
+    spectate: Res<Spectate>,
+    locale: Res<Locale>,
+    player: Query<&Dead, With<Player>>,
+    others: Query<&RemotePlayer>,
+    mut texts: Query<&mut BitmapText, With<SpectateText>>,
+) {
+    let value = match player.get_single() {
+        Ok(dead) => {
+            let remaining = (dead.timer.duration() - dead.timer.elapsed()).as_secs_f32();
+            let countdown = locale.get_args(
+                "respawn-countdown",
+                &[("seconds", (remaining.ceil() as u32).into())],
+            );
+            match spectate.target {
+                Some(SpectateTarget::Player(entity)) => {
+                    let name = others
+                        .get(entity)
+                        .map_or_else(|_| "?".into(), |remote| format!("P{}", remote.peer));
+                    format!(
+                        "{}\n{}\n{}",
+                        locale.get_args("spectating", &[("name", name.into())]),
+                        countdown,
+                        locale.get("spectate-hint")
+                    )
+                }
+                Some(SpectateTarget::Object(_)) | None => {
+                    format!("{}\n{}", locale.get("knocked-out"), countdown)
+                }
+            }
+        }
+        Err(_) => String::new(),
+    };
+    for mut text in &mut texts {
+        if text.value != value {
+            text.value = value.clone();
+        }
+    }
+}
@@ -0,0 +1,105 @@
+//! Stamina, spent on holding objects and sprinting.
+//!
+//! Holding something or sprinting drains the player's [`Stamina`]; after a moment of doing
+//! neither it refills. Running dry drops whatever the player holds, and both catching and
+//! sprinting stay off until the meter has refilled to [`RECOVER_FRACTION`].
+
+use crate::{Action, Player, PlayerCatch};
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::*;
+
+/// Drain per second while holding something.
+const HOLD_DRAIN: f32 = 12.0;
+/// Drain per second while sprinting.
+const SPRINT_DRAIN: f32 = 18.0;
+const REGEN_RATE: f32 = 25.0;
+/// Seconds of rest before stamina refills.
+const REGEN_DELAY: f32 = 0.8;
+/// Part of the meter to refill before an exhausted player can spend stamina again.
+pub const RECOVER_FRACTION: f32 = 0.3;
+
+pub struct StaminaPlugin;
+
+impl Plugin for StaminaPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Stamina>()
+            .add_system(add_stamina)
+            .add_system(update_stamina);
+    }
+}
+
+#[derive(Debug, Component, Reflect)]
+#[reflect(Component)]
+pub struct Stamina {
+    pub current: f32,
+    pub max: f32,
+    /// Whether the player is sprinting this frame.
+    pub sprinting: bool,
+    exhausted: bool,
+    rest: f32,
+}
+
+impl Default for Stamina {
+    fn default() -> Self {
+        Self {
+            current: 100.0,
+            max: 100.0,
+            sprinting: false,
+            exhausted: false,
+            rest: 0.0,
+        }
+    }
+}
+
+impl Stamina {
+    pub fn fraction(&self) -> f32 {
+        (self.current / self.max).clamp(0.0, 1.0)
+    }
+
+    /// Ran dry and hasn't recovered yet.
+    pub fn is_exhausted(&self) -> bool {
+        self.exhausted
+    }
+}
+
+fn add_stamina(mut commands: Commands, players: Query<Entity, Added<Player>>) {
+    for entity in &players {
+        commands.entity(entity).insert(Stamina::default());
+    }
+}
+
+fn update_stamina(
+    time: Res<Time>,
+    mut players: Query<(&ActionState<Action>, &PlayerCatch, &mut Stamina), With<Player>>,
+) {
+    let delta = time.delta_seconds();
+    for (action_state, catch, mut stamina) in &mut players {
+        stamina.sprinting = !stamina.exhausted
+            && action_state.pressed(Action::Sprint)
+            && action_state.pressed(Action::Move);
+
+        let mut drain = 0.0;
+        if catch.target.is_some() {
+            drain += HOLD_DRAIN;
+        }
+        if stamina.sprinting {
+            drain += SPRINT_DRAIN;
+        }
+
+        if drain > 0.0 {
+            stamina.rest = 0.0;
+            stamina.current = (stamina.current - drain * delta).max(0.0);
+        } else {
+            stamina.rest += delta;
+            if stamina.rest > REGEN_DELAY {
+                stamina.current = (stamina.current + REGEN_RATE * delta).min(stamina.max);
+            }
+        }
+
+        if stamina.current <= 0.0 {
+            stamina.exhausted = true;
+        } else if stamina.fraction() >= RECOVER_FRACTION {
+            stamina.exhausted = false;
+        }
+    }
+}
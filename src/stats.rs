@@ -0,0 +1,148 @@
+//! Lifetime statistics in the [`Profile`], and the screen that shows them.
+//!
+//! Each stat has a small system counting it from the events and state it comes from. Tab shows
+//! them all.
+
+use crate::{
+    ghost::TimeAttackFinished,
+    input_block::InputBlock,
+    level::{trigger::GoalReached, CurrentLevel, LevelDef},
+    locale::Locale,
+    profile::Profile,
+    stamina::Stamina,
+    Player, PlayerCatch,
+};
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+
+/// Moves longer than this in one frame are teleports, not travel.
+const MAX_STEP: f32 = 5.0;
+/// Travel is added to the profile in steps of this many meters, so it doesn't change every frame.
+const DISTANCE_STEP: f32 = 1.0;
+
+pub struct StatsPlugin;
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(count_throws)
+            .add_system(count_distance)
+            .add_system(count_goals)
+            .add_system(record_best_times)
+            .add_system(stats_screen);
+    }
+}
+
+fn count_throws(
+    mut profile: ResMut<Profile>,
+    mut holding: Local<bool>,
+    players: Query<(&PlayerCatch, Option<&Stamina>), With<Player>>,
+) {
+    let (catch, stamina) = match players.get_single() {
+        Ok(player) => player,
+        Err(_) => return,
+    };
+    // Letting go counts as a throw, unless it was a drop for lack of stamina.
+    let exhausted = stamina.map_or(false, Stamina::is_exhausted);
+    if *holding && catch.target.is_none() && !exhausted {
+        profile.stats.cubes_thrown += 1;
+    }
+    *holding = catch.target.is_some();
+}
+
+fn count_distance(
+    mut profile: ResMut<Profile>,
+    mut last: Local<Option<Vec3>>,
+    mut pending: Local<f32>,
+    players: Query<&GlobalTransform, With<Player>>,
+) {
+    let position = match players.get_single() {
+        Ok(transform) => transform.translation(),
+        Err(_) => return,
+    };
+    if let Some(last) = *last {
+        let step = position.distance(last);
+        if step < MAX_STEP {
+            *pending += step;
+        }
+    }
+    *last = Some(position);
+
+    if *pending >= DISTANCE_STEP {
+        profile.stats.distance += *pending;
+        *pending = 0.0;
+    }
+}
+
+fn count_goals(mut profile: ResMut<Profile>, mut goals: EventReader<GoalReached>) {
+    let count = goals.iter().count() as u32;
+    if count > 0 {
+        profile.stats.goals += count;
+    }
+}
+
+fn record_best_times(
+    current: Res<CurrentLevel>,
+    levels: Res<Assets<LevelDef>>,
+    mut profile: ResMut<Profile>,
+    mut finished: EventReader<TimeAttackFinished>,
+) {
+    let level = match levels.get(&current.handle) {
+        Some(level) => level.name.clone(),
+        None => return,
+    };
+    for event in finished.iter() {
+        let best = profile
+            .stats
+            .best_times
+            .entry(level.clone())
+            .or_insert(event.time);
+        *best = best.min(event.time);
+    }
+}
+
+fn stats_screen(
+    keys: Res<Input<KeyCode>>,
+    block: Res<InputBlock>,
+    locale: Res<Locale>,
+    profile: Res<Profile>,
+    mut open: Local<bool>,
+    mut egui_context: ResMut<EguiContext>,
+) {
+    if keys.just_pressed(KeyCode::Tab) && !block.typing() {
+        *open = !*open;
+    }
+    if !*open {
+        return;
+    }
+
+    let stats = &profile.stats;
+    egui::Window::new(locale.get("stats"))
+        .collapsible(false)
+        .resizable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            egui::Grid::new("stats").show(ui, |ui| {
+                let rows = [
+                    ("stats-thrown", stats.cubes_thrown.to_string()),
+                    ("stats-distance", format!("{:.0} m", stats.distance)),
+                    ("stats-goals", stats.goals.to_string()),
+                ];
+                for (key, value) in rows {
+                    ui.label(locale.get(key));
+                    ui.label(value);
+                    ui.end_row();
+                }
+            });
+
+            ui.separator();
+            ui.label(locale.get("stats-best-times"));
+            let mut times: Vec<_> = stats.best_times.iter().collect();
+            times.sort_by(|a, b| a.0.cmp(b.0));
+            egui::Grid::new("best-times").show(ui, |ui| {
+                for (level, time) in times {
+                    ui.label(level);
+                    ui.label(format!("{:.2} s", time));
+                    ui.end_row();
+                }
+            });
+        });
+}
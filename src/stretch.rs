@@ -0,0 +1,139 @@
+//! Streaks behind fast catch objects, so a throw reads as motion at 320x180 rather than as the cube
+//! jumping a few pixels every frame.
+//!
+//! Past [`STRETCH_SPEED`] a catch object gets a [`Streak`]: a copy of its mesh as a child, stretched
+//! back along the velocity by the distance covered in [`SHUTTER`] seconds. It leaves the collider
+//! alone, and is hidden again once the object slows down. It's an option in the display settings,
+//! off by default, and reduced motion keeps it off.
+
+use crate::{accessibility::AccessibilitySettings, profile::Profile, CatchObject, CUBE_SIZE};
+use bevy::{prelude::*, render::view::RenderLayers};
+use bevy_rapier3d::prelude::*;
+
+/// Objects faster than this, in meters per second, get a streak.
+pub const STRETCH_SPEED: f32 = 12.0;
+/// Seconds of travel a streak covers.
+const SHUTTER: f32 = 1.0 / 30.0;
+/// Longest streak, in cube sizes.
+const MAX_STRETCH: f32 = 4.0;
+
+pub struct StretchPlugin;
+
+impl Plugin for StretchPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(spawn_streaks)
+            .add_system(update_streaks.after(spawn_streaks));
+    }
+}
+
+/// Points from a catch object to its streak.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct Stretched(pub Entity);
+
+/// The stretched copy of a catch object's mesh, as its child.
+#[derive(Debug, Default, Clone, Copy, Component)]
+pub struct Streak;
+
+fn enabled(profile: &Profile, accessibility: &AccessibilitySettings) -> bool {
+    profile.display.motion_stretch && !accessibility.reduce_motion
+}
+
+#[allow(clippy::type_complexity)]
+fn spawn_streaks(
+    mut commands: Commands,
+    profile: Res<Profile>,
+    accessibility: Res<AccessibilitySettings>,
+    objects: Query<
+        (
+            Entity,
+            &Velocity,
+            &Handle<Mesh>,
+            &Handle<StandardMaterial>,
+            Option<&RenderLayers>,
+        ),
+        (With<CatchObject>, Without<Stretched>),
+    >,
+) {
+    if !enabled(&profile, &accessibility) {
+        return;
+    }
+    for (entity, velocity, mesh, material, layers) in &objects {
+        if velocity.linvel.length() <= STRETCH_SPEED {
+            continue;
+        }
+        let mut streak = commands.spawn_bundle(PbrBundle {
+            mesh: mesh.clone(),
+            material: material.clone(),
+            visibility: Visibility { is_visible: false },
+            ..default()
+        });
+        streak.insert(Streak);
+        if let Some(layers) = layers {
+            streak.insert(*layers);
+        }
+        let streak = streak.id();
+        commands
+            .entity(entity)
+            .insert(Stretched(streak))
+            .add_child(streak);
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn update_streaks(
+    profile: Res<Profile>,
+    accessibility: Res<AccessibilitySettings>,
+    objects: Query<
+        (
+            &Stretched,
+            &Velocity,
+            &GlobalTransform,
+            &Handle<Mesh>,
+            &Handle<StandardMaterial>,
+        ),
+        With<CatchObject>,
+    >,
+    mut streaks: Query<
+        (
+            &mut Transform,
+            &mut Visibility,
+            &mut Handle<Mesh>,
+            &mut Handle<StandardMaterial>,
+        ),
+        (With<Streak>, Without<CatchObject>),
+    >,
+) {
+    let enabled = enabled(&profile, &accessibility);
+    for (stretched, velocity, global, mesh, material) in &objects {
+        let (mut transform, mut visibility, mut streak_mesh, mut streak_material) =
+            match streaks.get_mut(stretched.0) {
+                Ok(streak) => streak,
+                Err(_) => continue,
+            };
+
+        let speed = velocity.linvel.length();
+        let visible = enabled && speed > STRETCH_SPEED;
+        if visibility.is_visible != visible {
+            visibility.is_visible = visible;
+        }
+        if !visible {
+            continue;
+        }
+
+        // Levels of detail and paint swap the object's handles; the streak follows.
+        if *streak_mesh != *mesh {
+            *streak_mesh = mesh.clone();
+        }
+        if *streak_material != *material {
+            *streak_material = material.clone();
+        }
+
+        // Stretch along the local Z axis, then turn that to trail behind the object.
+        let (_, rotation, _) = global.to_scale_rotation_translation();
+        let direction = rotation.inverse() * (velocity.linvel / speed);
+        let length = ((speed - STRETCH_SPEED) * SHUTTER).min(MAX_STRETCH * CUBE_SIZE);
+        transform.rotation = Quat::from_rotation_arc(Vec3::Z, direction);
+        transform.scale = Vec3::new(1.0, 1.0, 1.0 + length / CUBE_SIZE);
+        transform.translation = -0.5 * length * direction;
+    }
+}
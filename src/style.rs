@@ -0,0 +1,276 @@
+//! Style points for playing with flair.
+//!
+//! Detectors watch catches, throws, collisions and goals for moves worth rewarding: catching an
+//! object in mid-air, a throw that banks off a wall into a goal, and a juggle of more than one
+//! object in the air at once. Each move sends a [`StyleEvent`], which scores on the [`StyleMeter`]
+//! times its multiplier and raises the multiplier. Without a new move for a while the multiplier
+//! drains back down; the HUD shows it as a gauge with a rank letter.
+
+use crate::{
+    hud::popup::PopupEvent, level::trigger::GoalReached, locale::Locale, CatchObject, Player,
+    PlayerCatch,
+};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+/// Highest the multiplier goes.
+pub const MAX_MULTIPLIER: f32 = 5.0;
+/// Multiplier gained per move.
+const MULTIPLIER_STEP: f32 = 0.5;
+/// Seconds after a move before the multiplier starts draining.
+const GRACE: f32 = 2.0;
+/// How fast the multiplier drains, per second.
+const DRAIN_RATE: f32 = 0.5;
+/// Seconds a throw is followed for bank shots and juggles.
+const THROW_WINDOW: f32 = 5.0;
+/// Slowest an object moves to count as flying when caught, in meters per second.
+const AIR_SPEED: f32 = 4.0;
+/// Least clearance under an object for it to count as in the air, in meters.
+const AIR_HEIGHT: f32 = 1.0;
+const STYLE_COLOR: Color = Color::rgb(1.0, 0.5, 0.9);
+
+pub struct StylePlugin;
+
+impl Plugin for StylePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<StyleMeter>()
+            .add_event::<StyleEvent>()
+            .add_system(detect_catches_and_throws)
+            .add_system(detect_bounces)
+            .add_system(detect_bank_shots.after(detect_bounces))
+            .add_system(
+                score_style
+                    .after(detect_catches_and_throws)
+                    .after(detect_bank_shots),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StyleMove {
+    AirCatch,
+    BankShot,
+    Juggle,
+}
+
+impl StyleMove {
+    pub fn points(self) -> u32 {
+        match self {
+            StyleMove::AirCatch => 20,
+            StyleMove::BankShot => 50,
+            StyleMove::Juggle => 30,
+        }
+    }
+
+    pub fn key(self) -> &'static str {
+        match self {
+            StyleMove::AirCatch => "style-air-catch",
+            StyleMove::BankShot => "style-bank-shot",
+            StyleMove::Juggle => "style-juggle",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct StyleEvent {
+    pub style_move: StyleMove,
+    pub position: Vec3,
+}
+
+#[derive(Debug)]
+pub struct StyleMeter {
+    pub score: u32,
+    pub multiplier: f32,
+    /// Seconds since the last move.
+    pub idle: f32,
+}
+
+impl Default for StyleMeter {
+    fn default() -> Self {
+        Self {
+            score: 0,
+            multiplier: 1.0,
+            idle: 0.0,
+        }
+    }
+}
+
+impl StyleMeter {
+    /// How full the gauge is, from 0 at no multiplier to 1 at the highest.
+    pub fn fraction(&self) -> f32 {
+        (self.multiplier - 1.0) / (MAX_MULTIPLIER - 1.0)
+    }
+
+    /// Letter for how full the gauge is.
+    pub fn rank(&self) -> char {
+        const RANKS: [char; 5] = ['D', 'C', 'B', 'A', 'S'];
+        let index = (self.fraction() * RANKS.len() as f32) as usize;
+        RANKS[index.min(RANKS.len() - 1)]
+    }
+}
+
+/// A thrown object, followed for a while after it left the hand.
+#[derive(Debug, Component)]
+struct StyleThrow {
+    age: f32,
+    /// Bounced off a wall, up in the air.
+    banked: bool,
+    /// Came down on something.
+    landed: bool,
+}
+
+fn in_air(rapier_context: &RapierContext, entity: Entity, position: Vec3) -> bool {
+    let filter = QueryFilter::default()
+        .exclude_rigid_body(entity)
+        .exclude_sensors();
+    rapier_context
+        .cast_ray(position, Vec3::NEG_Y, AIR_HEIGHT, true, filter)
+        .is_none()
+}
+
+/// Catching a flying object is an air catch; throwing while an earlier throw is still flying is a
+/// juggle.
+#[allow(clippy::too_many_arguments)]
+fn detect_catches_and_throws(
+    mut commands: Commands,
+    time: Res<Time>,
+    rapier_context: Res<RapierContext>,
+    mut holding: Local<Option<Entity>>,
+    mut events: EventWriter<StyleEvent>,
+    players: Query<&PlayerCatch, With<Player>>,
+    objects: Query<(&GlobalTransform, &Velocity), With<CatchObject>>,
+    mut throws: Query<(Entity, &mut StyleThrow)>,
+) {
+    for (entity, mut throw) in &mut throws {
+        throw.age += time.delta_seconds();
+        if throw.age > THROW_WINDOW {
+            commands.entity(entity).remove::<StyleThrow>();
+        }
+    }
+    let catch = match players.get_single() {
+        Ok(catch) => catch,
+        Err(_) => return,
+    };
+    if *holding == catch.target {
+        return;
+    }
+
+    if let Some(object) = catch.target {
+        commands.entity(object).remove::<StyleThrow>();
+        if let Ok((transform, velocity)) = objects.get(object) {
+            let position = transform.translation();
+            if velocity.linvel.length() > AIR_SPEED && in_air(&rapier_context, object, position) {
+                events.send(StyleEvent {
+                    style_move: StyleMove::AirCatch,
+                    position,
+                });
+            }
+        }
+    }
+    if let Some(object) = *holding {
+        let flying = throws
+            .iter()
+            .any(|(entity, throw)| entity != object && !throw.landed);
+        if let (true, Ok((transform, _))) = (flying, objects.get(object)) {
+            events.send(StyleEvent {
+                style_move: StyleMove::Juggle,
+                position: transform.translation(),
+            });
+        }
+        commands.entity(object).insert(StyleThrow {
+            age: 0.0,
+            banked: false,
+            landed: false,
+        });
+    }
+    *holding = catch.target;
+}
+
+/// A hit with the ground close below is a landing; a hit on fixed geometry up in the air is a
+/// bounce off a wall. Goals and triggers are sensors and don't count.
+fn detect_bounces(
+    rapier_context: Res<RapierContext>,
+    mut collisions: EventReader<CollisionEvent>,
+    mut throws: Query<(&mut StyleThrow, &GlobalTransform)>,
+    bodies: Query<&RigidBody>,
+    sensors: Query<(), With<Sensor>>,
+) {
+    for event in collisions.iter() {
+        let (a, b) = match *event {
+            CollisionEvent::Started(a, b, _) => (a, b),
+            _ => continue,
+        };
+        for (entity, other) in [(a, b), (b, a)] {
+            let (mut throw, transform) = match throws.get_mut(entity) {
+                Ok(throw) => throw,
+                Err(_) => continue,
+            };
+            if sensors.get(other).is_ok() {
+                continue;
+            }
+            if !in_air(&rapier_context, entity, transform.translation()) {
+                throw.landed = true;
+            } else if bodies
+                .get(other)
+                .map_or(true, |body| *body == RigidBody::Fixed)
+            {
+                throw.banked = true;
+            }
+        }
+    }
+}
+
+fn detect_bank_shots(
+    mut goals: EventReader<GoalReached>,
+    mut events: EventWriter<StyleEvent>,
+    throws: Query<(&StyleThrow, &GlobalTransform)>,
+) {
+    for goal in goals.iter() {
+        if let Ok((throw, transform)) = throws.get(goal.object) {
+            if throw.banked {
+                events.send(StyleEvent {
+                    style_move: StyleMove::BankShot,
+                    position: transform.translation(),
+                });
+            }
+        }
+    }
+}
+
+fn score_style(
+    time: Res<Time>,
+    locale: Res<Locale>,
+    mut meter: ResMut<StyleMeter>,
+    mut events: EventReader<StyleEvent>,
+    mut popups: EventWriter<PopupEvent>,
+) {
+    let mut scored = false;
+    for event in events.iter() {
+        let points = (event.style_move.points() as f32 * meter.multiplier).round() as u32;
+        meter.score += points;
+        meter.multiplier = (meter.multiplier + MULTIPLIER_STEP).min(MAX_MULTIPLIER);
+        meter.idle = 0.0;
+        scored = true;
+        popups.send(
+            PopupEvent::new(
+                locale.get_args(
+                    "style-points",
+                    &[
+                        ("move", locale.get(event.style_move.key()).into()),
+                        ("points", points.into()),
+                    ],
+                ),
+                event.position + Vec3::Y,
+            )
+            .with_color(STYLE_COLOR),
+        );
+    }
+    if scored || meter.multiplier <= 1.0 {
+        return;
+    }
+
+    meter.idle += time.delta_seconds();
+    if meter.idle > GRACE {
+        meter.multiplier = (meter.multiplier - DRAIN_RATE * time.delta_seconds()).max(1.0);
+    }
+}
@@ -0,0 +1,440 @@
+//! What things are made of.
+//!
+//! Level colliders carry a [`SurfaceType`]; anything without one counts as stone. Footsteps of the
+//! player and impacts of thrown objects raycast for the surface they touch, then play its sound
+//! from `assets/sounds` and kick up dust in its color.
+//!
+//! Some surfaces change how things move on them: ice is slippery for both characters and
+//! objects, and mud slows characters down and keeps them from jumping high. Characters look for the
+//! surface under their feet every frame, which is every physics step with the variable timestep.
+
+use crate::{cues::load_sound, layers::SpawnOnLayerExt, CatchObject, Player};
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+use bevy_mod_wanderlust::ControllerSettings;
+use bevy_rapier3d::prelude::*;
+use serde::Deserialize;
+
+const FOOTSTEP_INTERVAL: f32 = 0.4;
+/// Slower than this, in meters per second, makes no steps.
+const WALK_SPEED: f32 = 1.5;
+/// How far below the player the ground can be for a step.
+const FOOT_REACH: f32 = 1.5;
+/// Objects slowing down this much in one frame hit something.
+const IMPACT_SPEED: f32 = 6.0;
+/// How far ahead of an object the surface it hit can be.
+const IMPACT_REACH: f32 = 1.5;
+const DUST_TIME: f32 = 0.5;
+const DUST_SIZE: f32 = 0.1;
+
+pub struct SurfacePlugin;
+
+impl Plugin for SurfacePlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<SurfaceType>()
+            .init_resource::<SurfaceAssets>()
+            .add_system(apply_surface_friction)
+            .add_system(tint_surfaces)
+            .add_system(apply_surface_control)
+            .add_system(footsteps)
+            .add_system(impacts)
+            .add_system(update_dust);
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Component, Reflect)]
+#[reflect_value(Component)]
+pub enum SurfaceType {
+    #[default]
+    Stone,
+    Metal,
+    Wood,
+    Ice,
+    Mud,
+}
+
+/// How a surface changes the movement of characters on it, as factors of their own settings.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SurfaceControl {
+    pub acceleration: f32,
+    pub max_speed: f32,
+    pub jump: f32,
+}
+
+impl Default for SurfaceControl {
+    fn default() -> Self {
+        Self {
+            acceleration: 1.0,
+            max_speed: 1.0,
+            jump: 1.0,
+        }
+    }
+}
+
+impl SurfaceType {
+    pub const ALL: [SurfaceType; 5] = [
+        SurfaceType::Stone,
+        SurfaceType::Metal,
+        SurfaceType::Wood,
+        SurfaceType::Ice,
+        SurfaceType::Mud,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SurfaceType::Stone => "stone",
+            SurfaceType::Metal => "metal",
+            SurfaceType::Wood => "wood",
+            SurfaceType::Ice => "ice",
+            SurfaceType::Mud => "mud",
+        }
+    }
+
+    pub fn dust_color(self) -> Color {
+        match self {
+            SurfaceType::Stone => Color::rgb(0.7, 0.7, 0.65),
+            SurfaceType::Metal => Color::rgb(1.0, 0.85, 0.5),
+            SurfaceType::Wood => Color::rgb(0.55, 0.4, 0.25),
+            SurfaceType::Ice => Color::rgb(0.8, 0.9, 1.0),
+            SurfaceType::Mud => Color::rgb(0.35, 0.25, 0.15),
+        }
+    }
+
+    /// Color of meshes made of the surface, or `None` to keep their own.
+    pub fn tint(self) -> Option<Color> {
+        match self {
+            SurfaceType::Ice => Some(Color::rgb(0.75, 0.9, 1.0)),
+            SurfaceType::Mud => Some(Color::rgb(0.3, 0.22, 0.12)),
+            _ => None,
+        }
+    }
+
+    /// Friction for rigid bodies touching the surface, or `None` for the physics default.
+    pub fn friction(self) -> Option<Friction> {
+        match self {
+            // The lower coefficient wins on ice, so everything slides.
+            SurfaceType::Ice => Some(Friction {
+                coefficient: 0.02,
+                combine_rule: CoefficientCombineRule::Min,
+            }),
+            SurfaceType::Mud => Some(Friction {
+                coefficient: 1.5,
+                combine_rule: CoefficientCombineRule::Max,
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn control(self) -> SurfaceControl {
+        match self {
+            SurfaceType::Ice => SurfaceControl {
+                acceleration: 0.15,
+                ..default()
+            },
+            SurfaceType::Mud => SurfaceControl {
+                max_speed: 0.4,
+                jump: 0.5,
+                ..default()
+            },
+            _ => SurfaceControl::default(),
+        }
+    }
+}
+
+/// The surface hit by a ray, if the ray hits anything.
+pub fn surface_at(
+    rapier_context: &RapierContext,
+    surfaces: &Query<&SurfaceType>,
+    origin: Vec3,
+    direction: Vec3,
+    max_toi: f32,
+    filter: QueryFilter,
+) -> Option<(SurfaceType, Vec3)> {
+    let (entity, toi) = rapier_context.cast_ray(origin, direction, max_toi, true, filter)?;
+    let surface = surfaces.get(entity).copied().unwrap_or_default();
+    Some((surface, origin + direction * toi))
+}
+
+struct SurfaceSound {
+    footstep: Option<Handle<AudioSource>>,
+    impact: Option<Handle<AudioSource>>,
+    dust: Handle<StandardMaterial>,
+}
+
+pub struct SurfaceAssets {
+    surfaces: HashMap<SurfaceType, SurfaceSound>,
+    dust_mesh: Handle<Mesh>,
+}
+
+impl FromWorld for SurfaceAssets {
+    fn from_world(world: &mut World) -> Self {
+        let dust_mesh = world
+            .resource_mut::<Assets<Mesh>>()
+            .add(shape::Cube::new(DUST_SIZE).into());
+        let surfaces = SurfaceType::ALL
+            .into_iter()
+            .map(|surface| {
+                let asset_server = world.resource::<AssetServer>();
+                let footstep = load_sound(asset_server, &format!("footstep_{}", surface.name()));
+                let impact = load_sound(asset_server, &format!("impact_{}", surface.name()));
+                let dust = world
+                    .resource_mut::<Assets<StandardMaterial>>()
+                    .add(StandardMaterial {
+                        base_color: surface.dust_color(),
+                        unlit: true,
+                        ..default()
+                    });
+                let sound = SurfaceSound {
+                    footstep,
+                    impact,
+                    dust,
+                };
+                (surface, sound)
+            })
+            .collect();
+        Self {
+            surfaces,
+            dust_mesh,
+        }
+    }
+}
+
+/// Movement settings of a character before any surface changed them.
+#[derive(Component)]
+pub struct BaseControl {
+    acceleration: f32,
+    max_speed: f32,
+    jump_initial_force: f32,
+}
+
+#[derive(Component)]
+struct Dust {
+    velocity: Vec3,
+    timer: Timer,
+}
+
+fn spawn_dust(
+    commands: &mut Commands,
+    assets: &SurfaceAssets,
+    surface: SurfaceType,
+    at: Vec3,
+    count: usize,
+) {
+    let material = &assets.surfaces[&surface].dust;
+    for index in 0..count {
+        let angle = index as f32 / count as f32 * std::f32::consts::TAU;
+        let velocity = Vec3::new(angle.cos(), 1.5, angle.sin());
+        commands
+            .spawn_on_world_layer(PbrBundle {
+                mesh: assets.dust_mesh.clone(),
+                material: material.clone(),
+                transform: Transform::from_translation(at),
+                ..default()
+            })
+            .insert(Dust {
+                velocity,
+                timer: Timer::from_seconds(DUST_TIME, false),
+            });
+    }
+}
+
+fn apply_surface_friction(
+    mut commands: Commands,
+    surfaces: Query<(Entity, &SurfaceType), Changed<SurfaceType>>,
+) {
+    for (entity, surface) in &surfaces {
+        match surface.friction() {
+            Some(friction) => commands.entity(entity).insert(friction),
+            None => commands.entity(entity).remove::<Friction>(),
+        };
+    }
+}
+
+/// Slippery and sticky surfaces should look it, so they are tinted along with their meshes.
+///
+/// Level materials are shared by name, so a tinted mesh gets a copy of its own to tint.
+fn tint_surfaces(
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    surfaces: Query<(Entity, &SurfaceType, Option<&Children>), Changed<SurfaceType>>,
+    mut handles: Query<&mut Handle<StandardMaterial>>,
+) {
+    for (entity, surface, children) in &surfaces {
+        let tint = match surface.tint() {
+            Some(tint) => tint,
+            None => continue,
+        };
+        let entities = std::iter::once(entity).chain(children.into_iter().flatten().copied());
+        for entity in entities {
+            let mut handle = match handles.get_mut(entity) {
+                Ok(handle) => handle,
+                Err(_) => continue,
+            };
+            if let Some(material) = materials.get(&handle) {
+                let tinted = StandardMaterial {
+                    base_color: tint,
+                    ..material.clone()
+                };
+                *handle = materials.add(tinted);
+            }
+        }
+    }
+}
+
+/// Scales the movement of every character by the surface under its feet.
+fn apply_surface_control(
+    mut commands: Commands,
+    rapier_context: Res<RapierContext>,
+    surfaces: Query<&SurfaceType>,
+    mut characters: Query<(
+        Entity,
+        &GlobalTransform,
+        &mut ControllerSettings,
+        Option<&BaseControl>,
+    )>,
+) {
+    for (entity, transform, mut settings, base) in &mut characters {
+        let base = match base {
+            Some(base) => base,
+            None => {
+                commands.entity(entity).insert(BaseControl {
+                    acceleration: settings.acceleration,
+                    max_speed: settings.max_speed,
+                    jump_initial_force: settings.jump_initial_force,
+                });
+                continue;
+            }
+        };
+
+        let filter = QueryFilter::default()
+            .exclude_rigid_body(entity)
+            .exclude_sensors();
+        // In the air, characters keep the control of where they jumped from.
+        let control = match surface_at(
+            &rapier_context,
+            &surfaces,
+            transform.translation(),
+            Vec3::NEG_Y,
+            FOOT_REACH,
+            filter,
+        ) {
+            Some((surface, _)) => surface.control(),
+            None => continue,
+        };
+
+        let acceleration = base.acceleration * control.acceleration;
+        let max_speed = base.max_speed * control.max_speed;
+        let jump_initial_force = base.jump_initial_force * control.jump;
+        if settings.acceleration != acceleration
+            || settings.max_speed != max_speed
+            || settings.jump_initial_force != jump_initial_force
+        {
+            settings.acceleration = acceleration;
+            settings.max_speed = max_speed;
+            settings.jump_initial_force = jump_initial_force;
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn footsteps(
+    mut commands: Commands,
+    time: Res<Time>,
+    audio: Res<Audio>,
+    rapier_context: Res<RapierContext>,
+    assets: Res<SurfaceAssets>,
+    mut timer: Local<Option<Timer>>,
+    surfaces: Query<&SurfaceType>,
+    players: Query<(Entity, &GlobalTransform, &Velocity), With<Player>>,
+) {
+    let timer = timer.get_or_insert_with(|| Timer::from_seconds(FOOTSTEP_INTERVAL, true));
+    if !timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    let (entity, transform, velocity) = match players.get_single() {
+        Ok(player) => player,
+        Err(_) => return,
+    };
+    if Vec2::new(velocity.linvel.x, velocity.linvel.z).length() < WALK_SPEED {
+        return;
+    }
+
+    let filter = QueryFilter::default()
+        .exclude_rigid_body(entity)
+        .exclude_sensors();
+    let ground = surface_at(
+        &rapier_context,
+        &surfaces,
+        transform.translation(),
+        Vec3::NEG_Y,
+        FOOT_REACH,
+        filter,
+    );
+    if let Some((surface, point)) = ground {
+        if let Some(sound) = &assets.surfaces[&surface].footstep {
+            audio.play(sound.clone());
+        }
+        spawn_dust(&mut commands, &assets, surface, point, 3);
+    }
+}
+
+/// Looks along where an object was going when it suddenly slowed down.
+#[allow(clippy::too_many_arguments)]
+fn impacts(
+    mut commands: Commands,
+    audio: Res<Audio>,
+    rapier_context: Res<RapierContext>,
+    assets: Res<SurfaceAssets>,
+    mut previous: Local<HashMap<Entity, Vec3>>,
+    surfaces: Query<&SurfaceType>,
+    objects: Query<(Entity, &GlobalTransform, &Velocity), With<CatchObject>>,
+) {
+    let mut current = HashMap::default();
+    let mut played = HashSet::default();
+    for (entity, transform, velocity) in &objects {
+        if let Some(linvel) = previous.get(&entity) {
+            if (*linvel - velocity.linvel).length() > IMPACT_SPEED {
+                let filter = QueryFilter::default()
+                    .exclude_rigid_body(entity)
+                    .exclude_sensors();
+                let hit = surface_at(
+                    &rapier_context,
+                    &surfaces,
+                    transform.translation(),
+                    linvel.normalize_or_zero(),
+                    IMPACT_REACH,
+                    filter,
+                );
+                if let Some((surface, point)) = hit {
+                    // One sound per surface a frame is plenty when a tower falls.
+                    if played.insert(surface) {
+                        if let Some(sound) = &assets.surfaces[&surface].impact {
+                            audio.play(sound.clone());
+                        }
+                    }
+                    spawn_dust(&mut commands, &assets, surface, point, 6);
+                }
+            }
+        }
+        current.insert(entity, velocity.linvel);
+    }
+    *previous = current;
+}
+
+fn update_dust(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut dust: Query<(Entity, &mut Dust, &mut Transform)>,
+) {
+    let delta = time.delta_seconds();
+    for (entity, mut dust, mut transform) in &mut dust {
+        if dust.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        dust.velocity.y -= 4.0 * delta;
+        transform.translation += dust.velocity * delta;
+        transform.scale = Vec3::splat(dust.timer.percent_left());
+    }
+}
@@ -0,0 +1,190 @@
+//! Security cameras and the monitors showing their feeds.
+//!
+//! A [`SurveillanceCamera`] renders a tiny feed of the level, and every [`Monitor`] on the same
+//! channel shows it. Feeds cost a whole extra render each, so only cameras with a monitor in view
+//! run, the nearest ones first, up to [`FeedBudget::max_active`]; the others keep showing their
+//! last picture. Monitors share [`layers::VIEW_MODEL`] with mirrors, so no feed ever films itself.
+
+use crate::{
+    layers::{self, SpawnOnLayerExt},
+    PlayerCamera,
+};
+use bevy::{
+    prelude::*,
+    render::{
+        camera::RenderTarget,
+        render_resource::*,
+        texture::{BevyDefault, ImageSampler},
+    },
+};
+use bevy_inspector_egui::Inspectable;
+
+/// Resolution of a feed, in pixels.
+const FEED_SIZE: [u32; 2] = [64, 36];
+
+pub struct SurveillancePlugin;
+
+impl Plugin for SurveillancePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FeedBudget>()
+            .add_system(setup_cameras)
+            .add_system(setup_monitors.after(setup_cameras))
+            .add_system(schedule_feeds.after(setup_cameras));
+    }
+}
+
+#[derive(Debug, Clone, Inspectable)]
+pub struct FeedBudget {
+    /// Most feeds rendered in a frame.
+    #[inspectable(min = 0, max = 8)]
+    pub max_active: usize,
+}
+
+impl Default for FeedBudget {
+    fn default() -> Self {
+        Self { max_active: 2 }
+    }
+}
+
+/// Films along its local -z axis for the monitors on `channel`.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct SurveillanceCamera {
+    pub channel: u32,
+}
+
+/// Shows the feed of the camera on `channel` across a quad facing along its local z axis.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct Monitor {
+    pub channel: u32,
+}
+
+/// Renders the feed of a security camera; its field of view is in the camera settings.
+#[derive(Component)]
+pub struct FeedCamera;
+
+/// The render camera of a security camera, and the image it renders into.
+#[derive(Component)]
+struct Feed {
+    camera: Entity,
+    image: Handle<Image>,
+}
+
+fn setup_cameras(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    cameras: Query<Entity, Added<SurveillanceCamera>>,
+) {
+    for entity in &cameras {
+        let size = Extent3d {
+            width: FEED_SIZE[0],
+            height: FEED_SIZE[1],
+            ..default()
+        };
+        let mut image = Image {
+            texture_descriptor: TextureDescriptor {
+                label: None,
+                size,
+                dimension: TextureDimension::D2,
+                format: TextureFormat::bevy_default(),
+                mip_level_count: 1,
+                sample_count: 1,
+                usage: TextureUsages::TEXTURE_BINDING
+                    | TextureUsages::COPY_DST
+                    | TextureUsages::RENDER_ATTACHMENT,
+            },
+            sampler_descriptor: ImageSampler::Descriptor(SamplerDescriptor {
+                mag_filter: FilterMode::Nearest,
+                min_filter: FilterMode::Nearest,
+                mipmap_filter: FilterMode::Nearest,
+                ..default()
+            }),
+            ..default()
+        };
+        image.resize(size);
+        let image = images.add(image);
+
+        let camera = commands
+            .spawn_on_world_layer(Camera3dBundle {
+                camera: Camera {
+                    priority: -7,
+                    target: RenderTarget::Image(image.clone()),
+                    is_active: false,
+                    ..default()
+                },
+                ..default()
+            })
+            .insert(FeedCamera)
+            .id();
+        commands
+            .entity(entity)
+            .add_child(camera)
+            .insert(Feed { camera, image });
+    }
+}
+
+/// Puts feeds on monitors, once the camera of their channel is up.
+fn setup_monitors(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    feeds: Query<(&SurveillanceCamera, &Feed)>,
+    monitors: Query<(Entity, &Monitor), Without<Handle<StandardMaterial>>>,
+) {
+    for (entity, monitor) in &monitors {
+        let feed = match feeds
+            .iter()
+            .find(|(camera, _)| camera.channel == monitor.channel)
+        {
+            Some((_, feed)) => feed,
+            None => continue,
+        };
+        let material = materials.add(StandardMaterial {
+            base_color_texture: Some(feed.image.clone()),
+            emissive: Color::WHITE,
+            emissive_texture: Some(feed.image.clone()),
+            unlit: true,
+            ..default()
+        });
+        commands
+            .entity(entity)
+            .insert(material)
+            .insert(layers::VIEW_MODEL);
+    }
+}
+
+/// Runs the cameras behind the monitors in view, nearest monitor first, within the budget.
+fn schedule_feeds(
+    budget: Res<FeedBudget>,
+    viewers: Query<&GlobalTransform, With<PlayerCamera>>,
+    monitors: Query<(&Monitor, &GlobalTransform, &ComputedVisibility)>,
+    feeds: Query<(&SurveillanceCamera, &Feed)>,
+    mut cameras: Query<&mut Camera>,
+) {
+    let eye = match viewers.get_single() {
+        Ok(viewer) => viewer.translation(),
+        Err(_) => return,
+    };
+    let mut wanted: Vec<_> = feeds
+        .iter()
+        .filter_map(|(camera, feed)| {
+            monitors
+                .iter()
+                .filter(|(monitor, _, visibility)| {
+                    monitor.channel == camera.channel && visibility.is_visible()
+                })
+                .map(|(_, transform, _)| transform.translation().distance(eye))
+                .reduce(f32::min)
+                .map(|distance| (distance, feed.camera))
+        })
+        .collect();
+    wanted.sort_by(|a, b| a.0.total_cmp(&b.0));
+    wanted.truncate(budget.max_active);
+
+    for (_, feed) in &feeds {
+        if let Ok(mut camera) = cameras.get_mut(feed.camera) {
+            let active = wanted.iter().any(|(_, entity)| *entity == feed.camera);
+            if camera.is_active != active {
+                camera.is_active = active;
+            }
+        }
+    }
+}
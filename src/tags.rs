@@ -0,0 +1,72 @@
+//! Names for entities, so level logic can refer to "every door tagged `gate_a`" instead of ids.
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+pub struct TagsPlugin;
+
+impl Plugin for TagsPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Tags>();
+    }
+}
+
+/// A small set of tags; entities rarely have more than a couple, so a vector is enough.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Component, Reflect)]
+#[reflect(Component)]
+pub struct Tags(pub Vec<String>);
+
+impl Tags {
+    pub fn new<I, S>(tags: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let mut result = Self::default();
+        for tag in tags {
+            result.insert(tag);
+        }
+        result
+    }
+
+    pub fn has(&self, tag: &str) -> bool {
+        self.0.iter().any(|other| other == tag)
+    }
+
+    pub fn insert(&mut self, tag: impl Into<String>) {
+        let tag = tag.into();
+        if !self.has(&tag) {
+            self.0.push(tag);
+        }
+    }
+
+    pub fn remove(&mut self, tag: &str) {
+        self.0.retain(|other| other != tag);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().map(String::as_str)
+    }
+}
+
+/// Looks entities up by tag from within a system.
+#[derive(SystemParam)]
+pub struct Tagged<'w, 's> {
+    query: Query<'w, 's, (Entity, &'static Tags)>,
+}
+
+impl<'w, 's> Tagged<'w, 's> {
+    pub fn find_by_tag<'a>(&'a self, tag: &'a str) -> impl Iterator<Item = Entity> + 'a {
+        self.query
+            .iter()
+            .filter(move |(_, tags)| tags.has(tag))
+            .map(|(entity, _)| entity)
+    }
+
+    pub fn first_by_tag(&self, tag: &str) -> Option<Entity> {
+        self.find_by_tag(tag).next()
+    }
+
+    pub fn tags(&self, entity: Entity) -> Option<&Tags> {
+        self.query.get(entity).ok().map(|(_, tags)| tags)
+    }
+}
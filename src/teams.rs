@@ -0,0 +1,348 @@
+//! Two teams, for playing the arena as cube dodgeball.
+//!
+//! With `--teams`, every player and bot gets a [`Team`] by the side of the arena they spawn on,
+//! and wears a glowing trim in its color; goals of a team wear one too. A goal scored in a team's
+//! goal counts for the other team, and one in a goal of no team for whoever threw the object, so
+//! catch objects remember who last held them in [`ThrownBy`]. Unless friendly fire is on, objects
+//! thrown by teammates don't knock anyone out. Each peer picks the mode from its own command line.
+
+use crate::{
+    accessibility::AccessibilitySettings,
+    bots::Bot,
+    cli::Args,
+    hold::Held,
+    hud::bitmap::{BitmapAlign, BitmapText, BitmapTextBundle},
+    layers::SpawnOnLayerExt,
+    level::{trigger::GoalReached, Goal},
+    locale::Locale,
+    match_flow::{Lobby, MatchState},
+    net::{Network, PeerId, RemotePlayer, HOST_PEER},
+    Player, RENDER_SIZE,
+};
+use bevy::prelude::*;
+use bevy_inspector_egui::Inspectable;
+use bevy_rapier3d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Thickness of the trims, in meters.
+const TRIM_THICKNESS: f32 = 0.06;
+/// Radius of the trims around players, in meters.
+const PLAYER_TRIM_RADIUS: f32 = 0.55;
+const SCORE_MARGIN: f32 = 4.0;
+
+pub struct TeamPlugin;
+
+impl Plugin for TeamPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Team>()
+            .init_resource::<TeamSettings>()
+            .init_resource::<TeamScores>()
+            .init_resource::<TeamAssets>()
+            .add_startup_system(setup_team_score)
+            .add_system(assign_teams)
+            .add_system(add_team_trims.after(assign_teams))
+            .add_system(recolor_team_trims)
+            .add_system(mark_throws)
+            .add_system(count_team_goals.after(mark_throws))
+            .add_system(update_team_score.after(count_team_goals))
+            .add_system_set(
+                SystemSet::on_enter(MatchState::Countdown).with_system(reset_team_scores),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Component, Reflect)]
+#[reflect_value(Component)]
+pub enum Team {
+    Red,
+    Blue,
+}
+
+impl Team {
+    pub fn index(self) -> usize {
+        match self {
+            Team::Red => 0,
+            Team::Blue => 1,
+        }
+    }
+
+    pub fn other(self) -> Self {
+        match self {
+            Team::Red => Team::Blue,
+            Team::Blue => Team::Red,
+        }
+    }
+
+    /// Spawn points alternate between the two ends of the arena.
+    pub fn for_peer(peer: PeerId, lobby: &Lobby) -> Self {
+        let index = lobby.spawns.get(&peer).copied().unwrap_or(peer as usize);
+        if index % 2 == 0 {
+            Team::Red
+        } else {
+            Team::Blue
+        }
+    }
+}
+
+#[derive(Debug, Clone, Inspectable)]
+pub struct TeamSettings {
+    pub enabled: bool,
+    /// Lets objects thrown by teammates knock players out.
+    pub friendly_fire: bool,
+}
+
+impl FromWorld for TeamSettings {
+    fn from_world(world: &mut World) -> Self {
+        Self {
+            enabled: world
+                .get_resource::<Args>()
+                .map_or(false, |args| args.teams),
+            friendly_fire: false,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TeamScores(pub [u32; 2]);
+
+/// Who last held a catch object.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct ThrownBy {
+    pub holder: Entity,
+    pub team: Option<Team>,
+}
+
+/// The glowing trim showing the team of its parent.
+#[derive(Component)]
+struct TeamTrim;
+
+#[derive(Component)]
+struct TeamScoreText;
+
+/// One trim material per team, recolored with the palette.
+pub struct TeamAssets {
+    materials: [Handle<StandardMaterial>; 2],
+}
+
+impl FromWorld for TeamAssets {
+    fn from_world(world: &mut World) -> Self {
+        let palette = world.resource::<AccessibilitySettings>().palette();
+        let mut materials = world.resource_mut::<Assets<StandardMaterial>>();
+        Self {
+            materials: palette
+                .teams
+                .map(|color| materials.add(trim_material(color))),
+        }
+    }
+}
+
+fn trim_material(color: Color) -> StandardMaterial {
+    let mut emissive = color;
+    emissive.set_a(1.0);
+    StandardMaterial {
+        base_color: color,
+        emissive,
+        ..default()
+    }
+}
+
+impl TeamSettings {
+    /// Whether an object last held as `thrown` may knock out a player of `team`.
+    pub fn can_hit(&self, thrown: Option<&ThrownBy>, team: Option<&Team>) -> bool {
+        if !self.enabled || self.friendly_fire {
+            return true;
+        }
+        match (thrown.and_then(|thrown| thrown.team), team) {
+            (Some(thrower), Some(team)) => thrower != *team,
+            _ => true,
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn assign_teams(
+    mut commands: Commands,
+    settings: Res<TeamSettings>,
+    network: Res<Network>,
+    lobby: Res<Lobby>,
+    players: Query<(Entity, Option<&Team>), With<Player>>,
+    others: Query<(Entity, &RemotePlayer, Option<&Team>)>,
+    bots: Query<(Entity, &Bot, Option<&Team>)>,
+) {
+    if !settings.enabled {
+        return;
+    }
+    let local = network.local_peer().unwrap_or(HOST_PEER);
+    let peers = players
+        .iter()
+        .map(|(entity, team)| (entity, local, team))
+        .chain(
+            others
+                .iter()
+                .map(|(entity, remote, team)| (entity, remote.peer, team)),
+        )
+        .chain(
+            bots.iter()
+                .map(|(entity, bot, team)| (entity, bot.peer, team)),
+        );
+    for (entity, peer, team) in peers {
+        let wanted = Team::for_peer(peer, &lobby);
+        if team != Some(&wanted) {
+            commands.entity(entity).insert(wanted);
+        }
+    }
+}
+
+/// Puts a trim on everything that joined a team, and keeps its color with the team.
+#[allow(clippy::type_complexity)]
+fn add_team_trims(
+    mut commands: Commands,
+    assets: Res<TeamAssets>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    teams: Query<
+        (
+            Entity,
+            &Team,
+            Option<&Collider>,
+            Option<&Children>,
+            Option<&Goal>,
+        ),
+        Changed<Team>,
+    >,
+    mut trims: Query<&mut Handle<StandardMaterial>, With<TeamTrim>>,
+) {
+    for (entity, team, collider, children, goal) in &teams {
+        let material = assets.materials[team.index()].clone();
+        let mut existing = children
+            .into_iter()
+            .flatten()
+            .filter(|child| trims.contains(**child))
+            .peekable();
+        if existing.peek().is_some() {
+            for child in existing.copied().collect::<Vec<_>>() {
+                if let Ok(mut handle) = trims.get_mut(child) {
+                    *handle = material.clone();
+                }
+            }
+            continue;
+        }
+
+        // Goals get a ring around their pad, players a band around their waist.
+        let (radius, height) = match (goal, collider) {
+            (Some(_), Some(collider)) => {
+                let extents = collider.raw.compute_local_aabb().half_extents();
+                (extents.x.max(extents.z), 0.05)
+            }
+            _ => (PLAYER_TRIM_RADIUS, 1.0),
+        };
+        let mesh = meshes.add(
+            shape::Torus {
+                radius,
+                ring_radius: TRIM_THICKNESS,
+                ..default()
+            }
+            .into(),
+        );
+        let trim = commands
+            .spawn_on_world_layer(PbrBundle {
+                mesh,
+                material,
+                transform: Transform::from_xyz(0.0, height, 0.0),
+                ..default()
+            })
+            .insert(TeamTrim)
+            .id();
+        commands.entity(entity).add_child(trim);
+    }
+}
+
+fn recolor_team_trims(
+    settings: Res<AccessibilitySettings>,
+    assets: Res<TeamAssets>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    let palette = settings.palette();
+    for (handle, color) in assets.materials.iter().zip(palette.teams) {
+        if let Some(material) = materials.get_mut(handle) {
+            *material = trim_material(color);
+        }
+    }
+}
+
+fn mark_throws(
+    mut commands: Commands,
+    held: Query<(Entity, &Held), Added<Held>>,
+    teams: Query<&Team>,
+    parents: Query<&Parent>,
+) {
+    for (entity, held) in &held {
+        // Bots catch with a child of their body.
+        let team = teams.get(held.holder).ok().or_else(|| {
+            let parent = parents.get(held.holder).ok()?;
+            teams.get(parent.get()).ok()
+        });
+        commands.entity(entity).insert(ThrownBy {
+            holder: held.holder,
+            team: team.copied(),
+        });
+    }
+}
+
+fn count_team_goals(
+    settings: Res<TeamSettings>,
+    mut scores: ResMut<TeamScores>,
+    mut goals: EventReader<GoalReached>,
+    teams: Query<&Team>,
+    thrown: Query<&ThrownBy>,
+) {
+    if !settings.enabled {
+        return;
+    }
+    for event in goals.iter() {
+        let scorer = match teams.get(event.goal) {
+            Ok(team) => Some(team.other()),
+            Err(_) => thrown.get(event.object).ok().and_then(|thrown| thrown.team),
+        };
+        if let Some(team) = scorer {
+            scores.0[team.index()] += 1;
+        }
+    }
+}
+
+fn reset_team_scores(mut scores: ResMut<TeamScores>) {
+    *scores = TeamScores::default();
+}
+
+fn setup_team_score(mut commands: Commands) {
+    let position = Vec2::new(0.0, 0.5 * RENDER_SIZE[1] as f32 - SCORE_MARGIN);
+    commands
+        .spawn_on_hud_layer(BitmapTextBundle::new(
+            BitmapText::new("", Color::WHITE, BitmapAlign::Center),
+            position,
+        ))
+        .insert(TeamScoreText);
+}
+
+fn update_team_score(
+    settings: Res<TeamSettings>,
+    scores: Res<TeamScores>,
+    locale: Res<Locale>,
+    mut texts: Query<&mut BitmapText, With<TeamScoreText>>,
+) {
+    let value = if settings.enabled {
+        locale.get_args(
+            "team-score",
+            &[("red", scores.0[0].into()), ("blue", scores.0[1].into())],
+        )
+    } else {
+        String::new()
+    };
+    for mut text in &mut texts {
+        if text.value != value {
+            text.value = value.clone();
+        }
+    }
+}
@@ -0,0 +1,254 @@
+//! On-screen controls for touchscreens.
+//!
+//! The first touch, or `--touch` on the command line, brings up a virtual stick in the bottom
+//! left of the HUD and buttons in the bottom right. A finger anywhere else on the right half drags
+//! the view around like the mouse does. All of it is written into the player's [`ActionState`]
+//! right after leafwing reads the other devices, so the rest of the game can't tell the difference.
+
+use crate::{
+    cli::Args,
+    hud::bitmap::{BitmapAlign, BitmapText, BitmapTextBundle},
+    layers::SpawnOnLayerExt,
+    Action, Player, RENDER_SIZE,
+};
+use bevy::prelude::*;
+use leafwing_input_manager::{
+    action_state::ActionData, axislike::DualAxisData, buttonlike::ButtonState,
+    plugin::InputManagerSystem, prelude::*,
+};
+
+/// Distance from the stick center to its rim, in render target pixels.
+const STICK_RADIUS: f32 = 16.0;
+const STICK_CENTER: Vec2 = Vec2::new(-124.0, -54.0);
+const BUTTON_SIZE: f32 = 20.0;
+/// Look input per window pixel dragged, comparable to moving the mouse by as much.
+const LOOK_SCALE: f32 = 1.0;
+/// Buttons in the bottom right, with their icon and position in render target pixels.
+const BUTTONS: [(Action, char, Vec2); 2] = [
+    (Action::Jump, '^', Vec2::new(136.0, -66.0)),
+    (Action::Catch, 'o', Vec2::new(108.0, -50.0)),
+];
+
+pub struct TouchPlugin;
+
+impl Plugin for TouchPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TouchInput>()
+            .add_system(enable_touch_controls)
+            .add_system(show_touch_controls.after(enable_touch_controls))
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                apply_touch_input.after(InputManagerSystem::Update),
+            );
+    }
+}
+
+/// Fingers on the controls, by touch id.
+#[derive(Default)]
+pub struct TouchInput {
+    pub enabled: bool,
+    stick: Option<u64>,
+    look: Option<(u64, Vec2)>,
+    /// Actions held by touch last frame, to tell presses from holds.
+    held: Vec<Action>,
+    /// Pushed direction of the stick, up to unit length.
+    axis: Vec2,
+}
+
+#[derive(Component)]
+struct TouchStick;
+
+#[derive(Component)]
+struct TouchKnob;
+
+#[derive(Component)]
+struct TouchButton(Action);
+
+/// Where `position` in the window lands on the HUD, measured from its center.
+fn window_to_hud(window: &Window, position: Vec2) -> Vec2 {
+    let size = Vec2::new(RENDER_SIZE[0] as f32, RENDER_SIZE[1] as f32);
+    let scale = size / Vec2::new(window.width(), window.height());
+    position * scale - 0.5 * size
+}
+
+fn enable_touch_controls(
+    mut commands: Commands,
+    args: Res<Args>,
+    touches: Res<Touches>,
+    mut input: ResMut<TouchInput>,
+) {
+    if input.enabled || !(args.touch || touches.any_just_pressed()) {
+        return;
+    }
+    input.enabled = true;
+
+    commands
+        .spawn_on_hud_layer(SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgba(1.0, 1.0, 1.0, 0.15),
+                custom_size: Some(Vec2::splat(2.0 * STICK_RADIUS)),
+                ..default()
+            },
+            transform: Transform::from_translation(STICK_CENTER.extend(0.0)),
+            ..default()
+        })
+        .insert(TouchStick)
+        .with_children(|parent| {
+            parent
+                .spawn_on_hud_layer(SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::rgba(1.0, 1.0, 1.0, 0.5),
+                        custom_size: Some(Vec2::splat(0.5 * STICK_RADIUS)),
+                        ..default()
+                    },
+                    transform: Transform::from_xyz(0.0, 0.0, 0.1),
+                    ..default()
+                })
+                .insert(TouchKnob);
+        });
+
+    for (action, icon, position) in BUTTONS {
+        commands
+            .spawn_on_hud_layer(SpriteBundle {
+                sprite: Sprite {
+                    color: Color::rgba(1.0, 1.0, 1.0, 0.15),
+                    custom_size: Some(Vec2::splat(BUTTON_SIZE)),
+                    ..default()
+                },
+                transform: Transform::from_translation(position.extend(0.0)),
+                ..default()
+            })
+            .insert(TouchButton(action))
+            .with_children(|parent| {
+                parent.spawn_on_hud_layer(BitmapTextBundle::new(
+                    BitmapText::new(icon.to_string(), Color::WHITE, BitmapAlign::Center),
+                    Vec2::ZERO,
+                ));
+            });
+    }
+}
+
+/// Tracks the fingers, and writes what they do into the actions of the player.
+fn apply_touch_input(
+    windows: Res<Windows>,
+    touches: Res<Touches>,
+    mut input: ResMut<TouchInput>,
+    mut players: Query<&mut ActionState<Action>, With<Player>>,
+) {
+    if !input.enabled {
+        return;
+    }
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+
+    if let Some(id) = input.stick {
+        if touches.get_pressed(id).is_none() {
+            input.stick = None;
+        }
+    }
+    if let Some((id, _)) = input.look {
+        if touches.get_pressed(id).is_none() {
+            input.look = None;
+        }
+    }
+
+    // New fingers go to the stick if they land near it, the look area if on the right half.
+    for touch in touches.iter_just_pressed() {
+        let position = window_to_hud(window, touch.position());
+        let on_button = BUTTONS
+            .iter()
+            .any(|(_, _, center)| (position - *center).abs().max_element() < 0.5 * BUTTON_SIZE);
+        if input.stick.is_none() && position.distance(STICK_CENTER) < 2.0 * STICK_RADIUS {
+            input.stick = Some(touch.id());
+        } else if input.look.is_none() && position.x > 0.0 && !on_button {
+            input.look = Some((touch.id(), touch.position()));
+        }
+    }
+
+    input.axis = match input.stick.and_then(|id| touches.get_pressed(id)) {
+        Some(touch) => {
+            let offset = window_to_hud(window, touch.position()) - STICK_CENTER;
+            (offset / STICK_RADIUS).clamp_length_max(1.0)
+        }
+        None => Vec2::ZERO,
+    };
+
+    // Dragging up looks up, which the mouse reports as moving by negative y.
+    let mut look = Vec2::ZERO;
+    if let Some((id, last)) = input.look {
+        if let Some(touch) = touches.get_pressed(id) {
+            let delta = touch.position() - last;
+            look = LOOK_SCALE * Vec2::new(delta.x, -delta.y);
+            input.look = Some((id, touch.position()));
+        }
+    }
+
+    let held: Vec<_> = BUTTONS
+        .iter()
+        .filter(|(_, _, center)| {
+            touches.iter().any(|touch| {
+                let position = window_to_hud(window, touch.position());
+                (position - *center).abs().max_element() < 0.5 * BUTTON_SIZE
+            })
+        })
+        .map(|(action, _, _)| *action)
+        .collect();
+
+    for mut action_state in &mut players {
+        if input.axis != Vec2::ZERO {
+            let mut data: ActionData = action_state.action_data(Action::Move);
+            data.state = ButtonState::Pressed;
+            data.value = input.axis.length();
+            data.axis_pair = Some(DualAxisData::new(input.axis.x, input.axis.y));
+            action_state.set_action_data(Action::Move, data);
+        }
+        if look != Vec2::ZERO {
+            let mut data: ActionData = action_state.action_data(Action::Look);
+            data.state = ButtonState::Pressed;
+            data.value = look.length();
+            data.axis_pair = Some(DualAxisData::new(look.x, look.y));
+            action_state.set_action_data(Action::Look, data);
+        }
+
+        // Buttons nobody touches are left to the other devices.
+        for (action, _, _) in BUTTONS {
+            let was_pressed = input.held.contains(&action);
+            let pressed = held.contains(&action);
+            let state = match (was_pressed, pressed) {
+                (false, true) => ButtonState::JustPressed,
+                (true, true) => ButtonState::Pressed,
+                (true, false) if !action_state.pressed(action) => ButtonState::JustReleased,
+                _ => continue,
+            };
+            let mut data: ActionData = action_state.action_data(action);
+            data.state = state;
+            data.value = if pressed { 1.0 } else { 0.0 };
+            action_state.set_action_data(action, data);
+        }
+    }
+    input.held = held;
+}
+
+fn show_touch_controls(
+    input: Res<TouchInput>,
+    mut knobs: Query<&mut Transform, With<TouchKnob>>,
+    mut buttons: Query<(&TouchButton, &mut Sprite)>,
+) {
+    if !input.is_changed() {
+        return;
+    }
+    for mut transform in &mut knobs {
+        let offset = STICK_RADIUS * input.axis;
+        transform.translation = offset.round().extend(0.1);
+    }
+    for (button, mut sprite) in &mut buttons {
+        let alpha = if input.held.contains(&button.0) {
+            0.4
+        } else {
+            0.15
+        };
+        sprite.color.set_a(alpha);
+    }
+}
@@ -0,0 +1,193 @@
+//! Gameplay numbers that designers tweak, kept out of the code.
+//!
+//! [`TUNING_FILE`] holds a [`Tuning`] in RON. It loads like any other asset, so with file watching
+//! on (debug builds) saving the file changes the feel of the running game. The resource of the
+//! same type is what systems read; it starts at the defaults and follows the file from then on.
+
+use crate::{catch_class::CatchClass, loading::LoadingAssets, Player};
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+    utils::BoxedFuture,
+};
+use bevy_inspector_egui::Inspectable;
+use serde::Deserialize;
+
+pub const TUNING_FILE: &str = "gameplay.tuning.ron";
+
+pub struct TuningPlugin;
+
+impl Plugin for TuningPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<Tuning>()
+            .init_asset_loader::<TuningLoader>()
+            .init_resource::<Tuning>()
+            .add_startup_system(load_tuning)
+            .add_system(reload_tuning)
+            .add_system(apply_tuning.after(reload_tuning));
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, TypeUuid, Inspectable)]
+#[uuid = "8f0c2d6e-41a7-4b9e-a3d5-7e1f6c9b2a04"]
+#[serde(default)]
+pub struct Tuning {
+    /// How fast a caught object is pulled in, per square meter away.
+    #[inspectable(min = 0.0, max = 50.0)]
+    pub catch_pull: f32,
+    /// How quickly throws weaken with distance from the catch point; 0 throws at full speed from
+    /// anywhere.
+    #[inspectable(min = 0.0, max = 5.0)]
+    pub throw_falloff: f32,
+    /// Speed cap of pulled objects.
+    #[inspectable(min = 0.0, max = 500.0)]
+    pub max_catch_speed: f32,
+    #[inspectable(min = 0.0, max = 1000.0)]
+    pub throw_speed: f32,
+    /// Scale of the player's movement.
+    #[inspectable(min = 0.0, max = 5.0)]
+    pub walk_speed: f32,
+    /// Speed scale while sprinting.
+    #[inspectable(min = 1.0, max = 3.0)]
+    pub sprint_scale: f32,
+    /// Turning speed of the sun, in radians per second.
+    #[inspectable(min = -1.0, max = 1.0)]
+    pub light_rotation_speed: f32,
+    pub light_physics: ClassPhysics,
+    pub medium_physics: ClassPhysics,
+    pub heavy_physics: ClassPhysics,
+}
+
+/// How the physics engine treats the catch objects of one [`CatchClass`].
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Inspectable)]
+#[serde(default)]
+pub struct ClassPhysics {
+    /// Continuous collision detection, so fast objects can't pass through thin walls.
+    pub ccd: bool,
+    /// Velocity solver iterations. Rapier solves the whole world at once, so the most any class
+    /// asks for applies to every body.
+    #[inspectable(min = 1, max = 16)]
+    pub solver_iterations: usize,
+    /// Bounciness, from 0 for none to 1 for no energy lost.
+    #[inspectable(min = 0.0, max = 1.0)]
+    pub restitution: f32,
+}
+
+impl Default for ClassPhysics {
+    fn default() -> Self {
+        Self {
+            ccd: true,
+            solver_iterations: 4,
+            restitution: 0.0,
+        }
+    }
+}
+
+impl Default for Tuning {
+    fn default() -> Self {
+        Self {
+            catch_pull: 10.0,
+            throw_falloff: 1.0,
+            max_catch_speed: 100.0,
+            throw_speed: 200.0,
+            walk_speed: 1.0,
+            sprint_scale: 1.6,
+            light_rotation_speed: 0.1,
+            light_physics: ClassPhysics {
+                ccd: false,
+                solver_iterations: 4,
+                restitution: 0.3,
+            },
+            medium_physics: ClassPhysics {
+                ccd: true,
+                solver_iterations: 4,
+                restitution: 0.1,
+            },
+            heavy_physics: ClassPhysics {
+                ccd: true,
+                solver_iterations: 8,
+                restitution: 0.0,
+            },
+        }
+    }
+}
+
+impl Tuning {
+    pub fn class_physics(&self, class: CatchClass) -> ClassPhysics {
+        match class {
+            CatchClass::Light => self.light_physics,
+            CatchClass::Medium => self.medium_physics,
+            CatchClass::Heavy => self.heavy_physics,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct TuningLoader;
+
+impl AssetLoader for TuningLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let tuning: Tuning = ron::de::from_bytes(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(tuning));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["tuning.ron"]
+    }
+}
+
+/// Keeps the file loaded, so it is watched.
+pub struct TuningHandle(pub Handle<Tuning>);
+
+fn load_tuning(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut loading: ResMut<LoadingAssets>,
+) {
+    let handle = asset_server.load(TUNING_FILE);
+    loading.add(&handle);
+    commands.insert_resource(TuningHandle(handle));
+}
+
+fn reload_tuning(
+    handle: Option<Res<TuningHandle>>,
+    assets: Res<Assets<Tuning>>,
+    mut tuning: ResMut<Tuning>,
+    mut events: EventReader<AssetEvent<Tuning>>,
+) {
+    let handle = match handle {
+        Some(handle) => handle,
+        None => return,
+    };
+    for event in events.iter() {
+        match event {
+            AssetEvent::Created { handle: changed } | AssetEvent::Modified { handle: changed }
+                if *changed == handle.0 =>
+            {
+                if let Some(loaded) = assets.get(&handle.0) {
+                    info!("Tuning loaded from {}", TUNING_FILE);
+                    *tuning = loaded.clone();
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Moves the tuned speeds onto the player; throw speed goes through skills, see `progression`.
+fn apply_tuning(tuning: Res<Tuning>, mut players: Query<(&mut Player, ChangeTrackers<Player>)>) {
+    for (mut player, tracker) in &mut players {
+        if tuning.is_changed() || tracker.is_added() {
+            player.speed = tuning.walk_speed;
+            player.max_catch_speed = tuning.max_catch_speed;
+        }
+    }
+}
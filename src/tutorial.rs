@@ -0,0 +1,180 @@
+//! Prompts that teach the controls the first time a player needs them.
+//!
+//! Levels place [`TriggerAction::Tutorial`] volumes where a lesson fits, e.g. next to the first
+//! cubes. Walking in queues the listed [`TutorialStep`]s; each shows a prompt in the pixel font and
+//! stays up until the player does what it asks. Finished steps are kept in the [`Profile`] and
+//! never shown again, and F1 skips the tutorial for good.
+
+use crate::{
+    hud::bitmap::{BitmapAlign, BitmapText, BitmapTextBundle},
+    layers::SpawnOnLayerExt,
+    level::trigger::{Trigger, TriggerAction, Triggered},
+    locale::Locale,
+    profile::Profile,
+    Action, Player, PlayerCatch, RENDER_SIZE,
+};
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+const SKIP_KEY: KeyCode = KeyCode::F1;
+/// Height of the prompt above the bottom of the screen, in render target pixels.
+const PROMPT_MARGIN: f32 = 48.0;
+const PROMPT_COLOR: Color = Color::rgb(1.0, 0.9, 0.5);
+
+pub struct TutorialPlugin;
+
+impl Plugin for TutorialPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Tutorial>()
+            .add_startup_system(setup_tutorial_prompt)
+            .add_system(queue_tutorial_steps)
+            .add_system(advance_tutorial.after(queue_tutorial_steps))
+            .add_system(skip_tutorial)
+            .add_system(update_tutorial_prompt.after(advance_tutorial));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TutorialStep {
+    Move,
+    Look,
+    Jump,
+    Sprint,
+    /// Pull an object in with the catcher.
+    Catch,
+    /// Let go of a caught object to throw it.
+    Throw,
+    Repel,
+}
+
+impl TutorialStep {
+    fn prompt(self) -> &'static str {
+        match self {
+            TutorialStep::Move => "tutorial-move",
+            TutorialStep::Look => "tutorial-look",
+            TutorialStep::Jump => "tutorial-jump",
+            TutorialStep::Sprint => "tutorial-sprint",
+            TutorialStep::Catch => "tutorial-catch",
+            TutorialStep::Throw => "tutorial-throw",
+            TutorialStep::Repel => "tutorial-repel",
+        }
+    }
+}
+
+/// Steps waiting to be shown, the first one being on screen.
+#[derive(Debug, Default)]
+pub struct Tutorial {
+    pub steps: VecDeque<TutorialStep>,
+}
+
+#[derive(Component)]
+struct TutorialPrompt;
+
+fn setup_tutorial_prompt(mut commands: Commands) {
+    let position = Vec2::new(0.0, -0.5 * RENDER_SIZE[1] as f32 + PROMPT_MARGIN);
+    commands
+        .spawn_on_hud_layer(BitmapTextBundle::new(
+            BitmapText::new("", PROMPT_COLOR, BitmapAlign::Center),
+            position,
+        ))
+        .insert(TutorialPrompt);
+}
+
+fn queue_tutorial_steps(
+    profile: Res<Profile>,
+    mut tutorial: ResMut<Tutorial>,
+    mut events: EventReader<Triggered>,
+    triggers: Query<&Trigger>,
+    players: Query<(), With<Player>>,
+) {
+    for event in events.iter() {
+        if !players.contains(event.activator) || profile.tutorial_skipped {
+            continue;
+        }
+        let trigger = match triggers.get(event.trigger) {
+            Ok(trigger) => trigger,
+            Err(_) => continue,
+        };
+        for action in &trigger.actions {
+            if let TriggerAction::Tutorial { steps } = action {
+                for step in steps {
+                    if !profile.tutorial.contains(step) && !tutorial.steps.contains(step) {
+                        tutorial.steps.push_back(*step);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Watches the input for what the current step asks, and moves on once the player did it.
+fn advance_tutorial(
+    mut profile: ResMut<Profile>,
+    mut tutorial: ResMut<Tutorial>,
+    mut holding: Local<bool>,
+    players: Query<(&ActionState<Action>, &PlayerCatch), With<Player>>,
+) {
+    let (action_state, catch) = match players.get_single() {
+        Ok(player) => player,
+        Err(_) => return,
+    };
+    let was_holding = *holding;
+    *holding = catch.target.is_some();
+
+    let step = match tutorial.steps.front() {
+        Some(step) => *step,
+        None => return,
+    };
+    let done = match step {
+        TutorialStep::Move => action_state.pressed(Action::Move),
+        TutorialStep::Look => action_state
+            .axis_pair(Action::Look)
+            .map_or(false, |axis| Vec2::new(axis.x(), axis.y()) != Vec2::ZERO),
+        TutorialStep::Jump => action_state.just_pressed(Action::Jump),
+        TutorialStep::Sprint => {
+            action_state.pressed(Action::Sprint) && action_state.pressed(Action::Move)
+        }
+        TutorialStep::Catch => catch.target.is_some(),
+        TutorialStep::Throw => was_holding && catch.target.is_none(),
+        TutorialStep::Repel => action_state.just_pressed(Action::Repel),
+    };
+    if done {
+        tutorial.steps.pop_front();
+        if !profile.tutorial.contains(&step) {
+            profile.tutorial.push(step);
+        }
+    }
+}
+
+fn skip_tutorial(
+    keys: Res<Input<KeyCode>>,
+    mut profile: ResMut<Profile>,
+    mut tutorial: ResMut<Tutorial>,
+) {
+    if keys.just_pressed(SKIP_KEY) && !tutorial.steps.is_empty() {
+        tutorial.steps.clear();
+        profile.tutorial_skipped = true;
+    }
+}
+
+fn update_tutorial_prompt(
+    tutorial: Res<Tutorial>,
+    locale: Res<Locale>,
+    mut texts: Query<&mut BitmapText, With<TutorialPrompt>>,
+) {
+    let value = match tutorial.steps.front() {
+        Some(step) => format!(
+            "{}\n{}",
+            locale.get(step.prompt()),
+            locale.get("tutorial-skip")
+        ),
+        None => String::new(),
+    };
+    for mut text in &mut texts {
+        if text.value != value {
+            text.value = value.clone();
+        }
+    }
+}
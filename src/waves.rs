@@ -0,0 +1,467 @@
+//! Waves of bots and cubes during a match.
+//!
+//! The [`WaveDirector`] walks through a [`WaveTable`] loaded from `assets/waves.ron`: each wave
+//! waits for its delay, spawns its entries at a [`WaveSpawnPoint`] with a matching tag, and is
+//! cleared once its bots are gone or its duration runs out. Only the host directs waves; bots reach
+//! clients as remote players, but spawned cubes stay on the host for now.
+//!
+//! A wave can also place [`CubeSpawner`]s, which keep dropping cubes until the wave is cleared.
+//! Starting and clearing a wave show a banner and play `wave_started` and `wave_cleared` from
+//! `assets/sounds`.
+
+use crate::{
+    bots::{free_bot_peer, spawn_bot, Bot, BotAssets},
+    cues::load_sound,
+    hud::{
+        bitmap::{BitmapAlign, BitmapText, BitmapTextBundle},
+        composite::HudEffects,
+    },
+    level::LevelEntity,
+    lifetime::Debris,
+    locale::Locale,
+    match_flow::MatchState,
+    materials::MaterialLibrary,
+    net::{Network, ServerMessage},
+    props::SpawnPropExt,
+    spawner::CubeSpawner,
+    CUBE_SIZE,
+};
+use bevy::prelude::*;
+use rand::Rng;
+use serde::Deserialize;
+use std::fs;
+
+pub const WAVE_TABLE_PATH: &str = "assets/waves.ron";
+const BANNER_TIME: f32 = 3.0;
+
+pub struct WavePlugin;
+
+impl Plugin for WavePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<WaveDirector>()
+            .init_resource::<WaveAssets>()
+            .add_event::<WaveStarted>()
+            .add_event::<WaveCleared>()
+            .add_startup_system(setup_wave_spawn_points)
+            .add_startup_system(setup_wave_banner)
+            .add_system_set(SystemSet::on_enter(MatchState::InMatch).with_system(start_waves))
+            .add_system_set(SystemSet::on_update(MatchState::InMatch).with_system(run_waves))
+            .add_system_set(SystemSet::on_exit(MatchState::InMatch).with_system(stop_waves))
+            .add_system_set(
+                SystemSet::on_enter(MatchState::Countdown).with_system(clear_wave_members),
+            )
+            .add_system(stop_wave_spawners)
+            .add_system(wave_banner)
+            .add_system(wave_sounds);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum SpawnKind {
+    Bot,
+    Cube,
+    /// A [`CubeSpawner`] with the default settings, running until the wave is cleared.
+    Spawner,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WaveEntry {
+    pub kind: SpawnKind,
+    pub count: usize,
+    /// Tag of the [`WaveSpawnPoint`]s to spawn at.
+    pub location: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Wave {
+    /// Seconds to wait before the wave starts.
+    pub delay: f32,
+    /// Clears the wave after this long, even if its bots are still around.
+    #[serde(default)]
+    pub duration: Option<f32>,
+    pub entries: Vec<WaveEntry>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WaveTable {
+    pub waves: Vec<Wave>,
+}
+
+impl Default for WaveTable {
+    fn default() -> Self {
+        let entry = |kind, count, location: &str| WaveEntry {
+            kind,
+            count,
+            location: location.into(),
+        };
+        Self {
+            waves: vec![
+                Wave {
+                    delay: 3.0,
+                    duration: Some(20.0),
+                    entries: vec![entry(SpawnKind::Cube, 6, "sky")],
+                },
+                Wave {
+                    delay: 5.0,
+                    duration: None,
+                    entries: vec![
+                        entry(SpawnKind::Bot, 1, "north"),
+                        entry(SpawnKind::Cube, 4, "sky"),
+                    ],
+                },
+                Wave {
+                    delay: 5.0,
+                    duration: None,
+                    entries: vec![
+                        entry(SpawnKind::Bot, 1, "east"),
+                        entry(SpawnKind::Bot, 1, "west"),
+                    ],
+                },
+            ],
+        }
+    }
+}
+
+impl WaveTable {
+    pub fn load() -> Self {
+        let table = fs::read_to_string(WAVE_TABLE_PATH)
+            .map_err(|err| err.to_string())
+            .and_then(|text| ron::from_str(&text).map_err(|err| err.to_string()));
+        match table {
+            Ok(table) => table,
+            Err(err) => {
+                warn!(
+                    "Failed to load {}, using the default waves: {}",
+                    WAVE_TABLE_PATH, err
+                );
+                Self::default()
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum WavePhase {
+    Idle,
+    Waiting { wave: usize, timer: Timer },
+    Active { wave: usize, timer: Option<Timer> },
+    Finished,
+}
+
+pub struct WaveDirector {
+    pub table: WaveTable,
+    pub phase: WavePhase,
+}
+
+impl Default for WaveDirector {
+    fn default() -> Self {
+        Self {
+            table: WaveTable::load(),
+            phase: WavePhase::Idle,
+        }
+    }
+}
+
+impl WaveDirector {
+    fn wait_for(&mut self, wave: usize) {
+        self.phase = match self.table.waves.get(wave) {
+            Some(next) => WavePhase::Waiting {
+                wave,
+                timer: Timer::from_seconds(next.delay, false),
+            },
+            None => WavePhase::Finished,
+        };
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct WaveStarted {
+    pub wave: usize,
+    pub total: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct WaveCleared {
+    pub wave: usize,
+    pub total: usize,
+}
+
+/// Where waves spawn entities tagged `tag`, scattered within `radius`.
+#[derive(Debug, Component)]
+pub struct WaveSpawnPoint {
+    pub tag: String,
+    pub radius: f32,
+}
+
+/// Spawned by a wave.
+#[derive(Debug, Component)]
+pub struct WaveMember(pub usize);
+
+pub struct WaveAssets {
+    cube_mesh: Handle<Mesh>,
+    cube_material: Handle<StandardMaterial>,
+    started_sound: Option<Handle<AudioSource>>,
+    cleared_sound: Option<Handle<AudioSource>>,
+}
+
+impl FromWorld for WaveAssets {
+    fn from_world(world: &mut World) -> Self {
+        let cube_mesh = world
+            .resource_mut::<Assets<Mesh>>()
+            .add(shape::Cube::new(CUBE_SIZE).into());
+        let cube_material = world.resource_scope(|world, library: Mut<MaterialLibrary>| {
+            library.unique(
+                "cube",
+                &mut world.resource_mut::<Assets<StandardMaterial>>(),
+            )
+        });
+        let asset_server = world.resource::<AssetServer>();
+        Self {
+            cube_mesh,
+            cube_material,
+            started_sound: load_sound(asset_server, "wave_started"),
+            cleared_sound: load_sound(asset_server, "wave_cleared"),
+        }
+    }
+}
+
+#[derive(Component)]
+struct WaveBanner {
+    timer: Timer,
+}
+
+fn setup_wave_spawn_points(mut commands: Commands) {
+    let points = [
+        ("north", Vec3::new(0.0, 2.0, -35.0), 4.0),
+        ("south", Vec3::new(0.0, 2.0, 35.0), 4.0),
+        ("east", Vec3::new(35.0, 2.0, 0.0), 4.0),
+        ("west", Vec3::new(-35.0, 2.0, 0.0), 4.0),
+        ("sky", Vec3::new(25.0, 20.0, 25.0), 6.0),
+        ("sky", Vec3::new(-25.0, 20.0, 25.0), 6.0),
+        ("sky", Vec3::new(25.0, 20.0, -25.0), 6.0),
+        ("sky", Vec3::new(-25.0, 20.0, -25.0), 6.0),
+    ];
+    for (tag, position, radius) in points {
+        commands
+            .spawn_bundle(TransformBundle::from_transform(
+                Transform::from_translation(position)
+                    .looking_at(Vec3::new(0.0, position.y, 0.0), Vec3::Y),
+            ))
+            .insert(WaveSpawnPoint {
+                tag: tag.into(),
+                radius,
+            });
+    }
+}
+
+fn setup_wave_banner(mut commands: Commands) {
+    commands
+        .spawn_bundle(BitmapTextBundle::new(
+            BitmapText::new("", Color::WHITE, BitmapAlign::Center),
+            Vec2::new(0.0, 60.0),
+        ))
+        .insert(WaveBanner {
+            timer: Timer::from_seconds(BANNER_TIME, false),
+        });
+}
+
+fn start_waves(network: Res<Network>, mut director: ResMut<WaveDirector>) {
+    if network.is_authoritative() {
+        director.wait_for(0);
+    }
+}
+
+fn stop_waves(mut director: ResMut<WaveDirector>) {
+    director.phase = WavePhase::Idle;
+}
+
+#[allow(clippy::too_many_arguments)]
+fn run_waves(
+    mut commands: Commands,
+    time: Res<Time>,
+    network: Res<Network>,
+    assets: Res<WaveAssets>,
+    bot_assets: Res<BotAssets>,
+    mut director: ResMut<WaveDirector>,
+    mut started: EventWriter<WaveStarted>,
+    mut cleared: EventWriter<WaveCleared>,
+    spawn_points: Query<(&WaveSpawnPoint, &Transform)>,
+    bots: Query<(&Bot, Option<&WaveMember>)>,
+) {
+    if !network.is_authoritative() {
+        return;
+    }
+
+    let director = &mut *director;
+    let total = director.table.waves.len();
+    match &mut director.phase {
+        WavePhase::Waiting { wave, timer } => {
+            if !timer.tick(time.delta()).finished() {
+                return;
+            }
+            let wave = *wave;
+            let entries = director.table.waves[wave].entries.clone();
+
+            let mut rng = rand::thread_rng();
+            let mut used: Vec<_> = bots.iter().map(|(bot, _)| bot.peer).collect();
+            for entry in &entries {
+                let points: Vec<_> = spawn_points
+                    .iter()
+                    .filter(|(point, _)| point.tag == entry.location)
+                    .collect();
+                if points.is_empty() {
+                    warn!("No wave spawn point tagged {}", entry.location);
+                    continue;
+                }
+
+                for _ in 0..entry.count {
+                    let (point, transform) = points[rng.gen_range(0..points.len())];
+                    let offset = Vec3::new(
+                        rng.gen_range(-point.radius..=point.radius),
+                        0.0,
+                        rng.gen_range(-point.radius..=point.radius),
+                    );
+                    let transform = Transform {
+                        translation: transform.translation + offset,
+                        ..*transform
+                    };
+
+                    let entity = match entry.kind {
+                        SpawnKind::Bot => {
+                            let peer = free_bot_peer(&used);
+                            used.push(peer);
+                            spawn_bot(&mut commands, &bot_assets, transform, peer)
+                        }
+                        SpawnKind::Cube => commands
+                            .spawn_catch_cube(
+                                assets.cube_mesh.clone(),
+                                assets.cube_material.clone(),
+                                "cube",
+                                transform,
+                            )
+                            .insert(Debris)
+                            .id(),
+                        SpawnKind::Spawner => commands
+                            .spawn_bundle(TransformBundle::from_transform(transform))
+                            .insert(CubeSpawner::default())
+                            .id(),
+                    };
+                    commands
+                        .entity(entity)
+                        .insert_bundle((WaveMember(wave), LevelEntity));
+                }
+            }
+
+            info!(target: "waves", wave = wave + 1, total, "Wave started");
+            started.send(WaveStarted { wave, total });
+            director.phase = WavePhase::Active {
+                wave,
+                timer: director.table.waves[wave]
+                    .duration
+                    .map(|duration| Timer::from_seconds(duration, false)),
+            };
+        }
+        WavePhase::Active { wave, timer } => {
+            let wave = *wave;
+            let timed_out = timer
+                .as_mut()
+                .map_or(false, |timer| timer.tick(time.delta()).finished());
+            let remaining = bots
+                .iter()
+                .filter(|(_, member)| member.map_or(false, |member| member.0 == wave))
+                .count();
+            let has_bots = director.table.waves[wave]
+                .entries
+                .iter()
+                .any(|entry| entry.kind == SpawnKind::Bot && entry.count > 0);
+
+            if timed_out || (remaining == 0 && (has_bots || timer.is_none())) {
+                info!("Wave {} of {} cleared", wave + 1, total);
+                cleared.send(WaveCleared { wave, total });
+                director.wait_for(wave + 1);
+            }
+        }
+        WavePhase::Idle | WavePhase::Finished => {}
+    }
+}
+
+/// Spawners of a wave only run while the wave does; their cubes stay.
+fn stop_wave_spawners(
+    mut commands: Commands,
+    mut cleared: EventReader<WaveCleared>,
+    spawners: Query<(Entity, &WaveMember), With<CubeSpawner>>,
+) {
+    for event in cleared.iter() {
+        for (entity, member) in &spawners {
+            if member.0 == event.wave {
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+    }
+}
+
+/// Removes what the waves of the last match left behind.
+fn clear_wave_members(
+    mut commands: Commands,
+    network: Res<Network>,
+    members: Query<(Entity, Option<&Bot>), With<WaveMember>>,
+) {
+    for (entity, bot) in &members {
+        commands.entity(entity).despawn_recursive();
+        if let Some(bot) = bot {
+            network.broadcast(&ServerMessage::Leave { peer: bot.peer }, None);
+        }
+    }
+}
+
+fn wave_banner(
+    time: Res<Time>,
+    locale: Res<Locale>,
+    mut hud: ResMut<HudEffects>,
+    mut started: EventReader<WaveStarted>,
+    mut cleared: EventReader<WaveCleared>,
+    mut banners: Query<(&mut WaveBanner, &mut BitmapText)>,
+) {
+    for (mut banner, mut text) in &mut banners {
+        for event in started.iter() {
+            text.value = locale.get_args(
+                "wave-started",
+                &[
+                    ("wave", (event.wave + 1).into()),
+                    ("total", event.total.into()),
+                ],
+            );
+            banner.timer.reset();
+            hud.shake(3.0);
+        }
+        for event in cleared.iter() {
+            text.value = if event.wave + 1 == event.total {
+                locale.get("waves-cleared")
+            } else {
+                locale.get_args("wave-cleared", &[("wave", (event.wave + 1).into())])
+            };
+            banner.timer.reset();
+        }
+
+        banner.timer.tick(time.delta());
+        let alpha = 1.0 - banner.timer.percent();
+        if text.color.a() != alpha {
+            text.color.set_a(alpha);
+        }
+    }
+}
+
+fn wave_sounds(
+    audio: Res<Audio>,
+    assets: Res<WaveAssets>,
+    mut started: EventReader<WaveStarted>,
+    mut cleared: EventReader<WaveCleared>,
+) {
+    let sounds = started
+        .iter()
+        .filter_map(|_| assets.started_sound.as_ref())
+        .chain(cleared.iter().filter_map(|_| assets.cleared_sound.as_ref()));
+    for sound in sounds {
+        audio.play(sound.clone());
+    }
+}